@@ -20,6 +20,9 @@ extern "C" {
 /* Opaque handle to embedder instance */
 typedef void* EmbedderHandle;
 
+/* Opaque batch of embeddings allocated by the library */
+typedef struct EmbeddingBatch EmbeddingBatch;
+
 /* Error codes returned by API functions */
 typedef enum {
     EMBEDDER_SUCCESS = 0,
@@ -28,14 +31,24 @@ typedef enum {
     EMBEDDER_INITIALIZATION_FAILED = 3,
     EMBEDDER_EMBEDDING_FAILED = 4,
     EMBEDDER_INVALID_HANDLE = 5,
-    EMBEDDER_BUFFER_TOO_SMALL = 6
+    EMBEDDER_BUFFER_TOO_SMALL = 6,
+    EMBEDDER_PANIC = 7,
+    EMBEDDER_INVALID_ARGUMENT = 8,
+    EMBEDDER_CANCELLED = 9
 } EmbedderErrorCode;
 
+/* Similarity metrics */
+#define EMBEDDER_SIMILARITY_COSINE 0u
+#define EMBEDDER_SIMILARITY_DOT    1u
+
 /*
  * Initialize an embedder with the specified model.
  * 
+ * The weights are loaded once per process and shared by all handles for the
+ * same model; they are released when the last of those handles is freed.
+ * 
  * Parameters:
- *   model_name: Name of the model ("mini_lm_v2" or "jina")
+ *   model_name: Name of the model ("mini_lm_v2", "jina", "multilingual_e5_base", ...)
  * 
  * Returns:
  *   Handle to embedder instance, or NULL on failure
@@ -49,6 +62,107 @@ typedef enum {
  */
 EmbedderHandle embedder_init(const char* model_name);
 
+/* Pooling strategies for EmbedderOptions.pooling */
+#define EMBEDDER_POOLING_MEAN 0u
+#define EMBEDDER_POOLING_CLS  1u
+/* Since ABI 1.3 */
+#define EMBEDDER_POOLING_LAST_TOKEN 2u
+/* The pooling the model was trained with (last token for decoders, else mean) */
+#define EMBEDDER_POOLING_MODEL 3u
+
+/*
+ * Options for embedder_init_with_options(). The struct is size-prefixed:
+ * set struct_size to sizeof(EmbedderOptions) (embedder_options_init() needs
+ * it too), so older and newer library versions can accept it.
+ */
+typedef struct {
+    size_t struct_size;
+    /* "mini_lm_v2" or "jina"; NULL selects "mini_lm_v2" */
+    const char* model_name;
+    /* Non-zero to L2-normalize embeddings (default 1) */
+    int32_t normalize;
+    /* Truncate texts to this many tokens; 0 keeps the model limit (default 0) */
+    size_t max_tokens;
+    /* EMBEDDER_POOLING_MODEL (default since ABI 1.3), EMBEDDER_POOLING_MEAN,
+       EMBEDDER_POOLING_CLS or EMBEDDER_POOLING_LAST_TOKEN; since ABI 1.1 */
+    uint32_t pooling;
+} EmbedderOptions;
+
+/*
+ * Fill an options struct with the library defaults.
+ * 
+ * Parameters:
+ *   options: Struct with struct_size already set
+ * 
+ * Returns:
+ *   EMBEDDER_SUCCESS, EMBEDDER_NULL_POINTER, or EMBEDDER_INVALID_ARGUMENT
+ *   if struct_size is too small
+ */
+EmbedderErrorCode embedder_options_init(EmbedderOptions* options);
+
+/*
+ * Initialize an embedder from an options struct.
+ * 
+ * Parameters:
+ *   options: Options, or NULL for the defaults
+ * 
+ * Returns:
+ *   Handle to embedder instance, or NULL on failure
+ * 
+ * Example:
+ *   EmbedderOptions options = { sizeof(EmbedderOptions) };
+ *   embedder_options_init(&options);
+ *   options.model_name = "jina";
+ *   options.max_tokens = 256;
+ *   EmbedderHandle handle = embedder_init_with_options(&options);
+ */
+EmbedderHandle embedder_init_with_options(const EmbedderOptions* options);
+
+/*
+ * Initialize an embedder from explicit model files, e.g. weights with
+ * non-standard file names. Since ABI 1.2.
+ * 
+ * Parameters:
+ *   model_name: Architecture of the model ("mini_lm_v2" or "jina")
+ *   tokenizer_path: Path of tokenizer.json
+ *   config_path: Path of config.json, or NULL for the model's default config
+ *   weights_path: Path of the safetensors weights
+ * 
+ * Returns:
+ *   Handle to embedder instance, or NULL on failure
+ */
+EmbedderHandle embedder_init_from_files(
+    const char* model_name,
+    const char* tokenizer_path,
+    const char* config_path,
+    const char* weights_path
+);
+
+/*
+ * Initialize an embedder from model files held in memory, e.g. bundled or
+ * decrypted weights. The buffers are copied and may be freed right after
+ * the call. Since ABI 1.2.
+ * 
+ * Parameters:
+ *   model_name: Architecture of the model ("mini_lm_v2" or "jina")
+ *   tokenizer_data, tokenizer_len: Contents of tokenizer.json
+ *   config_data, config_len: Contents of config.json, or NULL for the
+ *                            model's default config
+ *   weights_data, weights_len: Contents of the safetensors weights
+ * 
+ * Returns:
+ *   Handle to embedder instance, or NULL on failure
+ */
+EmbedderHandle embedder_init_from_buffers(
+    const char* model_name,
+    const uint8_t* tokenizer_data,
+    size_t tokenizer_len,
+    const uint8_t* config_data,
+    size_t config_len,
+    const uint8_t* weights_data,
+    size_t weights_len
+);
+
 /*
  * Generate an embedding for a single text string.
  * 
@@ -80,6 +194,60 @@ EmbedderErrorCode embedder_embed(
     size_t* actual_size
 );
 
+/*
+ * Generate an embedding for a UTF-8 text given as pointer and byte length.
+ * 
+ * The text does not need a null terminator and may contain NUL characters.
+ * text may be NULL when text_len is 0. The other parameters and the return
+ * value are the same as for embedder_embed().
+ */
+EmbedderErrorCode embedder_embed_utf8(
+    EmbedderHandle handle,
+    const uint8_t* text,
+    size_t text_len,
+    float* output_buffer,
+    size_t buffer_size,
+    size_t* actual_size
+);
+
+/*
+ * Generate an embedding for a UTF-16 text given as pointer and length in
+ * code units (the native string layout of .NET and PowerShell).
+ * 
+ * Unpaired surrogates are reported as EMBEDDER_INVALID_UTF8. The other
+ * parameters and the return value are the same as for embedder_embed().
+ * 
+ * Example (C#):
+ *   embedder_embed_utf16(handle, text, (UIntPtr)text.Length,
+ *                        buffer, (UIntPtr)buffer.Length, out actual);
+ */
+EmbedderErrorCode embedder_embed_utf16(
+    EmbedderHandle handle,
+    const uint16_t* text,
+    size_t text_len,
+    float* output_buffer,
+    size_t buffer_size,
+    size_t* actual_size
+);
+
+/*
+ * Generate an embedding for a PNG or JPEG image (the bytes of the file)
+ * with a text-image model ("clip", "siglip"), in the same vector space as
+ * the texts. Since ABI 1.4.
+ * 
+ * Returns EMBEDDER_INVALID_ARGUMENT if the model does not embed images and
+ * EMBEDDER_EMBEDDING_FAILED if the image cannot be decoded. The other
+ * parameters and the return value are the same as for embedder_embed().
+ */
+EmbedderErrorCode embedder_embed_image(
+    EmbedderHandle handle,
+    const uint8_t* image,
+    size_t image_len,
+    float* output_buffer,
+    size_t buffer_size,
+    size_t* actual_size
+);
+
 /*
  * Generate embeddings for multiple texts in a batch.
  * 
@@ -117,6 +285,349 @@ EmbedderErrorCode embedder_embed_batch(
     size_t* total_written
 );
 
+/*
+ * Batch variants of embedder_embed_utf8() and embedder_embed_utf16().
+ * 
+ * texts and text_lens are parallel arrays of num_texts elements; text_lens
+ * holds bytes (UTF-8) or code units (UTF-16). The other parameters and the
+ * return value are the same as for embedder_embed_batch().
+ */
+EmbedderErrorCode embedder_embed_batch_utf8(
+    EmbedderHandle handle,
+    const uint8_t** texts,
+    const size_t* text_lens,
+    size_t num_texts,
+    float* output_buffer,
+    size_t buffer_size,
+    size_t* embedding_dim,
+    size_t* total_written
+);
+
+EmbedderErrorCode embedder_embed_batch_utf16(
+    EmbedderHandle handle,
+    const uint16_t** texts,
+    const size_t* text_lens,
+    size_t num_texts,
+    float* output_buffer,
+    size_t buffer_size,
+    size_t* embedding_dim,
+    size_t* total_written
+);
+
+/*
+ * Progress and cancellation callbacks for embedder_embed_batch_progress().
+ * Both callbacks are optional (may be NULL) and run on the calling thread,
+ * between batches. user_data is passed through unchanged.
+ * Like EmbedderOptions, the struct is size-prefixed.
+ */
+typedef struct {
+    /* Must be sizeof(EmbedderProgress) */
+    size_t struct_size;
+    /* Called after each batch with texts embedded so far and the total */
+    void (*on_progress)(size_t done, size_t total, void* user_data);
+    /* Polled before each batch; return non-zero to cancel */
+    int32_t (*is_cancelled)(void* user_data);
+    void* user_data;
+} EmbedderProgress;
+
+/*
+ * Generate embeddings in batches with progress reporting and cancellation.
+ * 
+ * Parameters:
+ *   handle: Embedder handle
+ *   texts: Array of text pointers (null-terminated UTF-8 strings)
+ *   num_texts: Number of texts in array
+ *   batch_size: Texts run through the model at once (0 = default of 32)
+ *   output_buffer: Buffer for all embeddings (flattened)
+ *   buffer_size: Total buffer size in floats (>= num_texts * dimension)
+ *   embedding_dim: Output - dimension of each embedding
+ *   num_completed: Output - number of embeddings written
+ *   progress: Callbacks, or NULL
+ * 
+ * Returns:
+ *   EMBEDDER_SUCCESS when all texts were embedded,
+ *   EMBEDDER_CANCELLED when is_cancelled returned non-zero (the first
+ *   num_completed embeddings are in output_buffer), error code otherwise
+ * 
+ * Example:
+ *   static volatile int cancel = 0;
+ *   static int32_t check_cancel(void* ud) { return cancel; }
+ *   static void report(size_t done, size_t total, void* ud) {
+ *       printf("%zu / %zu\n", done, total);
+ *   }
+ *   EmbedderProgress progress = { sizeof(EmbedderProgress), report, check_cancel, NULL };
+ *   err = embedder_embed_batch_progress(handle, texts, n, 64, buffer,
+ *                                       n * dim, &dim, &done, &progress);
+ */
+EmbedderErrorCode embedder_embed_batch_progress(
+    EmbedderHandle handle,
+    const char** texts,
+    size_t num_texts,
+    size_t batch_size,
+    float* output_buffer,
+    size_t buffer_size,
+    size_t* embedding_dim,
+    size_t* num_completed,
+    const EmbedderProgress* progress
+);
+
+/*
+ * Generate embeddings for multiple texts into a batch allocated by the library.
+ * 
+ * Parameters:
+ *   handle: Embedder handle
+ *   texts: Array of text pointers (null-terminated UTF-8 strings)
+ *   num_texts: Number of texts in array
+ * 
+ * Returns:
+ *   Batch of embeddings, or NULL if handle or texts is NULL
+ * 
+ * Note:
+ *   A failing text does not abort the batch. Check each item with
+ *   embedder_batch_status(); the last error of the handle describes the
+ *   first failing text. The batch must be freed with embedder_batch_free().
+ * 
+ * Example:
+ *   const char* texts[] = {"First", "Second", "Third"};
+ *   EmbeddingBatch* batch = embedder_embed_batch_alloc(handle, texts, 3);
+ *   size_t dim = embedder_batch_dimension(batch);
+ *   for (size_t i = 0; i < embedder_batch_count(batch); i++) {
+ *       if (embedder_batch_status(batch, i) == EMBEDDER_SUCCESS) {
+ *           const float* row = embedder_batch_row(batch, i);
+ *           printf("%zu: %f ... (%zu floats)\n", i, row[0], dim);
+ *       }
+ *   }
+ *   embedder_batch_free(batch);
+ */
+EmbeddingBatch* embedder_embed_batch_alloc(
+    EmbedderHandle handle,
+    const char** texts,
+    size_t num_texts
+);
+
+/*
+ * Library-allocated batch variants for length-prefixed UTF-8 and UTF-16 texts.
+ * Invalid UTF-8 and unpaired surrogates are reported per item as
+ * EMBEDDER_INVALID_UTF8 through embedder_batch_status().
+ */
+EmbeddingBatch* embedder_embed_batch_alloc_utf8(
+    EmbedderHandle handle,
+    const uint8_t** texts,
+    const size_t* text_lens,
+    size_t num_texts
+);
+
+EmbeddingBatch* embedder_embed_batch_alloc_utf16(
+    EmbedderHandle handle,
+    const uint16_t** texts,
+    const size_t* text_lens,
+    size_t num_texts
+);
+
+/* Number of items in a batch (0 if batch is NULL). */
+size_t embedder_batch_count(const EmbeddingBatch* batch);
+
+/* Embedding dimension of every row in a batch (0 if batch is NULL). */
+size_t embedder_batch_dimension(const EmbeddingBatch* batch);
+
+/*
+ * Status of one item in a batch: EMBEDDER_SUCCESS or the item's error code.
+ * Returns EMBEDDER_NULL_POINTER if batch is NULL and
+ * EMBEDDER_INVALID_ARGUMENT if index is out of range.
+ */
+EmbedderErrorCode embedder_batch_status(const EmbeddingBatch* batch, size_t index);
+
+/*
+ * Embedding of one item in a batch (embedder_batch_dimension() floats).
+ * Returns NULL if the index is out of range or the item failed.
+ * Valid until embedder_batch_free().
+ */
+const float* embedder_batch_row(const EmbeddingBatch* batch, size_t index);
+
+/*
+ * All rows of a batch stored contiguously (count * dimension floats).
+ * Failed rows are zero-filled. Valid until embedder_batch_free().
+ */
+const float* embedder_batch_data(const EmbeddingBatch* batch);
+
+/*
+ * Free a batch returned by embedder_embed_batch_alloc().
+ * Must be called exactly once per batch.
+ */
+void embedder_batch_free(EmbeddingBatch* batch);
+
+/*
+ * Compute the similarity between two texts embedded with the handle's model.
+ * 
+ * Parameters:
+ *   handle: Embedder handle
+ *   text_a, text_b: Texts to compare (null-terminated UTF-8 strings)
+ *   metric: EMBEDDER_SIMILARITY_COSINE or EMBEDDER_SIMILARITY_DOT
+ *   score: Output - similarity score
+ * 
+ * Note:
+ *   Both metrics give the same score while the handle normalizes its
+ *   embeddings (the default); with normalize set to 0, only cosine divides
+ *   by the norms.
+ * 
+ * Example:
+ *   float score;
+ *   embedder_similarity(handle, "cat", "kitten",
+ *                       EMBEDDER_SIMILARITY_COSINE, &score);
+ */
+EmbedderErrorCode embedder_similarity(
+    EmbedderHandle handle,
+    const char* text_a,
+    const char* text_b,
+    uint32_t metric,
+    float* score
+);
+
+/*
+ * Compute the similarity between two vectors of dim floats.
+ * Does not need an embedder handle.
+ */
+EmbedderErrorCode embedder_similarity_vectors(
+    const float* a,
+    const float* b,
+    size_t dim,
+    uint32_t metric,
+    float* score
+);
+
+/*
+ * Compute the similarity of every row of a against every row of b.
+ * 
+ * Parameters:
+ *   a: Flattened matrix of rows_a * dim floats
+ *   b: Flattened matrix of rows_b * dim floats
+ *   dim: Vector dimension (> 0)
+ *   metric: EMBEDDER_SIMILARITY_COSINE or EMBEDDER_SIMILARITY_DOT
+ *   output_buffer: Receives the row-major rows_a * rows_b score matrix
+ *   buffer_size: Size of output_buffer in floats
+ * 
+ * Returns:
+ *   EMBEDDER_BUFFER_TOO_SMALL if buffer_size < rows_a * rows_b
+ */
+EmbedderErrorCode embedder_similarity_matrix(
+    const float* a,
+    size_t rows_a,
+    const float* b,
+    size_t rows_b,
+    size_t dim,
+    uint32_t metric,
+    float* output_buffer,
+    size_t buffer_size
+);
+
+/*
+ * Find the k rows of an embedding matrix most similar to a query vector.
+ * 
+ * Parameters:
+ *   query: Query vector of dim floats
+ *   matrix: Flattened matrix of num_rows * dim floats
+ *   num_rows: Number of vectors in matrix
+ *   dim: Vector dimension (> 0)
+ *   k: Maximum number of results
+ *   metric: EMBEDDER_SIMILARITY_COSINE or EMBEDDER_SIMILARITY_DOT
+ *   out_indices: Receives up to k row indices, best first
+ *   out_scores: Receives the matching scores
+ *   out_count: Output - number of results (min(k, num_rows))
+ * 
+ * Example:
+ *   size_t idx[3], n;
+ *   float scores[3];
+ *   embedder_topk(query, docs, num_docs, dim, 3,
+ *                 EMBEDDER_SIMILARITY_DOT, idx, scores, &n);
+ */
+EmbedderErrorCode embedder_topk(
+    const float* query,
+    const float* matrix,
+    size_t num_rows,
+    size_t dim,
+    size_t k,
+    uint32_t metric,
+    size_t* out_indices,
+    float* out_scores,
+    size_t* out_count
+);
+
+/*
+ * Score how relevant each document is to a query with a cross-encoder
+ * ("ms_marco_minilm", "cross_encoder"), e.g. to rerank the best results of
+ * embedder_topk(). Since ABI 1.5.
+ * 
+ * Parameters:
+ *   handle: Embedder handle of a cross-encoder
+ *   query: Null-terminated UTF-8 query
+ *   documents: Array of num_documents null-terminated UTF-8 documents
+ *   num_documents: Number of documents
+ *   out_scores: Receives num_documents scores (logits), in the order of the
+ *               documents; higher is more relevant
+ * 
+ * Returns:
+ *   EMBEDDER_SUCCESS on success, EMBEDDER_INVALID_ARGUMENT if the model is
+ *   not a cross-encoder, another error code otherwise
+ * 
+ * Example:
+ *   const char* docs[] = {"Rust is a language", "It is sunny"};
+ *   float scores[2];
+ *   embedder_rerank(reranker, "What is Rust?", docs, 2, scores);
+ */
+EmbedderErrorCode embedder_rerank(
+    EmbedderHandle handle,
+    const char* query,
+    const char** documents,
+    size_t num_documents,
+    float* out_scores
+);
+
+/*
+ * Get the embedding dimension of the loaded model.
+ * 
+ * Parameters:
+ *   handle: Embedder handle
+ * 
+ * Returns:
+ *   Number of floats per embedding (384 for mini_lm_v2, 768 for jina),
+ *   or 0 if handle is NULL
+ * 
+ * Example:
+ *   size_t dim = embedder_get_dimension(handle);
+ *   float* buffer = malloc(dim * num_texts * sizeof(float));
+ */
+size_t embedder_get_dimension(EmbedderHandle handle);
+
+/*
+ * Get the maximum number of tokens the loaded model accepts per text.
+ * 
+ * Parameters:
+ *   handle: Embedder handle
+ * 
+ * Returns:
+ *   Maximum sequence length in tokens, or 0 if handle is NULL
+ */
+size_t embedder_get_max_tokens(EmbedderHandle handle);
+
+/*
+ * Get the metadata of the loaded model as a JSON string.
+ * 
+ * Parameters:
+ *   handle: Embedder handle
+ * 
+ * Returns:
+ *   JSON object with model_id, revision, dimension, max_tokens,
+ *   pooling, normalize and dtype, or NULL if handle is NULL
+ * 
+ * Note:
+ *   The string is owned by the handle and valid until embedder_free().
+ *   Do NOT free it.
+ * 
+ * Example:
+ *   printf("Model: %s\n", embedder_get_model_info(handle));
+ */
+const char* embedder_get_model_info(EmbedderHandle handle);
+
 /*
  * Get the last error message from the embedder.
  * 
@@ -167,6 +678,92 @@ void embedder_free_error(char* error_str);
  */
 void embedder_free(EmbedderHandle handle);
 
+/* Log levels for embedder_set_log_callback() */
+#define EMBEDDER_LOG_OFF   0
+#define EMBEDDER_LOG_ERROR 1
+#define EMBEDDER_LOG_WARN  2
+#define EMBEDDER_LOG_INFO  3
+#define EMBEDDER_LOG_DEBUG 4
+#define EMBEDDER_LOG_TRACE 5
+
+/*
+ * Logging callback. target is the emitting module and message the formatted
+ * event ("message key=value ..."); both are only valid during the call.
+ */
+typedef void (*EmbedderLogCallback)(
+    int32_t level,
+    const char* target,
+    const char* message,
+    void* user_data
+);
+
+/*
+ * Register a callback receiving the library's log events (model loading,
+ * hub downloads, inference).
+ * 
+ * Parameters:
+ *   callback: Function called for each event, or NULL to stop logging
+ *   max_level: Most verbose level forwarded (EMBEDDER_LOG_ERROR ..
+ *              EMBEDDER_LOG_TRACE), EMBEDDER_LOG_OFF forwards nothing
+ *   user_data: Passed unchanged to every callback invocation
+ * 
+ * Returns:
+ *   EMBEDDER_SUCCESS, or EMBEDDER_INITIALIZATION_FAILED if another tracing
+ *   subscriber is already installed in the process
+ * 
+ * Note:
+ *   The callback may run on any thread calling into the library. It may
+ *   call embedder_set_log_callback() itself; the change applies from the
+ *   next event.
+ * 
+ * Example:
+ *   static void on_log(int32_t level, const char* target,
+ *                      const char* message, void* user_data) {
+ *       fprintf(stderr, "[%d] %s: %s\n", level, target, message);
+ *   }
+ *   embedder_set_log_callback(on_log, EMBEDDER_LOG_INFO, NULL);
+ */
+EmbedderErrorCode embedder_set_log_callback(
+    EmbedderLogCallback callback,
+    int32_t max_level,
+    void* user_data
+);
+
+/*
+ * Get the ABI version of the library as (major << 16) | minor.
+ * The major version changes on incompatible changes, the minor version when
+ * functions or trailing struct fields are added. Code built against this
+ * header works with any library of the same major and a minor >= the one
+ * in EMBEDDER_ABI_VERSION.
+ * 
+ * Example:
+ *   if ((embedder_abi_version() >> 16) != EMBEDDER_ABI_VERSION_MAJOR) {
+ *       fprintf(stderr, "Incompatible library\n");
+ *   }
+ */
+#define EMBEDDER_ABI_VERSION_MAJOR 1u
+#define EMBEDDER_ABI_VERSION_MINOR 5u
+#define EMBEDDER_ABI_VERSION ((EMBEDDER_ABI_VERSION_MAJOR << 16) | EMBEDDER_ABI_VERSION_MINOR)
+
+uint32_t embedder_abi_version(void);
+
+/*
+ * Get a JSON description of what the library supports: version,
+ * abi_version, models, pooling, output_dtypes, similarity_metrics,
+ * input_encodings, thread_safety and the exported functions. models lists
+ * the models registered at the time of the call.
+ * 
+ * Returns:
+ *   JSON string valid for the life of the process (do not free)
+ * 
+ * Example:
+ *   {"version":"0.4.0","abi_version":65541,"models":["mini_lm_v2","jina",...],
+ *    "pooling":["mean","cls","last_token"],"output_dtypes":["f32"],
+ *    "thread_safety":{"handle":"externally_synchronized",
+ *                     "global_functions":"thread_safe"}, ...}
+ */
+const char* embedder_get_capabilities(void);
+
 /*
  * Get the library version string.
  * 
@@ -15,7 +15,10 @@ typedef enum {
     InitializationFailed = 3,
     EmbeddingFailed = 4,
     InvalidHandle = 5,
-    BufferTooSmall = 6
+    BufferTooSmall = 6,
+    Panic = 7,
+    InvalidArgument = 8,
+    Cancelled = 9
 } EmbedderErrorCode;
 
 // Function declarations
@@ -31,6 +34,37 @@ extern const char* embedder_get_last_error(EmbedderHandle handle);
 extern void embedder_free_error(char* error_str);
 extern void embedder_free(EmbedderHandle handle);
 extern const char* embedder_version(void);
+extern EmbedderErrorCode embedder_similarity(
+    EmbedderHandle handle,
+    const char* text_a,
+    const char* text_b,
+    unsigned int metric,
+    float* score
+);
+extern EmbedderErrorCode embedder_similarity_matrix(
+    const float* a,
+    size_t rows_a,
+    const float* b,
+    size_t rows_b,
+    size_t dim,
+    unsigned int metric,
+    float* output_buffer,
+    size_t buffer_size
+);
+extern EmbedderErrorCode embedder_topk(
+    const float* query,
+    const float* matrix,
+    size_t num_rows,
+    size_t dim,
+    size_t k,
+    unsigned int metric,
+    size_t* out_indices,
+    float* out_scores,
+    size_t* out_count
+);
+
+#define SIMILARITY_COSINE 0u
+#define SIMILARITY_DOT 1u
 
 int main() {
     printf("Rust Transformer DLL Example (C)\n");
@@ -92,6 +126,54 @@ int main() {
         printf("[%zu]: %.6f\n", i, embedding[i]);
     }
 
+    // Compare two texts
+    float score = 0.0f;
+    result = embedder_similarity(
+        handle, "The cat sits on the mat.", "A kitten rests on a rug.",
+        SIMILARITY_COSINE, &score
+    );
+    if (result == Success) {
+        printf("\nCosine similarity: %.4f\n", score);
+    }
+
+    // Rank a few documents against the query embedding
+    const char* docs[] = {"Goodbye, world!", "Hello there, planet.", "Rust is fast."};
+    const size_t num_docs = sizeof(docs) / sizeof(docs[0]);
+    float* matrix = (float*)malloc(num_docs * actual_size * sizeof(float));
+    if (matrix != NULL) {
+        size_t doc_size = 0;
+        int ok = 1;
+        for (size_t i = 0; i < num_docs && ok; i++) {
+            ok = embedder_embed(handle, docs[i], matrix + i * actual_size,
+                                actual_size, &doc_size) == Success;
+        }
+
+        size_t indices[2];
+        float scores[2];
+        size_t count = 0;
+        if (ok && embedder_topk(embedding, matrix, num_docs, actual_size, 2,
+                                SIMILARITY_DOT, indices, scores, &count) == Success) {
+            printf("Top %zu documents:\n", count);
+            for (size_t i = 0; i < count; i++) {
+                printf("  %.4f  %s\n", scores[i], docs[indices[i]]);
+            }
+        }
+
+        // Score every document against every other one
+        float pairs[3 * 3];
+        if (ok && embedder_similarity_matrix(matrix, num_docs, matrix, num_docs, actual_size,
+                                             SIMILARITY_COSINE, pairs, num_docs * num_docs) == Success) {
+            printf("Document similarity matrix:\n");
+            for (size_t i = 0; i < num_docs; i++) {
+                for (size_t j = 0; j < num_docs; j++) {
+                    printf("  %.4f", pairs[i * num_docs + j]);
+                }
+                printf("\n");
+            }
+        }
+        free(matrix);
+    }
+
     // Cleanup
     free(embedding);
     embedder_free(handle);
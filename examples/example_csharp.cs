@@ -17,7 +17,10 @@ class RustTransformerExample
         InitializationFailed = 3,
         EmbeddingFailed = 4,
         InvalidHandle = 5,
-        BufferTooSmall = 6
+        BufferTooSmall = 6,
+        Panic = 7,
+        InvalidArgument = 8,
+        Cancelled = 9
     }
 
     // P/Invoke declarations
@@ -33,6 +36,16 @@ class RustTransformerExample
         UIntPtr buffer_size,
         out UIntPtr actual_size);
 
+    // UTF-16 variant: .NET strings are passed as-is, no UTF-8 marshalling needed
+    [DllImport("rust_transformer.dll", CallingConvention = CallingConvention.Cdecl)]
+    private static extern EmbedderErrorCode embedder_embed_utf16(
+        IntPtr handle,
+        [MarshalAs(UnmanagedType.LPWStr)] string text,
+        UIntPtr text_len,
+        [Out] float[] output_buffer,
+        UIntPtr buffer_size,
+        out UIntPtr actual_size);
+
     [DllImport("rust_transformer.dll", CallingConvention = CallingConvention.Cdecl)]
     private static extern IntPtr embedder_get_last_error(IntPtr handle);
 
@@ -89,9 +102,10 @@ class RustTransformerExample
 
             // Generate embedding
             Console.WriteLine("Generating embedding...");
-            EmbedderErrorCode result = embedder_embed(
+            EmbedderErrorCode result = embedder_embed_utf16(
                 handle,
                 text,
+                (UIntPtr)text.Length,
                 embedding,
                 (UIntPtr)bufferSize,
                 out actualSize);
@@ -0,0 +1,44 @@
+// Example usage of the WebAssembly build from Node.js
+//
+// Build the package first (needs the wasm32-unknown-unknown target and wasm-pack):
+//   rustup target add wasm32-unknown-unknown
+//   wasm-pack build --target nodejs --out-dir pkg -- --features wasm
+//
+// Then run with a folder containing tokenizer.json, model.safetensors and config.json:
+//   node examples/example_node.mjs /path/to/all-MiniLM-L6-v2
+
+import { readFileSync } from "node:fs";
+import { join } from "node:path";
+import { createRequire } from "node:module";
+
+const require = createRequire(import.meta.url);
+const { Embedder } = require("../pkg/rust_transformer.js");
+
+const modelDir = process.argv[2];
+if (!modelDir) {
+    console.error("usage: node example_node.mjs <model folder>");
+    process.exit(1);
+}
+
+const embedder = new Embedder(
+    "mini_lm_v2",
+    readFileSync(join(modelDir, "tokenizer.json")),
+    readFileSync(join(modelDir, "model.safetensors")),
+    readFileSync(join(modelDir, "config.json")),
+);
+console.log("Model info:", JSON.parse(embedder.info));
+
+// Single text -> Float32Array
+const vector = embedder.embed("Hello, world!");
+console.log(`Dimension: ${vector.length}, first values: ${Array.from(vector.slice(0, 5))}`);
+
+// Several texts -> Array<Float32Array>
+const texts = ["The weather is sunny", "It is raining today", "Rust compiles to WebAssembly"];
+const vectors = embedder.embedBatch(texts, 16);
+
+const dot = (a, b) => a.reduce((sum, x, i) => sum + x * b[i], 0);
+texts.forEach((text, i) => {
+    console.log(`similarity("${texts[0]}", "${text}") = ${dot(vectors[0], vectors[i]).toFixed(4)}`);
+});
+
+embedder.free();
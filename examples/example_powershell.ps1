@@ -59,7 +59,10 @@ namespace RustTransformer {
         InitializationFailed = 3,
         EmbeddingFailed = 4,
         InvalidHandle = 5,
-        BufferTooSmall = 6
+        BufferTooSmall = 6,
+        Panic = 7,
+        InvalidArgument = 8,
+        Cancelled = 9
     }
     
     public static class Native {
@@ -49,6 +49,9 @@ class EmbedderErrorCode:
     EMBEDDING_FAILED = 4
     INVALID_HANDLE = 5
     BUFFER_TOO_SMALL = 6
+    PANIC = 7
+    INVALID_ARGUMENT = 8
+    CANCELLED = 9
     
     ERROR_NAMES = {
         0: "Success",
@@ -57,7 +60,10 @@ class EmbedderErrorCode:
         3: "Initialization Failed",
         4: "Embedding Failed",
         5: "Invalid Handle",
-        6: "Buffer Too Small"
+        6: "Buffer Too Small",
+        7: "Panic",
+        8: "Invalid Argument",
+        9: "Cancelled"
     }
     
     @classmethod
@@ -92,6 +98,28 @@ lib.embedder_embed_batch.argtypes = [
 ]
 lib.embedder_embed_batch.restype = ctypes.c_int
 
+lib.embedder_embed_batch_alloc.argtypes = [
+    c_void_p,  # handle
+    POINTER(c_char_p),  # texts
+    c_size_t  # num_texts
+]
+lib.embedder_embed_batch_alloc.restype = c_void_p
+
+lib.embedder_batch_count.argtypes = [c_void_p]
+lib.embedder_batch_count.restype = c_size_t
+
+lib.embedder_batch_dimension.argtypes = [c_void_p]
+lib.embedder_batch_dimension.restype = c_size_t
+
+lib.embedder_batch_status.argtypes = [c_void_p, c_size_t]
+lib.embedder_batch_status.restype = ctypes.c_int
+
+lib.embedder_batch_data.argtypes = [c_void_p]
+lib.embedder_batch_data.restype = POINTER(c_float)
+
+lib.embedder_batch_free.argtypes = [c_void_p]
+lib.embedder_batch_free.restype = None
+
 lib.embedder_get_last_error.argtypes = [c_void_p]
 lib.embedder_get_last_error.restype = c_char_p
 
@@ -139,51 +167,39 @@ class RustEmbedder:
         # Convert to Python list
         return list(output_buffer[:actual_size.value])
     
-    def embed_batch(self, texts, buffer_size_per_text=512):
-        """Generate embeddings for multiple texts"""
+    def embed_batch(self, texts):
+        """Generate embeddings for multiple texts (library-allocated result)"""
         if not texts:
             return []
         
         num_texts = len(texts)
-        total_buffer_size = buffer_size_per_text * num_texts
         
         # Prepare C string array
         c_texts = (c_char_p * num_texts)()
         for i, text in enumerate(texts):
             c_texts[i] = text.encode('utf-8')
         
-        # Prepare output buffer
-        output_buffer = (c_float * total_buffer_size)()
-        embedding_dim = c_size_t()
-        total_written = c_size_t()
-        
-        # Call the function
-        result = lib.embedder_embed_batch(
-            self.handle,
-            c_texts,
-            num_texts,
-            output_buffer,
-            total_buffer_size,
-            byref(embedding_dim),
-            byref(total_written)
-        )
-        
-        if result != EmbedderErrorCode.SUCCESS:
-            error_msg = self.get_last_error()
-            raise RuntimeError(
-                f"Batch embedding failed: {EmbedderErrorCode.get_name(result)}"
-                + (f" - {error_msg}" if error_msg else "")
-            )
-        
-        # Convert to list of embeddings
-        dim = embedding_dim.value
-        embeddings = []
-        for i in range(num_texts):
-            start = i * dim
-            end = start + dim
-            embeddings.append(list(output_buffer[start:end]))
+        # The library sizes and allocates the result, no buffer guessing needed
+        batch = lib.embedder_embed_batch_alloc(self.handle, c_texts, num_texts)
+        if not batch:
+            raise RuntimeError("Batch embedding failed: invalid handle or input")
         
-        return embeddings
+        try:
+            for i in range(num_texts):
+                result = lib.embedder_batch_status(batch, i)
+                if result != EmbedderErrorCode.SUCCESS:
+                    error_msg = self.get_last_error()
+                    raise RuntimeError(
+                        f"Batch embedding failed at text {i}: {EmbedderErrorCode.get_name(result)}"
+                        + (f" - {error_msg}" if error_msg else "")
+                    )
+            
+            # Convert to list of embeddings
+            dim = lib.embedder_batch_dimension(batch)
+            data = lib.embedder_batch_data(batch)
+            return [data[i * dim:(i + 1) * dim] for i in range(num_texts)]
+        finally:
+            lib.embedder_batch_free(batch)
     
     def get_last_error(self):
         """Get the last error message"""
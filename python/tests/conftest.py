@@ -0,0 +1,35 @@
+import os
+
+import pytest
+
+from tiny_model import write_tiny_model
+
+
+@pytest.fixture(scope="session")
+def tiny_model_dir(tmp_path_factory):
+    """Folder with a tiny random BERT model, used instead of downloading one"""
+    return write_tiny_model(tmp_path_factory.mktemp("tiny_bert"))
+
+
+@pytest.fixture(scope="session")
+def embedder_factory(tiny_model_dir):
+    """Builds embedders for the tiny model; the folder is read at build time"""
+    import rust_transformer
+
+    def factory(**kwargs):
+        previous = os.environ.get("BERT_MODEL_FOLDER")
+        os.environ["BERT_MODEL_FOLDER"] = str(tiny_model_dir)
+        try:
+            return rust_transformer.Embedder("mini_lm_v2", **kwargs)
+        finally:
+            if previous is None:
+                del os.environ["BERT_MODEL_FOLDER"]
+            else:
+                os.environ["BERT_MODEL_FOLDER"] = previous
+
+    return factory
+
+
+@pytest.fixture(scope="session")
+def embedder(embedder_factory):
+    return embedder_factory()
@@ -0,0 +1,125 @@
+import threading
+
+import numpy as np
+import pytest
+
+import rust_transformer
+from tiny_model import HIDDEN_SIZE, MAX_POSITIONS
+
+TEXTS = [
+    "the cat sat on the mat",
+    "dogs are fast",
+    "hello world",
+    "search query about the weather today",
+    "rust and python",
+]
+
+
+def test_module_metadata():
+    assert rust_transformer.__version__
+    assert "mini_lm_v2" in rust_transformer.SUPPORTED_MODELS
+
+
+def test_info(embedder):
+    info = embedder.info
+    assert info["dimension"] == HIDDEN_SIZE
+    assert info["max_tokens"] == MAX_POSITIONS
+    assert info["pooling"] == "mean"
+    assert info["padding_side"] == "right"
+    assert info["normalize"] is True
+    assert info["dtype"] == "f32"
+    assert embedder.dimension == HIDDEN_SIZE
+    assert embedder.max_tokens == MAX_POSITIONS
+
+
+def test_encode_shape_and_dtype(embedder):
+    embeddings = embedder.encode(TEXTS)
+    assert isinstance(embeddings, np.ndarray)
+    assert embeddings.shape == (len(TEXTS), HIDDEN_SIZE)
+    assert embeddings.dtype == np.float32
+    assert embeddings.flags["C_CONTIGUOUS"]
+    assert embeddings.flags["WRITEABLE"]
+
+
+def test_encode_empty_list(embedder):
+    embeddings = embedder.encode([])
+    assert embeddings.shape == (0, HIDDEN_SIZE)
+
+
+def test_encode_is_normalized_by_default(embedder):
+    norms = np.linalg.norm(embedder.encode(TEXTS), axis=1)
+    np.testing.assert_allclose(norms, 1.0, rtol=1e-5)
+
+
+def test_normalize_override(embedder):
+    raw = embedder.encode(TEXTS, normalize=False)
+    normalized = embedder.encode(TEXTS, normalize=True)
+    assert not np.allclose(np.linalg.norm(raw, axis=1), 1.0)
+    np.testing.assert_allclose(
+        raw / np.linalg.norm(raw, axis=1, keepdims=True), normalized, rtol=1e-4, atol=1e-6
+    )
+    # The override only applies to that call
+    assert embedder.info["normalize"] is True
+
+
+def test_batch_size_does_not_change_results(embedder):
+    single = embedder.encode(TEXTS, batch_size=1)
+    batched = embedder.encode(TEXTS, batch_size=len(TEXTS))
+    np.testing.assert_allclose(single, batched, rtol=1e-4, atol=1e-5)
+
+
+def test_encode_is_deterministic(embedder):
+    np.testing.assert_array_equal(embedder.encode(TEXTS), embedder.encode(TEXTS))
+
+
+def test_invalid_batch_size(embedder):
+    with pytest.raises(ValueError):
+        embedder.encode(TEXTS, batch_size=0)
+
+
+def test_encode_rejects_single_string(embedder):
+    with pytest.raises(TypeError):
+        embedder.encode("not a list")
+
+
+def test_unknown_model():
+    with pytest.raises(ValueError, match="Unknown model"):
+        rust_transformer.Embedder("does_not_exist")
+
+
+def test_unknown_pooling(embedder_factory):
+    with pytest.raises(ValueError, match="Unknown pooling"):
+        embedder_factory(pooling="max")
+
+
+def test_unknown_task(embedder_factory):
+    with pytest.raises(ValueError, match="Unknown task"):
+        embedder_factory(task="summary")
+
+
+def test_embedders_have_independent_settings(embedder_factory):
+    mean = embedder_factory()
+    cls = embedder_factory(pooling="cls", normalize=False, max_tokens=8)
+    assert cls.info["pooling"] == "cls"
+    assert cls.info["normalize"] is False
+    assert cls.max_tokens == 8
+    assert mean.info["pooling"] == "mean"
+    assert mean.max_tokens == MAX_POSITIONS
+    assert not np.allclose(mean.encode(TEXTS), cls.encode(TEXTS))
+
+
+def test_encode_from_threads(embedder):
+    expected = embedder.encode(TEXTS)
+    results = [None] * 4
+
+    def work(i):
+        results[i] = embedder.encode(TEXTS)
+
+    threads = [threading.Thread(target=work, args=(i,)) for i in range(len(results))]
+    for thread in threads:
+        thread.start()
+    for thread in threads:
+        thread.join()
+
+    for result in results:
+        np.testing.assert_allclose(result, expected, rtol=1e-5)
@@ -0,0 +1,180 @@
+"""
+Writes a tiny randomly initialized BERT model (config.json, tokenizer.json,
+model.safetensors) that the library can load through BERT_MODEL_FOLDER.
+
+The embeddings are meaningless, but the model has the same structure as
+all-MiniLM-L6-v2 and loads in milliseconds, which is all the tests need.
+Only the standard library is used so the script also works without numpy:
+
+    python python/tests/tiny_model.py /tmp/tiny_bert
+"""
+
+import json
+import random
+import struct
+import sys
+from array import array
+from pathlib import Path
+
+HIDDEN_SIZE = 32
+NUM_LAYERS = 2
+NUM_HEADS = 4
+INTERMEDIATE_SIZE = 64
+MAX_POSITIONS = 64
+
+SPECIAL_TOKENS = ["[PAD]", "[UNK]", "[CLS]", "[SEP]", "[MASK]"]
+WORDS = [
+    "the", "a", "is", "are", "and", "of", "to", "in", "hello", "world",
+    "cat", "cats", "dog", "dogs", "sat", "on", "mat", "rust", "python",
+    "fast", "text", "embedding", "model", "search", "query", "document",
+    "weather", "today", "sunny", "rain", "##s", "##ing", "##ed",
+]
+VOCAB = SPECIAL_TOKENS + WORDS + list("abcdefghijklmnopqrstuvwxyz0123456789.,!?")
+
+
+def config():
+    return {
+        "architectures": ["BertModel"],
+        "model_type": "bert",
+        "vocab_size": len(VOCAB),
+        "hidden_size": HIDDEN_SIZE,
+        "num_hidden_layers": NUM_LAYERS,
+        "num_attention_heads": NUM_HEADS,
+        "intermediate_size": INTERMEDIATE_SIZE,
+        "hidden_act": "gelu",
+        "hidden_dropout_prob": 0.1,
+        "max_position_embeddings": MAX_POSITIONS,
+        "type_vocab_size": 2,
+        "initializer_range": 0.02,
+        "layer_norm_eps": 1e-12,
+        "pad_token_id": 0,
+        "position_embedding_type": "absolute",
+    }
+
+
+def tokenizer():
+    def special(token):
+        return {"id": token, "type_id": 0}
+
+    return {
+        "version": "1.0",
+        "truncation": None,
+        "padding": None,
+        "added_tokens": [
+            {
+                "id": i,
+                "content": token,
+                "single_word": False,
+                "lstrip": False,
+                "rstrip": False,
+                "normalized": False,
+                "special": True,
+            }
+            for i, token in enumerate(SPECIAL_TOKENS)
+        ],
+        "normalizer": {
+            "type": "BertNormalizer",
+            "clean_text": True,
+            "handle_chinese_chars": True,
+            "strip_accents": None,
+            "lowercase": True,
+        },
+        "pre_tokenizer": {"type": "BertPreTokenizer"},
+        "post_processor": {
+            "type": "TemplateProcessing",
+            "single": [
+                {"SpecialToken": special("[CLS]")},
+                {"Sequence": {"id": "A", "type_id": 0}},
+                {"SpecialToken": special("[SEP]")},
+            ],
+            "pair": [
+                {"SpecialToken": special("[CLS]")},
+                {"Sequence": {"id": "A", "type_id": 0}},
+                {"SpecialToken": special("[SEP]")},
+                {"Sequence": {"id": "B", "type_id": 1}},
+                {"SpecialToken": {"id": "[SEP]", "type_id": 1}},
+            ],
+            "special_tokens": {
+                token: {"id": token, "ids": [VOCAB.index(token)], "tokens": [token]}
+                for token in ("[CLS]", "[SEP]")
+            },
+        },
+        "decoder": {"type": "WordPiece", "prefix": "##", "cleanup": True},
+        "model": {
+            "type": "WordPiece",
+            "unk_token": "[UNK]",
+            "continuing_subword_prefix": "##",
+            "max_input_chars_per_word": 100,
+            "vocab": {token: i for i, token in enumerate(VOCAB)},
+        },
+    }
+
+
+def tensors():
+    """Tensor name -> shape, as expected by candle's BertModel"""
+    shapes = {
+        "embeddings.word_embeddings.weight": (len(VOCAB), HIDDEN_SIZE),
+        "embeddings.position_embeddings.weight": (MAX_POSITIONS, HIDDEN_SIZE),
+        "embeddings.token_type_embeddings.weight": (2, HIDDEN_SIZE),
+        "embeddings.LayerNorm.weight": (HIDDEN_SIZE,),
+        "embeddings.LayerNorm.bias": (HIDDEN_SIZE,),
+    }
+    for i in range(NUM_LAYERS):
+        layer = f"encoder.layer.{i}"
+        for name in ("query", "key", "value"):
+            shapes[f"{layer}.attention.self.{name}.weight"] = (HIDDEN_SIZE, HIDDEN_SIZE)
+            shapes[f"{layer}.attention.self.{name}.bias"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.attention.output.dense.weight"] = (HIDDEN_SIZE, HIDDEN_SIZE)
+        shapes[f"{layer}.attention.output.dense.bias"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.attention.output.LayerNorm.weight"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.attention.output.LayerNorm.bias"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.intermediate.dense.weight"] = (INTERMEDIATE_SIZE, HIDDEN_SIZE)
+        shapes[f"{layer}.intermediate.dense.bias"] = (INTERMEDIATE_SIZE,)
+        shapes[f"{layer}.output.dense.weight"] = (HIDDEN_SIZE, INTERMEDIATE_SIZE)
+        shapes[f"{layer}.output.dense.bias"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.output.LayerNorm.weight"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.output.LayerNorm.bias"] = (HIDDEN_SIZE,)
+    return shapes
+
+
+def write_safetensors(path, shapes, seed=0):
+    rng = random.Random(seed)
+    header = {}
+    data = bytearray()
+    for name, shape in shapes.items():
+        count = 1
+        for dim in shape:
+            count *= dim
+        if name.endswith("LayerNorm.weight"):
+            values = array("f", [1.0] * count)
+        elif name.endswith("bias"):
+            values = array("f", [0.0] * count)
+        else:
+            values = array("f", (rng.gauss(0.0, 0.02) for _ in range(count)))
+        if sys.byteorder != "little":
+            values.byteswap()
+        start = len(data)
+        data += values.tobytes()
+        header[name] = {"dtype": "F32", "shape": list(shape), "data_offsets": [start, len(data)]}
+
+    header_bytes = json.dumps(header).encode("utf-8")
+    header_bytes += b" " * (-len(header_bytes) % 8)
+    with open(path, "wb") as f:
+        f.write(struct.pack("<Q", len(header_bytes)))
+        f.write(header_bytes)
+        f.write(data)
+
+
+def write_tiny_model(folder):
+    folder = Path(folder)
+    folder.mkdir(parents=True, exist_ok=True)
+    (folder / "config.json").write_text(json.dumps(config(), indent=2))
+    (folder / "tokenizer.json").write_text(json.dumps(tokenizer()))
+    write_safetensors(folder / "model.safetensors", tensors())
+    return folder
+
+
+if __name__ == "__main__":
+    if len(sys.argv) != 2:
+        sys.exit(f"usage: {sys.argv[0]} <output folder>")
+    print(write_tiny_model(sys.argv[1]))
@@ -0,0 +1,182 @@
+// abi.rs - ABI versioning and capability reporting for the C API
+use crate::embed_utils::registered_models;
+use serde::Serialize;
+use std::ffi::{CStr, CString};
+use std::ptr;
+use std::sync::Mutex;
+
+/// Incremented on incompatible changes to existing functions or structs
+pub const ABI_VERSION_MAJOR: u32 = 1;
+/// Incremented when functions or trailing struct fields are added
+pub const ABI_VERSION_MINOR: u32 = 5;
+
+/// `major << 16 | minor`, as returned by `embedder_abi_version()`
+pub const ABI_VERSION: u32 = (ABI_VERSION_MAJOR << 16) | ABI_VERSION_MINOR;
+
+/// Every function exported by this version of the library
+const FUNCTIONS: &[&str] = &[
+    "embedder_abi_version",
+    "embedder_batch_count",
+    "embedder_batch_data",
+    "embedder_batch_dimension",
+    "embedder_batch_free",
+    "embedder_batch_row",
+    "embedder_batch_status",
+    "embedder_embed",
+    "embedder_embed_batch",
+    "embedder_embed_batch_alloc",
+    "embedder_embed_batch_alloc_utf16",
+    "embedder_embed_batch_alloc_utf8",
+    "embedder_embed_batch_progress",
+    "embedder_embed_batch_utf16",
+    "embedder_embed_batch_utf8",
+    "embedder_embed_image",
+    "embedder_embed_utf16",
+    "embedder_embed_utf8",
+    "embedder_free",
+    "embedder_free_error",
+    "embedder_get_capabilities",
+    "embedder_get_dimension",
+    "embedder_get_last_error",
+    "embedder_get_max_tokens",
+    "embedder_get_model_info",
+    "embedder_init",
+    "embedder_init_from_buffers",
+    "embedder_init_from_files",
+    "embedder_init_with_options",
+    "embedder_options_init",
+    "embedder_rerank",
+    "embedder_set_log_callback",
+    "embedder_similarity",
+    "embedder_similarity_matrix",
+    "embedder_similarity_vectors",
+    "embedder_topk",
+    "embedder_version",
+];
+
+#[derive(Serialize)]
+struct Capabilities<'a> {
+    version: &'static str,
+    abi_version: u32,
+    abi_version_major: u32,
+    abi_version_minor: u32,
+    models: &'a [String],
+    pooling: &'static [&'static str],
+    output_dtypes: &'static [&'static str],
+    similarity_metrics: &'static [&'static str],
+    input_encodings: &'static [&'static str],
+    thread_safety: ThreadSafety,
+    functions: &'static [&'static str],
+}
+
+#[derive(Serialize)]
+struct ThreadSafety {
+    /// A handle may be used from any thread, but by one thread at a time
+    handle: &'static str,
+    /// Functions that do not take a handle may be called concurrently
+    global_functions: &'static str,
+}
+
+/// Last capabilities string and the registered models it lists
+static CAPABILITIES: Mutex<Option<(Vec<String>, &'static CStr)>> = Mutex::new(None);
+
+/// JSON description of the library, returned by `embedder_get_capabilities()`.
+///
+/// `models` lists the models registered at the time of the call. The string is
+/// rebuilt when that list changes; earlier strings are leaked rather than freed,
+/// so pointers returned before stay valid. Models are only ever registered a
+/// handful of times per process, which bounds the leak.
+pub fn capabilities() -> &'static CStr {
+    let models = registered_models();
+    let mut cached = CAPABILITIES.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((cached_models, json)) = cached.as_ref()
+        && *cached_models == models
+    {
+        return json;
+    }
+
+    let json: &'static CStr = Box::leak(capabilities_json(&models).into_boxed_c_str());
+    *cached = Some((models, json));
+    json
+}
+
+fn capabilities_json(models: &[String]) -> CString {
+    let capabilities = Capabilities {
+        version: env!("CARGO_PKG_VERSION"),
+        abi_version: ABI_VERSION,
+        abi_version_major: ABI_VERSION_MAJOR,
+        abi_version_minor: ABI_VERSION_MINOR,
+        models,
+        pooling: &["mean", "cls", "last_token"],
+        output_dtypes: &["f32"],
+        similarity_metrics: &["cosine", "dot"],
+        input_encodings: &["utf8_cstr", "utf8", "utf16"],
+        thread_safety: ThreadSafety {
+            handle: "externally_synchronized",
+            global_functions: "thread_safe",
+        },
+        functions: FUNCTIONS,
+    };
+    let json = serde_json::to_string(&capabilities).unwrap_or_default();
+    CString::new(json).unwrap_or_default()
+}
+
+/// A `#[repr(C)]` struct passed with its size in its first field.
+pub trait SizePrefixed: Copy + Default {
+    /// Offset of the end of each field, in field order, starting with
+    /// `struct_size`
+    const FIELD_ENDS: &'static [usize];
+}
+
+/// Number of bytes of a `struct_size`-byte struct that cover whole fields of
+/// `T`, or `None` if it does not even hold the size field.
+///
+/// A size ending partway through a field drops that field, so it keeps its
+/// default rather than a mix of the caller's and the default bytes.
+fn whole_fields_len<T: SizePrefixed>(struct_size: usize) -> Option<usize> {
+    T::FIELD_ENDS
+        .iter()
+        .copied()
+        .take_while(|&end| end <= struct_size)
+        .last()
+}
+
+/// Read a caller-provided struct whose first field is its `struct_size`.
+///
+/// Only the fields that lie wholly within the first `struct_size` bytes are
+/// read, so a caller built against an older header (smaller struct) gets
+/// defaults for the fields it does not know about, and fields added by a newer
+/// header are ignored. Returns `None` if `struct_size` is too small to hold the
+/// size field itself.
+///
+/// # Safety
+/// - ptr must point to at least `struct_size` readable bytes
+pub unsafe fn read_size_prefixed<T: SizePrefixed>(ptr: *const T) -> Option<T> {
+    let struct_size = unsafe { ptr.cast::<usize>().read_unaligned() };
+    let len = whole_fields_len::<T>(struct_size)?;
+
+    let mut value = T::default();
+    unsafe {
+        ptr::copy_nonoverlapping(ptr.cast::<u8>(), (&mut value as *mut T).cast::<u8>(), len)
+    };
+    Some(value)
+}
+
+/// Write the defaults of a size-prefixed struct into caller memory, without
+/// writing past the `struct_size` the caller set or into a field it only
+/// partly covers.
+///
+/// # Safety
+/// - ptr must point to at least `struct_size` writable bytes
+pub unsafe fn write_size_prefixed<T: SizePrefixed>(ptr: *mut T) -> bool {
+    let struct_size = unsafe { ptr.cast::<usize>().read_unaligned() };
+    let Some(len) = whole_fields_len::<T>(struct_size) else {
+        return false;
+    };
+
+    let value = T::default();
+    unsafe { ptr::copy_nonoverlapping((&value as *const T).cast::<u8>(), ptr.cast::<u8>(), len) };
+    // Keep the caller's size, which may be larger than ours
+    unsafe { ptr.cast::<usize>().write_unaligned(struct_size) };
+    true
+}
@@ -0,0 +1,556 @@
+// api.rs - stable Rust API, re-exported at the crate root
+//
+// Everything reachable from here follows semver: breaking changes only come
+// with a new major (0.x: minor) version. The types wrap the internal
+// `embed_utils` ones so candle and tokenizers never leak into signatures.
+use crate::embed_utils::{
+    self, Embed, EmbeddingError, Source, TextEmbedder, TextEmbedderBuilder, registered_models,
+};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+pub use crate::embed_utils::{
+    DistillOptions, DistilledModel, EmbedProgress, ModelInfo, MultiVectorEmbedding, PaddingSide,
+    Pooling, SUPPORTED_MODELS, SparseEmbedding, Task,
+};
+
+/// Errors returned by the Rust API
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// No model is registered under this name, see `registered_models`
+    #[error("Unknown model: '{0}'. Supported models: '{models}'", models = registered_models().join("', '"))]
+    UnknownModel(String),
+
+    /// A builder setting is out of range or model files are missing
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+
+    /// The requested device is not available in this build or on this machine
+    #[error("Device not available: {0}")]
+    Device(String),
+
+    /// Reading model files failed
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// Downloading model files from the HuggingFace Hub failed
+    #[error("Download error: {0}")]
+    Download(String),
+
+    /// The model `config.json` could not be parsed
+    #[error("Invalid model config: {0}")]
+    Config(String),
+
+    /// Loading or running the tokenizer failed
+    #[error("Tokenizer error: {0}")]
+    Tokenizer(String),
+
+    /// Loading the weights or running the model failed
+    #[error("Model error: {0}")]
+    Model(String),
+
+    /// An image could not be decoded
+    #[error("Image error: {0}")]
+    Image(String),
+
+    /// `EmbedProgress::is_cancelled` stopped a batch; `embeddings` holds the
+    /// texts embedded before that, in order
+    #[error("Embedding cancelled after {} texts", embeddings.len())]
+    Cancelled { embeddings: Vec<Vec<f32>> },
+}
+
+impl From<EmbeddingError> for Error {
+    fn from(err: EmbeddingError) -> Self {
+        match err {
+            EmbeddingError::Io(e) => Self::Io(e),
+            EmbeddingError::Serde(e) => Self::Config(e.to_string()),
+            #[cfg(not(target_arch = "wasm32"))]
+            EmbeddingError::HfHub(e) => Self::Download(e.to_string()),
+            EmbeddingError::Tokenizer(e) => Self::Tokenizer(e.to_string()),
+            EmbeddingError::Image(e) => Self::Image(e.to_string()),
+            EmbeddingError::Candle(e) => Self::Model(e.to_string()),
+            EmbeddingError::ModelTypeError(message) => Self::InvalidArgument(message),
+            EmbeddingError::Load(err) => err,
+            EmbeddingError::Cancelled { embeddings } => Self::Cancelled { embeddings },
+        }
+    }
+}
+
+// For `ModelLoader` implementations
+impl From<candle_core::Error> for Error {
+    fn from(err: candle_core::Error) -> Self {
+        Self::Model(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Config(err.to_string())
+    }
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Device the model runs on
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Device {
+    #[default]
+    Cpu,
+    /// CUDA GPU with the given ordinal; requires a CUDA-enabled candle build
+    Cuda(usize),
+    /// Metal GPU with the given ordinal (macOS)
+    Metal(usize),
+}
+
+impl Device {
+    fn to_candle(self) -> Result<candle_core::Device> {
+        let device = match self {
+            Self::Cpu => Ok(candle_core::Device::Cpu),
+            Self::Cuda(ordinal) => candle_core::Device::new_cuda(ordinal),
+            Self::Metal(ordinal) => candle_core::Device::new_metal(ordinal),
+        };
+        device.map_err(|e| Error::Device(format!("{:?}: {}", self, e)))
+    }
+}
+
+/// Type the model weights are loaded as; embeddings are always returned as f32.
+///
+/// BERT models (`mini_lm_v2`) only run in F32, and BF16 needs a GPU device.
+/// F16 halves the memory of large decoder models (`gte_qwen2`, `e5_mistral`)
+/// and runs on the CPU.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DType {
+    #[default]
+    F32,
+    F16,
+    BF16,
+}
+
+impl DType {
+    fn to_candle(self) -> candle_core::DType {
+        match self {
+            Self::F32 => candle_core::DType::F32,
+            Self::F16 => candle_core::DType::F16,
+            Self::BF16 => candle_core::DType::BF16,
+        }
+    }
+}
+
+/// Configures and loads an [`Embedder`].
+///
+/// Model files come from `BERT_MODEL_FOLDER` / `JINA_MODEL_FOLDER` or the
+/// HuggingFace Hub, unless they are given explicitly with the `*_path` or
+/// `*_bytes` methods.
+///
+/// ```no_run
+/// use rust_transformer::{DType, EmbedderBuilder, Pooling};
+///
+/// let mut embedder = EmbedderBuilder::new("mini_lm_v2")
+///     .dtype(DType::F32)
+///     .pooling(Pooling::Mean)
+///     .max_tokens(256)
+///     .batch_size(16)
+///     .build()?;
+/// let embeddings = embedder.embed_batch(&["Hello world", "Goodbye"])?;
+/// assert_eq!(embeddings[0].len(), embedder.dimension());
+/// # Ok::<(), rust_transformer::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct EmbedderBuilder {
+    model: String,
+    device: Device,
+    dtype: DType,
+    pooling: Option<Pooling>,
+    normalize: bool,
+    max_tokens: Option<usize>,
+    batch_size: usize,
+    task: Option<Task>,
+    query_prefix: Option<String>,
+    document_prefix: Option<String>,
+    tokenizer: Option<Source>,
+    config: Option<Source>,
+    weights: Option<Source>,
+}
+
+impl EmbedderBuilder {
+    /// Start from a model name, see [`registered_models`]
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            model: model.into(),
+            device: Device::Cpu,
+            dtype: DType::F32,
+            pooling: None,
+            normalize: true,
+            max_tokens: None,
+            batch_size: 32,
+            task: None,
+            query_prefix: None,
+            document_prefix: None,
+            tokenizer: None,
+            config: None,
+            weights: None,
+        }
+    }
+
+    /// Device to run on (default: CPU)
+    pub fn device(mut self, device: Device) -> Self {
+        self.device = device;
+        self
+    }
+
+    /// Type of the loaded weights (default: F32)
+    pub fn dtype(mut self, dtype: DType) -> Self {
+        self.dtype = dtype;
+        self
+    }
+
+    /// Pooling of token embeddings (default: the model's own, mean for the
+    /// bundled models)
+    pub fn pooling(mut self, pooling: Pooling) -> Self {
+        self.pooling = Some(pooling);
+        self
+    }
+
+    /// L2-normalize the embeddings (default: true)
+    pub fn normalize(mut self, normalize: bool) -> Self {
+        self.normalize = normalize;
+        self
+    }
+
+    /// Truncate texts to at most `max_tokens` tokens; capped at the model's
+    /// own limit (default: the model's limit)
+    pub fn max_tokens(mut self, max_tokens: usize) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// Number of texts per forward pass in [`Embedder::embed_batch`] (default: 32)
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Embed texts as queries or documents, for models trained with task
+    /// prefixes such as `nomic` (default: no prefix)
+    pub fn task(mut self, task: Task) -> Self {
+        self.task = Some(task);
+        self
+    }
+
+    /// Prefix of texts embedded as [`Task::Query`], replacing the model's
+    /// own; e.g. an instruction template for decoder models:
+    /// `"Instruct: Given a question, retrieve answers\nQuery: "`
+    pub fn query_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.query_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Prefix of texts embedded as [`Task::Document`], replacing the model's own
+    pub fn document_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.document_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Path of the `tokenizer.json` file
+    pub fn tokenizer_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.tokenizer = Some(Source::Path(path.into()));
+        self
+    }
+
+    /// Contents of a `tokenizer.json` file
+    pub fn tokenizer_bytes(mut self, bytes: Vec<u8>) -> Self {
+        self.tokenizer = Some(Source::Bytes(bytes));
+        self
+    }
+
+    /// Path of the `config.json` file; without one the default architecture
+    /// of the model is used
+    pub fn config_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config = Some(Source::Path(path.into()));
+        self
+    }
+
+    /// Contents of a `config.json` file
+    pub fn config_bytes(mut self, bytes: Vec<u8>) -> Self {
+        self.config = Some(Source::Bytes(bytes));
+        self
+    }
+
+    /// Path of the safetensors weights; the file is memory-mapped
+    pub fn weights_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.weights = Some(Source::Path(path.into()));
+        self
+    }
+
+    /// Contents of a safetensors weights file
+    pub fn weights_bytes(mut self, bytes: Vec<u8>) -> Self {
+        self.weights = Some(Source::Bytes(bytes));
+        self
+    }
+
+    /// Load the model. Embedders loaded by name, or from the same weights and
+    /// config files, share their weights with every other embedder of the same
+    /// model, dtype and device. Weights given as bytes are never shared.
+    pub fn build(self) -> Result<Embedder> {
+        if self.batch_size == 0 {
+            return Err(Error::InvalidArgument(
+                "batch_size must be greater than 0".into(),
+            ));
+        }
+        if self.max_tokens == Some(0) {
+            return Err(Error::InvalidArgument(
+                "max_tokens must be greater than 0".into(),
+            ));
+        }
+
+        let device = self.device.to_candle()?;
+        let model_type = embed_utils::model_type_on(&self.model, device)
+            .map_err(|_| Error::UnknownModel(self.model.clone()))?;
+
+        let mut embedder = match (self.tokenizer, self.config, self.weights) {
+            (None, None, None) => {
+                model_type.build_text_embedder_with_dtype(self.dtype.to_candle())?
+            }
+            (tokenizer, config, weights) => {
+                let mut builder =
+                    TextEmbedderBuilder::new(model_type).dtype(self.dtype.to_candle());
+                if let Some(tokenizer) = tokenizer {
+                    builder = builder.tokenizer(tokenizer);
+                }
+                if let Some(config) = config {
+                    builder = builder.config(config);
+                }
+                if let Some(weights) = weights {
+                    builder = builder.weights(weights);
+                }
+                builder.build()?
+            }
+        };
+
+        if let Some(pooling) = self.pooling {
+            embedder.set_pooling(pooling);
+        }
+        embedder.set_normalize(self.normalize);
+        embedder.set_task(self.task);
+        if let Some(prefix) = self.query_prefix {
+            embedder.set_query_prefix(Some(prefix));
+        }
+        if let Some(prefix) = self.document_prefix {
+            embedder.set_document_prefix(Some(prefix));
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            embedder.set_max_tokens(max_tokens)?;
+        }
+
+        Ok(Embedder {
+            embedder,
+            batch_size: self.batch_size,
+        })
+    }
+}
+
+/// A loaded text embedding model.
+///
+/// Cloning is cheap: clones share the model weights, so give each thread its
+/// own clone instead of sharing one behind a lock.
+#[derive(Clone)]
+pub struct Embedder {
+    embedder: TextEmbedder,
+    batch_size: usize,
+}
+
+impl Embedder {
+    /// Load `model` with the default settings, same as
+    /// `EmbedderBuilder::new(model).build()`
+    pub fn new(model: &str) -> Result<Self> {
+        EmbedderBuilder::new(model).build()
+    }
+
+    /// Start configuring an embedder for `model`
+    pub fn builder(model: impl Into<String>) -> EmbedderBuilder {
+        EmbedderBuilder::new(model)
+    }
+
+    /// Embed a single text
+    pub fn embed(&mut self, text: &str) -> Result<Vec<f32>> {
+        Ok(self.embedder.embed(text)?)
+    }
+
+    /// Embed several texts, `batch_size` at a time; one vector per text, in order
+    pub fn embed_batch<S: AsRef<str>>(&mut self, texts: &[S]) -> Result<Vec<Vec<f32>>> {
+        let texts = texts.iter().map(|text| text.as_ref().to_string()).collect();
+        Ok(Embed::embed(&mut self.embedder, texts, self.batch_size)?)
+    }
+
+    /// Same as `embed_batch`, calling `progress` after each batch and polling
+    /// it for cancellation before the next one.
+    ///
+    /// On cancellation returns `Error::Cancelled` with the embeddings of the
+    /// texts completed so far.
+    pub fn embed_batch_with_progress<S: AsRef<str>>(
+        &mut self,
+        texts: &[S],
+        progress: &mut dyn EmbedProgress,
+    ) -> Result<Vec<Vec<f32>>> {
+        let texts = texts.iter().map(|text| text.as_ref().to_string()).collect();
+        Ok(self
+            .embedder
+            .embed_with_progress(texts, self.batch_size, progress)?)
+    }
+
+    /// Embed images (the bytes of PNG or JPEG files), `batch_size` at a time,
+    /// into the vector space of the texts; only text–image models (`clip`,
+    /// `siglip`) embed images.
+    ///
+    /// ```no_run
+    /// use rust_transformer::{Embedder, similarity::cosine};
+    ///
+    /// let mut embedder = Embedder::new("clip")?;
+    /// let screenshot = std::fs::read("screenshot.png")?;
+    /// let images = embedder.embed_images(&[screenshot])?;
+    /// let query = embedder.embed("a login form")?;
+    /// println!("{}", cosine(&query, &images[0]));
+    /// # Ok::<(), rust_transformer::Error>(())
+    /// ```
+    pub fn embed_images<B: AsRef<[u8]>>(&self, images: &[B]) -> Result<Vec<Vec<f32>>> {
+        let images: Vec<&[u8]> = images.iter().map(AsRef::as_ref).collect();
+        Ok(self.embedder.embed_images(&images, self.batch_size)?)
+    }
+
+    /// Embed the PNG or JPEG file at `path`, see [`Embedder::embed_images`]
+    pub fn embed_image_file(&self, path: impl AsRef<Path>) -> Result<Vec<f32>> {
+        let image = std::fs::read(path)?;
+        Ok(self.embedder.embed_image(&image)?)
+    }
+
+    /// Score how relevant each of `documents` is to `query` with a
+    /// cross-encoder (`ms_marco_minilm`, `cross_encoder`), `batch_size` pairs
+    /// at a time. Returns one logit per document, in order; higher is more
+    /// relevant. Cross-encoders read the query and document together, so they
+    /// are slower than comparing embeddings and meant to rerank the best
+    /// results of an embedding search.
+    ///
+    /// ```no_run
+    /// use rust_transformer::Embedder;
+    ///
+    /// let mut reranker = Embedder::new("ms_marco_minilm")?;
+    /// let documents = ["Rust is a systems programming language", "The weather is nice"];
+    /// let scores = reranker.rerank("What is Rust?", &documents)?;
+    /// assert!(scores[0] > scores[1]);
+    /// # Ok::<(), rust_transformer::Error>(())
+    /// ```
+    pub fn rerank<S: AsRef<str>>(&mut self, query: &str, documents: &[S]) -> Result<Vec<f32>> {
+        let documents: Vec<&str> = documents.iter().map(AsRef::as_ref).collect();
+        Ok(self.embedder.rerank(query, &documents, self.batch_size)?)
+    }
+
+    /// Sparse lexical embeddings of `texts` with a SPLADE model (`splade`),
+    /// `batch_size` texts at a time: the weight of every vocabulary token
+    /// relevant to a text, by token ID. Compare them with
+    /// [`similarity::sparse_dot`](crate::similarity::sparse_dot), e.g. next
+    /// to dense embeddings for hybrid search.
+    ///
+    /// ```no_run
+    /// use rust_transformer::{Embedder, similarity::sparse_dot};
+    ///
+    /// let mut splade = Embedder::new("splade")?;
+    /// let query = splade.embed_sparse(&["what is rust?"])?;
+    /// let documents = splade.embed_sparse(&["Rust is a systems programming language"])?;
+    /// println!("{}", sparse_dot(&query[0], &documents[0]));
+    /// # Ok::<(), rust_transformer::Error>(())
+    /// ```
+    pub fn embed_sparse<S: AsRef<str>>(&mut self, texts: &[S]) -> Result<Vec<SparseEmbedding>> {
+        let texts = texts.iter().map(|text| text.as_ref().to_string()).collect();
+        Ok(self.embedder.embed_sparse(texts, self.batch_size)?)
+    }
+
+    /// Token embeddings of `texts` with a late-interaction model (`colbert`),
+    /// `batch_size` texts at a time: one normalized vector per token. Queries
+    /// are padded with `[MASK]` to `info().query_max_tokens` tokens, all of
+    /// which keep their vector; documents leave out padding and punctuation.
+    /// Score a query against documents with
+    /// [`similarity::max_sim`](crate::similarity::max_sim).
+    ///
+    /// ```no_run
+    /// use rust_transformer::{Embedder, Task, similarity::max_sim};
+    ///
+    /// let mut colbert = Embedder::new("colbert")?;
+    /// let query = colbert.embed_tokens(&["what is rust?"], Task::Query)?;
+    /// let documents =
+    ///     colbert.embed_tokens(&["Rust is a systems programming language"], Task::Document)?;
+    /// println!("{}", max_sim(&query[0], &documents[0]));
+    /// # Ok::<(), rust_transformer::Error>(())
+    /// ```
+    pub fn embed_tokens<S: AsRef<str>>(
+        &mut self,
+        texts: &[S],
+        task: Task,
+    ) -> Result<Vec<MultiVectorEmbedding>> {
+        let texts = texts.iter().map(|text| text.as_ref().to_string()).collect();
+        Ok(self.embedder.embed_tokens(texts, task, self.batch_size)?)
+    }
+
+    /// Metadata of the loaded model (dimension, max tokens, pooling, ...)
+    pub fn info(&self) -> &ModelInfo {
+        self.embedder.info()
+    }
+
+    /// Whether `other` uses the same copy of the model weights, as embedders
+    /// loaded by name for the same model, dtype and device do
+    pub fn shares_weights(&self, other: &Embedder) -> bool {
+        self.embedder.shares_weights(&other.embedder)
+    }
+
+    /// Dimension of the embedding vectors
+    pub fn dimension(&self) -> usize {
+        self.embedder.dimension()
+    }
+
+    /// Maximum number of tokens per text; longer texts are truncated
+    pub fn max_tokens(&self) -> usize {
+        self.embedder.max_tokens()
+    }
+
+    /// Number of texts per forward pass in `embed_batch`
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    /// Distill a static model from this one: embed every token of the
+    /// vocabulary, `batch_size` tokens at a time, and write the lookup table
+    /// to `folder` in the model2vec format, loadable as `model2vec`
+    /// (`MODEL2VEC_MODEL_FOLDER`). Its embeddings stay in the vector space of
+    /// this model, though less precise.
+    ///
+    /// ```no_run
+    /// use rust_transformer::{DistillOptions, Embedder};
+    ///
+    /// let mut embedder = Embedder::new("mini_lm_v2")?;
+    /// let options = DistillOptions::new().pca_dims(256).sif_coefficient(1e-4);
+    /// let distilled = embedder.distill("mini_lm_v2_static", &options)?;
+    /// assert_eq!(distilled.dimension, 256);
+    /// # Ok::<(), rust_transformer::Error>(())
+    /// ```
+    pub fn distill(
+        &mut self,
+        folder: impl AsRef<Path>,
+        options: &DistillOptions,
+    ) -> Result<DistilledModel> {
+        Ok(self
+            .embedder
+            .distill(folder.as_ref(), self.batch_size, options)?)
+    }
+}
+
+impl fmt::Debug for Embedder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Embedder")
+            .field("info", self.info())
+            .field("batch_size", &self.batch_size)
+            .finish()
+    }
+}
@@ -1,14 +1,51 @@
-use candle_core::{Device, Tensor};
+use candle_core::{DType, Device, Tensor};
 use candle_nn::VarBuilder;
-use candle_transformers::models::bert::{
-    BertModel, Config, HiddenAct, PositionEmbeddingType, DTYPE,
-};
+use candle_transformers::models::bert::{BertModel, DTYPE};
+use candle_transformers::models::xlm_roberta::XLMRobertaModel;
+#[cfg(not(target_arch = "wasm32"))]
 use hf_hub::{api::sync::Api, Repo, RepoType};
+use serde::Serialize;
+use std::fmt;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use thiserror::Error;
-use tokenizers::{PaddingParams, Tokenizer};
+use tokenizers::{PaddingDirection, PaddingParams, Tokenizer, TruncationParams};
+use tracing::{debug, info, trace, warn};
+use web_time::Instant;
+mod architectures;
+mod builder;
+mod clip_implementation;
+mod colbert_implementation;
+mod cross_encoder_implementation;
+mod decoder_implementation;
+mod distill;
+mod image;
 mod jina_implementation;
-use jina_implementation::{Config as JinaConfig, JinaModel};
+mod modernbert_implementation;
+mod multi_vector;
+mod nomic_implementation;
+mod registry;
+mod rerank;
+pub mod similarity;
+mod sparse;
+mod splade_implementation;
+mod static_implementation;
+pub(crate) use builder::Source;
+pub use builder::TextEmbedderBuilder;
+pub use architectures::{LoadedModel, ModelLoader, register_model, registered_models};
+use clip_implementation::{ClipModel, SiglipModel};
+use colbert_implementation::ColbertModel;
+use cross_encoder_implementation::CrossEncoderModel;
+use decoder_implementation::DecoderModel;
+pub use distill::{DistillOptions, DistilledModel};
+pub use image::ImagePreprocessing;
+use jina_implementation::JinaModel;
+use modernbert_implementation::ModernBertModel;
+pub use multi_vector::MultiVectorEmbedding;
+use nomic_implementation::NomicBertModel;
+pub use sparse::SparseEmbedding;
+use splade_implementation::SpladeModel;
+use static_implementation::StaticModel;
 
 #[derive(Error, Debug)]
 pub enum EmbeddingError {
@@ -18,89 +55,189 @@ pub enum EmbeddingError {
     #[error("Serde JSON error: {0}")]
     Serde(#[from] serde_json::Error),
 
+    #[cfg(not(target_arch = "wasm32"))]
     #[error("HF Hub error: {0}")]
     HfHub(#[from] hf_hub::api::sync::ApiError),
 
     #[error("Tokenizer error: {0}")]
     Tokenizer(#[from] tokenizers::Error),
 
+    #[error("Image error: {0}")]
+    Image(#[from] ::image::ImageError),
+
     #[error("Candle error: {0}")]
     Candle(#[from] candle_core::Error),
 
     #[error("Model type error: {0}")]
     ModelTypeError(String),
+
+    /// Returned by a `ModelLoader`
+    #[error(transparent)]
+    Load(#[from] crate::api::Error),
+
+    #[error("Embedding cancelled after {} texts", embeddings.len())]
+    Cancelled { embeddings: Vec<Vec<f32>> },
 }
 
+/// Cloning is cheap: the clone shares the model weights
+#[derive(Clone)]
 pub struct TextEmbedder {
-    model: Box<dyn EmbedModel>,
+    /// Shared with every other embedder of the same model, see `registry`
+    model: Arc<dyn EmbedModel>,
     tokenizer: Tokenizer,
+    info: ModelInfo,
+    task: Option<Task>,
+}
+
+/// Pooling strategy applied to the token embeddings of a sequence
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+#[serde(rename_all = "snake_case")]
+pub enum Pooling {
+    /// Average of all non-padding tokens
+    Mean,
+    /// Embedding of the first (`[CLS]`) token
+    Cls,
+    /// Embedding of the last non-padding token, for decoder models
+    LastToken,
+}
+
+/// Side of the sequences padding tokens are added to when texts of different
+/// lengths are embedded together
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+#[serde(rename_all = "snake_case")]
+pub enum PaddingSide {
+    #[default]
+    Right,
+    /// Decoder models, so the last token of every sequence is at the end
+    Left,
+}
+
+/// What the embedded texts are used for, for models trained with task
+/// prefixes (e.g. "search_query: " for nomic-embed-text)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Task {
+    /// Search queries, compared against documents
+    Query,
+    /// Documents or passages being searched
+    Document,
+}
+
+/// Static description of a loaded model, available before embedding anything
+#[derive(Clone, Debug, Serialize)]
+#[non_exhaustive]
+pub struct ModelInfo {
+    /// HuggingFace model ID, e.g. "sentence-transformers/all-MiniLM-L6-v2"
+    pub model_id: String,
+    pub revision: String,
+    /// Length of the embedding vectors
+    pub dimension: usize,
+    /// Texts are truncated to this many tokens
+    pub max_tokens: usize,
+    pub pooling: Pooling,
+    pub padding_side: PaddingSide,
+    /// Whether embeddings are L2-normalized
+    pub normalize: bool,
+    /// Type of the loaded weights ("f32", "f16", ...)
+    pub dtype: String,
+    /// Prepended to texts embedded as `Task::Query`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query_prefix: Option<String>,
+    /// Prepended to texts embedded as `Task::Document`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub document_prefix: Option<String>,
+    /// Side of the square images are resized to, for text–image models
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_size: Option<usize>,
+    /// Whether the model is a cross-encoder, which scores query–passage pairs
+    /// (`rerank`) instead of embedding texts on their own
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub cross_encoder: bool,
+    /// Whether the model is a sparse model, whose embeddings weigh every token
+    /// of the vocabulary (`embed_sparse`)
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub sparse: bool,
+    /// Whether the model is a late-interaction model, whose texts are compared
+    /// token by token (`embed_tokens`)
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub multi_vector: bool,
+    /// Length queries of a late-interaction model are padded to with `[MASK]`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query_max_tokens: Option<usize>,
 }
 
-#[derive(Clone, Debug)]
-pub enum ModelType {
-    Bert(Device),
-    Jina(Device),
+/// A registered model architecture and the device it runs on, see `architectures`
+#[derive(Clone)]
+pub struct ModelType {
+    name: String,
+    loader: Arc<dyn ModelLoader>,
+    device: Device,
+}
+
+impl fmt::Debug for ModelType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ModelType")
+            .field("name", &self.name)
+            .field("model_id", &self.loader.model_id())
+            .field("device", &self.device)
+            .finish()
+    }
 }
 
 impl ModelType {
     fn get_model_id(&self) -> String {
-        match &self {
-            Self::Bert(_) => "sentence-transformers/all-MiniLM-L6-v2".to_string(),
-            Self::Jina(_) => "jinaai/jina-embeddings-v2-base-en".to_string(),
-        }
+        self.loader.model_id().to_string()
     }
 
-    fn get_jina_model(&self, vb: VarBuilder) -> Result<JinaModel, EmbeddingError> {
-        match &self {
-            Self::Jina(_) => {
-                let config = JinaConfig::v2_base();
-                Ok(JinaModel::load(vb, &config)?)
-            }
-            _ => Err(EmbeddingError::ModelTypeError(
-                "Incorrect Model Type".into(),
-            )),
-        }
+    fn get_revision(&self) -> String {
+        self.loader.revision().to_string()
     }
 
-    fn get_bert_model(&self, vb: VarBuilder) -> Result<BertModel, EmbeddingError> {
-        match &self {
-            Self::Bert(_) => {
-                let config = Config {
-                    vocab_size: 30522,
-                    hidden_size: 384,
-                    num_hidden_layers: 6,
-                    num_attention_heads: 12,
-                    intermediate_size: 1536,
-                    hidden_act: HiddenAct::Gelu,
-                    hidden_dropout_prob: 0.1,
-                    max_position_embeddings: 512,
-                    type_vocab_size: 2,
-                    initializer_range: 0.02,
-                    layer_norm_eps: 1e-12,
-                    pad_token_id: 0,
-                    position_embedding_type: PositionEmbeddingType::Absolute,
-                    use_cache: true,
-                    classifier_dropout: None,
-                    model_type: Some("bert".to_string()),
-                };
-                Ok(BertModel::load(vb, &config)?)
-            }
-            _ => Err(EmbeddingError::ModelTypeError(
-                "Incorrect Model Type".into(),
-            )),
+    fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// Reject dtypes the device cannot run; model-specific limits are checked
+    /// by the loaders
+    fn check_dtype(&self, dtype: DType) -> Result<(), EmbeddingError> {
+        if dtype == DType::BF16 && self.device.is_cpu() {
+            return Err(EmbeddingError::ModelTypeError(
+                "bf16 is not supported on the CPU".into(),
+            ));
         }
+        Ok(())
     }
 
-    fn get_local_model_path(&self) -> Option<PathBuf> {
-        match &self {
-            Self::Bert(_) => std::env::var("BERT_MODEL_FOLDER").ok().map(PathBuf::from),
-            Self::Jina(_) => std::env::var("JINA_MODEL_FOLDER").ok().map(PathBuf::from),
+    fn model_info(&self, loaded: &LoadedModel, dtype: DType) -> ModelInfo {
+        ModelInfo {
+            model_id: self.get_model_id(),
+            revision: self.get_revision(),
+            dimension: loaded.dimension,
+            max_tokens: loaded.max_tokens,
+            pooling: loaded.pooling,
+            padding_side: loaded.padding_side,
+            normalize: true,
+            dtype: dtype.as_str().to_string(),
+            query_prefix: loaded.query_prefix.clone(),
+            document_prefix: loaded.document_prefix.clone(),
+            image_size: loaded.model.image_preprocessing().map(|p| p.size),
+            cross_encoder: loaded.model.is_cross_encoder(),
+            sparse: loaded.model.is_sparse(),
+            multi_vector: loaded.model.is_multi_vector(),
+            query_max_tokens: loaded.query_max_tokens,
         }
     }
 
-    fn load_from_local(&self, local_path: &Path) -> Result<(PathBuf, PathBuf), EmbeddingError> {
+    fn load_from_local(&self, local_path: &Path) -> Result<ModelFiles, EmbeddingError> {
+        debug!(path = %local_path.display(), "Loading model files from local folder");
         let tokenizer_path = local_path.join("tokenizer.json");
         let weights_path = local_path.join("model.safetensors");
+        let index_path = local_path.join(WEIGHTS_INDEX);
+        let config_path = local_path.join("config.json");
+        let pooling_config_path = local_path.join(POOLING_CONFIG);
+        let tokenizer_config_path = local_path.join("tokenizer_config.json");
 
         if !tokenizer_path.exists() {
             return Err(EmbeddingError::Io(std::io::Error::new(
@@ -109,51 +246,220 @@ impl ModelType {
             )));
         }
 
-        if !weights_path.exists() {
+        let weights = if weights_path.exists() {
+            vec![weights_path]
+        } else if index_path.exists() {
+            shard_names(&index_path)?
+                .into_iter()
+                .map(|shard| local_path.join(shard))
+                .collect()
+        } else {
             return Err(EmbeddingError::Io(std::io::Error::new(
                 std::io::ErrorKind::NotFound,
                 format!("Model weights file not found at {:?}", weights_path),
             )));
-        }
+        };
 
-        Ok((tokenizer_path, weights_path))
+        Ok(ModelFiles {
+            tokenizer: tokenizer_path,
+            weights,
+            config: config_path.exists().then_some(config_path),
+            pooling_config: pooling_config_path.exists().then_some(pooling_config_path),
+            tokenizer_config: tokenizer_config_path
+                .exists()
+                .then_some(tokenizer_config_path),
+        })
     }
 
-    fn load_from_hub(&self) -> Result<(PathBuf, PathBuf), EmbeddingError> {
+    #[cfg(target_arch = "wasm32")]
+    fn load_from_hub(&self) -> Result<ModelFiles, EmbeddingError> {
+        Err(EmbeddingError::ModelTypeError(
+            "Downloading models is not supported on wasm32, load them from bytes instead".into(),
+        ))
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_from_hub(&self) -> Result<ModelFiles, EmbeddingError> {
         let model_id = self.get_model_id();
-        let repo = Repo::new(model_id, RepoType::Model);
+        let repo = Repo::with_revision(model_id, RepoType::Model, self.get_revision());
+        info!(
+            model_id = %self.get_model_id(),
+            revision = %self.get_revision(),
+            "Fetching model files from HuggingFace Hub"
+        );
         let api = Api::new()?;
         let api = api.repo(repo);
         let tokenizer = api.get("tokenizer.json")?;
-        let weights = api.get("model.safetensors")?;
-        Ok((tokenizer, weights))
+        // Large models split their weights into shards listed in an index
+        let weights = match api.get("model.safetensors") {
+            Ok(weights) => vec![weights],
+            Err(e) => {
+                debug!(error = %e, "No model.safetensors, looking for shards");
+                let index = api.get(WEIGHTS_INDEX).map_err(|_| e)?;
+                shard_names(&index)?
+                    .iter()
+                    .map(|shard| api.get(shard))
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+        };
+        // Not every repository has one; the loader then uses its default architecture
+        let config = api
+            .get("config.json")
+            .inspect_err(|e| debug!(error = %e, "No config.json in the repository"))
+            .ok();
+        // Optional too, the loader's pooling and padding side are kept without them
+        let pooling_config = api
+            .get(POOLING_CONFIG)
+            .inspect_err(|e| debug!(error = %e, "No pooling config in the repository"))
+            .ok();
+        let tokenizer_config = api
+            .get("tokenizer_config.json")
+            .inspect_err(|e| debug!(error = %e, "No tokenizer_config.json in the repository"))
+            .ok();
+        debug!(
+            tokenizer = %tokenizer.display(),
+            weights = weights.len(),
+            "Model files available in hub cache"
+        );
+        Ok(ModelFiles {
+            tokenizer,
+            weights,
+            config,
+            pooling_config,
+            tokenizer_config,
+        })
     }
 
     pub fn build_text_embedder(&self) -> Result<TextEmbedder, EmbeddingError> {
-        let device = match &self {
-            Self::Bert(device) => device,
-            Self::Jina(device) => device,
-        };
+        self.build_text_embedder_with_dtype(DTYPE)
+    }
 
-        // Try to load from local path first, fall back to HuggingFace Hub
-        let (tokenizer_filename, weights_filename) = if let Some(local_path) = self.get_local_model_path() {
-            self.load_from_local(&local_path)?
-        } else {
-            self.load_from_hub()?
+    /// Same as `build_text_embedder`, loading the weights as `dtype`
+    pub fn build_text_embedder_with_dtype(
+        &self,
+        dtype: DType,
+    ) -> Result<TextEmbedder, EmbeddingError> {
+        let device = self.device();
+
+        self.check_dtype(dtype)?;
+        let key = registry::ModelKey {
+            name: self.name.clone(),
+            model_id: self.get_model_id(),
+            path: self.loader.local_path(),
+            config: None,
+            revision: self.get_revision(),
+            dtype: dtype.as_str().to_string(),
+            device: format!("{:?}", device.location()),
         };
+        let (model, mut tokenizer, info) = registry::get_or_load(key, || {
+            let started = Instant::now();
+            info!(model_id = %self.get_model_id(), "Loading model");
 
-        let tokenizer = Tokenizer::from_file(tokenizer_filename)?;
+            // Try to load from local path first, fall back to HuggingFace Hub
+            let files = if let Some(local_path) = self.loader.local_path() {
+                self.load_from_local(&local_path)
+            } else {
+                self.load_from_hub()
+            };
+            let files = files.inspect_err(|e| {
+                warn!(model_id = %self.get_model_id(), error = %e, "Failed to locate model files")
+            })?;
 
-        let vb =
-            unsafe { VarBuilder::from_mmaped_safetensors(&[weights_filename], DTYPE, device)? };
+            let tokenizer = Tokenizer::from_file(files.tokenizer)?;
+            let config = read_optional(files.config)?;
+            let pooling_config = read_optional(files.pooling_config)?;
+            let tokenizer_config = read_optional(files.tokenizer_config)?;
+            let vb = unsafe {
+                VarBuilder::from_mmaped_safetensors(&files.weights, dtype, device)?
+            };
+            let (model, info) = self.load_model(
+                vb,
+                config.as_deref(),
+                pooling_config.as_deref(),
+                tokenizer_config.as_deref(),
+            )?;
 
-        let model: Box<dyn EmbedModel> = match &self {
-            Self::Bert(_) => Box::new(self.get_bert_model(vb)?),
-            Self::Jina(_) => Box::new(self.get_jina_model(vb)?),
-        };
+            info!(
+                model_id = %self.get_model_id(),
+                elapsed_ms = started.elapsed().as_millis() as u64,
+                "Model loaded"
+            );
+            Ok((model, tokenizer, info))
+        })?;
+
+        // Truncation and padding are per embedder, so each gets its own tokenizer
+        tokenizer.with_truncation(Some(TruncationParams {
+            max_length: info.max_tokens,
+            ..Default::default()
+        }))?;
 
-        Ok(TextEmbedder { model, tokenizer })
+        Ok(TextEmbedder {
+            model,
+            tokenizer,
+            info,
+            task: None,
+        })
     }
+
+    /// Instantiate the model from its weights, using `config` (the contents of
+    /// a `config.json`) or the default architecture of the model type, and the
+    /// pooling and padding side of `pooling_config` and `tokenizer_config`
+    /// (`1_Pooling/config.json` and `tokenizer_config.json`) when given
+    fn load_model(
+        &self,
+        vb: VarBuilder,
+        config: Option<&[u8]>,
+        pooling_config: Option<&[u8]>,
+        tokenizer_config: Option<&[u8]>,
+    ) -> Result<(Box<dyn EmbedModel>, ModelInfo), EmbeddingError> {
+        let dtype = vb.dtype();
+        let loaded = self
+            .loader
+            .load(vb, config)?
+            .with_settings(pooling_config, tokenizer_config)?;
+        let info = self.model_info(&loaded, dtype);
+        Ok((loaded.model, info))
+    }
+}
+
+/// Paths of the files a model is loaded from
+struct ModelFiles {
+    tokenizer: PathBuf,
+    /// `model.safetensors`, or the shards listed in `WEIGHTS_INDEX`
+    weights: Vec<PathBuf>,
+    config: Option<PathBuf>,
+    /// `POOLING_CONFIG`, saved by sentence-transformers
+    pooling_config: Option<PathBuf>,
+    tokenizer_config: Option<PathBuf>,
+}
+
+/// Pooling module settings of sentence-transformers models
+const POOLING_CONFIG: &str = "1_Pooling/config.json";
+
+/// Contents of an optional model file
+fn read_optional(path: Option<PathBuf>) -> Result<Option<Vec<u8>>, EmbeddingError> {
+    let Some(path) = path else {
+        return Ok(None);
+    };
+    debug!(path = %path.display(), "Reading model file");
+    Ok(Some(std::fs::read(path)?))
+}
+
+/// Index of the weights of models split into several safetensors files
+const WEIGHTS_INDEX: &str = "model.safetensors.index.json";
+
+/// File names of the shards listed in a weights index, in order
+fn shard_names(index: &Path) -> Result<Vec<String>, EmbeddingError> {
+    #[derive(serde::Deserialize)]
+    struct WeightsIndex {
+        weight_map: std::collections::HashMap<String, String>,
+    }
+
+    let index: WeightsIndex = serde_json::from_slice(&std::fs::read(index)?)?;
+    let mut shards: Vec<String> = index.weight_map.into_values().collect();
+    shards.sort();
+    shards.dedup();
+    Ok(shards)
 }
 
 pub trait Embed {
@@ -161,17 +467,100 @@ pub trait Embed {
         &mut self,
         column: Vec<String>,
         batch_size: usize,
+    ) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        self.embed_with_progress(column, batch_size, &mut NoProgress)
+    }
+
+    /// Same as `embed`, reporting progress and polling for cancellation between batches.
+    /// On cancellation returns `EmbeddingError::Cancelled` with the embeddings completed so far.
+    fn embed_with_progress(
+        &mut self,
+        column: Vec<String>,
+        batch_size: usize,
+        progress: &mut dyn EmbedProgress,
     ) -> Result<Vec<Vec<f32>>, EmbeddingError>;
 }
 
+/// Progress and cancellation hook for `Embedder::embed_batch_with_progress`
+/// and `Embed::embed_with_progress`
+pub trait EmbedProgress {
+    /// Called after each batch with the number of texts embedded so far
+    fn on_progress(&mut self, done: usize, total: usize);
+
+    /// Polled before each batch; returning true stops embedding
+    fn is_cancelled(&mut self) -> bool;
+}
+
+struct NoProgress;
+
+impl EmbedProgress for NoProgress {
+    fn on_progress(&mut self, _done: usize, _total: usize) {}
+
+    fn is_cancelled(&mut self) -> bool {
+        false
+    }
+}
+
+/// A transformer encoder producing one embedding per token
 pub trait EmbedModel: Send + Sync {
     fn device(&self) -> &Device;
+
+    /// Token embeddings of shape `(batch, tokens, dimension)` for `input_ids`
+    /// and `token_type_ids` of shape `(batch, tokens)`; `attention_mask` is 1
+    /// for real tokens and 0 for padding
     fn forward(
         &self,
         input_ids: &Tensor,
         token_type_ids: &Tensor,
         attention_mask: Option<&Tensor>,
-    ) -> Result<Tensor, EmbeddingError>;
+    ) -> candle_core::Result<Tensor>;
+
+    /// Row-major `(vocab, dimension)` table of static models, which embed a
+    /// text as the mean of its token rows instead of running `forward`
+    fn lookup_table(&self) -> Option<&[f32]> {
+        None
+    }
+
+    /// How images are turned into pixel values, for text–image models that
+    /// embed images into the space of their texts with `forward_images`
+    fn image_preprocessing(&self) -> Option<&ImagePreprocessing> {
+        None
+    }
+
+    /// Image embeddings of shape `(batch, dimension)` for pixel values of
+    /// shape `(batch, 3, size, size)`
+    fn forward_images(&self, _pixel_values: &Tensor) -> candle_core::Result<Tensor> {
+        candle_core::bail!("the model does not embed images")
+    }
+
+    /// Whether the model is a cross-encoder, scoring query–passage pairs with
+    /// `forward_pairs`
+    fn is_cross_encoder(&self) -> bool {
+        false
+    }
+
+    /// Relevance scores of shape `(batch,)` for query–passage pairs encoded
+    /// as one sequence each; `token_type_ids` are 1 for the passage tokens
+    fn forward_pairs(
+        &self,
+        _input_ids: &Tensor,
+        _token_type_ids: &Tensor,
+        _attention_mask: Option<&Tensor>,
+    ) -> candle_core::Result<Tensor> {
+        candle_core::bail!("the model does not score text pairs")
+    }
+
+    /// Whether `forward` returns one weight per vocabulary token and text,
+    /// mostly 0, instead of dense embeddings
+    fn is_sparse(&self) -> bool {
+        false
+    }
+
+    /// Whether `forward` returns token embeddings trained to be compared one
+    /// by one with MaxSim rather than pooled
+    fn is_multi_vector(&self) -> bool {
+        false
+    }
 }
 
 impl EmbedModel for BertModel {
@@ -184,8 +573,8 @@ impl EmbedModel for BertModel {
         input_ids: &Tensor,
         token_type_ids: &Tensor,
         attention_mask: Option<&Tensor>,
-    ) -> Result<Tensor, EmbeddingError> {
-        Ok(self.forward(input_ids, token_type_ids, attention_mask)?)
+    ) -> candle_core::Result<Tensor> {
+        self.forward(input_ids, token_type_ids, attention_mask)
     }
 }
 
@@ -199,74 +588,452 @@ impl EmbedModel for JinaModel {
         input_ids: &Tensor,
         token_type_ids: &Tensor,
         attention_mask: Option<&Tensor>,
-    ) -> Result<Tensor, EmbeddingError> {
-        Ok(self.forward(input_ids, token_type_ids, attention_mask)?)
+    ) -> candle_core::Result<Tensor> {
+        self.forward(input_ids, token_type_ids, attention_mask)
+    }
+}
+
+impl EmbedModel for NomicBertModel {
+    fn device(&self) -> &Device {
+        &self.device
+    }
+
+    fn forward(
+        &self,
+        input_ids: &Tensor,
+        token_type_ids: &Tensor,
+        attention_mask: Option<&Tensor>,
+    ) -> candle_core::Result<Tensor> {
+        self.forward(input_ids, token_type_ids, attention_mask)
+    }
+}
+
+impl EmbedModel for ModernBertModel {
+    fn device(&self) -> &Device {
+        &self.device
+    }
+
+    fn forward(
+        &self,
+        input_ids: &Tensor,
+        token_type_ids: &Tensor,
+        attention_mask: Option<&Tensor>,
+    ) -> candle_core::Result<Tensor> {
+        self.forward(input_ids, token_type_ids, attention_mask)
+    }
+}
+
+impl EmbedModel for DecoderModel {
+    fn device(&self) -> &Device {
+        &self.device
+    }
+
+    fn forward(
+        &self,
+        input_ids: &Tensor,
+        token_type_ids: &Tensor,
+        attention_mask: Option<&Tensor>,
+    ) -> candle_core::Result<Tensor> {
+        self.forward(input_ids, token_type_ids, attention_mask)
+    }
+}
+
+impl EmbedModel for StaticModel {
+    fn device(&self) -> &Device {
+        &self.device
+    }
+
+    fn forward(
+        &self,
+        input_ids: &Tensor,
+        _token_type_ids: &Tensor,
+        _attention_mask: Option<&Tensor>,
+    ) -> candle_core::Result<Tensor> {
+        self.forward(input_ids)
+    }
+
+    fn lookup_table(&self) -> Option<&[f32]> {
+        Some(self.table())
+    }
+}
+
+impl EmbedModel for ClipModel {
+    fn device(&self) -> &Device {
+        &self.device
+    }
+
+    fn forward(
+        &self,
+        input_ids: &Tensor,
+        _token_type_ids: &Tensor,
+        _attention_mask: Option<&Tensor>,
+    ) -> candle_core::Result<Tensor> {
+        self.forward(input_ids)
+    }
+
+    fn image_preprocessing(&self) -> Option<&ImagePreprocessing> {
+        Some(self.preprocessing())
+    }
+
+    fn forward_images(&self, pixel_values: &Tensor) -> candle_core::Result<Tensor> {
+        self.forward_images(pixel_values)
+    }
+}
+
+impl EmbedModel for ColbertModel {
+    fn device(&self) -> &Device {
+        &self.device
+    }
+
+    fn forward(
+        &self,
+        input_ids: &Tensor,
+        token_type_ids: &Tensor,
+        attention_mask: Option<&Tensor>,
+    ) -> candle_core::Result<Tensor> {
+        self.forward(input_ids, token_type_ids, attention_mask)
+    }
+
+    fn is_multi_vector(&self) -> bool {
+        true
+    }
+}
+
+impl EmbedModel for CrossEncoderModel {
+    fn device(&self) -> &Device {
+        &self.device
+    }
+
+    fn forward(
+        &self,
+        input_ids: &Tensor,
+        token_type_ids: &Tensor,
+        attention_mask: Option<&Tensor>,
+    ) -> candle_core::Result<Tensor> {
+        self.forward(input_ids, token_type_ids, attention_mask)
+    }
+
+    fn is_cross_encoder(&self) -> bool {
+        true
+    }
+
+    fn forward_pairs(
+        &self,
+        input_ids: &Tensor,
+        token_type_ids: &Tensor,
+        attention_mask: Option<&Tensor>,
+    ) -> candle_core::Result<Tensor> {
+        self.forward_pairs(input_ids, token_type_ids, attention_mask)
+    }
+}
+
+impl EmbedModel for SpladeModel {
+    fn device(&self) -> &Device {
+        &self.device
+    }
+
+    fn forward(
+        &self,
+        input_ids: &Tensor,
+        token_type_ids: &Tensor,
+        attention_mask: Option<&Tensor>,
+    ) -> candle_core::Result<Tensor> {
+        self.forward(input_ids, token_type_ids, attention_mask)
+    }
+
+    fn is_sparse(&self) -> bool {
+        true
+    }
+}
+
+impl EmbedModel for SiglipModel {
+    fn device(&self) -> &Device {
+        &self.device
+    }
+
+    fn forward(
+        &self,
+        input_ids: &Tensor,
+        _token_type_ids: &Tensor,
+        attention_mask: Option<&Tensor>,
+    ) -> candle_core::Result<Tensor> {
+        self.forward(input_ids, attention_mask)
+    }
+
+    fn image_preprocessing(&self) -> Option<&ImagePreprocessing> {
+        Some(self.preprocessing())
+    }
+
+    fn forward_images(&self, pixel_values: &Tensor) -> candle_core::Result<Tensor> {
+        self.forward_images(pixel_values)
+    }
+}
+
+/// candle's XLM-RoBERTa keeps no device and always needs an attention mask
+pub struct XlmRobertaModel {
+    model: XLMRobertaModel,
+    device: Device,
+}
+
+impl XlmRobertaModel {
+    pub fn new(model: XLMRobertaModel, device: Device) -> Self {
+        Self { model, device }
+    }
+}
+
+impl EmbedModel for XlmRobertaModel {
+    fn device(&self) -> &Device {
+        &self.device
+    }
+
+    fn forward(
+        &self,
+        input_ids: &Tensor,
+        token_type_ids: &Tensor,
+        attention_mask: Option<&Tensor>,
+    ) -> candle_core::Result<Tensor> {
+        let attention_mask = match attention_mask {
+            Some(mask) => mask.clone(),
+            None => input_ids.ones_like()?,
+        };
+        self.model
+            .forward(input_ids, &attention_mask, token_type_ids, None, None, None)
     }
 }
 
 impl Embed for TextEmbedder {
-    fn embed(
+    fn embed_with_progress(
         &mut self,
         column: Vec<String>,
         batch_size: usize,
+        progress: &mut dyn EmbedProgress,
     ) -> Result<Vec<Vec<f32>>, EmbeddingError> {
-        let device = self.model.device();
+        if self.info.pooling == Pooling::Mean
+            && let Some(table) = self.model.lookup_table()
+        {
+            return self.embed_static(table, column, batch_size, progress);
+        }
+        self.configure_padding();
+
+        // chunk based approach
+        let mut all_embeddings = Vec::with_capacity(column.len());
+        let started = Instant::now();
+        debug!(texts = column.len(), batch_size, "Embedding texts");
+
+        for chunk in column.chunks(batch_size) {
+            if progress.is_cancelled() {
+                info!(
+                    done = all_embeddings.len(),
+                    total = column.len(),
+                    "Embedding cancelled"
+                );
+                return Err(EmbeddingError::Cancelled {
+                    embeddings: all_embeddings,
+                });
+            }
+
+            let chunk = match self.prefix() {
+                Some(prefix) => chunk.iter().map(|text| format!("{prefix}{text}")).collect(),
+                None => chunk.to_vec(),
+            };
+            let tokens = self.tokenizer.encode_batch(chunk, true)?;
+
+            let chunk_embeddings = self.embed_encodings(&tokens)?;
+            all_embeddings.extend(chunk_embeddings);
+            progress.on_progress(all_embeddings.len(), column.len());
+        }
+        debug!(
+            texts = column.len(),
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "Embedding finished"
+        );
+        Ok(all_embeddings)
+    }
+}
 
-        // padding
+impl TextEmbedder {
+    /// Pad batches to their longest text on the model's padding side
+    fn configure_padding(&mut self) {
+        let direction = match self.info.padding_side {
+            PaddingSide::Right => PaddingDirection::Right,
+            PaddingSide::Left => PaddingDirection::Left,
+        };
         if let Some(pp) = self.tokenizer.get_padding_mut() {
-            pp.strategy = tokenizers::PaddingStrategy::BatchLongest
+            pp.strategy = tokenizers::PaddingStrategy::BatchLongest;
+            pp.direction = direction;
         } else {
+            // "[PAD]" for WordPiece vocabularies, "<pad>" for sentencepiece ones
+            let (pad_id, pad_token) = ["[PAD]", "<pad>"]
+                .into_iter()
+                .find_map(|token| Some((self.tokenizer.token_to_id(token)?, token)))
+                .unwrap_or((0, "[PAD]"));
             let pp = PaddingParams {
                 strategy: tokenizers::PaddingStrategy::BatchLongest,
+                direction,
+                pad_id,
+                pad_token: pad_token.to_string(),
                 ..Default::default()
             };
             self.tokenizer.with_padding(Some(pp));
         }
+    }
 
-        // chunk based approach
+    /// Pooled (and, if enabled, normalized) embeddings of a batch of padded
+    /// encodings
+    fn embed_encodings(
+        &self,
+        tokens: &[tokenizers::Encoding],
+    ) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        let (embeddings, attention_mask) = self.token_embeddings(tokens)?;
+
+        let pooled_embeddings = match self.info.pooling {
+            Pooling::Mean => {
+                let attention_mask = attention_mask.to_dtype(DType::F32)?;
+                let masked_embeddings = embeddings.broadcast_mul(&attention_mask.unsqueeze(2)?)?;
+                let sum_embeddings = masked_embeddings.sum(1)?;
+                let real_token_counts = attention_mask.sum(1)?.maximum(1e-8)?;
+                sum_embeddings.broadcast_div(&real_token_counts.unsqueeze(1)?)?
+            }
+            Pooling::Cls => embeddings.narrow(1, 0, 1)?.squeeze(1)?,
+            Pooling::LastToken => last_token(&embeddings, tokens)?,
+        };
+        let output_embeddings = if self.info.normalize {
+            normalize_l2(&pooled_embeddings)?
+        } else {
+            pooled_embeddings
+        };
+        Ok(output_embeddings.to_vec2()?)
+    }
+
+    /// Token embeddings `(batch, tokens, dimension)` of a batch of padded
+    /// encodings, before pooling, and their `(batch, tokens)` attention mask
+    fn token_embeddings(
+        &self,
+        tokens: &[tokenizers::Encoding],
+    ) -> Result<(Tensor, Tensor), EmbeddingError> {
+        let device = self.model.device();
+        let token_ids = stack_encodings(tokens, device, tokenizers::Encoding::get_ids)?;
+        let attention_mask =
+            stack_encodings(tokens, device, tokenizers::Encoding::get_attention_mask)?;
+        // All 0 for single texts; pairs mark their second text with 1
+        let token_type_ids = stack_encodings(tokens, device, tokenizers::Encoding::get_type_ids)?;
+
+        trace!(shape = ?token_ids.dims(), "Running model forward pass");
+        let embeddings = self
+            .model
+            .forward(&token_ids, &token_type_ids, Some(&attention_mask))?
+            .to_dtype(DType::F32)?;
+        Ok((embeddings, attention_mask))
+    }
+
+    /// `embed_with_progress` for static models: the mean of the `table` rows
+    /// of the tokens of each text, leaving out special and unknown tokens like
+    /// model2vec does. Texts are neither padded nor run through the model.
+    fn embed_static(
+        &self,
+        table: &[f32],
+        column: Vec<String>,
+        batch_size: usize,
+        progress: &mut dyn EmbedProgress,
+    ) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        let dimension = self.info.dimension;
+        let unknown = ["[UNK]", "<unk>"]
+            .into_iter()
+            .find_map(|token| self.tokenizer.token_to_id(token));
         let mut all_embeddings = Vec::with_capacity(column.len());
+        let started = Instant::now();
+        debug!(texts = column.len(), batch_size, "Embedding by lookup");
 
         for chunk in column.chunks(batch_size) {
-            let tokens = self.tokenizer.encode_batch(chunk.to_vec(), true)?;
-
-            let token_ids = tokens
-                .iter()
-                .map(|tokens| {
-                    let tokens = tokens.get_ids().to_vec();
-                    Ok(Tensor::new(tokens.as_slice(), device)?)
-                })
-                .collect::<Result<Vec<_>, EmbeddingError>>()?;
+            if progress.is_cancelled() {
+                info!(
+                    done = all_embeddings.len(),
+                    total = column.len(),
+                    "Embedding cancelled"
+                );
+                return Err(EmbeddingError::Cancelled {
+                    embeddings: all_embeddings,
+                });
+            }
 
-            let attention_mask = tokens
-                .iter()
-                .map(|tokens| {
-                    let tokens = tokens.get_attention_mask().to_vec();
-                    Ok(Tensor::new(tokens.as_slice(), device)?)
-                })
-                .collect::<Result<Vec<_>, EmbeddingError>>()?;
-
-            let token_ids = Tensor::stack(&token_ids, 0)?;
-            let attention_mask = Tensor::stack(&attention_mask, 0)?;
-            let token_type_ids = token_ids.zeros_like()?;
-
-            let embeddings =
-                self.model
-                    .forward(&token_ids, &token_type_ids, Some(&attention_mask))?;
-
-            let attention_mask = attention_mask.to_dtype(candle_core::DType::F32)?;
-            let masked_embeddings = embeddings.broadcast_mul(&attention_mask.unsqueeze(2)?)?;
-            let sum_embeddings = masked_embeddings.sum(1)?;
-            let real_token_counts = attention_mask.sum(1)?.maximum(1e-8)?;
-            let mean_embeddings = sum_embeddings.broadcast_div(&real_token_counts.unsqueeze(1)?)?;
-            let normalized_embeddings = normalize_l2(&mean_embeddings)?;
-
-            let chunk_embeddings = normalized_embeddings.to_vec2()?;
-            all_embeddings.extend(chunk_embeddings);
+            let chunk = match self.prefix() {
+                Some(prefix) => chunk.iter().map(|text| format!("{prefix}{text}")).collect(),
+                None => chunk.to_vec(),
+            };
+            for tokens in self.tokenizer.encode_batch_fast(chunk, false)? {
+                let mut embedding = vec![0f32; dimension];
+                let mut count = 0;
+                for (&id, &mask) in tokens.get_ids().iter().zip(tokens.get_attention_mask()) {
+                    let start = id as usize * dimension;
+                    let Some(row) = table.get(start..start + dimension) else {
+                        continue;
+                    };
+                    if mask == 0 || Some(id) == unknown {
+                        continue;
+                    }
+                    embedding.iter_mut().zip(row).for_each(|(e, r)| *e += r);
+                    count += 1;
+                }
+                let mut scale = 1.0 / count.max(1) as f32;
+                if self.info.normalize {
+                    let norm = embedding.iter().map(|e| e * e).sum::<f32>().sqrt() * scale;
+                    if norm > 0.0 {
+                        scale /= norm;
+                    }
+                }
+                embedding.iter_mut().for_each(|e| *e *= scale);
+                all_embeddings.push(embedding);
+            }
+            progress.on_progress(all_embeddings.len(), column.len());
         }
+        debug!(
+            texts = column.len(),
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "Embedding finished"
+        );
         Ok(all_embeddings)
     }
 }
 
+/// `(batch, tokens)` tensor of one field (IDs, attention mask, ...) of a
+/// batch of padded encodings
+fn stack_encodings(
+    tokens: &[tokenizers::Encoding],
+    device: &Device,
+    field: fn(&tokenizers::Encoding) -> &[u32],
+) -> Result<Tensor, EmbeddingError> {
+    let rows = tokens
+        .iter()
+        .map(|tokens| Tensor::new(field(tokens), device))
+        .collect::<candle_core::Result<Vec<_>>>()?;
+    Ok(Tensor::stack(&rows, 0)?)
+}
+
+/// Embedding of the last non-padding token of each sequence, whichever side
+/// the padding is on
+fn last_token(
+    embeddings: &Tensor,
+    tokens: &[tokenizers::Encoding],
+) -> Result<Tensor, EmbeddingError> {
+    let (b_size, seq_len, _) = embeddings.dims3()?;
+    let indices = tokens
+        .iter()
+        .enumerate()
+        .map(|(row, tokens)| {
+            let last = tokens
+                .get_attention_mask()
+                .iter()
+                .rposition(|&m| m != 0)
+                .unwrap_or(0);
+            (row * seq_len + last) as u32
+        })
+        .collect::<Vec<_>>();
+    let indices = Tensor::new(indices.as_slice(), embeddings.device())?;
+    Ok(embeddings
+        .reshape((b_size * seq_len, ()))?
+        .index_select(&indices, 0)?)
+}
+
 fn normalize_l2(v: &Tensor) -> Result<Tensor, EmbeddingError> {
     Ok(v.broadcast_div(&v.sqr()?.sum_keepdim(1)?.sqrt()?)?)
 }
@@ -277,21 +1044,125 @@ impl TextEmbedder {
         let embeddings = Embed::embed(self, vec![text.to_string()], 1)?;
         Ok(embeddings.into_iter().next().unwrap_or_default())
     }
+
+    /// Metadata of the loaded model (dimension, max tokens, pooling, ...)
+    pub fn info(&self) -> &ModelInfo {
+        &self.info
+    }
+
+    /// Whether `other` uses the same copy of the model weights
+    pub fn shares_weights(&self, other: &TextEmbedder) -> bool {
+        Arc::ptr_eq(&self.model, &other.model)
+    }
+
+    /// Dimension of the embedding vectors produced by this model
+    pub fn dimension(&self) -> usize {
+        self.info.dimension
+    }
+
+    /// Maximum number of tokens the model accepts per text
+    pub fn max_tokens(&self) -> usize {
+        self.info.max_tokens
+    }
+
+    /// Enable or disable L2 normalization of the output embeddings
+    pub fn set_normalize(&mut self, normalize: bool) {
+        self.info.normalize = normalize;
+    }
+
+    /// Change how token embeddings are pooled into one vector per text. Only
+    /// affects this embedder, not others sharing the same weights.
+    pub fn set_pooling(&mut self, pooling: Pooling) {
+        self.info.pooling = pooling;
+    }
+
+    /// Embed texts as queries or documents, prepending the model's prefix for
+    /// that task; no effect on models without task prefixes
+    pub fn set_task(&mut self, task: Option<Task>) {
+        self.task = task;
+    }
+
+    /// Replace the model's query prefix, e.g. with an instruction template
+    /// for another retrieval task
+    pub fn set_query_prefix(&mut self, prefix: Option<String>) {
+        self.info.query_prefix = prefix;
+    }
+
+    /// Replace the model's document prefix
+    pub fn set_document_prefix(&mut self, prefix: Option<String>) {
+        self.info.document_prefix = prefix;
+    }
+
+    /// Prefix prepended to every text for the current task
+    fn prefix(&self) -> Option<&str> {
+        self.task_prefix(self.task?)
+    }
+
+    /// Prefix prepended to texts embedded for `task`
+    fn task_prefix(&self, task: Task) -> Option<&str> {
+        match task {
+            Task::Query => self.info.query_prefix.as_deref(),
+            Task::Document => self.info.document_prefix.as_deref(),
+        }
+    }
+
+    /// Truncate texts to at most `max_tokens` tokens; the value is capped at the
+    /// model's own limit
+    pub fn set_max_tokens(&mut self, max_tokens: usize) -> Result<(), EmbeddingError> {
+        let max_tokens = max_tokens.min(self.info.max_tokens);
+        self.tokenizer.with_truncation(Some(TruncationParams {
+            max_length: max_tokens,
+            ..Default::default()
+        }))?;
+        self.info.max_tokens = max_tokens;
+        Ok(())
+    }
+}
+
+/// Built-in model names; `registered_models` also lists models registered by
+/// other crates
+pub const SUPPORTED_MODELS: &[&str] = &[
+    "mini_lm_v2",
+    "jina",
+    "xlm_roberta",
+    "multilingual_e5_base",
+    "paraphrase_multilingual",
+    "nomic",
+    "modernbert",
+    "gte_qwen2",
+    "e5_mistral",
+    "model2vec",
+    "potion_base_8m",
+    "clip",
+    "siglip",
+    "cross_encoder",
+    "ms_marco_minilm",
+    "splade",
+    "colbert",
+];
+
+/// Resolve a model name to the model type running on the CPU
+pub fn model_type(model_name: &str) -> Result<ModelType, EmbeddingError> {
+    model_type_on(model_name, Device::Cpu)
+}
+
+/// Resolve a registered model name to the model type running on `device`
+pub fn model_type_on(model_name: &str, device: Device) -> Result<ModelType, EmbeddingError> {
+    match architectures::lookup(model_name) {
+        Some((name, loader)) => Ok(ModelType {
+            name,
+            loader,
+            device,
+        }),
+        None => Err(EmbeddingError::ModelTypeError(format!(
+            "Unknown model: '{}'. Supported models: '{}'",
+            model_name,
+            registered_models().join("', '")
+        ))),
+    }
 }
 
 /// Factory function to build a TextEmbedder from a model name string
 pub fn build_text_embedder(model_name: &str) -> Result<TextEmbedder, EmbeddingError> {
-    let device = Device::Cpu;
-    
-    let model_type = match model_name.to_lowercase().as_str() {
-        "mini_lm_v2" | "mini_lm" | "bert" => ModelType::Bert(device),
-        "jina" => ModelType::Jina(device),
-        _ => {
-            return Err(EmbeddingError::ModelTypeError(
-                format!("Unknown model: '{}'. Supported models: 'mini_lm_v2', 'jina'", model_name)
-            ));
-        }
-    };
-    
-    model_type.build_text_embedder()
+    model_type(model_name)?.build_text_embedder()
 }
\ No newline at end of file
@@ -0,0 +1,810 @@
+// Model architectures, looked up by name when building an embedder.
+//
+// The built-in models are registered on first use. Other crates add their own
+// with `register_model`, after which the name is accepted everywhere a model
+// name is: `EmbedderBuilder`, the CLI `--model` flag, the C API, the Python and
+// SQLite extensions.
+
+use super::clip_implementation::{ClipConfig, ClipModel, SiglipConfig, SiglipModel};
+use super::colbert_implementation::ColbertModel;
+use super::cross_encoder_implementation::{Config as CrossEncoderConfig, CrossEncoderModel};
+use super::decoder_implementation::{Config as DecoderConfig, DecoderModel};
+use super::jina_implementation::{Config as JinaConfig, JinaModel};
+use super::modernbert_implementation::{Config as ModernBertConfig, ModernBertModel};
+use super::nomic_implementation::{Config as NomicConfig, NomicBertModel};
+use super::splade_implementation::SpladeModel;
+use super::static_implementation::StaticModel;
+use super::{EmbedModel, PaddingSide, Pooling, XlmRobertaModel};
+use crate::api::Error;
+use candle_core::DType;
+use candle_nn::VarBuilder;
+use candle_transformers::models::bert::{BertModel, Config, HiddenAct, PositionEmbeddingType};
+use candle_transformers::models::xlm_roberta::{Config as XlmRobertaConfig, XLMRobertaModel};
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use tracing::info;
+
+/// A loaded model and the shape of its output
+#[non_exhaustive]
+pub struct LoadedModel {
+    pub model: Box<dyn EmbedModel>,
+    /// Length of the embedding vectors
+    pub dimension: usize,
+    /// Maximum number of tokens per text
+    pub max_tokens: usize,
+    /// Prepended to queries, for models trained with task prefixes
+    pub query_prefix: Option<String>,
+    /// Prepended to documents, for models trained with task prefixes
+    pub document_prefix: Option<String>,
+    /// Pooling the model was trained with (default: mean)
+    pub pooling: Pooling,
+    /// Side padding goes to in batches (default: right)
+    pub padding_side: PaddingSide,
+    /// Length queries of late-interaction models are padded to with `[MASK]`
+    pub query_max_tokens: Option<usize>,
+}
+
+impl LoadedModel {
+    pub fn new(model: impl EmbedModel + 'static, dimension: usize, max_tokens: usize) -> Self {
+        Self {
+            model: Box::new(model),
+            dimension,
+            max_tokens,
+            query_prefix: None,
+            document_prefix: None,
+            pooling: Pooling::Mean,
+            padding_side: PaddingSide::Right,
+            query_max_tokens: None,
+        }
+    }
+
+    /// Set the pooling the model was trained with
+    pub fn with_pooling(mut self, pooling: Pooling) -> Self {
+        self.pooling = pooling;
+        self
+    }
+
+    /// Set the side padding goes to in batches
+    pub fn with_padding_side(mut self, padding_side: PaddingSide) -> Self {
+        self.padding_side = padding_side;
+        self
+    }
+
+    /// Set the length queries are padded to with `[MASK]`, for late-interaction
+    /// models
+    pub fn with_query_max_tokens(mut self, query_max_tokens: usize) -> Self {
+        self.query_max_tokens = Some(query_max_tokens);
+        self
+    }
+
+    /// Set the prefixes texts get when embedded as queries or documents
+    pub fn with_prefixes(mut self, query: impl Into<String>, document: impl Into<String>) -> Self {
+        self.query_prefix = Some(query.into());
+        self.document_prefix = Some(document.into());
+        self
+    }
+
+    /// Apply the pooling of a sentence-transformers `1_Pooling/config.json`
+    /// and the `padding_side` of a `tokenizer_config.json`, keeping the ones
+    /// the loader set where a file or the setting is absent
+    pub(crate) fn with_settings(
+        mut self,
+        pooling_config: Option<&[u8]>,
+        tokenizer_config: Option<&[u8]>,
+    ) -> Result<Self, Error> {
+        if let Some(config) = pooling_config {
+            let config: serde_json::Map<String, Value> = serde_json::from_slice(config)?;
+            let modes: Vec<&str> = config
+                .iter()
+                .filter(|(key, value)| {
+                    key.starts_with("pooling_mode_") && value.as_bool() == Some(true)
+                })
+                .map(|(key, _)| key.as_str())
+                .collect();
+            self.pooling = match modes.as_slice() {
+                [] => self.pooling,
+                ["pooling_mode_mean_tokens"] => Pooling::Mean,
+                ["pooling_mode_cls_token"] => Pooling::Cls,
+                ["pooling_mode_lasttoken"] => Pooling::LastToken,
+                _ => {
+                    return Err(Error::Config(format!(
+                        "unsupported pooling {modes:?} in 1_Pooling/config.json"
+                    )));
+                }
+            };
+        }
+        if let Some(config) = tokenizer_config {
+            let config: Value = serde_json::from_slice(config)?;
+            self.padding_side = match config["padding_side"].as_str() {
+                None => self.padding_side,
+                Some("right") => PaddingSide::Right,
+                Some("left") => PaddingSide::Left,
+                Some(side) => {
+                    return Err(Error::Config(format!(
+                        "unsupported padding_side '{side}' in tokenizer_config.json"
+                    )));
+                }
+            };
+        }
+        Ok(self)
+    }
+}
+
+/// Loads one model architecture from its weights.
+///
+/// Files are taken from `local_path` when it returns a folder, otherwise
+/// downloaded from the HuggingFace Hub repository `model_id`. Both provide
+/// `tokenizer.json`, `model.safetensors` and, when present, `config.json`.
+/// When the folder or repository also has a sentence-transformers
+/// `1_Pooling/config.json` or a `tokenizer_config.json` with a `padding_side`,
+/// those override the pooling and padding side `load` sets.
+pub trait ModelLoader: Send + Sync {
+    /// HuggingFace Hub repository of the model
+    fn model_id(&self) -> &str;
+
+    /// Git revision of the repository
+    fn revision(&self) -> &str {
+        "main"
+    }
+
+    /// Local folder with the model files, used instead of the hub
+    fn local_path(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// Build the model; `config` is the contents of `config.json` if there is one
+    fn load(&self, vb: VarBuilder, config: Option<&[u8]>) -> Result<LoadedModel, Error>;
+}
+
+struct Registration {
+    name: String,
+    loader: Arc<dyn ModelLoader>,
+}
+
+/// Other names of the built-in models
+const ALIASES: &[(&str, &str)] = &[("mini_lm", "mini_lm_v2"), ("bert", "mini_lm_v2")];
+
+static LOADERS: Lazy<RwLock<Vec<Registration>>> = Lazy::new(|| {
+    RwLock::new(vec![
+        Registration {
+            name: "mini_lm_v2".into(),
+            loader: Arc::new(MiniLm),
+        },
+        Registration {
+            name: "jina".into(),
+            loader: Arc::new(Jina),
+        },
+        Registration {
+            name: "xlm_roberta".into(),
+            loader: Arc::new(XlmRoberta::default()),
+        },
+        Registration {
+            name: "multilingual_e5_base".into(),
+            loader: Arc::new(XlmRoberta::hub("intfloat/multilingual-e5-base")),
+        },
+        Registration {
+            name: "paraphrase_multilingual".into(),
+            loader: Arc::new(XlmRoberta::hub(
+                "sentence-transformers/paraphrase-multilingual-mpnet-base-v2",
+            )),
+        },
+        Registration {
+            name: "nomic".into(),
+            loader: Arc::new(Nomic),
+        },
+        Registration {
+            name: "modernbert".into(),
+            loader: Arc::new(ModernBert),
+        },
+        Registration {
+            name: "gte_qwen2".into(),
+            loader: Arc::new(Decoder {
+                model_id: "Alibaba-NLP/gte-Qwen2-1.5B-instruct",
+                folder_variable: "GTE_QWEN2_MODEL_FOLDER",
+            }),
+        },
+        Registration {
+            name: "e5_mistral".into(),
+            loader: Arc::new(Decoder {
+                model_id: "intfloat/e5-mistral-7b-instruct",
+                folder_variable: "E5_MISTRAL_MODEL_FOLDER",
+            }),
+        },
+        Registration {
+            name: "model2vec".into(),
+            loader: Arc::new(Model2Vec::default()),
+        },
+        Registration {
+            name: "potion_base_8m".into(),
+            loader: Arc::new(Model2Vec::hub("minishlab/potion-base-8M")),
+        },
+        Registration {
+            name: "clip".into(),
+            loader: Arc::new(Clip),
+        },
+        Registration {
+            name: "siglip".into(),
+            loader: Arc::new(Siglip),
+        },
+        Registration {
+            name: "cross_encoder".into(),
+            loader: Arc::new(CrossEncoder::default()),
+        },
+        Registration {
+            name: "ms_marco_minilm".into(),
+            loader: Arc::new(CrossEncoder::hub("cross-encoder/ms-marco-MiniLM-L6-v2")),
+        },
+        Registration {
+            name: "splade".into(),
+            loader: Arc::new(Splade),
+        },
+        Registration {
+            name: "colbert".into(),
+            loader: Arc::new(Colbert),
+        },
+    ])
+});
+
+/// Register `loader` under `name` (case-insensitive), replacing any model
+/// registered under the same name, built-in ones included.
+pub fn register_model(name: &str, loader: impl ModelLoader + 'static) {
+    let name = name.to_lowercase();
+    info!(name = %name, model_id = %loader.model_id(), "Registering model");
+    let mut loaders = LOADERS.write().unwrap_or_else(|e| e.into_inner());
+    let loader: Arc<dyn ModelLoader> = Arc::new(loader);
+    match loaders.iter_mut().find(|r| r.name == name) {
+        Some(registration) => registration.loader = loader,
+        None => loaders.push(Registration { name, loader }),
+    }
+}
+
+/// Names of all registered models, built-in ones first
+pub fn registered_models() -> Vec<String> {
+    let loaders = LOADERS.read().unwrap_or_else(|e| e.into_inner());
+    loaders.iter().map(|r| r.name.clone()).collect()
+}
+
+/// The loader registered under `name` and its canonical name
+pub(crate) fn lookup(name: &str) -> Option<(String, Arc<dyn ModelLoader>)> {
+    let name = name.to_lowercase();
+    let loaders = LOADERS.read().unwrap_or_else(|e| e.into_inner());
+    let find = |name: &str| loaders.iter().find(|r| r.name == name);
+    let registration = find(&name).or_else(|| {
+        ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == name)
+            .and_then(|(_, target)| find(target))
+    })?;
+    Some((registration.name.clone(), registration.loader.clone()))
+}
+
+/// candle's BERT builds its attention mask from f32::MIN, which overflows to
+/// -inf in half precision and turns into NaN; XLM-RoBERTa hardcodes an F32 mask
+fn require_f32(vb: &VarBuilder, architecture: &str) -> Result<(), Error> {
+    match vb.dtype() {
+        DType::F32 => Ok(()),
+        dtype => Err(Error::InvalidArgument(format!(
+            "{} is not supported by the {} model",
+            dtype.as_str(),
+            architecture
+        ))),
+    }
+}
+
+/// sentence-transformers/all-MiniLM-L6-v2, or any BERT given its `config.json`
+struct MiniLm;
+
+impl ModelLoader for MiniLm {
+    fn model_id(&self) -> &str {
+        "sentence-transformers/all-MiniLM-L6-v2"
+    }
+
+    fn local_path(&self) -> Option<PathBuf> {
+        std::env::var("BERT_MODEL_FOLDER").ok().map(PathBuf::from)
+    }
+
+    fn load(&self, vb: VarBuilder, config: Option<&[u8]>) -> Result<LoadedModel, Error> {
+        require_f32(&vb, "BERT")?;
+
+        let config: Config = match config {
+            Some(config) => serde_json::from_slice(config)?,
+            None => minilm_l6(),
+        };
+        let model = BertModel::load(vb, &config)?;
+        Ok(LoadedModel::new(
+            model,
+            config.hidden_size,
+            config.max_position_embeddings,
+        ))
+    }
+}
+
+/// The 6-layer MiniLM architecture of all-MiniLM-L6-v2 and ms-marco-MiniLM-L6-v2
+fn minilm_l6() -> Config {
+    Config {
+        vocab_size: 30522,
+        hidden_size: 384,
+        num_hidden_layers: 6,
+        num_attention_heads: 12,
+        intermediate_size: 1536,
+        hidden_act: HiddenAct::Gelu,
+        hidden_dropout_prob: 0.1,
+        max_position_embeddings: 512,
+        type_vocab_size: 2,
+        initializer_range: 0.02,
+        layer_norm_eps: 1e-12,
+        pad_token_id: 0,
+        position_embedding_type: PositionEmbeddingType::Absolute,
+        use_cache: true,
+        classifier_dropout: None,
+        model_type: Some("bert".to_string()),
+    }
+}
+
+/// The 12-layer bert-base-uncased architecture
+fn bert_base() -> Config {
+    Config {
+        hidden_size: 768,
+        num_hidden_layers: 12,
+        intermediate_size: 3072,
+        ..minilm_l6()
+    }
+}
+
+/// jinaai/jina-embeddings-v2-base-en
+struct Jina;
+
+impl ModelLoader for Jina {
+    fn model_id(&self) -> &str {
+        "jinaai/jina-embeddings-v2-base-en"
+    }
+
+    fn local_path(&self) -> Option<PathBuf> {
+        std::env::var("JINA_MODEL_FOLDER").ok().map(PathBuf::from)
+    }
+
+    fn load(&self, vb: VarBuilder, config: Option<&[u8]>) -> Result<LoadedModel, Error> {
+        let config: JinaConfig = match config {
+            Some(config) => serde_json::from_slice(config)?,
+            None => JinaConfig::v2_base(),
+        };
+        let model = JinaModel::load(vb, &config)?;
+        Ok(LoadedModel::new(
+            model,
+            config.hidden_size,
+            config.max_position_embeddings,
+        ))
+    }
+}
+
+/// XLM-RoBERTa encoders (multilingual-e5, paraphrase-multilingual-mpnet,
+/// bge-m3, ...) with their large sentencepiece vocabularies. Without a
+/// `config.json` the multilingual-e5-base architecture is used.
+struct XlmRoberta {
+    model_id: &'static str,
+    /// Read `XLM_ROBERTA_MODEL_FOLDER`; only for the generic `xlm_roberta` name
+    local_folder: bool,
+}
+
+impl XlmRoberta {
+    fn hub(model_id: &'static str) -> Self {
+        Self {
+            model_id,
+            local_folder: false,
+        }
+    }
+}
+
+impl Default for XlmRoberta {
+    fn default() -> Self {
+        Self {
+            model_id: "intfloat/multilingual-e5-base",
+            local_folder: true,
+        }
+    }
+}
+
+impl ModelLoader for XlmRoberta {
+    fn model_id(&self) -> &str {
+        self.model_id
+    }
+
+    fn local_path(&self) -> Option<PathBuf> {
+        if !self.local_folder {
+            return None;
+        }
+        std::env::var("XLM_ROBERTA_MODEL_FOLDER")
+            .ok()
+            .map(PathBuf::from)
+    }
+
+    fn load(&self, vb: VarBuilder, config: Option<&[u8]>) -> Result<LoadedModel, Error> {
+        require_f32(&vb, "XLM-RoBERTa")?;
+
+        let config: XlmRobertaConfig = match config {
+            Some(config) => serde_json::from_slice(config)?,
+            None => serde_json::from_value(serde_json::json!({
+                "hidden_size": 768,
+                "layer_norm_eps": 1e-5,
+                "attention_probs_dropout_prob": 0.1,
+                "hidden_dropout_prob": 0.1,
+                "num_attention_heads": 12,
+                "position_embedding_type": "absolute",
+                "intermediate_size": 3072,
+                "hidden_act": "gelu",
+                "num_hidden_layers": 12,
+                "vocab_size": 250002,
+                "max_position_embeddings": 514,
+                "type_vocab_size": 1,
+                "pad_token_id": 1,
+            }))?,
+        };
+        // Checkpoints saved from a task model nest the encoder under "roberta"
+        let vb = if vb.contains_tensor("roberta.embeddings.word_embeddings.weight") {
+            vb.pp("roberta")
+        } else {
+            vb
+        };
+        let device = vb.device().clone();
+        let model = XLMRobertaModel::new(&config, vb)?;
+        // Positions are numbered from pad_token_id + 1
+        let max_tokens = config.max_position_embeddings - config.pad_token_id as usize - 1;
+        Ok(LoadedModel::new(
+            XlmRobertaModel::new(model, device),
+            config.hidden_size,
+            max_tokens,
+        ))
+    }
+}
+
+/// nomic-ai/nomic-embed-text-v1.5, a long-context BERT with rotary position
+/// embeddings and SwiGLU. Texts need a task prefix, see `Task`.
+struct Nomic;
+
+impl ModelLoader for Nomic {
+    fn model_id(&self) -> &str {
+        "nomic-ai/nomic-embed-text-v1.5"
+    }
+
+    fn local_path(&self) -> Option<PathBuf> {
+        std::env::var("NOMIC_MODEL_FOLDER").ok().map(PathBuf::from)
+    }
+
+    fn load(&self, vb: VarBuilder, config: Option<&[u8]>) -> Result<LoadedModel, Error> {
+        let config: NomicConfig = match config {
+            Some(config) => serde_json::from_slice(config)?,
+            None => NomicConfig::v1_5(),
+        };
+        let model = NomicBertModel::load(vb, &config)?;
+        Ok(LoadedModel::new(model, config.n_embd, config.max_tokens())
+            .with_prefixes("search_query: ", "search_document: "))
+    }
+}
+
+/// nomic-ai/modernbert-embed-base, or any ModernBERT encoder given its
+/// `config.json`: alternating global and sliding window attention, GeGLU, and
+/// padding removed before the encoder runs. Texts need a task prefix, see `Task`.
+struct ModernBert;
+
+impl ModelLoader for ModernBert {
+    fn model_id(&self) -> &str {
+        "nomic-ai/modernbert-embed-base"
+    }
+
+    fn local_path(&self) -> Option<PathBuf> {
+        std::env::var("MODERNBERT_MODEL_FOLDER")
+            .ok()
+            .map(PathBuf::from)
+    }
+
+    fn load(&self, vb: VarBuilder, config: Option<&[u8]>) -> Result<LoadedModel, Error> {
+        let config: ModernBertConfig = match config {
+            Some(config) => serde_json::from_slice(config)?,
+            None => ModernBertConfig::base(),
+        };
+        // Checkpoints saved from a task model nest the encoder under "model"
+        let vb = if vb.contains_tensor("model.embeddings.tok_embeddings.weight") {
+            vb.pp("model")
+        } else {
+            vb
+        };
+        let model = ModernBertModel::load(vb, &config)?;
+        Ok(
+            LoadedModel::new(model, config.hidden_size, config.max_position_embeddings)
+                .with_prefixes("search_query: ", "search_document: "),
+        )
+    }
+}
+
+/// Instruction template gte-Qwen2 and e5-mistral were trained with for web
+/// search queries; documents get no prefix
+const SEARCH_INSTRUCTION: &str =
+    "Instruct: Given a web search query, retrieve relevant passages that answer the query\nQuery: ";
+
+/// Decoder embedders (gte-Qwen2, e5-mistral and other Qwen2 or Mistral models
+/// given their `config.json`) with last-token pooling and left padding, unless
+/// the model's `1_Pooling/config.json` and `tokenizer_config.json` say
+/// otherwise. The tokenizer is expected to append the end-of-sequence token
+/// whose embedding is used.
+struct Decoder {
+    model_id: &'static str,
+    folder_variable: &'static str,
+}
+
+impl ModelLoader for Decoder {
+    fn model_id(&self) -> &str {
+        self.model_id
+    }
+
+    fn local_path(&self) -> Option<PathBuf> {
+        std::env::var(self.folder_variable).ok().map(PathBuf::from)
+    }
+
+    fn load(&self, vb: VarBuilder, config: Option<&[u8]>) -> Result<LoadedModel, Error> {
+        let config: DecoderConfig = match config {
+            Some(config) => serde_json::from_slice(config)?,
+            None => {
+                return Err(Error::Config(
+                    "decoder models need their config.json".into(),
+                ));
+            }
+        };
+        if !matches!(config.model_type.as_str(), "qwen2" | "mistral") {
+            return Err(Error::Config(format!(
+                "unsupported decoder model type '{}', expected 'qwen2' or 'mistral'",
+                config.model_type
+            )));
+        }
+        // Checkpoints saved from the causal LM nest the decoder under "model"
+        let vb = if vb.contains_tensor("model.embed_tokens.weight") {
+            vb.pp("model")
+        } else {
+            vb
+        };
+        let model = DecoderModel::load(vb, &config)?;
+        Ok(
+            LoadedModel::new(model, config.hidden_size, config.max_position_embeddings)
+                .with_prefixes(SEARCH_INSTRUCTION, "")
+                .with_pooling(Pooling::LastToken)
+                .with_padding_side(PaddingSide::Left),
+        )
+    }
+}
+
+/// Static models in the model2vec format (potion and other distilled
+/// models), which average precomputed token vectors without a forward pass.
+/// Their `config.json` describes how they were distilled and is not needed.
+struct Model2Vec {
+    model_id: &'static str,
+    /// Read `MODEL2VEC_MODEL_FOLDER`; only for the generic `model2vec` name
+    local_folder: bool,
+}
+
+impl Model2Vec {
+    fn hub(model_id: &'static str) -> Self {
+        Self {
+            model_id,
+            local_folder: false,
+        }
+    }
+}
+
+impl Default for Model2Vec {
+    fn default() -> Self {
+        Self {
+            model_id: "minishlab/potion-base-8M",
+            local_folder: true,
+        }
+    }
+}
+
+impl ModelLoader for Model2Vec {
+    fn model_id(&self) -> &str {
+        self.model_id
+    }
+
+    fn local_path(&self) -> Option<PathBuf> {
+        if !self.local_folder {
+            return None;
+        }
+        std::env::var("MODEL2VEC_MODEL_FOLDER")
+            .ok()
+            .map(PathBuf::from)
+    }
+
+    fn load(&self, vb: VarBuilder, _config: Option<&[u8]>) -> Result<LoadedModel, Error> {
+        let model = StaticModel::load(vb)?;
+        let dimension = model.dimension();
+        // Static models have no position limit; 512 is model2vec's default
+        Ok(LoadedModel::new(model, dimension, 512))
+    }
+}
+
+/// openai/clip-vit-base-patch32, or any CLIP model with quick GELU given its
+/// `config.json`. Texts and images (`TextEmbedder::embed_images`) share one
+/// vector space; a text is embedded as its end-of-text token, the last one.
+struct Clip;
+
+impl ModelLoader for Clip {
+    fn model_id(&self) -> &str {
+        "openai/clip-vit-base-patch32"
+    }
+
+    fn local_path(&self) -> Option<PathBuf> {
+        std::env::var("CLIP_MODEL_FOLDER").ok().map(PathBuf::from)
+    }
+
+    fn load(&self, vb: VarBuilder, config: Option<&[u8]>) -> Result<LoadedModel, Error> {
+        let config: ClipConfig = match config {
+            Some(config) => serde_json::from_slice(config)?,
+            None => ClipConfig::default(),
+        };
+        let max_tokens = config.text_config.max_position_embeddings;
+        let model = ClipModel::load(vb, &config)?;
+        Ok(LoadedModel::new(model, config.projection_dim, max_tokens)
+            .with_pooling(Pooling::LastToken))
+    }
+}
+
+/// google/siglip2-base-patch16-224, or any SigLIP model given its
+/// `config.json`. Like CLIP, texts and images share one vector space; the text
+/// tower pools by itself and returns a single token per text.
+struct Siglip;
+
+impl ModelLoader for Siglip {
+    fn model_id(&self) -> &str {
+        "google/siglip2-base-patch16-224"
+    }
+
+    fn local_path(&self) -> Option<PathBuf> {
+        std::env::var("SIGLIP_MODEL_FOLDER").ok().map(PathBuf::from)
+    }
+
+    fn load(&self, vb: VarBuilder, config: Option<&[u8]>) -> Result<LoadedModel, Error> {
+        let config: SiglipConfig = match config {
+            Some(config) => serde_json::from_slice(config)?,
+            None => {
+                return Err(Error::Config("SigLIP models need their config.json".into()));
+            }
+        };
+        let text = &config.text_config;
+        let model = SiglipModel::load(vb, &config)?;
+        Ok(
+            LoadedModel::new(model, text.hidden_size, text.max_position_embeddings)
+                .with_pooling(Pooling::Cls),
+        )
+    }
+}
+
+/// BERT cross-encoders (ms-marco-MiniLM and other
+/// `BertForSequenceClassification` rerankers with one label), which score
+/// query–passage pairs with `TextEmbedder::rerank`. Without a `config.json` the
+/// ms-marco-MiniLM-L6-v2 architecture is used.
+struct CrossEncoder {
+    model_id: &'static str,
+    /// Read `CROSS_ENCODER_MODEL_FOLDER`; only for the generic `cross_encoder` name
+    local_folder: bool,
+}
+
+impl CrossEncoder {
+    fn hub(model_id: &'static str) -> Self {
+        Self {
+            model_id,
+            local_folder: false,
+        }
+    }
+}
+
+impl Default for CrossEncoder {
+    fn default() -> Self {
+        Self {
+            model_id: "cross-encoder/ms-marco-MiniLM-L6-v2",
+            local_folder: true,
+        }
+    }
+}
+
+impl ModelLoader for CrossEncoder {
+    fn model_id(&self) -> &str {
+        self.model_id
+    }
+
+    fn local_path(&self) -> Option<PathBuf> {
+        if !self.local_folder {
+            return None;
+        }
+        std::env::var("CROSS_ENCODER_MODEL_FOLDER")
+            .ok()
+            .map(PathBuf::from)
+    }
+
+    fn load(&self, vb: VarBuilder, config: Option<&[u8]>) -> Result<LoadedModel, Error> {
+        require_f32(&vb, "BERT")?;
+
+        let config: CrossEncoderConfig = match config {
+            Some(config) => serde_json::from_slice(config)?,
+            None => CrossEncoderConfig {
+                bert: minilm_l6(),
+                id2label: None,
+            },
+        };
+        if config.num_labels() != 1 {
+            return Err(Error::Config(format!(
+                "cross-encoders with {} labels are not supported, expected 1",
+                config.num_labels()
+            )));
+        }
+        let model = CrossEncoderModel::load(vb, &config)?;
+        Ok(LoadedModel::new(
+            model,
+            config.bert.hidden_size,
+            config.bert.max_position_embeddings,
+        ))
+    }
+}
+
+/// naver/splade-cocondenser-ensembledistil, or any SPLADE model on a BERT
+/// masked language model given its `config.json`. The model pools by itself
+/// and returns one weight per vocabulary token, see `TextEmbedder::embed_sparse`.
+struct Splade;
+
+impl ModelLoader for Splade {
+    fn model_id(&self) -> &str {
+        "naver/splade-cocondenser-ensembledistil"
+    }
+
+    fn local_path(&self) -> Option<PathBuf> {
+        std::env::var("SPLADE_MODEL_FOLDER").ok().map(PathBuf::from)
+    }
+
+    fn load(&self, vb: VarBuilder, config: Option<&[u8]>) -> Result<LoadedModel, Error> {
+        require_f32(&vb, "BERT")?;
+
+        let config: Config = match config {
+            Some(config) => serde_json::from_slice(config)?,
+            None => bert_base(),
+        };
+        let model = SpladeModel::load(vb, &config)?;
+        Ok(
+            LoadedModel::new(model, config.vocab_size, config.max_position_embeddings)
+                .with_pooling(Pooling::Cls),
+        )
+    }
+}
+
+/// colbert-ir/colbertv2.0, or any ColBERT model on BERT given its
+/// `config.json`. Texts keep one vector per token, see
+/// `TextEmbedder::embed_tokens`; pooled embeddings average the projected tokens.
+struct Colbert;
+
+/// `query_maxlen` of colbertv2.0
+const COLBERT_QUERY_MAX_TOKENS: usize = 32;
+
+impl ModelLoader for Colbert {
+    fn model_id(&self) -> &str {
+        "colbert-ir/colbertv2.0"
+    }
+
+    fn local_path(&self) -> Option<PathBuf> {
+        std::env::var("COLBERT_MODEL_FOLDER")
+            .ok()
+            .map(PathBuf::from)
+    }
+
+    fn load(&self, vb: VarBuilder, config: Option<&[u8]>) -> Result<LoadedModel, Error> {
+        require_f32(&vb, "BERT")?;
+
+        let config: Config = match config {
+            Some(config) => serde_json::from_slice(config)?,
+            None => bert_base(),
+        };
+        let model = ColbertModel::load(vb, &config)?;
+        let dimension = model.dimension();
+        Ok(
+            LoadedModel::new(model, dimension, config.max_position_embeddings)
+                .with_query_max_tokens(COLBERT_QUERY_MAX_TOKENS),
+        )
+    }
+}
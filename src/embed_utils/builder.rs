@@ -0,0 +1,204 @@
+// Building a `TextEmbedder` from explicit model files or byte buffers, for
+// weights that do not follow the `BERT_MODEL_FOLDER` layout or never touch
+// the disk (bundled, encrypted or downloaded by the application itself).
+
+use super::{DTYPE, EmbedModel, EmbeddingError, ModelInfo, ModelType, TextEmbedder, registry};
+use candle_core::DType;
+use candle_nn::VarBuilder;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokenizers::{Tokenizer, TruncationParams};
+use tracing::{debug, info};
+use web_time::Instant;
+
+/// Where a model file comes from
+pub(crate) enum Source {
+    Path(PathBuf),
+    Bytes(Vec<u8>),
+}
+
+impl fmt::Debug for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Path(path) => f.debug_tuple("Path").field(path).finish(),
+            Self::Bytes(bytes) => write!(f, "Bytes({} bytes)", bytes.len()),
+        }
+    }
+}
+
+impl Source {
+    fn read(self) -> Result<Vec<u8>, EmbeddingError> {
+        match self {
+            Self::Path(path) => {
+                debug!(path = %path.display(), "Reading model file");
+                Ok(std::fs::read(path)?)
+            }
+            Self::Bytes(bytes) => Ok(bytes),
+        }
+    }
+}
+
+/// Builds a `TextEmbedder` from a tokenizer, weights and optional config given
+/// as file paths or byte buffers. Without a config, the default architecture of
+/// the model type is used.
+///
+/// Embedders built from a weights file (and a config file, if any) share their
+/// weights through the model registry with every other embedder built from the
+/// same files. Weights given as bytes have no identity to share them by, so
+/// each embedder built from buffers holds its own copy.
+pub struct TextEmbedderBuilder {
+    model_type: ModelType,
+    tokenizer: Option<Source>,
+    config: Option<Source>,
+    weights: Option<Source>,
+    dtype: DType,
+}
+
+impl TextEmbedderBuilder {
+    pub fn new(model_type: ModelType) -> Self {
+        Self {
+            model_type,
+            tokenizer: None,
+            config: None,
+            weights: None,
+            dtype: DTYPE,
+        }
+    }
+
+    pub(crate) fn tokenizer(mut self, source: Source) -> Self {
+        self.tokenizer = Some(source);
+        self
+    }
+
+    pub(crate) fn config(mut self, source: Source) -> Self {
+        self.config = Some(source);
+        self
+    }
+
+    pub(crate) fn weights(mut self, source: Source) -> Self {
+        self.weights = Some(source);
+        self
+    }
+
+    /// Path of the `tokenizer.json` file
+    pub fn tokenizer_path(self, path: impl Into<PathBuf>) -> Self {
+        self.tokenizer(Source::Path(path.into()))
+    }
+
+    /// Contents of a `tokenizer.json` file
+    pub fn tokenizer_bytes(self, bytes: Vec<u8>) -> Self {
+        self.tokenizer(Source::Bytes(bytes))
+    }
+
+    /// Path of the `config.json` file
+    pub fn config_path(self, path: impl Into<PathBuf>) -> Self {
+        self.config(Source::Path(path.into()))
+    }
+
+    /// Contents of a `config.json` file
+    pub fn config_bytes(self, bytes: Vec<u8>) -> Self {
+        self.config(Source::Bytes(bytes))
+    }
+
+    /// Path of the safetensors weights; the file is memory-mapped
+    pub fn weights_path(self, path: impl Into<PathBuf>) -> Self {
+        self.weights(Source::Path(path.into()))
+    }
+
+    /// Contents of a safetensors weights file
+    pub fn weights_bytes(self, bytes: Vec<u8>) -> Self {
+        self.weights(Source::Bytes(bytes))
+    }
+
+    /// Type the weights are converted to when loading (F32 by default)
+    pub fn dtype(mut self, dtype: DType) -> Self {
+        self.dtype = dtype;
+        self
+    }
+
+    pub fn build(self) -> Result<TextEmbedder, EmbeddingError> {
+        self.model_type.check_dtype(self.dtype)?;
+
+        let tokenizer = self
+            .tokenizer
+            .ok_or_else(|| EmbeddingError::ModelTypeError("No tokenizer given".into()))?
+            .read()?;
+        let mut tokenizer = Tokenizer::from_bytes(tokenizer)?;
+        let weights = self
+            .weights
+            .ok_or_else(|| EmbeddingError::ModelTypeError("No weights given".into()))?;
+
+        let (model, info) = match (&weights, &self.config) {
+            (Source::Path(weights_path), None | Some(Source::Path(_))) => {
+                let key = registry::ModelKey {
+                    name: self.model_type.name.clone(),
+                    model_id: self.model_type.get_model_id(),
+                    path: Some(canonical(weights_path)),
+                    config: match &self.config {
+                        Some(Source::Path(path)) => Some(canonical(path)),
+                        _ => None,
+                    },
+                    revision: String::new(),
+                    dtype: self.dtype.as_str().to_string(),
+                    device: format!("{:?}", self.model_type.device().location()),
+                };
+                let (model, _, info) = registry::get_or_load(key, || {
+                    let (model, info) = load(&self.model_type, weights, self.config, self.dtype)?;
+                    Ok((model, tokenizer.clone(), info))
+                })?;
+                (model, info)
+            }
+            _ => {
+                let (model, info) = load(&self.model_type, weights, self.config, self.dtype)?;
+                (Arc::from(model), info)
+            }
+        };
+
+        // The tokenizer of these files, not the one of the embedder that loaded the weights
+        tokenizer.with_truncation(Some(TruncationParams {
+            max_length: info.max_tokens,
+            ..Default::default()
+        }))?;
+
+        Ok(TextEmbedder {
+            model,
+            tokenizer,
+            info,
+            task: None,
+        })
+    }
+}
+
+/// Registry key path of a model file, so that two spellings of it share weights
+fn canonical(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Instantiate the model of `model_type` from `weights` and `config`
+fn load(
+    model_type: &ModelType,
+    weights: Source,
+    config: Option<Source>,
+    dtype: DType,
+) -> Result<(Box<dyn EmbedModel>, ModelInfo), EmbeddingError> {
+    let started = Instant::now();
+    let device = model_type.device();
+    let config = config.map(Source::read).transpose()?;
+
+    let vb = match weights {
+        Source::Path(path) => {
+            debug!(path = %path.display(), "Memory-mapping model weights");
+            unsafe { VarBuilder::from_mmaped_safetensors(&[path], dtype, device)? }
+        }
+        Source::Bytes(bytes) => VarBuilder::from_buffered_safetensors(bytes, dtype, device)?,
+    };
+    let (model, info) = model_type.load_model(vb, config.as_deref(), None, None)?;
+
+    info!(
+        model_id = %info.model_id,
+        elapsed_ms = started.elapsed().as_millis() as u64,
+        "Model loaded from explicit files"
+    );
+    Ok((model, info))
+}
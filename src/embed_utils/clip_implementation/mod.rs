@@ -0,0 +1,261 @@
+use super::image::ImagePreprocessing;
+use candle_core::{DType, Device, Module, Result, Tensor};
+use candle_nn::{Linear, VarBuilder, linear_no_bias};
+use candle_transformers::models::clip::text_model::{
+    Activation, ClipTextConfig, ClipTextTransformer,
+};
+use candle_transformers::models::clip::vision_model::{ClipVisionConfig, ClipVisionTransformer};
+use candle_transformers::models::siglip;
+use serde::Deserialize;
+
+// Text–image models embed texts and images into one vector space, so text
+// queries can be matched against images. The text and vision towers are
+// candle's CLIP (https://arxiv.org/abs/2103.00020) and SigLIP
+// (https://arxiv.org/abs/2303.15343) ones; this module reads their
+// `config.json` and adapts the text tower to `EmbedModel`.
+
+// Config of CLIP, with the defaults of transformers' `CLIPConfig`, which are
+// https://huggingface.co/openai/clip-vit-base-patch32/blob/main/config.json
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ClipConfig {
+    pub text_config: ClipTextSection,
+    pub vision_config: ClipVisionSection,
+    /// Length of the embeddings of both towers
+    pub projection_dim: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ClipTextSection {
+    pub vocab_size: usize,
+    pub hidden_size: usize,
+    pub intermediate_size: usize,
+    pub num_hidden_layers: usize,
+    pub num_attention_heads: usize,
+    pub max_position_embeddings: usize,
+    pub hidden_act: String,
+}
+
+impl Default for ClipTextSection {
+    fn default() -> Self {
+        Self {
+            vocab_size: 49408,
+            hidden_size: 512,
+            intermediate_size: 2048,
+            num_hidden_layers: 12,
+            num_attention_heads: 8,
+            max_position_embeddings: 77,
+            hidden_act: "quick_gelu".into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ClipVisionSection {
+    pub hidden_size: usize,
+    pub intermediate_size: usize,
+    pub num_hidden_layers: usize,
+    pub num_attention_heads: usize,
+    pub num_channels: usize,
+    pub image_size: usize,
+    pub patch_size: usize,
+    pub hidden_act: String,
+}
+
+impl Default for ClipVisionSection {
+    fn default() -> Self {
+        Self {
+            hidden_size: 768,
+            intermediate_size: 3072,
+            num_hidden_layers: 12,
+            num_attention_heads: 12,
+            num_channels: 3,
+            image_size: 224,
+            patch_size: 32,
+            hidden_act: "quick_gelu".into(),
+        }
+    }
+}
+
+impl Default for ClipConfig {
+    fn default() -> Self {
+        Self {
+            text_config: ClipTextSection::default(),
+            vision_config: ClipVisionSection::default(),
+            projection_dim: 512,
+        }
+    }
+}
+
+impl ClipConfig {
+    /// Activation of both towers; candle's CLIP only implements quick GELU,
+    /// the one of the OpenAI checkpoints
+    fn activation(&self) -> Result<Activation> {
+        for act in [&self.text_config.hidden_act, &self.vision_config.hidden_act] {
+            if act != "quick_gelu" {
+                candle_core::bail!("unsupported CLIP activation '{act}', expected 'quick_gelu'")
+            }
+        }
+        Ok(Activation::QuickGelu)
+    }
+
+    fn text(&self) -> Result<ClipTextConfig> {
+        let text = &self.text_config;
+        Ok(ClipTextConfig {
+            vocab_size: text.vocab_size,
+            embed_dim: text.hidden_size,
+            activation: self.activation()?,
+            intermediate_size: text.intermediate_size,
+            max_position_embeddings: text.max_position_embeddings,
+            pad_with: None,
+            num_hidden_layers: text.num_hidden_layers,
+            num_attention_heads: text.num_attention_heads,
+            projection_dim: self.projection_dim,
+        })
+    }
+
+    fn vision(&self) -> Result<ClipVisionConfig> {
+        let vision = &self.vision_config;
+        Ok(ClipVisionConfig {
+            embed_dim: vision.hidden_size,
+            activation: self.activation()?,
+            intermediate_size: vision.intermediate_size,
+            num_hidden_layers: vision.num_hidden_layers,
+            num_attention_heads: vision.num_attention_heads,
+            projection_dim: self.projection_dim,
+            num_channels: vision.num_channels,
+            image_size: vision.image_size,
+            patch_size: vision.patch_size,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ClipModel {
+    text_model: ClipTextTransformer,
+    text_projection: Linear,
+    vision_model: ClipVisionTransformer,
+    visual_projection: Linear,
+    preprocessing: ImagePreprocessing,
+    dtype: DType,
+    pub device: Device,
+}
+
+impl ClipModel {
+    pub fn load(vb: VarBuilder, config: &ClipConfig) -> Result<Self> {
+        let text = config.text()?;
+        let vision = config.vision()?;
+        Ok(Self {
+            text_model: ClipTextTransformer::new(vb.pp("text_model"), &text)?,
+            text_projection: linear_no_bias(
+                text.embed_dim,
+                config.projection_dim,
+                vb.pp("text_projection"),
+            )?,
+            vision_model: ClipVisionTransformer::new(vb.pp("vision_model"), &vision)?,
+            visual_projection: linear_no_bias(
+                vision.embed_dim,
+                config.projection_dim,
+                vb.pp("visual_projection"),
+            )?,
+            preprocessing: ImagePreprocessing::clip(vision.image_size),
+            dtype: vb.dtype(),
+            device: vb.device().clone(),
+        })
+    }
+
+    pub fn preprocessing(&self) -> &ImagePreprocessing {
+        &self.preprocessing
+    }
+
+    /// Projected embeddings of every token, `(batch, tokens, projection_dim)`.
+    /// CLIP embeds a text as its end-of-text token, the last one; attention is
+    /// causal, so padding after it changes nothing.
+    pub fn forward(&self, input_ids: &Tensor) -> Result<Tensor> {
+        self.text_model
+            .forward_with_mask(input_ids, usize::MAX)?
+            .apply(&self.text_projection)
+    }
+
+    /// Projected `(batch, projection_dim)` embeddings of the images, from the
+    /// class token
+    pub fn forward_images(&self, pixel_values: &Tensor) -> Result<Tensor> {
+        pixel_values
+            .to_dtype(self.dtype)?
+            .apply(&self.vision_model)?
+            .apply(&self.visual_projection)
+    }
+}
+
+// Config of SigLIP, based on
+// https://huggingface.co/google/siglip-base-patch16-224/blob/main/config.json
+pub type SiglipConfig = siglip::Config;
+
+#[derive(Clone, Debug)]
+pub struct SiglipModel {
+    text_model: siglip::TextModel,
+    vision_model: siglip::VisionModel,
+    /// Texts are padded with `pad_token_id` to `max_position_embeddings`
+    max_tokens: usize,
+    pad_token_id: u32,
+    preprocessing: ImagePreprocessing,
+    dtype: DType,
+    pub device: Device,
+}
+
+impl SiglipModel {
+    pub fn load(vb: VarBuilder, config: &SiglipConfig) -> Result<Self> {
+        let text = &config.text_config;
+        let vision = &config.vision_config;
+        if text.hidden_size != vision.hidden_size {
+            candle_core::bail!(
+                "SigLIP towers of different sizes: text {}, vision {}",
+                text.hidden_size,
+                vision.hidden_size
+            )
+        }
+        Ok(Self {
+            text_model: siglip::TextModel::new(text, vb.pp("text_model"))?,
+            vision_model: siglip::VisionModel::new(vision, true, vb.pp("vision_model"))?,
+            max_tokens: text.max_position_embeddings,
+            pad_token_id: text.pad_token_id,
+            preprocessing: ImagePreprocessing::siglip(vision.image_size),
+            dtype: vb.dtype(),
+            device: vb.device().clone(),
+        })
+    }
+
+    pub fn preprocessing(&self) -> &ImagePreprocessing {
+        &self.preprocessing
+    }
+
+    /// Pooled text embeddings as a single token, `(batch, 1, hidden_size)`.
+    /// SigLIP was trained on texts padded to the full context and embeds them
+    /// as their last position, so the padding is redone here: masked tokens
+    /// become `pad_token_id` and every text gets `max_position_embeddings`
+    /// tokens.
+    pub fn forward(&self, input_ids: &Tensor, attention_mask: Option<&Tensor>) -> Result<Tensor> {
+        let ids = input_ids.to_vec2::<u32>()?;
+        let mask = match attention_mask {
+            Some(mask) => mask.to_vec2::<u32>()?,
+            None => input_ids.ones_like()?.to_vec2::<u32>()?,
+        };
+        let mut padded = vec![self.pad_token_id; ids.len() * self.max_tokens];
+        for (row, (ids, mask)) in ids.iter().zip(&mask).enumerate() {
+            let tokens = ids.iter().zip(mask).filter(|&(_, &m)| m != 0);
+            for (position, (&id, _)) in tokens.take(self.max_tokens).enumerate() {
+                padded[row * self.max_tokens + position] = id;
+            }
+        }
+        let input_ids = Tensor::from_vec(padded, (ids.len(), self.max_tokens), &self.device)?;
+        self.text_model.forward(&input_ids)?.unsqueeze(1)
+    }
+
+    /// Pooled `(batch, hidden_size)` embeddings of the images, from the
+    /// attention pooling head
+    pub fn forward_images(&self, pixel_values: &Tensor) -> Result<Tensor> {
+        pixel_values.to_dtype(self.dtype)?.apply(&self.vision_model)
+    }
+}
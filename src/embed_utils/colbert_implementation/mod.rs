@@ -0,0 +1,59 @@
+use candle_core::{Device, Module, Result, Tensor};
+use candle_nn::{Linear, VarBuilder};
+use candle_transformers::models::bert::{BertModel, Config};
+
+// ColBERT (https://arxiv.org/abs/2004.12832) keeps one vector per token
+// instead of pooling them: a BERT encoder whose token embeddings go through a
+// linear projection without bias to a small dimension (128 for colbertv2.0).
+// A query and a document are compared by late interaction: every query token
+// is matched with its most similar document token (MaxSim) and the
+// similarities are summed.
+//
+// Checkpoints keep the encoder under "bert" and the projection as "linear";
+// its output size is not in `config.json`, so it is read from the weight.
+
+pub struct ColbertModel {
+    bert: BertModel,
+    projection: Linear,
+    pub device: Device,
+}
+
+impl ColbertModel {
+    pub fn load(vb: VarBuilder, config: &Config) -> Result<Self> {
+        let encoder = if vb.contains_tensor("bert.embeddings.word_embeddings.weight") {
+            vb.pp("bert")
+        } else {
+            vb.clone()
+        };
+        let weight = vb.get_unchecked("linear.weight")?;
+        let (_, hidden_size) = weight.dims2()?;
+        if hidden_size != config.hidden_size {
+            candle_core::bail!(
+                "linear.weight projects from {hidden_size} dimensions, expected {}",
+                config.hidden_size
+            );
+        }
+        Ok(Self {
+            bert: BertModel::load(encoder, config)?,
+            projection: Linear::new(weight, None),
+            device: vb.device().clone(),
+        })
+    }
+
+    /// Length of the projected token vectors
+    pub fn dimension(&self) -> usize {
+        self.projection.weight().dim(0).unwrap_or_default()
+    }
+
+    pub fn forward(
+        &self,
+        input_ids: &Tensor,
+        token_type_ids: &Tensor,
+        attention_mask: Option<&Tensor>,
+    ) -> Result<Tensor> {
+        let hidden = self
+            .bert
+            .forward(input_ids, token_type_ids, attention_mask)?;
+        self.projection.forward(&hidden)
+    }
+}
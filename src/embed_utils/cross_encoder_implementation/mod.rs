@@ -0,0 +1,77 @@
+use candle_core::{Device, Module, Result, Tensor};
+use candle_nn::{Linear, VarBuilder, linear};
+use candle_transformers::models::bert::{self, BertModel};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+// Cross-encoders score a query and a passage read together as one sequence,
+// `[CLS] query [SEP] passage [SEP]` with token type 1 for the passage, instead
+// of comparing two embeddings. This is transformers'
+// `BertForSequenceClassification`: candle's BERT encoder, the pooler (a dense
+// layer with tanh on the `[CLS]` token) and a linear head with one output, the
+// relevance logit (https://arxiv.org/abs/1901.04085).
+
+/// Config of a BERT cross-encoder: the encoder's and the labels of the head
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    #[serde(flatten)]
+    pub bert: bert::Config,
+    /// Names of the outputs of the head; rerankers have a single one
+    #[serde(default)]
+    pub id2label: Option<HashMap<String, String>>,
+}
+
+impl Config {
+    /// Number of outputs of the classification head
+    pub fn num_labels(&self) -> usize {
+        self.id2label.as_ref().map_or(1, HashMap::len)
+    }
+}
+
+pub struct CrossEncoderModel {
+    bert: BertModel,
+    pooler: Linear,
+    classifier: Linear,
+    pub device: Device,
+}
+
+impl CrossEncoderModel {
+    pub fn load(vb: VarBuilder, config: &Config) -> Result<Self> {
+        let hidden_size = config.bert.hidden_size;
+        // Checkpoints of the task model nest the encoder and pooler under "bert"
+        let encoder = if vb.contains_tensor("bert.embeddings.word_embeddings.weight") {
+            vb.pp("bert")
+        } else {
+            vb.clone()
+        };
+        Ok(Self {
+            bert: BertModel::load(encoder.clone(), &config.bert)?,
+            pooler: linear(hidden_size, hidden_size, encoder.pp("pooler.dense"))?,
+            classifier: linear(hidden_size, config.num_labels(), vb.pp("classifier"))?,
+            device: vb.device().clone(),
+        })
+    }
+
+    /// Token embeddings of the encoder, `(batch, tokens, hidden_size)`
+    pub fn forward(
+        &self,
+        input_ids: &Tensor,
+        token_type_ids: &Tensor,
+        attention_mask: Option<&Tensor>,
+    ) -> Result<Tensor> {
+        self.bert.forward(input_ids, token_type_ids, attention_mask)
+    }
+
+    /// Relevance logits of query–passage pairs, `(batch,)`
+    pub fn forward_pairs(
+        &self,
+        input_ids: &Tensor,
+        token_type_ids: &Tensor,
+        attention_mask: Option<&Tensor>,
+    ) -> Result<Tensor> {
+        let hidden = self.forward(input_ids, token_type_ids, attention_mask)?;
+        let cls = hidden.narrow(1, 0, 1)?.squeeze(1)?;
+        let pooled = self.pooler.forward(&cls)?.tanh()?;
+        self.classifier.forward(&pooled)?.squeeze(1)
+    }
+}
@@ -0,0 +1,347 @@
+use candle_core::{DType, Device, Module, Result, Tensor};
+use candle_nn::ops::softmax_last_dim;
+use candle_nn::rotary_emb::rope;
+use candle_nn::{
+    Embedding, Linear, RmsNorm, VarBuilder, embedding, linear_b, linear_no_bias, rms_norm,
+};
+use candle_transformers::utils::repeat_kv;
+use serde::Deserialize;
+
+// Config of Qwen2 and Mistral style decoders, based on
+// https://huggingface.co/Alibaba-NLP/gte-Qwen2-1.5B-instruct/blob/main/config.json
+// https://huggingface.co/intfloat/e5-mistral-7b-instruct/blob/main/config.json
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Config {
+    /// "qwen2" or "mistral"
+    pub model_type: String,
+    pub vocab_size: usize,
+    pub hidden_size: usize,
+    pub intermediate_size: usize,
+    pub num_hidden_layers: usize,
+    pub num_attention_heads: usize,
+    /// Fewer than `num_attention_heads` for grouped-query attention
+    #[serde(default)]
+    pub num_key_value_heads: Option<usize>,
+    #[serde(default)]
+    pub head_dim: Option<usize>,
+    pub max_position_embeddings: usize,
+    pub rms_norm_eps: f64,
+    #[serde(default = "default_rope_theta")]
+    pub rope_theta: f64,
+    pub hidden_act: candle_nn::Activation,
+    /// Biases on the query, key and value projections; Qwen2 has them
+    #[serde(default)]
+    pub attention_bias: Option<bool>,
+    #[serde(default)]
+    pub sliding_window: Option<usize>,
+    /// Qwen2 lists a `sliding_window` but only uses it when this is set
+    #[serde(default)]
+    pub use_sliding_window: Option<bool>,
+    /// Embedders fine-tuned with bidirectional attention (gte-Qwen2) set this
+    /// to false
+    #[serde(default = "default_is_causal")]
+    pub is_causal: bool,
+}
+
+fn default_rope_theta() -> f64 {
+    10000.0
+}
+
+fn default_is_causal() -> bool {
+    true
+}
+
+impl Config {
+    fn head_dim(&self) -> usize {
+        self.head_dim
+            .unwrap_or(self.hidden_size / self.num_attention_heads)
+    }
+
+    fn num_key_value_heads(&self) -> usize {
+        self.num_key_value_heads.unwrap_or(self.num_attention_heads)
+    }
+
+    fn qkv_bias(&self) -> bool {
+        self.attention_bias.unwrap_or(self.model_type == "qwen2")
+    }
+
+    fn sliding_window(&self) -> Option<usize> {
+        match self.use_sliding_window {
+            Some(false) => None,
+            _ => self.sliding_window,
+        }
+    }
+}
+
+// Rotary position embeddings in the non-interleaved (GPT-NeoX) layout
+#[derive(Clone, Debug)]
+struct RotaryEmbedding {
+    sin: Tensor,
+    cos: Tensor,
+}
+
+impl RotaryEmbedding {
+    fn new(config: &Config, dtype: DType, device: &Device) -> Result<Self> {
+        let head_dim = config.head_dim();
+        let inv_freq: Vec<f32> = (0..head_dim)
+            .step_by(2)
+            .map(|i| 1f32 / config.rope_theta.powf(i as f64 / head_dim as f64) as f32)
+            .collect();
+        let inv_freq = Tensor::new(inv_freq, device)?.reshape((1, ()))?;
+        let positions = Tensor::arange(0u32, config.max_position_embeddings as u32, device)?
+            .to_dtype(DType::F32)?
+            .reshape((config.max_position_embeddings, 1))?;
+        let freqs = positions.matmul(&inv_freq)?;
+        Ok(Self {
+            sin: freqs.sin()?.to_dtype(dtype)?,
+            cos: freqs.cos()?.to_dtype(dtype)?,
+        })
+    }
+
+    /// Rotate `xs` of shape (batch, heads, seq, head_dim)
+    fn apply(&self, xs: &Tensor) -> Result<Tensor> {
+        let seq_len = xs.dim(2)?;
+        let cos = self.cos.narrow(0, 0, seq_len)?;
+        let sin = self.sin.narrow(0, 0, seq_len)?;
+        rope(&xs.contiguous()?, &cos, &sin)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct DecoderAttention {
+    q_proj: Linear,
+    k_proj: Linear,
+    v_proj: Linear,
+    o_proj: Linear,
+    rotary: RotaryEmbedding,
+    num_heads: usize,
+    num_kv_heads: usize,
+    head_dim: usize,
+}
+
+impl DecoderAttention {
+    fn new(vb: VarBuilder, config: &Config, rotary: RotaryEmbedding) -> Result<Self> {
+        let head_dim = config.head_dim();
+        let num_heads = config.num_attention_heads;
+        let num_kv_heads = config.num_key_value_heads();
+        let bias = config.qkv_bias();
+        let q_proj = linear_b(
+            config.hidden_size,
+            num_heads * head_dim,
+            bias,
+            vb.pp("q_proj"),
+        )?;
+        let k_proj = linear_b(
+            config.hidden_size,
+            num_kv_heads * head_dim,
+            bias,
+            vb.pp("k_proj"),
+        )?;
+        let v_proj = linear_b(
+            config.hidden_size,
+            num_kv_heads * head_dim,
+            bias,
+            vb.pp("v_proj"),
+        )?;
+        let o_proj = linear_no_bias(num_heads * head_dim, config.hidden_size, vb.pp("o_proj"))?;
+        Ok(Self {
+            q_proj,
+            k_proj,
+            v_proj,
+            o_proj,
+            rotary,
+            num_heads,
+            num_kv_heads,
+            head_dim,
+        })
+    }
+
+    fn forward(&self, xs: &Tensor, attention_bias: &Tensor) -> Result<Tensor> {
+        let (b_size, seq_len, _) = xs.dims3()?;
+        let heads = |xs: Tensor, num_heads: usize| {
+            xs.reshape((b_size, seq_len, num_heads, self.head_dim))?
+                .transpose(1, 2)
+        };
+        let query = heads(self.q_proj.forward(xs)?, self.num_heads)?;
+        let key = heads(self.k_proj.forward(xs)?, self.num_kv_heads)?;
+        let value = heads(self.v_proj.forward(xs)?, self.num_kv_heads)?;
+
+        let query = self.rotary.apply(&query)?;
+        let key = self.rotary.apply(&key)?;
+        let n_rep = self.num_heads / self.num_kv_heads;
+        let key = repeat_kv(key, n_rep)?.contiguous()?;
+        let value = repeat_kv(value, n_rep)?.contiguous()?;
+
+        // Scores and softmax in f32, half precision overflows on long inputs
+        let query = (query * (1.0 / (self.head_dim as f64).sqrt()))?;
+        let attention_scores = query.matmul(&key.t()?)?.to_dtype(DType::F32)?;
+        let attention_scores = attention_scores.broadcast_add(attention_bias)?;
+        let attention_probs = softmax_last_dim(&attention_scores)?.to_dtype(value.dtype())?;
+        let context = attention_probs.matmul(&value)?.transpose(1, 2)?.reshape((
+            b_size,
+            seq_len,
+            self.num_heads * self.head_dim,
+        ))?;
+        self.o_proj.forward(&context)
+    }
+}
+
+// SwiGLU feed-forward: down(act(gate(x)) * up(x))
+#[derive(Clone, Debug)]
+struct DecoderMlp {
+    gate_proj: Linear,
+    up_proj: Linear,
+    down_proj: Linear,
+    act: candle_nn::Activation,
+}
+
+impl DecoderMlp {
+    fn new(vb: VarBuilder, config: &Config) -> Result<Self> {
+        let gate_proj = linear_no_bias(
+            config.hidden_size,
+            config.intermediate_size,
+            vb.pp("gate_proj"),
+        )?;
+        let up_proj = linear_no_bias(
+            config.hidden_size,
+            config.intermediate_size,
+            vb.pp("up_proj"),
+        )?;
+        let down_proj = linear_no_bias(
+            config.intermediate_size,
+            config.hidden_size,
+            vb.pp("down_proj"),
+        )?;
+        Ok(Self {
+            gate_proj,
+            up_proj,
+            down_proj,
+            act: config.hidden_act,
+        })
+    }
+}
+
+impl Module for DecoderMlp {
+    fn forward(&self, xs: &Tensor) -> Result<Tensor> {
+        let gate = self.act.forward(&self.gate_proj.forward(xs)?)?;
+        self.down_proj.forward(&(gate * self.up_proj.forward(xs)?)?)
+    }
+}
+
+// Pre-norm layer with RMSNorm
+#[derive(Clone, Debug)]
+struct DecoderLayer {
+    input_layernorm: RmsNorm,
+    self_attn: DecoderAttention,
+    post_attention_layernorm: RmsNorm,
+    mlp: DecoderMlp,
+}
+
+impl DecoderLayer {
+    fn new(vb: VarBuilder, config: &Config, rotary: RotaryEmbedding) -> Result<Self> {
+        let input_layernorm = rms_norm(
+            config.hidden_size,
+            config.rms_norm_eps,
+            vb.pp("input_layernorm"),
+        )?;
+        let self_attn = DecoderAttention::new(vb.pp("self_attn"), config, rotary)?;
+        let post_attention_layernorm = rms_norm(
+            config.hidden_size,
+            config.rms_norm_eps,
+            vb.pp("post_attention_layernorm"),
+        )?;
+        let mlp = DecoderMlp::new(vb.pp("mlp"), config)?;
+        Ok(Self {
+            input_layernorm,
+            self_attn,
+            post_attention_layernorm,
+            mlp,
+        })
+    }
+
+    fn forward(&self, xs: &Tensor, attention_bias: &Tensor) -> Result<Tensor> {
+        let attention_output = self
+            .self_attn
+            .forward(&self.input_layernorm.forward(xs)?, attention_bias)?;
+        let xs = (attention_output + xs)?;
+        let mlp_output = self
+            .mlp
+            .forward(&self.post_attention_layernorm.forward(&xs)?)?;
+        mlp_output + xs
+    }
+}
+
+/// Decoder-only transformer (Qwen2, Mistral) returning its last hidden states,
+/// without the language modelling head
+#[derive(Clone, Debug)]
+pub struct DecoderModel {
+    embed_tokens: Embedding,
+    layers: Vec<DecoderLayer>,
+    norm: RmsNorm,
+    is_causal: bool,
+    sliding_window: Option<usize>,
+    pub device: Device,
+}
+
+impl DecoderModel {
+    pub fn load(vb: VarBuilder, config: &Config) -> Result<Self> {
+        let embed_tokens = embedding(config.vocab_size, config.hidden_size, vb.pp("embed_tokens"))?;
+        let rotary = RotaryEmbedding::new(config, vb.dtype(), vb.device())?;
+        let layers = (0..config.num_hidden_layers)
+            .map(|index| {
+                DecoderLayer::new(vb.pp(format!("layers.{index}")), config, rotary.clone())
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let norm = rms_norm(config.hidden_size, config.rms_norm_eps, vb.pp("norm"))?;
+        Ok(Self {
+            embed_tokens,
+            layers,
+            norm,
+            is_causal: config.is_causal,
+            sliding_window: config.sliding_window(),
+            device: vb.device().clone(),
+        })
+    }
+
+    /// Additive f32 bias of shape (batch, 1, seq, seq) hiding padding, future
+    /// tokens for causal models, and tokens beyond the sliding window
+    fn attention_bias(&self, mask: &[Vec<u32>]) -> Result<Tensor> {
+        let b_size = mask.len();
+        let seq_len = mask.first().map_or(0, Vec::len);
+        let mut bias = Vec::with_capacity(b_size * seq_len * seq_len);
+        for mask in mask {
+            for i in 0..seq_len {
+                bias.extend((0..seq_len).map(|j| {
+                    let hidden = mask[j] == 0
+                        || (self.is_causal && j > i)
+                        || self.sliding_window.is_some_and(|window| i >= j + window);
+                    if hidden { f32::MIN } else { 0.0 }
+                }));
+            }
+        }
+        Tensor::from_vec(bias, (b_size, 1, seq_len, seq_len), &self.device)
+    }
+
+    /// Positions count from the start of the padded sequence; rotary
+    /// embeddings only depend on the distance between tokens, so left padding
+    /// does not change the result
+    pub fn forward(
+        &self,
+        input_ids: &Tensor,
+        _token_type_ids: &Tensor,
+        attention_mask: Option<&Tensor>,
+    ) -> Result<Tensor> {
+        let (b_size, seq_len) = input_ids.dims2()?;
+        let mask = match attention_mask {
+            Some(mask) => mask.to_dtype(DType::U32)?.to_vec2::<u32>()?,
+            None => vec![vec![1; seq_len]; b_size],
+        };
+        let attention_bias = self.attention_bias(&mask)?;
+
+        let mut xs = self.embed_tokens.forward(input_ids)?;
+        for layer in self.layers.iter() {
+            xs = layer.forward(&xs, &attention_bias)?;
+        }
+        self.norm.forward(&xs)
+    }
+}
@@ -0,0 +1,345 @@
+// Distillation of static models, following model2vec's `distill`: every token
+// of the vocabulary is run through the transformer on its own (with the
+// tokenizer's special tokens around it), and the pooled vectors become the
+// lookup table of a model2vec model that `static_implementation` loads.
+use super::{EmbeddingError, TextEmbedder};
+use candle_core::{DType, Device, Tensor};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use tokenizers::{Encoding, Token, pad_encodings};
+use tracing::{debug, info};
+use web_time::Instant;
+
+/// Settings of `Embedder::distill`
+#[derive(Clone, Debug, Default)]
+pub struct DistillOptions {
+    pub(crate) pca_dims: Option<usize>,
+    pub(crate) sif_coefficient: Option<f32>,
+}
+
+impl DistillOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reduce the token vectors to `dims` dimensions with PCA (default: keep
+    /// the model's dimension)
+    pub fn pca_dims(mut self, dims: usize) -> Self {
+        self.pca_dims = Some(dims);
+        self
+    }
+
+    /// Weight tokens by `coefficient / (coefficient + p)`, where `p` is the
+    /// frequency estimated from the token's rank in the vocabulary, so common
+    /// tokens count less in the average; model2vec uses 1e-4 (default: no
+    /// weighting)
+    pub fn sif_coefficient(mut self, coefficient: f32) -> Self {
+        self.sif_coefficient = Some(coefficient);
+        self
+    }
+}
+
+/// Shape of a distilled static model
+#[derive(Clone, Debug, Serialize)]
+#[non_exhaustive]
+pub struct DistilledModel {
+    /// Number of rows of the lookup table, one per token
+    pub vocab_size: usize,
+    /// Length of the embedding vectors
+    pub dimension: usize,
+}
+
+impl TextEmbedder {
+    /// Embed every token of the vocabulary and write a static model to
+    /// `folder`: `tokenizer.json`, `model.safetensors` and `config.json` in the
+    /// model2vec format, loadable as `model2vec`
+    pub fn distill(
+        &mut self,
+        folder: &Path,
+        batch_size: usize,
+        options: &DistillOptions,
+    ) -> Result<DistilledModel, EmbeddingError> {
+        // Only models that pool one vector per text have token vectors to keep
+        let kind = if self.info.sparse {
+            Some("a sparse model")
+        } else if self.info.multi_vector {
+            Some("a multi-vector model")
+        } else if self.info.cross_encoder {
+            Some("a cross-encoder")
+        } else {
+            None
+        };
+        if let Some(kind) = kind {
+            return Err(EmbeddingError::ModelTypeError(format!(
+                "{} is {kind}, which cannot be distilled into a static model",
+                self.info.model_id
+            )));
+        }
+
+        let started = Instant::now();
+        // One row per ID, also for IDs missing from the vocabulary
+        let vocab_size = self
+            .tokenizer
+            .get_vocab(true)
+            .into_values()
+            .max()
+            .map_or(0, |id| id as usize + 1);
+        info!(
+            model_id = %self.info.model_id,
+            vocab_size,
+            "Distilling static model"
+        );
+
+        // Token vectors are stored unnormalized; the static model normalizes
+        // the averages instead
+        self.configure_padding();
+        let normalize = std::mem::replace(&mut self.info.normalize, false);
+        let table = self.embed_vocabulary(vocab_size, batch_size.max(1));
+        self.info.normalize = normalize;
+        let mut table = table?;
+
+        if let Some(dims) = options.pca_dims {
+            table = pca(&table, dims)?;
+        }
+        if let Some(coefficient) = options.sif_coefficient {
+            sif_weighting(&mut table, coefficient);
+        }
+        let dimension = table.first().map_or(0, Vec::len);
+        self.save_static(folder, table, dimension, options)?;
+
+        info!(
+            path = %folder.display(),
+            dimension,
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "Static model written"
+        );
+        Ok(DistilledModel {
+            vocab_size,
+            dimension,
+        })
+    }
+
+    /// Pooled vector of each token ID below `vocab_size`
+    fn embed_vocabulary(
+        &self,
+        vocab_size: usize,
+        batch_size: usize,
+    ) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        let padding = self.tokenizer.get_padding().cloned().unwrap_or_default();
+        let ids: Vec<u32> = (0..vocab_size as u32).collect();
+        let mut table = Vec::with_capacity(vocab_size);
+        for chunk in ids.chunks(batch_size) {
+            let mut encodings = chunk
+                .iter()
+                .map(|&id| self.token_encoding(id))
+                .collect::<Result<Vec<_>, _>>()?;
+            pad_encodings(&mut encodings, &padding)?;
+            table.extend(self.embed_encodings(&encodings)?);
+            debug!(done = table.len(), total = vocab_size, "Distilling");
+        }
+        Ok(table)
+    }
+
+    /// Encoding of a single token, with the special tokens the tokenizer adds
+    /// around texts
+    fn token_encoding(&self, id: u32) -> Result<Encoding, EmbeddingError> {
+        let token = self.tokenizer.id_to_token(id).unwrap_or_default();
+        let encoding = Encoding::from_tokens(vec![Token::new(id, token, (0, 0))], 0);
+        Ok(self.tokenizer.post_process(encoding, None, true)?)
+    }
+
+    fn save_static(
+        &self,
+        folder: &Path,
+        table: Vec<Vec<f32>>,
+        dimension: usize,
+        options: &DistillOptions,
+    ) -> Result<(), EmbeddingError> {
+        std::fs::create_dir_all(folder)?;
+
+        let rows = table.len();
+        let table = Tensor::from_vec(table.concat(), (rows, dimension), &Device::Cpu)?;
+        candle_core::safetensors::save(
+            &HashMap::from([("embeddings".to_string(), table)]),
+            folder.join("model.safetensors"),
+        )?;
+
+        // Static models tokenize whole texts, without padding or truncation
+        let mut tokenizer = self.tokenizer.clone();
+        tokenizer.with_truncation(None)?;
+        tokenizer.with_padding(None);
+        tokenizer.save(folder.join("tokenizer.json"), true)?;
+
+        let config = serde_json::json!({
+            "model_type": "model2vec",
+            "architectures": ["StaticModel"],
+            "tokenizer_name": self.info.model_id,
+            "apply_pca": options.pca_dims,
+            "sif_coefficient": options.sif_coefficient,
+            "hidden_dim": dimension,
+            "normalize": true,
+        });
+        std::fs::write(
+            folder.join("config.json"),
+            serde_json::to_string_pretty(&config)?,
+        )?;
+        Ok(())
+    }
+}
+
+/// Extra basis vectors `pca` iterates with beyond the requested components,
+/// as scikit-learn's randomized solver
+const OVERSAMPLES: usize = 10;
+
+/// Project the centered rows onto their `dims` principal components, largest
+/// variance first; like scikit-learn, each component is signed so that its
+/// largest coefficient is positive.
+///
+/// The components are found like scikit-learn's randomized solver: a few
+/// power iterations of a random basis slightly larger than `dims` with the
+/// covariance matrix, then the eigenvectors of the covariance within that
+/// basis. Each iteration costs O(d²·dims) rather than the O(d³) of a full
+/// eigendecomposition of the covariance.
+fn pca(rows: &[Vec<f32>], dims: usize) -> candle_core::Result<Vec<Vec<f32>>> {
+    let (n, d) = (rows.len(), rows.first().map_or(0, Vec::len));
+    let dims = dims.min(d);
+    if n == 0 || dims == 0 {
+        return Ok(rows.to_vec());
+    }
+
+    let x = Tensor::from_vec(rows.concat(), (n, d), &Device::Cpu)?;
+    let centered = x.broadcast_sub(&x.mean_keepdim(0)?)?;
+    let covariance = centered.t()?.matmul(&centered)?.to_dtype(DType::F64)?;
+
+    // (d, k) orthonormal basis that captures the top `dims` components
+    let k = (dims + OVERSAMPLES).min(d);
+    let basis = if k == d {
+        Tensor::eye(d, DType::F64, &Device::Cpu)?
+    } else {
+        // Deterministic start that is not orthogonal to any component in practice
+        let start: Vec<f64> = (0..d * k)
+            .map(|i| ((i + 1) as f64 * 0.618_033_988_749_895).fract() - 0.5)
+            .collect();
+        let mut basis = orthonormalize(&Tensor::from_vec(start, (d, k), &Device::Cpu)?)?;
+        let iterations = if dims < d / 10 { 7 } else { 4 };
+        for _ in 0..iterations {
+            basis = orthonormalize(&covariance.matmul(&basis)?)?;
+        }
+        basis
+    };
+
+    // Eigenvectors of the covariance within the basis, mapped back to (d, k)
+    let projected = basis.t()?.matmul(&covariance.matmul(&basis)?)?;
+    let (values, vectors) = symmetric_eigen(projected.flatten_all()?.to_vec1::<f64>()?, k);
+    let vectors = basis
+        .matmul(&Tensor::from_vec(vectors, (k, k), &Device::Cpu)?)?
+        .to_vec2::<f64>()?;
+
+    let mut order: Vec<usize> = (0..k).collect();
+    order.sort_by(|&a, &b| values[b].total_cmp(&values[a]));
+    // (d, dims) matrix with the components as columns
+    let mut components = vec![0f32; d * dims];
+    for (column, &c) in order[..dims].iter().enumerate() {
+        let largest = (0..d)
+            .map(|i| vectors[i][c])
+            .max_by(|a, b| a.abs().total_cmp(&b.abs()))
+            .unwrap_or(0.0);
+        let sign = if largest < 0.0 { -1.0 } else { 1.0 };
+        for i in 0..d {
+            components[i * dims + column] = (vectors[i][c] * sign) as f32;
+        }
+    }
+    let components = Tensor::from_vec(components, (d, dims), &Device::Cpu)?;
+    centered.matmul(&components)?.to_vec2()
+}
+
+/// Orthonormal basis of the columns of the (d, k) matrix `a`, by modified
+/// Gram–Schmidt; a column with nothing left after removing the earlier ones
+/// becomes zero
+fn orthonormalize(a: &Tensor) -> candle_core::Result<Tensor> {
+    let (d, k) = a.dims2()?;
+    let mut columns = a.t()?.to_vec2::<f64>()?;
+    for c in 0..k {
+        let (done, rest) = columns.split_at_mut(c);
+        let column = &mut rest[0];
+        let before = column.iter().map(|v| v * v).sum::<f64>().sqrt();
+        for earlier in done.iter() {
+            let dot: f64 = column.iter().zip(earlier).map(|(a, b)| a * b).sum();
+            column
+                .iter_mut()
+                .zip(earlier)
+                .for_each(|(v, e)| *v -= dot * e);
+        }
+        let norm = column.iter().map(|v| v * v).sum::<f64>().sqrt();
+        let scale = if norm > before * 1e-10 {
+            1.0 / norm
+        } else {
+            0.0
+        };
+        column.iter_mut().for_each(|v| *v *= scale);
+    }
+    Tensor::from_vec(columns.concat(), (k, d), &Device::Cpu)?.t()
+}
+
+/// Eigenvalues and eigenvectors (the columns of the returned row-major
+/// matrix) of the symmetric `d`×`d` matrix `a`, by cyclic Jacobi rotations;
+/// O(d³) per sweep, so only for the small projected matrix of `pca`
+fn symmetric_eigen(mut a: Vec<f64>, d: usize) -> (Vec<f64>, Vec<f64>) {
+    let mut v = vec![0f64; d * d];
+    for i in 0..d {
+        v[i * d + i] = 1.0;
+    }
+    let total: f64 = a.iter().map(|x| x * x).sum();
+    for _ in 0..100 {
+        let off_diagonal: f64 = (0..d)
+            .flat_map(|i| (0..d).filter(move |&j| j != i).map(move |j| (i, j)))
+            .map(|(i, j)| a[i * d + j] * a[i * d + j])
+            .sum();
+        if off_diagonal <= total * 1e-24 {
+            break;
+        }
+        for p in 0..d {
+            for q in p + 1..d {
+                let apq = a[p * d + q];
+                if apq == 0.0 {
+                    continue;
+                }
+                // Rotation that zeroes a[p][q], see Numerical Recipes 11.1
+                let theta = (a[q * d + q] - a[p * d + p]) / (2.0 * apq);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                for k in 0..d {
+                    let (akp, akq) = (a[k * d + p], a[k * d + q]);
+                    a[k * d + p] = c * akp - s * akq;
+                    a[k * d + q] = s * akp + c * akq;
+                }
+                for k in 0..d {
+                    let (apk, aqk) = (a[p * d + k], a[q * d + k]);
+                    a[p * d + k] = c * apk - s * aqk;
+                    a[q * d + k] = s * apk + c * aqk;
+                }
+                for k in 0..d {
+                    let (vkp, vkq) = (v[k * d + p], v[k * d + q]);
+                    v[k * d + p] = c * vkp - s * vkq;
+                    v[k * d + q] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+    ((0..d).map(|i| a[i * d + i]).collect(), v)
+}
+
+/// Scale each row by `coefficient / (coefficient + p)`, with the Zipf
+/// frequency `p` of its rank; vocabularies list frequent tokens first
+fn sif_weighting(rows: &mut [Vec<f32>], coefficient: f32) {
+    let inverse_ranks: Vec<f64> = (0..rows.len())
+        .map(|rank| 1.0 / (rank + 2) as f64)
+        .collect();
+    let total: f64 = inverse_ranks.iter().sum();
+    for (row, inverse_rank) in rows.iter_mut().zip(inverse_ranks) {
+        let weight = (coefficient as f64 / (coefficient as f64 + inverse_rank / total)) as f32;
+        row.iter_mut().for_each(|v| *v *= weight);
+    }
+}
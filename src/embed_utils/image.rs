@@ -0,0 +1,134 @@
+// Images for the text–image models (CLIP, SigLIP): PNG and JPEG files are
+// decoded locally and turned into the pixel values of the vision tower the
+// way the transformers image processors of these models do, then embedded
+// into the vector space of the texts.
+use super::{EmbeddingError, TextEmbedder, normalize_l2};
+use candle_core::{DType, Device, Tensor};
+use image::imageops::{self, FilterType};
+use tracing::debug;
+use web_time::Instant;
+
+/// Channel statistics of the images CLIP was trained on
+const CLIP_MEAN: [f32; 3] = [0.48145466, 0.4578275, 0.40821073];
+const CLIP_STD: [f32; 3] = [0.26862954, 0.2613026, 0.2757771];
+
+/// How images become the `(3, size, size)` pixel values a vision tower expects
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImagePreprocessing {
+    /// Side of the square the vision tower takes
+    pub size: usize,
+    /// Resize the shorter side to `size` and crop the center square (CLIP)
+    /// instead of stretching the whole image to `size`×`size` (SigLIP)
+    pub center_crop: bool,
+    /// Subtracted from the RGB values, scaled to [0, 1]
+    pub mean: [f32; 3],
+    /// The centered RGB values are divided by this
+    pub std: [f32; 3],
+}
+
+impl ImagePreprocessing {
+    /// Preprocessing of CLIP models
+    pub fn clip(size: usize) -> Self {
+        Self {
+            size,
+            center_crop: true,
+            mean: CLIP_MEAN,
+            std: CLIP_STD,
+        }
+    }
+
+    /// Preprocessing of SigLIP models
+    pub fn siglip(size: usize) -> Self {
+        Self {
+            size,
+            center_crop: false,
+            mean: [0.5; 3],
+            std: [0.5; 3],
+        }
+    }
+
+    /// Decode a PNG or JPEG image and return its `(3, size, size)` pixel values
+    pub fn pixel_values(&self, bytes: &[u8], device: &Device) -> Result<Tensor, EmbeddingError> {
+        let image = image::load_from_memory(bytes)?.to_rgb8();
+        let (width, height) = image.dimensions();
+        let size = self.size as u32;
+        // Bicubic like the transformers processors; images already at the
+        // target size are copied as they are
+        let image = if self.center_crop {
+            let (short, long) = (width.min(height), width.max(height));
+            let long = (size as u64 * long as u64 / short.max(1) as u64) as u32;
+            let (width, height) = if width <= height {
+                (size, long)
+            } else {
+                (long, size)
+            };
+            let resized = imageops::resize(&image, width, height, FilterType::CatmullRom);
+            let (left, top) = ((width - size) / 2, (height - size) / 2);
+            imageops::crop_imm(&resized, left, top, size, size).to_image()
+        } else {
+            imageops::resize(&image, size, size, FilterType::CatmullRom)
+        };
+
+        let plane = (size * size) as usize;
+        let mut values = vec![0f32; 3 * plane];
+        for (i, pixel) in image.pixels().enumerate() {
+            for channel in 0..3 {
+                let value = pixel.0[channel] as f32 / 255.0;
+                values[channel * plane + i] = (value - self.mean[channel]) / self.std[channel];
+            }
+        }
+        Ok(Tensor::from_vec(values, (3, self.size, self.size), device)?)
+    }
+}
+
+impl TextEmbedder {
+    /// Embed images (the bytes of PNG or JPEG files), `batch_size` at a time,
+    /// into the vector space of the texts; only text–image models have a
+    /// vision tower
+    pub fn embed_images(
+        &self,
+        images: &[&[u8]],
+        batch_size: usize,
+    ) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        let Some(preprocessing) = self.model.image_preprocessing() else {
+            return Err(EmbeddingError::ModelTypeError(format!(
+                "{} does not embed images",
+                self.info.model_id
+            )));
+        };
+        let device = self.model.device();
+        let mut all_embeddings = Vec::with_capacity(images.len());
+        let started = Instant::now();
+        debug!(images = images.len(), batch_size, "Embedding images");
+
+        for chunk in images.chunks(batch_size.max(1)) {
+            let pixel_values = chunk
+                .iter()
+                .map(|bytes| preprocessing.pixel_values(bytes, device))
+                .collect::<Result<Vec<_>, _>>()?;
+            let pixel_values = Tensor::stack(&pixel_values, 0)?;
+            let embeddings = self
+                .model
+                .forward_images(&pixel_values)?
+                .to_dtype(DType::F32)?;
+            let embeddings = if self.info.normalize {
+                normalize_l2(&embeddings)?
+            } else {
+                embeddings
+            };
+            all_embeddings.extend(embeddings.to_vec2()?);
+        }
+        debug!(
+            images = images.len(),
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "Embedding finished"
+        );
+        Ok(all_embeddings)
+    }
+
+    /// Embed a single image
+    pub fn embed_image(&self, image: &[u8]) -> Result<Vec<f32>, EmbeddingError> {
+        let embeddings = self.embed_images(&[image], 1)?;
+        Ok(embeddings.into_iter().next().unwrap_or_default())
+    }
+}
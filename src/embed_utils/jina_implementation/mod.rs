@@ -6,6 +6,7 @@ use candle_nn::{
 use serde::Deserialize;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum PositionEmbeddingType {
     Alibi,
     Absolute,
@@ -0,0 +1,380 @@
+use candle_core::{D, DType, Device, IndexOp, Module, Result, Tensor};
+use candle_nn::ops::softmax_last_dim;
+use candle_nn::rotary_emb::rope;
+use candle_nn::{
+    Embedding, LayerNorm, Linear, VarBuilder, embedding, layer_norm, layer_norm_no_bias, linear_b,
+};
+use serde::Deserialize;
+
+// Config of ModernBERT, based on
+// https://huggingface.co/answerdotai/ModernBERT-base/blob/main/config.json
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Config {
+    pub vocab_size: usize,
+    pub hidden_size: usize,
+    pub intermediate_size: usize,
+    pub num_hidden_layers: usize,
+    pub num_attention_heads: usize,
+    pub max_position_embeddings: usize,
+    pub hidden_activation: candle_nn::Activation,
+    pub norm_eps: f64,
+    #[serde(default)]
+    pub norm_bias: bool,
+    #[serde(default)]
+    pub attention_bias: bool,
+    #[serde(default)]
+    pub mlp_bias: bool,
+    pub global_rope_theta: f64,
+    /// Defaults to `global_rope_theta` when missing
+    #[serde(default)]
+    pub local_rope_theta: Option<f64>,
+    /// Every n-th layer, starting with the first, attends to the whole sequence
+    pub global_attn_every_n_layers: usize,
+    /// Width of the sliding window of the other layers
+    pub local_attention: usize,
+}
+
+impl Config {
+    pub fn base() -> Self {
+        Self {
+            vocab_size: 50368,
+            hidden_size: 768,
+            intermediate_size: 1152,
+            num_hidden_layers: 22,
+            num_attention_heads: 12,
+            max_position_embeddings: 8192,
+            hidden_activation: candle_nn::Activation::Gelu,
+            norm_eps: 1e-5,
+            norm_bias: false,
+            attention_bias: false,
+            mlp_bias: false,
+            global_rope_theta: 160000.0,
+            local_rope_theta: Some(10000.0),
+            global_attn_every_n_layers: 3,
+            local_attention: 128,
+        }
+    }
+
+    fn norm(&self, vb: VarBuilder) -> Result<LayerNorm> {
+        if self.norm_bias {
+            layer_norm(self.hidden_size, self.norm_eps, vb)
+        } else {
+            layer_norm_no_bias(self.hidden_size, self.norm_eps, vb)
+        }
+    }
+}
+
+// Rotary position embeddings in the non-interleaved (GPT-NeoX) layout,
+// shared by all layers with the same theta
+#[derive(Clone, Debug)]
+struct RotaryEmbedding {
+    sin: Tensor,
+    cos: Tensor,
+}
+
+impl RotaryEmbedding {
+    fn new(config: &Config, theta: f64, dtype: DType, device: &Device) -> Result<Self> {
+        let head_dim = config.hidden_size / config.num_attention_heads;
+        let inv_freq: Vec<f32> = (0..head_dim)
+            .step_by(2)
+            .map(|i| 1f32 / theta.powf(i as f64 / head_dim as f64) as f32)
+            .collect();
+        let inv_freq = Tensor::new(inv_freq, device)?.reshape((1, ()))?;
+        let positions = Tensor::arange(0u32, config.max_position_embeddings as u32, device)?
+            .to_dtype(DType::F32)?
+            .reshape((config.max_position_embeddings, 1))?;
+        let freqs = positions.matmul(&inv_freq)?;
+        Ok(Self {
+            sin: freqs.sin()?.to_dtype(dtype)?,
+            cos: freqs.cos()?.to_dtype(dtype)?,
+        })
+    }
+
+    /// Rotate `xs` of shape (batch, heads, seq, head_dim)
+    fn apply(&self, xs: &Tensor) -> Result<Tensor> {
+        let seq_len = xs.dim(2)?;
+        let cos = self.cos.narrow(0, 0, seq_len)?;
+        let sin = self.sin.narrow(0, 0, seq_len)?;
+        rope(&xs.contiguous()?, &cos, &sin)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct ModernBertAttention {
+    wqkv: Linear,
+    wo: Linear,
+    rotary: RotaryEmbedding,
+    /// Tokens attend to neighbours at most this far away; None for global layers
+    window: Option<usize>,
+    num_heads: usize,
+    head_dim: usize,
+}
+
+impl ModernBertAttention {
+    fn new(
+        vb: VarBuilder,
+        config: &Config,
+        rotary: RotaryEmbedding,
+        window: Option<usize>,
+    ) -> Result<Self> {
+        let hidden_size = config.hidden_size;
+        let wqkv = linear_b(
+            hidden_size,
+            3 * hidden_size,
+            config.attention_bias,
+            vb.pp("Wqkv"),
+        )?;
+        let wo = linear_b(hidden_size, hidden_size, config.attention_bias, vb.pp("Wo"))?;
+        Ok(Self {
+            wqkv,
+            wo,
+            rotary,
+            window,
+            num_heads: config.num_attention_heads,
+            head_dim: hidden_size / config.num_attention_heads,
+        })
+    }
+
+    /// Additive bias hiding tokens outside the sliding window
+    fn window_bias(&self, seq_len: usize, dtype: DType, device: &Device) -> Result<Option<Tensor>> {
+        let window = match self.window {
+            Some(window) if window + 1 < seq_len => window,
+            _ => return Ok(None),
+        };
+        let bias: Vec<f32> = (0..seq_len)
+            .flat_map(|i| {
+                (0..seq_len).map(move |j| {
+                    if i.abs_diff(j) <= window {
+                        0.0
+                    } else {
+                        f32::NEG_INFINITY
+                    }
+                })
+            })
+            .collect();
+        Ok(Some(
+            Tensor::from_vec(bias, (seq_len, seq_len), device)?.to_dtype(dtype)?,
+        ))
+    }
+
+    /// `xs` holds the tokens of all sequences back to back, `sequences` the
+    /// offset and length of each
+    fn forward(&self, xs: &Tensor, sequences: &[(usize, usize)]) -> Result<Tensor> {
+        let (total, hidden_size) = xs.dims2()?;
+        let qkv = self
+            .wqkv
+            .forward(xs)?
+            .reshape((total, 3, self.num_heads, self.head_dim))?;
+
+        let mut contexts = Vec::with_capacity(sequences.len());
+        for &(offset, seq_len) in sequences {
+            // (3, 1, heads, seq, head_dim)
+            let qkv = qkv.narrow(0, offset, seq_len)?.permute((1, 2, 0, 3))?;
+            let qkv = qkv.unsqueeze(1)?;
+            let query = self.rotary.apply(&qkv.i(0)?)?;
+            let key = self.rotary.apply(&qkv.i(1)?)?;
+            let value = qkv.i(2)?.contiguous()?;
+
+            let attention_scores = query.matmul(&key.t()?)?;
+            let mut attention_scores = (attention_scores / (self.head_dim as f64).sqrt())?;
+            if let Some(bias) = self.window_bias(seq_len, xs.dtype(), xs.device())? {
+                attention_scores = attention_scores.broadcast_add(&bias)?;
+            }
+            let attention_probs = softmax_last_dim(&attention_scores)?;
+            let context = attention_probs
+                .matmul(&value)?
+                .transpose(1, 2)?
+                .reshape((seq_len, hidden_size))?;
+            contexts.push(context);
+        }
+        self.wo.forward(&Tensor::cat(&contexts, 0)?)
+    }
+}
+
+// GeGLU feed-forward: Wo(act(input) * gate), with input and gate from one Wi
+#[derive(Clone, Debug)]
+struct ModernBertMlp {
+    wi: Linear,
+    act: candle_nn::Activation,
+    wo: Linear,
+    intermediate_size: usize,
+}
+
+impl ModernBertMlp {
+    fn new(vb: VarBuilder, config: &Config) -> Result<Self> {
+        let wi = linear_b(
+            config.hidden_size,
+            config.intermediate_size * 2,
+            config.mlp_bias,
+            vb.pp("Wi"),
+        )?;
+        let wo = linear_b(
+            config.intermediate_size,
+            config.hidden_size,
+            config.mlp_bias,
+            vb.pp("Wo"),
+        )?;
+        Ok(Self {
+            wi,
+            act: config.hidden_activation,
+            wo,
+            intermediate_size: config.intermediate_size,
+        })
+    }
+}
+
+impl Module for ModernBertMlp {
+    fn forward(&self, xs: &Tensor) -> Result<Tensor> {
+        let xs = self.wi.forward(xs)?;
+        let input = xs.narrow(D::Minus1, 0, self.intermediate_size)?;
+        let gate = xs.narrow(D::Minus1, self.intermediate_size, self.intermediate_size)?;
+        self.wo.forward(&(self.act.forward(&input)? * gate)?)
+    }
+}
+
+// Pre-norm layer; the first one has no attention norm since the embeddings
+// are already normalized
+#[derive(Clone, Debug)]
+struct ModernBertLayer {
+    attn_norm: Option<LayerNorm>,
+    attn: ModernBertAttention,
+    mlp_norm: LayerNorm,
+    mlp: ModernBertMlp,
+}
+
+impl ModernBertLayer {
+    fn new(
+        vb: VarBuilder,
+        config: &Config,
+        index: usize,
+        rotary: RotaryEmbedding,
+        window: Option<usize>,
+    ) -> Result<Self> {
+        let attn_norm = match index {
+            0 => None,
+            _ => Some(config.norm(vb.pp("attn_norm"))?),
+        };
+        let attn = ModernBertAttention::new(vb.pp("attn"), config, rotary, window)?;
+        let mlp_norm = config.norm(vb.pp("mlp_norm"))?;
+        let mlp = ModernBertMlp::new(vb.pp("mlp"), config)?;
+        Ok(Self {
+            attn_norm,
+            attn,
+            mlp_norm,
+            mlp,
+        })
+    }
+
+    fn forward(&self, xs: &Tensor, sequences: &[(usize, usize)]) -> Result<Tensor> {
+        let normed = match &self.attn_norm {
+            Some(norm) => norm.forward(xs)?,
+            None => xs.clone(),
+        };
+        let xs = (self.attn.forward(&normed, sequences)? + xs)?;
+        let mlp_output = self.mlp.forward(&self.mlp_norm.forward(&xs)?)?;
+        mlp_output + xs
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ModernBertModel {
+    tok_embeddings: Embedding,
+    embeddings_norm: LayerNorm,
+    layers: Vec<ModernBertLayer>,
+    final_norm: LayerNorm,
+    pub device: Device,
+}
+
+impl ModernBertModel {
+    pub fn load(vb: VarBuilder, config: &Config) -> Result<Self> {
+        let tok_embeddings = embedding(
+            config.vocab_size,
+            config.hidden_size,
+            vb.pp("embeddings.tok_embeddings"),
+        )?;
+        let embeddings_norm = config.norm(vb.pp("embeddings.norm"))?;
+
+        let global_rotary =
+            RotaryEmbedding::new(config, config.global_rope_theta, vb.dtype(), vb.device())?;
+        let local_rotary = RotaryEmbedding::new(
+            config,
+            config.local_rope_theta.unwrap_or(config.global_rope_theta),
+            vb.dtype(),
+            vb.device(),
+        )?;
+        let layers = (0..config.num_hidden_layers)
+            .map(|index| {
+                let vb = vb.pp(format!("layers.{index}"));
+                if index % config.global_attn_every_n_layers == 0 {
+                    ModernBertLayer::new(vb, config, index, global_rotary.clone(), None)
+                } else {
+                    let window = Some(config.local_attention / 2);
+                    ModernBertLayer::new(vb, config, index, local_rotary.clone(), window)
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let final_norm = config.norm(vb.pp("final_norm"))?;
+
+        Ok(Self {
+            tok_embeddings,
+            embeddings_norm,
+            layers,
+            final_norm,
+            device: vb.device().clone(),
+        })
+    }
+
+    /// Padding tokens are removed before the encoder runs and the real tokens
+    /// of all sequences are processed together; their positions in the output
+    /// are zero
+    pub fn forward(
+        &self,
+        input_ids: &Tensor,
+        _token_type_ids: &Tensor,
+        attention_mask: Option<&Tensor>,
+    ) -> Result<Tensor> {
+        let (b_size, seq_len) = input_ids.dims2()?;
+        let mask = match attention_mask {
+            Some(mask) => mask.to_dtype(DType::U32)?.to_vec2::<u32>()?,
+            None => vec![vec![1; seq_len]; b_size],
+        };
+
+        // Index of each real token in the flattened batch, and where each
+        // sequence starts among them
+        let mut indices = Vec::new();
+        let mut sequences = Vec::with_capacity(b_size);
+        for (row, mask) in mask.iter().enumerate() {
+            let offset = indices.len();
+            indices.extend(
+                mask.iter()
+                    .enumerate()
+                    .filter(|&(_, &m)| m != 0)
+                    .map(|(column, _)| (row * seq_len + column) as u32),
+            );
+            sequences.push((offset, indices.len() - offset));
+        }
+        let total = indices.len();
+
+        let input_ids = input_ids
+            .flatten_all()?
+            .index_select(&Tensor::new(indices.as_slice(), input_ids.device())?, 0)?;
+        let xs = self.tok_embeddings.forward(&input_ids)?;
+        let mut xs = self.embeddings_norm.forward(&xs)?;
+        for layer in self.layers.iter() {
+            xs = layer.forward(&xs, &sequences)?;
+        }
+        let xs = self.final_norm.forward(&xs)?;
+
+        // Scatter the tokens back, padding positions pick the zero row at `total`
+        let mut positions = vec![total as u32; b_size * seq_len];
+        for (packed, &index) in indices.iter().enumerate() {
+            positions[index as usize] = packed as u32;
+        }
+        let positions = Tensor::new(positions, input_ids.device())?;
+        let hidden_size = xs.dim(1)?;
+        let zeros = Tensor::zeros((1, hidden_size), xs.dtype(), xs.device())?;
+        Tensor::cat(&[xs, zeros], 0)?
+            .index_select(&positions, 0)?
+            .reshape((b_size, seq_len, hidden_size))
+    }
+}
@@ -0,0 +1,172 @@
+// Multi-vector embeddings for late interaction (ColBERT). Instead of pooling
+// the token embeddings of a text into one vector, every token keeps its own;
+// `similarity::max_sim` then matches each query token with its most similar
+// document token. Like ColBERT, texts get a `[Q]` or `[D]` marker after
+// `[CLS]`, queries are padded with `[MASK]` to a fixed length and keep every
+// token, and documents leave out padding and punctuation tokens.
+use super::{EmbeddingError, Task, TextEmbedder};
+use tokenizers::Encoding;
+use tracing::debug;
+use web_time::Instant;
+
+/// One vector per token of a text, in order
+pub type MultiVectorEmbedding = Vec<Vec<f32>>;
+
+/// ColBERT's `[Q]` marker, an unused token of the BERT vocabulary
+const QUERY_MARKER: &str = "[unused0]";
+/// ColBERT's `[D]` marker
+const DOCUMENT_MARKER: &str = "[unused1]";
+/// Queries are padded with it, letting the model expand them with extra terms
+const MASK: &str = "[MASK]";
+
+impl TextEmbedder {
+    /// Token embeddings of `column` embedded as `task`, `batch_size` texts at
+    /// a time, each L2-normalized if normalization is enabled. Queries have
+    /// `query_max_tokens` vectors, including those of their `[MASK]` padding;
+    /// documents have one per token that is not punctuation. Only
+    /// late-interaction models (`colbert`) produce them.
+    pub fn embed_tokens(
+        &mut self,
+        column: Vec<String>,
+        task: Task,
+        batch_size: usize,
+    ) -> Result<Vec<MultiVectorEmbedding>, EmbeddingError> {
+        if !self.info.multi_vector {
+            return Err(EmbeddingError::ModelTypeError(format!(
+                "{} is not a multi-vector model",
+                self.info.model_id
+            )));
+        }
+        self.configure_padding();
+        // Queries have a fixed length, documents are padded to the longest
+        let (marker, length) = match task {
+            Task::Query => (
+                QUERY_MARKER,
+                self.info
+                    .query_max_tokens
+                    .map(|length| length.min(self.info.max_tokens)),
+            ),
+            Task::Document => (DOCUMENT_MARKER, None),
+        };
+        let marker = self.token_id(marker)?;
+        let pad_id = match length {
+            Some(_) => self.token_id(MASK)?,
+            None => self
+                .tokenizer
+                .get_padding()
+                .map_or(0, |padding| padding.pad_id),
+        };
+
+        let mut all_embeddings = Vec::with_capacity(column.len());
+        let started = Instant::now();
+        debug!(texts = column.len(), ?task, batch_size, "Embedding tokens");
+
+        for chunk in column.chunks(batch_size.max(1)) {
+            let chunk = match self.task_prefix(task) {
+                Some(prefix) => chunk.iter().map(|text| format!("{prefix}{text}")).collect(),
+                None => chunk.to_vec(),
+            };
+            let ids = self
+                .tokenizer
+                .encode_batch(chunk, true)?
+                .iter()
+                .map(|tokens| self.marked_ids(tokens, marker, length))
+                .collect::<Vec<_>>();
+            let longest = ids.iter().map(Vec::len).max().unwrap_or(0);
+            let tokens: Vec<Encoding> = ids
+                .iter()
+                .map(|ids| self.padded_encoding(ids, pad_id, length.unwrap_or(longest)))
+                .collect();
+            let (embeddings, _) = self.token_embeddings(&tokens)?;
+
+            for (rows, ids) in embeddings.to_vec3::<f32>()?.into_iter().zip(&ids) {
+                let vectors: Vec<_> = match task {
+                    // Every query token is kept, [MASK] padding included
+                    Task::Query => rows.into_iter().take(length.unwrap_or(ids.len())).collect(),
+                    _ => rows
+                        .into_iter()
+                        .zip(ids)
+                        .filter(|&(_, &id)| !self.is_punctuation(id))
+                        .map(|(vector, _)| vector)
+                        .collect(),
+                };
+                let vectors = vectors
+                    .into_iter()
+                    .map(|mut vector| {
+                        let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+                        if self.info.normalize && norm > 0.0 {
+                            vector.iter_mut().for_each(|v| *v /= norm);
+                        }
+                        vector
+                    })
+                    .collect();
+                all_embeddings.push(vectors);
+            }
+        }
+        debug!(
+            texts = column.len(),
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "Token embedding finished"
+        );
+        Ok(all_embeddings)
+    }
+
+    fn token_id(&self, token: &str) -> Result<u32, EmbeddingError> {
+        self.tokenizer.token_to_id(token).ok_or_else(|| {
+            EmbeddingError::ModelTypeError(format!(
+                "The tokenizer of {} has no {token} token",
+                self.info.model_id
+            ))
+        })
+    }
+
+    /// IDs of the tokens of a text, without padding, with `marker` after
+    /// `[CLS]`. A text of `max_length` or more tokens loses the ones before
+    /// its final `[SEP]`, making room for the marker.
+    fn marked_ids(&self, tokens: &Encoding, marker: u32, max_length: Option<usize>) -> Vec<u32> {
+        let mut ids: Vec<u32> = tokens
+            .get_ids()
+            .iter()
+            .zip(tokens.get_attention_mask())
+            .filter(|&(_, &mask)| mask != 0)
+            .map(|(&id, _)| id)
+            .collect();
+        ids.insert(1.min(ids.len()), marker);
+        let max_length = max_length.unwrap_or(self.info.max_tokens).max(2);
+        if ids.len() > max_length {
+            ids.drain(max_length - 1..ids.len() - 1);
+        }
+        ids
+    }
+
+    /// Encoding of `ids` padded on the right to `length` with `pad_id`, which
+    /// is not attended to
+    fn padded_encoding(&self, ids: &[u32], pad_id: u32, length: usize) -> Encoding {
+        let mut ids = ids.to_vec();
+        let attention_mask = (0..length).map(|i| u32::from(i < ids.len())).collect();
+        ids.resize(length, pad_id);
+        let tokens = ids
+            .iter()
+            .map(|&id| self.tokenizer.id_to_token(id).unwrap_or_default())
+            .collect();
+        Encoding::new(
+            ids,
+            vec![0; length],
+            tokens,
+            vec![None; length],
+            vec![(0, 0); length],
+            vec![0; length],
+            attention_mask,
+            vec![],
+            Default::default(),
+        )
+    }
+
+    /// Whether the token is a single ASCII punctuation character, ColBERT's
+    /// skiplist
+    fn is_punctuation(&self, id: u32) -> bool {
+        let token = self.tokenizer.id_to_token(id).unwrap_or_default();
+        let mut chars = token.chars();
+        matches!((chars.next(), chars.next()), (Some(c), None) if c.is_ascii_punctuation())
+    }
+}
@@ -0,0 +1,323 @@
+use candle_core::{D, DType, Device, Module, Result, Tensor};
+use candle_nn::ops::softmax_last_dim;
+use candle_nn::rotary_emb::rope;
+use candle_nn::{Embedding, LayerNorm, Linear, VarBuilder, embedding, layer_norm, linear_b};
+use serde::Deserialize;
+
+// Config of nomic-embed-text, based on
+// https://huggingface.co/nomic-ai/nomic-embed-text-v1.5/blob/main/config.json
+// (GPT-2 style key names)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Config {
+    pub vocab_size: usize,
+    pub n_embd: usize,
+    pub n_head: usize,
+    pub n_layer: usize,
+    pub n_inner: usize,
+    pub n_positions: usize,
+    /// Context the model was trained on; longer inputs need rotary scaling
+    #[serde(default)]
+    pub max_trained_positions: Option<usize>,
+    pub type_vocab_size: usize,
+    pub layer_norm_epsilon: f64,
+    pub rotary_emb_base: f64,
+    #[serde(default = "default_rotary_emb_fraction")]
+    pub rotary_emb_fraction: f64,
+    #[serde(default)]
+    pub rotary_emb_interleaved: bool,
+    #[serde(default)]
+    pub qkv_proj_bias: bool,
+    #[serde(default)]
+    pub mlp_fc1_bias: bool,
+    #[serde(default)]
+    pub mlp_fc2_bias: bool,
+}
+
+fn default_rotary_emb_fraction() -> f64 {
+    1.0
+}
+
+impl Config {
+    pub fn v1_5() -> Self {
+        Self {
+            vocab_size: 30528,
+            n_embd: 768,
+            n_head: 12,
+            n_layer: 12,
+            n_inner: 3072,
+            n_positions: 8192,
+            max_trained_positions: Some(2048),
+            type_vocab_size: 2,
+            layer_norm_epsilon: 1e-12,
+            rotary_emb_base: 1000.0,
+            rotary_emb_fraction: 1.0,
+            rotary_emb_interleaved: false,
+            qkv_proj_bias: false,
+            mlp_fc1_bias: false,
+            mlp_fc2_bias: false,
+        }
+    }
+
+    /// Longest input the model handles without rotary scaling
+    pub fn max_tokens(&self) -> usize {
+        self.max_trained_positions
+            .unwrap_or(self.n_positions)
+            .min(self.n_positions)
+    }
+}
+
+// Word and token type embeddings; positions are encoded by the rotary
+// embeddings inside the attention instead
+#[derive(Clone, Debug)]
+struct NomicEmbeddings {
+    word_embeddings: Embedding,
+    token_type_embeddings: Embedding,
+}
+
+impl NomicEmbeddings {
+    fn new(vb: VarBuilder, config: &Config) -> Result<Self> {
+        let word_embeddings =
+            embedding(config.vocab_size, config.n_embd, vb.pp("word_embeddings"))?;
+        let token_type_embeddings = embedding(
+            config.type_vocab_size,
+            config.n_embd,
+            vb.pp("token_type_embeddings"),
+        )?;
+        Ok(Self {
+            word_embeddings,
+            token_type_embeddings,
+        })
+    }
+
+    fn forward(&self, input_ids: &Tensor, token_type_ids: &Tensor) -> Result<Tensor> {
+        let input_embeddings = self.word_embeddings.forward(input_ids)?;
+        let token_type_embeddings = self.token_type_embeddings.forward(token_type_ids)?;
+        input_embeddings + token_type_embeddings
+    }
+}
+
+// Rotary position embeddings in the non-interleaved (GPT-NeoX) layout
+#[derive(Clone, Debug)]
+struct RotaryEmbedding {
+    sin: Tensor,
+    cos: Tensor,
+    rotary_dim: usize,
+}
+
+impl RotaryEmbedding {
+    fn new(config: &Config, head_dim: usize, dtype: DType, device: &Device) -> Result<Self> {
+        // rope() expects an even number of rotated dimensions
+        let rotary_dim = ((head_dim as f64 * config.rotary_emb_fraction) as usize) / 2 * 2;
+        let inv_freq: Vec<f32> = (0..rotary_dim)
+            .step_by(2)
+            .map(|i| 1f32 / config.rotary_emb_base.powf(i as f64 / rotary_dim as f64) as f32)
+            .collect();
+        let inv_freq = Tensor::new(inv_freq, device)?.reshape((1, ()))?;
+        let positions = Tensor::arange(0u32, config.n_positions as u32, device)?
+            .to_dtype(DType::F32)?
+            .reshape((config.n_positions, 1))?;
+        let freqs = positions.matmul(&inv_freq)?;
+        Ok(Self {
+            sin: freqs.sin()?.to_dtype(dtype)?,
+            cos: freqs.cos()?.to_dtype(dtype)?,
+            rotary_dim,
+        })
+    }
+
+    /// Rotate the first `rotary_dim` dimensions of `xs` (batch, heads, seq, head_dim)
+    fn apply(&self, xs: &Tensor) -> Result<Tensor> {
+        let (_, _, seq_len, head_dim) = xs.dims4()?;
+        let cos = self.cos.narrow(0, 0, seq_len)?;
+        let sin = self.sin.narrow(0, 0, seq_len)?;
+        if self.rotary_dim == head_dim {
+            return rope(&xs.contiguous()?, &cos, &sin);
+        }
+        let rotated = rope(
+            &xs.narrow(D::Minus1, 0, self.rotary_dim)?.contiguous()?,
+            &cos,
+            &sin,
+        )?;
+        let rest = xs.narrow(D::Minus1, self.rotary_dim, head_dim - self.rotary_dim)?;
+        Tensor::cat(&[rotated, rest], D::Minus1)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct NomicAttention {
+    wqkv: Linear,
+    out_proj: Linear,
+    rotary: RotaryEmbedding,
+    num_heads: usize,
+    head_dim: usize,
+}
+
+impl NomicAttention {
+    fn new(vb: VarBuilder, config: &Config) -> Result<Self> {
+        let head_dim = config.n_embd / config.n_head;
+        let wqkv = linear_b(
+            config.n_embd,
+            3 * config.n_embd,
+            config.qkv_proj_bias,
+            vb.pp("Wqkv"),
+        )?;
+        let out_proj = linear_b(
+            config.n_embd,
+            config.n_embd,
+            config.qkv_proj_bias,
+            vb.pp("out_proj"),
+        )?;
+        let rotary = RotaryEmbedding::new(config, head_dim, vb.dtype(), vb.device())?;
+        Ok(Self {
+            wqkv,
+            out_proj,
+            rotary,
+            num_heads: config.n_head,
+            head_dim,
+        })
+    }
+
+    fn forward(&self, xs: &Tensor, attention_bias: &Tensor) -> Result<Tensor> {
+        let (b_size, seq_len, hidden_size) = xs.dims3()?;
+        let qkv = self
+            .wqkv
+            .forward(xs)?
+            .reshape((b_size, seq_len, 3, self.num_heads, self.head_dim))?
+            .permute((2, 0, 3, 1, 4))?;
+        let query = self.rotary.apply(&qkv.get(0)?)?;
+        let key = self.rotary.apply(&qkv.get(1)?)?;
+        let value = qkv.get(2)?.contiguous()?;
+
+        let attention_scores = query.matmul(&key.t()?)?;
+        let attention_scores = (attention_scores / (self.head_dim as f64).sqrt())?;
+        let attention_scores = attention_scores.broadcast_add(attention_bias)?;
+        let attention_probs = softmax_last_dim(&attention_scores)?;
+        let context = attention_probs.matmul(&value)?.transpose(1, 2)?.reshape((
+            b_size,
+            seq_len,
+            hidden_size,
+        ))?;
+        self.out_proj.forward(&context)
+    }
+}
+
+// SwiGLU feed-forward: fc2(fc11(x) * silu(fc12(x)))
+#[derive(Clone, Debug)]
+struct NomicGatedMlp {
+    fc11: Linear,
+    fc12: Linear,
+    fc2: Linear,
+}
+
+impl NomicGatedMlp {
+    fn new(vb: VarBuilder, config: &Config) -> Result<Self> {
+        let fc11 = linear_b(
+            config.n_embd,
+            config.n_inner,
+            config.mlp_fc1_bias,
+            vb.pp("fc11"),
+        )?;
+        let fc12 = linear_b(
+            config.n_embd,
+            config.n_inner,
+            config.mlp_fc1_bias,
+            vb.pp("fc12"),
+        )?;
+        let fc2 = linear_b(
+            config.n_inner,
+            config.n_embd,
+            config.mlp_fc2_bias,
+            vb.pp("fc2"),
+        )?;
+        Ok(Self { fc11, fc12, fc2 })
+    }
+}
+
+impl Module for NomicGatedMlp {
+    fn forward(&self, xs: &Tensor) -> Result<Tensor> {
+        let value = self.fc11.forward(xs)?;
+        let gate = candle_nn::ops::silu(&self.fc12.forward(xs)?)?;
+        self.fc2.forward(&(value * gate)?)
+    }
+}
+
+// Post-norm block, like BERT
+#[derive(Clone, Debug)]
+struct NomicBlock {
+    attn: NomicAttention,
+    mlp: NomicGatedMlp,
+    norm1: LayerNorm,
+    norm2: LayerNorm,
+}
+
+impl NomicBlock {
+    fn new(vb: VarBuilder, config: &Config) -> Result<Self> {
+        let attn = NomicAttention::new(vb.pp("attn"), config)?;
+        let mlp = NomicGatedMlp::new(vb.pp("mlp"), config)?;
+        let norm1 = layer_norm(config.n_embd, config.layer_norm_epsilon, vb.pp("norm1"))?;
+        let norm2 = layer_norm(config.n_embd, config.layer_norm_epsilon, vb.pp("norm2"))?;
+        Ok(Self {
+            attn,
+            mlp,
+            norm1,
+            norm2,
+        })
+    }
+
+    fn forward(&self, xs: &Tensor, attention_bias: &Tensor) -> Result<Tensor> {
+        let xs = self
+            .norm1
+            .forward(&(self.attn.forward(xs, attention_bias)? + xs)?)?;
+        self.norm2.forward(&(self.mlp.forward(&xs)? + xs)?)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct NomicBertModel {
+    embeddings: NomicEmbeddings,
+    emb_ln: LayerNorm,
+    layers: Vec<NomicBlock>,
+    pub device: Device,
+}
+
+impl NomicBertModel {
+    pub fn load(vb: VarBuilder, config: &Config) -> Result<Self> {
+        if config.rotary_emb_interleaved {
+            candle_core::bail!("Interleaved rotary embeddings are not supported");
+        }
+        let embeddings = NomicEmbeddings::new(vb.pp("embeddings"), config)?;
+        let emb_ln = layer_norm(config.n_embd, config.layer_norm_epsilon, vb.pp("emb_ln"))?;
+        let layers = (0..config.n_layer)
+            .map(|index| NomicBlock::new(vb.pp(format!("encoder.layers.{index}")), config))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            embeddings,
+            emb_ln,
+            layers,
+            device: vb.device().clone(),
+        })
+    }
+
+    pub fn forward(
+        &self,
+        input_ids: &Tensor,
+        token_type_ids: &Tensor,
+        attention_mask: Option<&Tensor>,
+    ) -> Result<Tensor> {
+        let xs = self.embeddings.forward(input_ids, token_type_ids)?;
+        let mut xs = self.emb_ln.forward(&xs)?;
+        let dtype = xs.dtype();
+
+        // Additive bias hiding padding tokens: (batch, 1, 1, seq)
+        let attention_bias = match attention_mask {
+            Some(mask) => {
+                let mask = mask.to_dtype(DType::F32)?.unsqueeze(1)?.unsqueeze(1)?;
+                ((mask.ones_like()? - mask)? * -1e4)?.to_dtype(dtype)?
+            }
+            None => Tensor::zeros((1, 1, 1, 1), dtype, input_ids.device())?,
+        };
+        for layer in self.layers.iter() {
+            xs = layer.forward(&xs, &attention_bias)?;
+        }
+        Ok(xs)
+    }
+}
@@ -0,0 +1,86 @@
+// Process-wide cache of loaded models.
+//
+// Every `TextEmbedder` built for the same model name, model ID, local folder or
+// files, revision, dtype and device shares one copy of the weights. Entries are held
+// weakly: the weights are dropped once the last embedder using them is
+// dropped, and loaded again on the next request.
+
+use super::{EmbedModel, EmbeddingError, ModelInfo};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, Weak};
+use tokenizers::Tokenizer;
+use tracing::debug;
+
+/// Identifies one set of weights in the registry
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ModelKey {
+    /// Registered model name, since two architectures may load the same files
+    pub name: String,
+    pub model_id: String,
+    /// Folder the files are read from (or the weights file, for explicit
+    /// files), `None` for the hub cache
+    pub path: Option<PathBuf>,
+    /// Config file given explicitly, `None` for the folder's or the default
+    pub config: Option<PathBuf>,
+    pub revision: String,
+    pub dtype: String,
+    pub device: String,
+}
+
+struct Entry {
+    model: Weak<dyn EmbedModel>,
+    tokenizer: Tokenizer,
+    info: ModelInfo,
+}
+
+/// The weights of one key; its lock is held while they load
+type Slot = Arc<Mutex<Option<Entry>>>;
+
+static MODELS: Lazy<Mutex<HashMap<ModelKey, Slot>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Return the shared model, a copy of its tokenizer and its info for `key`,
+/// calling `load` only if no live embedder holds these weights yet.
+///
+/// Only the slot of `key` is locked while loading: concurrent requests for the
+/// same model wait for the first load instead of loading it twice, and other
+/// models load in parallel.
+pub fn get_or_load<F>(
+    key: ModelKey,
+    load: F,
+) -> Result<(Arc<dyn EmbedModel>, Tokenizer, ModelInfo), EmbeddingError>
+where
+    F: FnOnce() -> Result<(Box<dyn EmbedModel>, Tokenizer, ModelInfo), EmbeddingError>,
+{
+    let slot = {
+        let mut models = MODELS.lock().unwrap_or_else(|e| e.into_inner());
+        // Drop the slots of released weights nobody is loading
+        models.retain(|_, slot| {
+            Arc::strong_count(slot) > 1
+                || slot.try_lock().map_or(true, |entry| {
+                    entry
+                        .as_ref()
+                        .is_some_and(|entry| entry.model.strong_count() > 0)
+                })
+        });
+        models.entry(key.clone()).or_default().clone()
+    };
+
+    let mut entry = slot.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(entry) = entry.as_ref()
+        && let Some(model) = entry.model.upgrade()
+    {
+        debug!(model_id = %key.model_id, "Reusing loaded model weights");
+        return Ok((model, entry.tokenizer.clone(), entry.info.clone()));
+    }
+
+    let (model, tokenizer, info) = load()?;
+    let model: Arc<dyn EmbedModel> = Arc::from(model);
+    *entry = Some(Entry {
+        model: Arc::downgrade(&model),
+        tokenizer: tokenizer.clone(),
+        info: info.clone(),
+    });
+    Ok((model, tokenizer, info))
+}
@@ -0,0 +1,58 @@
+// Reranking with cross-encoders. A first search compares the embedding of the
+// query with those of many documents; a cross-encoder then reads the query and
+// each of the best candidates together, which is slower but more precise, and
+// scores how well the candidate answers the query.
+use super::{EmbeddingError, TextEmbedder, stack_encodings};
+use candle_core::DType;
+use tokenizers::{EncodeInput, Encoding};
+use tracing::debug;
+use web_time::Instant;
+
+impl TextEmbedder {
+    /// Relevance scores of `documents` for `query`, `batch_size` pairs at a
+    /// time: one logit per document, in order, higher for more relevant
+    /// documents. Pairs longer than `max_tokens` are truncated from their
+    /// longer text. Only cross-encoders score pairs.
+    pub fn rerank(
+        &mut self,
+        query: &str,
+        documents: &[&str],
+        batch_size: usize,
+    ) -> Result<Vec<f32>, EmbeddingError> {
+        if !self.info.cross_encoder {
+            return Err(EmbeddingError::ModelTypeError(format!(
+                "{} is not a cross-encoder",
+                self.info.model_id
+            )));
+        }
+        self.configure_padding();
+
+        let device = self.model.device();
+        let mut scores = Vec::with_capacity(documents.len());
+        let started = Instant::now();
+        debug!(documents = documents.len(), batch_size, "Reranking");
+
+        for chunk in documents.chunks(batch_size.max(1)) {
+            let pairs: Vec<EncodeInput> = chunk
+                .iter()
+                .map(|&document| (query, document).into())
+                .collect();
+            let tokens = self.tokenizer.encode_batch(pairs, true)?;
+            let token_ids = stack_encodings(&tokens, device, Encoding::get_ids)?;
+            let token_type_ids = stack_encodings(&tokens, device, Encoding::get_type_ids)?;
+            let attention_mask = stack_encodings(&tokens, device, Encoding::get_attention_mask)?;
+
+            let logits = self
+                .model
+                .forward_pairs(&token_ids, &token_type_ids, Some(&attention_mask))?
+                .to_dtype(DType::F32)?;
+            scores.extend(logits.to_vec1::<f32>()?);
+        }
+        debug!(
+            documents = documents.len(),
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "Reranking finished"
+        );
+        Ok(scores)
+    }
+}
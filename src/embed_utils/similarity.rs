@@ -0,0 +1,93 @@
+// Similarity and ranking over embedding vectors.
+//
+// `Cosine` divides by the norms of the vectors, so it holds for any input.
+// `Dot` skips the norm computation and only gives the same score for
+// L2-normalized vectors, which `TextEmbedder` returns unless normalization is
+// turned off.
+use super::{MultiVectorEmbedding, SparseEmbedding};
+
+/// Metric used to compare two embedding vectors
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Similarity {
+    Cosine,
+    Dot,
+}
+
+impl Similarity {
+    /// Score of `a` against `b`; both slices must have the same length
+    pub fn score(self, a: &[f32], b: &[f32]) -> f32 {
+        match self {
+            Self::Cosine => cosine(a, b),
+            Self::Dot => dot(a, b),
+        }
+    }
+}
+
+pub fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Dot product of two sparse embeddings, over the tokens both weigh
+pub fn sparse_dot(a: &SparseEmbedding, b: &SparseEmbedding) -> f32 {
+    let (small, large) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    small
+        .iter()
+        .filter_map(|(id, x)| large.get(id).map(|y| x * y))
+        .sum()
+}
+
+/// Late-interaction (ColBERT) score of a query against a document: the sum
+/// over the query tokens of the dot product with their most similar document
+/// token. 0.0 for an empty document.
+pub fn max_sim(query: &MultiVectorEmbedding, document: &MultiVectorEmbedding) -> f32 {
+    if document.is_empty() {
+        return 0.0;
+    }
+    query
+        .iter()
+        .map(|q| {
+            document
+                .iter()
+                .map(|d| dot(q, d))
+                .fold(f32::NEG_INFINITY, f32::max)
+        })
+        .sum()
+}
+
+/// Cosine similarity, 0.0 if either vector has zero norm
+pub fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let norms = dot(a, a).sqrt() * dot(b, b).sqrt();
+    if norms == 0.0 { 0.0 } else { dot(a, b) / norms }
+}
+
+/// Row-major `rows_a x rows_b` matrix of scores between two flattened sets of
+/// `dim`-sized vectors
+pub fn similarity_matrix(a: &[f32], b: &[f32], dim: usize, metric: Similarity) -> Vec<f32> {
+    a.chunks_exact(dim)
+        .flat_map(|row_a| {
+            b.chunks_exact(dim)
+                .map(move |row_b| metric.score(row_a, row_b))
+        })
+        .collect()
+}
+
+/// Indices and scores of the `k` rows of `matrix` most similar to `query`,
+/// best first. Returns fewer than `k` entries when the matrix has fewer rows;
+/// rows with equal scores keep their order.
+pub fn top_k(
+    query: &[f32],
+    matrix: &[f32],
+    dim: usize,
+    k: usize,
+    metric: Similarity,
+) -> Vec<(usize, f32)> {
+    let mut scores: Vec<(usize, f32)> = matrix
+        .chunks_exact(dim)
+        .map(|row| metric.score(query, row))
+        .enumerate()
+        .collect();
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scores.truncate(k);
+    scores
+}
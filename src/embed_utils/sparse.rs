@@ -0,0 +1,43 @@
+// Sparse lexical embeddings from SPLADE models. Their vectors span the whole
+// vocabulary and are mostly 0, so they are returned as maps from token ID to
+// weight; `similarity::sparse_dot` compares them, and they are meant to be
+// combined with dense embeddings for hybrid search.
+use super::{Embed, EmbeddingError, TextEmbedder};
+use std::collections::BTreeMap;
+
+/// Weight of each vocabulary token with a non-zero weight, by token ID
+pub type SparseEmbedding = BTreeMap<u32, f32>;
+
+impl TextEmbedder {
+    /// Sparse embeddings of `column`, `batch_size` texts at a time. Only
+    /// sparse models (`splade`) produce them; their weights are never
+    /// normalized.
+    pub fn embed_sparse(
+        &mut self,
+        column: Vec<String>,
+        batch_size: usize,
+    ) -> Result<Vec<SparseEmbedding>, EmbeddingError> {
+        if !self.info.sparse {
+            return Err(EmbeddingError::ModelTypeError(format!(
+                "{} is not a sparse model",
+                self.info.model_id
+            )));
+        }
+        let normalize = self.info.normalize;
+        self.info.normalize = false;
+        let embeddings = Embed::embed(self, column, batch_size);
+        self.info.normalize = normalize;
+
+        Ok(embeddings?
+            .into_iter()
+            .map(|weights| {
+                weights
+                    .into_iter()
+                    .enumerate()
+                    .filter(|&(_, weight)| weight > 0.0)
+                    .map(|(id, weight)| (id as u32, weight))
+                    .collect()
+            })
+            .collect())
+    }
+}
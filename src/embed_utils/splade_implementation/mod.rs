@@ -0,0 +1,89 @@
+use candle_core::{Device, Module, Result, Tensor};
+use candle_nn::{LayerNorm, Linear, VarBuilder, layer_norm, linear};
+use candle_transformers::models::bert::{BertModel, Config, HiddenAct};
+
+// SPLADE (https://arxiv.org/abs/2109.10086) embeds a text as a sparse vector
+// over the vocabulary: the masked language model head of BERT predicts a
+// logit for every vocabulary token at every position, and the weight of a
+// token is the maximum over the positions of log(1 + ReLU(logit)). Most
+// weights are 0, and the others read as the importance of a term (or of a
+// related term the text does not contain) for lexical matching.
+//
+// The head is transformers' `BertOnlyMLMHead`. Its decoder usually shares the
+// word embeddings, so checkpoints may leave the decoder weight out.
+
+pub struct SpladeModel {
+    bert: BertModel,
+    dense: Linear,
+    activation: HiddenAct,
+    layer_norm: LayerNorm,
+    decoder: Linear,
+    pub device: Device,
+}
+
+impl SpladeModel {
+    pub fn load(vb: VarBuilder, config: &Config) -> Result<Self> {
+        let (hidden_size, vocab_size) = (config.hidden_size, config.vocab_size);
+        // Checkpoints of the masked LM nest the encoder under "bert"
+        let encoder = if vb.contains_tensor("bert.embeddings.word_embeddings.weight") {
+            vb.pp("bert")
+        } else {
+            vb.clone()
+        };
+        let head = vb.pp("cls.predictions");
+        let decoder_weight = if head.contains_tensor("decoder.weight") {
+            head.get((vocab_size, hidden_size), "decoder.weight")?
+        } else {
+            encoder.get(
+                (vocab_size, hidden_size),
+                "embeddings.word_embeddings.weight",
+            )?
+        };
+        let decoder_bias = if head.contains_tensor("bias") {
+            head.get(vocab_size, "bias")?
+        } else {
+            head.get(vocab_size, "decoder.bias")?
+        };
+        Ok(Self {
+            bert: BertModel::load(encoder, config)?,
+            dense: linear(hidden_size, hidden_size, head.pp("transform.dense"))?,
+            activation: config.hidden_act,
+            layer_norm: layer_norm(
+                hidden_size,
+                config.layer_norm_eps,
+                head.pp("transform.LayerNorm"),
+            )?,
+            decoder: Linear::new(decoder_weight, Some(decoder_bias)),
+            device: vb.device().clone(),
+        })
+    }
+
+    /// Vocabulary weights of each text as a single token, `(batch, 1, vocab_size)`
+    pub fn forward(
+        &self,
+        input_ids: &Tensor,
+        token_type_ids: &Tensor,
+        attention_mask: Option<&Tensor>,
+    ) -> Result<Tensor> {
+        let hidden = self
+            .bert
+            .forward(input_ids, token_type_ids, attention_mask)?;
+        let hidden = self.dense.forward(&hidden)?;
+        let hidden = match self.activation {
+            HiddenAct::Gelu => hidden.gelu_erf()?,
+            HiddenAct::GeluApproximate => hidden.gelu()?,
+            HiddenAct::Relu => hidden.relu()?,
+        };
+        let logits = self
+            .decoder
+            .forward(&self.layer_norm.forward(&hidden)?)?
+            .relu()?;
+        // Padding gets weight 0, below that of any real token after the ReLU
+        let logits = match attention_mask {
+            Some(mask) => logits.broadcast_mul(&mask.to_dtype(logits.dtype())?.unsqueeze(2)?)?,
+            None => logits,
+        };
+        // log(1 + x) is increasing, so it is taken after the maximum
+        logits.max_keepdim(1)?.affine(1.0, 1.0)?.log()
+    }
+}
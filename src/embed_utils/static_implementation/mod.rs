@@ -0,0 +1,63 @@
+use candle_core::{D, DType, Device, Result, Tensor};
+use candle_nn::VarBuilder;
+
+// Static embeddings in the model2vec format
+// (https://github.com/MinishLab/model2vec): a table with one precomputed
+// vector per token, "embeddings" of shape (vocab, dimension). Newer models
+// add per-token "weights" that scale the rows, and quantized vocabularies a
+// "mapping" from token IDs to rows; both are folded into the table on load.
+#[derive(Clone, Debug)]
+pub struct StaticModel {
+    /// Row-major (vocab, dimension) table in f32, whatever the requested dtype
+    table: Vec<f32>,
+    dimension: usize,
+    pub device: Device,
+}
+
+impl StaticModel {
+    pub fn load(vb: VarBuilder) -> Result<Self> {
+        let mut embeddings = vb.get_unchecked_dtype("embeddings", DType::F32)?;
+        if vb.contains_tensor("mapping") {
+            let mapping = vb.get_unchecked_dtype("mapping", DType::U32)?;
+            embeddings = embeddings.index_select(&mapping, 0)?;
+        }
+        if vb.contains_tensor("weights") {
+            let weights = vb.get_unchecked_dtype("weights", DType::F32)?;
+            embeddings = embeddings.broadcast_mul(&weights.unsqueeze(D::Minus1)?)?;
+        }
+        let (_, dimension) = embeddings.dims2()?;
+        Ok(Self {
+            table: embeddings.flatten_all()?.to_vec1()?,
+            dimension,
+            device: vb.device().clone(),
+        })
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// Row-major (vocab, dimension) table of token vectors
+    pub fn table(&self) -> &[f32] {
+        &self.table
+    }
+
+    /// Vector of each token ID, for (batch, tokens) `input_ids`; IDs outside
+    /// the vocabulary get zeros
+    pub fn forward(&self, input_ids: &Tensor) -> Result<Tensor> {
+        let (b_size, seq_len) = input_ids.dims2()?;
+        let mut vectors = Vec::with_capacity(b_size * seq_len * self.dimension);
+        for id in input_ids.flatten_all()?.to_vec1::<u32>()? {
+            match self.row(id) {
+                Some(row) => vectors.extend_from_slice(row),
+                None => vectors.extend(std::iter::repeat_n(0f32, self.dimension)),
+            }
+        }
+        Tensor::from_vec(vectors, (b_size, seq_len, self.dimension), &self.device)
+    }
+
+    fn row(&self, id: u32) -> Option<&[f32]> {
+        let start = id as usize * self.dimension;
+        self.table.get(start..start + self.dimension)
+    }
+}
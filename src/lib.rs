@@ -1,20 +1,52 @@
 // lib.rs - FFI exports for creating a native DLL
+//! Sentence embeddings with candle transformer models, as a Rust library, a C
+//! ABI DLL and a CLI.
+//!
+//! The Rust API is [`EmbedderBuilder`], [`Embedder`] and the types they use,
+//! re-exported from this root. It follows semver; the `extern "C"` functions
+//! are versioned separately through `embedder_abi_version`.
+//!
+//! ```no_run
+//! let mut embedder = rust_transformer::Embedder::new("mini_lm_v2")?;
+//! let vector = embedder.embed("Hello world")?;
+//! # Ok::<(), rust_transformer::Error>(())
+//! ```
+mod abi;
+mod api;
 mod embed_utils;
+mod logging;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
-use embed_utils::TextEmbedder;
+use embed_utils::{Embed, EmbeddingError, TextEmbedder, TextEmbedderBuilder};
+use logging::EmbedderLogCallback;
+pub use logging::{
+    EMBEDDER_LOG_DEBUG, EMBEDDER_LOG_ERROR, EMBEDDER_LOG_INFO, EMBEDDER_LOG_OFF,
+    EMBEDDER_LOG_TRACE, EMBEDDER_LOG_WARN,
+};
+use std::any::Any;
+use std::borrow::Cow;
 use std::ffi::{CStr, CString};
-use std::os::raw::c_char;
+use std::mem;
+use std::os::raw::{c_char, c_void};
+use std::panic::{self, AssertUnwindSafe};
 use std::ptr;
 use std::slice;
 
 /// Opaque handle to the embedder instance
 pub struct EmbedderHandle {
     embedder: TextEmbedder,
+    model_info: CString,
     last_error: Option<String>,
 }
 
 /// Error codes returned by FFI functions
 #[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum EmbedderErrorCode {
     Success = 0,
     NullPointer = 1,
@@ -23,10 +55,111 @@ pub enum EmbedderErrorCode {
     EmbeddingFailed = 4,
     InvalidHandle = 5,
     BufferTooSmall = 6,
+    Panic = 7,
+    InvalidArgument = 8,
+    Cancelled = 9,
+}
+
+/// Similarity metrics accepted by the similarity and top-k functions
+pub const EMBEDDER_SIMILARITY_COSINE: u32 = 0;
+pub const EMBEDDER_SIMILARITY_DOT: u32 = 1;
+
+fn similarity_metric(metric: u32) -> Option<Similarity> {
+    match metric {
+        EMBEDDER_SIMILARITY_COSINE => Some(Similarity::Cosine),
+        EMBEDDER_SIMILARITY_DOT => Some(Similarity::Dot),
+        _ => None,
+    }
+}
+
+/// Pooling strategies accepted in `EmbedderOptions::pooling`
+pub const EMBEDDER_POOLING_MEAN: u32 = 0;
+pub const EMBEDDER_POOLING_CLS: u32 = 1;
+pub const EMBEDDER_POOLING_LAST_TOKEN: u32 = 2;
+/// The pooling the model was trained with (last token for decoders, else mean)
+pub const EMBEDDER_POOLING_MODEL: u32 = 3;
+
+/// `Some(None)` keeps the model's pooling, `None` for unknown values
+fn pooling_strategy(pooling: u32) -> Option<Option<Pooling>> {
+    match pooling {
+        EMBEDDER_POOLING_MEAN => Some(Some(Pooling::Mean)),
+        EMBEDDER_POOLING_CLS => Some(Some(Pooling::Cls)),
+        EMBEDDER_POOLING_LAST_TOKEN => Some(Some(Pooling::LastToken)),
+        EMBEDDER_POOLING_MODEL => Some(None),
+        _ => None,
+    }
+}
+
+/// Extract a readable message from a panic payload.
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "unknown panic payload".to_string()
+    }
+}
+
+/// Run the body of an FFI function that reports an error code, so that a panic
+/// never unwinds into the caller. The panic message is stored as the last error
+/// of `handle` when the handle is available.
+fn guard_handle<F>(handle: *mut EmbedderHandle, body: F) -> EmbedderErrorCode
+where
+    F: FnOnce() -> EmbedderErrorCode,
+{
+    guard_handle_or(handle, EmbedderErrorCode::Panic, body)
+}
+
+/// Same as `guard_handle` for FFI functions returning a value instead of an
+/// error code; `fallback` is returned if the body panics.
+fn guard_handle_or<T, F>(handle: *mut EmbedderHandle, fallback: T, body: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(value) => value,
+        Err(payload) => {
+            if !handle.is_null() {
+                let handle = unsafe { &mut *handle };
+                handle.last_error = Some(format!("Panic: {}", panic_message(payload)));
+            }
+            fallback
+        }
+    }
+}
+
+/// Run the body of an FFI function without an error code, returning `fallback`
+/// if it panics.
+fn guard_or<T, F>(fallback: T, body: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or(fallback)
+}
+
+/// Wrap a built embedder into a heap-allocated handle for the caller.
+fn into_handle(embedder: TextEmbedder) -> *mut EmbedderHandle {
+    let model_info = match serde_json::to_string(embedder.info())
+        .ok()
+        .and_then(|json| CString::new(json).ok())
+    {
+        Some(info) => info,
+        None => return ptr::null_mut(),
+    };
+    let handle = Box::new(EmbedderHandle {
+        embedder,
+        model_info,
+        last_error: None,
+    });
+    Box::into_raw(handle)
 }
 
 /// Initialize an embedder with the specified model.
 ///
+/// The weights are loaded once per process and shared by every handle for the
+/// same model; they are released when the last of those handles is freed.
+///
 /// # Parameters
 /// - `model_name`: C string containing the model name ("mini_lm_v2" or "jina")
 ///
@@ -38,73 +171,416 @@ pub enum EmbedderErrorCode {
 /// - The returned handle must be freed with `embedder_free()`
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn embedder_init(model_name: *const c_char) -> *mut EmbedderHandle {
-    if model_name.is_null() {
-        return ptr::null_mut();
+    guard_or(ptr::null_mut(), || {
+        if model_name.is_null() {
+            return ptr::null_mut();
+        }
+
+        let model_name_str = match unsafe { CStr::from_ptr(model_name) }.to_str() {
+            Ok(s) => s,
+            Err(_) => return ptr::null_mut(),
+        };
+
+        match embed_utils::build_text_embedder(model_name_str) {
+            Ok(embedder) => into_handle(embedder),
+            Err(_) => ptr::null_mut(),
+        }
+    })
+}
+
+/// Options for `embedder_init_with_options()`.
+///
+/// The struct is size-prefixed: callers set `struct_size` to
+/// `sizeof(EmbedderOptions)` from their header, so fields added in later
+/// versions get their defaults when an older caller uses a newer library, and
+/// are ignored when a newer caller uses an older library.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct EmbedderOptions {
+    pub struct_size: usize,
+    /// Model name ("mini_lm_v2" or "jina"); null selects "mini_lm_v2"
+    pub model_name: *const c_char,
+    /// Non-zero to L2-normalize the embeddings (default 1)
+    pub normalize: i32,
+    /// Truncate texts to this many tokens; 0 keeps the model limit (default 0)
+    pub max_tokens: usize,
+    /// `EMBEDDER_POOLING_MODEL` (default), `EMBEDDER_POOLING_MEAN`,
+    /// `EMBEDDER_POOLING_CLS` or `EMBEDDER_POOLING_LAST_TOKEN`
+    pub pooling: u32,
+}
+
+impl Default for EmbedderOptions {
+    fn default() -> Self {
+        Self {
+            struct_size: mem::size_of::<Self>(),
+            model_name: ptr::null(),
+            normalize: 1,
+            max_tokens: 0,
+            pooling: EMBEDDER_POOLING_MODEL,
+        }
     }
+}
 
-    let model_name_str = match unsafe { CStr::from_ptr(model_name) }.to_str() {
-        Ok(s) => s,
-        Err(_) => return ptr::null_mut(),
-    };
+impl abi::SizePrefixed for EmbedderOptions {
+    const FIELD_ENDS: &'static [usize] = &[
+        mem::size_of::<usize>(),
+        mem::offset_of!(Self, model_name) + mem::size_of::<*const c_char>(),
+        mem::offset_of!(Self, normalize) + mem::size_of::<i32>(),
+        mem::offset_of!(Self, max_tokens) + mem::size_of::<usize>(),
+        mem::offset_of!(Self, pooling) + mem::size_of::<u32>(),
+    ];
+}
+
+/// Fill an options struct with the library defaults.
+///
+/// # Parameters
+/// - `options`: Struct to fill; `struct_size` must be set by the caller first
+///
+/// # Returns
+/// - `Success`, `NullPointer` if options is null, or `InvalidArgument` if
+///   `struct_size` is too small
+///
+/// # Safety
+/// - options must point to at least `struct_size` writable bytes
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn embedder_options_init(options: *mut EmbedderOptions) -> EmbedderErrorCode {
+    guard_or(EmbedderErrorCode::Panic, || {
+        if options.is_null() {
+            return EmbedderErrorCode::NullPointer;
+        }
+        if unsafe { abi::write_size_prefixed(options) } {
+            EmbedderErrorCode::Success
+        } else {
+            EmbedderErrorCode::InvalidArgument
+        }
+    })
+}
+
+/// Initialize an embedder from an options struct.
+///
+/// Handles for the same model share one copy of the weights, so opening
+/// several handles with different pooling or normalization is cheap.
+///
+/// # Parameters
+/// - `options`: Size-prefixed options, or null for all defaults
+///
+/// # Returns
+/// - Pointer to EmbedderHandle on success, null pointer on failure
+///
+/// # Safety
+/// - options must be null or point to at least `struct_size` readable bytes
+/// - model_name must be null or a valid null-terminated C string
+/// - The returned handle must be freed with `embedder_free()`
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn embedder_init_with_options(
+    options: *const EmbedderOptions,
+) -> *mut EmbedderHandle {
+    guard_or(ptr::null_mut(), || {
+        let options = if options.is_null() {
+            EmbedderOptions::default()
+        } else {
+            match unsafe { abi::read_size_prefixed(options) } {
+                Some(options) => options,
+                None => return ptr::null_mut(),
+            }
+        };
+
+        let model_name_str = if options.model_name.is_null() {
+            "mini_lm_v2"
+        } else {
+            match unsafe { CStr::from_ptr(options.model_name) }.to_str() {
+                Ok(s) => s,
+                Err(_) => return ptr::null_mut(),
+            }
+        };
+
+        let Some(pooling) = pooling_strategy(options.pooling) else {
+            return ptr::null_mut();
+        };
 
-    match embed_utils::build_text_embedder(model_name_str) {
-        Ok(embedder) => {
-            let handle = Box::new(EmbedderHandle {
-                embedder,
-                last_error: None,
-            });
-            Box::into_raw(handle)
+        let mut embedder = match embed_utils::build_text_embedder(model_name_str) {
+            Ok(embedder) => embedder,
+            Err(_) => return ptr::null_mut(),
+        };
+        embedder.set_normalize(options.normalize != 0);
+        if let Some(pooling) = pooling {
+            embedder.set_pooling(pooling);
+        }
+        if options.max_tokens > 0 && embedder.set_max_tokens(options.max_tokens).is_err() {
+            return ptr::null_mut();
         }
-        Err(_) => ptr::null_mut(),
+
+        into_handle(embedder)
+    })
+}
+
+/// Read an optional null-terminated UTF-8 string; `Err` if it is not valid UTF-8.
+///
+/// # Safety
+/// - ptr must be null or a valid null-terminated C string
+unsafe fn optional_str<'a>(ptr: *const c_char) -> Result<Option<&'a str>, ()> {
+    if ptr.is_null() {
+        return Ok(None);
     }
+    unsafe { CStr::from_ptr(ptr) }
+        .to_str()
+        .map(Some)
+        .map_err(|_| ())
 }
 
-/// Generate an embedding for a single text string.
+/// Initialize an embedder from explicit model files instead of a model folder
+/// or the HuggingFace Hub, e.g. for weights with non-standard file names.
 ///
 /// # Parameters
-/// - `handle`: Pointer to EmbedderHandle returned by embedder_init()
-/// - `text`: C string containing the input text
-/// - `output_buffer`: Pre-allocated buffer to receive the embedding floats
-/// - `buffer_size`: Size of the output_buffer (number of f32 elements it can hold)
-/// - `actual_size`: Output parameter - will be set to the actual embedding dimension
+/// - `model_name`: Architecture of the model ("mini_lm_v2" or "jina")
+/// - `tokenizer_path`: Path of the `tokenizer.json` file
+/// - `config_path`: Path of the `config.json` file, or null for the default
+///   configuration of the model
+/// - `weights_path`: Path of the safetensors weights
 ///
 /// # Returns
-/// - EmbedderErrorCode indicating success or failure
+/// - Pointer to EmbedderHandle on success, null pointer on failure
 ///
 /// # Safety
-/// - handle must be a valid pointer returned by embedder_init()
-/// - text must be a valid null-terminated C string
-/// - output_buffer must point to allocated memory of at least buffer_size f32 elements
-/// - actual_size must be a valid pointer to write the output size
+/// - All strings must be valid null-terminated UTF-8 C strings (config_path may be null)
+/// - The returned handle must be freed with `embedder_free()`
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn embedder_embed(
-    handle: *mut EmbedderHandle,
-    text: *const c_char,
+pub unsafe extern "C" fn embedder_init_from_files(
+    model_name: *const c_char,
+    tokenizer_path: *const c_char,
+    config_path: *const c_char,
+    weights_path: *const c_char,
+) -> *mut EmbedderHandle {
+    guard_or(ptr::null_mut(), || {
+        let strings = unsafe {
+            (
+                optional_str(model_name),
+                optional_str(tokenizer_path),
+                optional_str(config_path),
+                optional_str(weights_path),
+            )
+        };
+        let (Ok(Some(model_name)), Ok(Some(tokenizer_path)), Ok(config_path), Ok(Some(weights_path))) =
+            strings
+        else {
+            return ptr::null_mut();
+        };
+
+        let mut builder = match embed_utils::model_type(model_name) {
+            Ok(model_type) => TextEmbedderBuilder::new(model_type)
+                .tokenizer_path(tokenizer_path)
+                .weights_path(weights_path),
+            Err(_) => return ptr::null_mut(),
+        };
+        if let Some(config_path) = config_path {
+            builder = builder.config_path(config_path);
+        }
+
+        match builder.build() {
+            Ok(embedder) => into_handle(embedder),
+            Err(_) => ptr::null_mut(),
+        }
+    })
+}
+
+/// Initialize an embedder from model files held in memory, e.g. weights that
+/// were bundled into the application or decrypted at runtime. The buffers are
+/// copied, so they can be freed as soon as this function returns.
+///
+/// # Parameters
+/// - `model_name`: Architecture of the model ("mini_lm_v2" or "jina")
+/// - `tokenizer_data`, `tokenizer_len`: Contents of `tokenizer.json`
+/// - `config_data`, `config_len`: Contents of `config.json`, or null for the
+///   default configuration of the model
+/// - `weights_data`, `weights_len`: Contents of the safetensors weights
+///
+/// # Returns
+/// - Pointer to EmbedderHandle on success, null pointer on failure
+///
+/// # Safety
+/// - model_name must be a valid null-terminated C string
+/// - Each data pointer must point to at least its length in bytes (config_data may be null)
+/// - The returned handle must be freed with `embedder_free()`
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn embedder_init_from_buffers(
+    model_name: *const c_char,
+    tokenizer_data: *const u8,
+    tokenizer_len: usize,
+    config_data: *const u8,
+    config_len: usize,
+    weights_data: *const u8,
+    weights_len: usize,
+) -> *mut EmbedderHandle {
+    guard_or(ptr::null_mut(), || {
+        let Ok(Some(model_name)) = (unsafe { optional_str(model_name) }) else {
+            return ptr::null_mut();
+        };
+        if tokenizer_data.is_null() || weights_data.is_null() {
+            return ptr::null_mut();
+        }
+
+        let tokenizer = unsafe { slice::from_raw_parts(tokenizer_data, tokenizer_len) };
+        let weights = unsafe { slice::from_raw_parts(weights_data, weights_len) };
+        let mut builder = match embed_utils::model_type(model_name) {
+            Ok(model_type) => TextEmbedderBuilder::new(model_type)
+                .tokenizer_bytes(tokenizer.to_vec())
+                .weights_bytes(weights.to_vec()),
+            Err(_) => return ptr::null_mut(),
+        };
+        if !config_data.is_null() {
+            let config = unsafe { slice::from_raw_parts(config_data, config_len) };
+            builder = builder.config_bytes(config.to_vec());
+        }
+
+        match builder.build() {
+            Ok(embedder) => into_handle(embedder),
+            Err(_) => ptr::null_mut(),
+        }
+    })
+}
+
+/// A text passed over the FFI, in one of the supported encodings.
+#[derive(Clone, Copy)]
+enum FfiText {
+    /// Null-terminated UTF-8
+    CStr(*const c_char),
+    /// UTF-8 bytes with an explicit length (may contain NUL)
+    Utf8(*const u8, usize),
+    /// UTF-16 code units with an explicit length (may contain NUL)
+    Utf16(*const u16, usize),
+}
+
+/// Why an `FfiText` could not be turned into a Rust string.
+struct TextError {
+    code: EmbedderErrorCode,
+    reason: &'static str,
+    detail: Option<String>,
+}
+
+impl TextError {
+    /// Error message stored as last error, with the batch index when there is one.
+    fn message(&self, index: Option<usize>) -> String {
+        let mut message = self.reason.to_string();
+        if let Some(i) = index {
+            message.push_str(&format!(" at index {}", i));
+        }
+        if let Some(detail) = &self.detail {
+            message.push_str(&format!(": {}", detail));
+        }
+        message
+    }
+}
+
+impl FfiText {
+    /// Decode the text. Invalid UTF-8 and unpaired UTF-16 surrogates are both
+    /// reported as `InvalidUtf8`.
+    ///
+    /// # Safety
+    /// - The pointer must be null or valid for the encoding and length of the variant
+    unsafe fn decode<'a>(self) -> Result<Cow<'a, str>, TextError> {
+        let null_pointer = TextError {
+            code: EmbedderErrorCode::NullPointer,
+            reason: "Null text pointer",
+            detail: None,
+        };
+        match self {
+            Self::CStr(text) => {
+                if text.is_null() {
+                    return Err(null_pointer);
+                }
+                unsafe { CStr::from_ptr(text) }
+                    .to_str()
+                    .map(Cow::Borrowed)
+                    .map_err(|e| TextError {
+                        code: EmbedderErrorCode::InvalidUtf8,
+                        reason: "Invalid UTF-8",
+                        detail: Some(e.to_string()),
+                    })
+            }
+            Self::Utf8(text, len) => {
+                let bytes: &[u8] = match (text.is_null(), len) {
+                    (_, 0) => &[],
+                    (true, _) => return Err(null_pointer),
+                    (false, _) => unsafe { slice::from_raw_parts(text, len) },
+                };
+                std::str::from_utf8(bytes)
+                    .map(Cow::Borrowed)
+                    .map_err(|e| TextError {
+                        code: EmbedderErrorCode::InvalidUtf8,
+                        reason: "Invalid UTF-8",
+                        detail: Some(e.to_string()),
+                    })
+            }
+            Self::Utf16(text, len) => {
+                let units: &[u16] = match (text.is_null(), len) {
+                    (_, 0) => &[],
+                    (true, _) => return Err(null_pointer),
+                    (false, _) => unsafe { slice::from_raw_parts(text, len) },
+                };
+                String::from_utf16(units)
+                    .map(Cow::Owned)
+                    .map_err(|e| TextError {
+                        code: EmbedderErrorCode::InvalidUtf8,
+                        reason: "Invalid UTF-16",
+                        detail: Some(e.to_string()),
+                    })
+            }
+        }
+    }
+}
+
+/// Collect `(ptr, len)` text arrays into `FfiText`s, or `None` if an array is null.
+///
+/// # Safety
+/// - texts and lens must be null or point to arrays of num_texts elements
+unsafe fn sized_texts<T>(
+    texts: *const *const T,
+    lens: *const usize,
+    num_texts: usize,
+    make: fn(*const T, usize) -> FfiText,
+) -> Option<Vec<FfiText>> {
+    if texts.is_null() || lens.is_null() {
+        return None;
+    }
+    let text_ptrs = unsafe { slice::from_raw_parts(texts, num_texts) };
+    let text_lens = unsafe { slice::from_raw_parts(lens, num_texts) };
+    Some(
+        text_ptrs
+            .iter()
+            .zip(text_lens)
+            .map(|(&text, &len)| make(text, len))
+            .collect(),
+    )
+}
+
+/// Shared implementation of the single-text embedding functions.
+///
+/// # Safety
+/// - The text pointer must be valid for its encoding
+/// - output_buffer must point to at least buffer_size f32 elements
+/// - actual_size must be a valid pointer
+unsafe fn embed_text(
+    handle: &mut EmbedderHandle,
+    text: FfiText,
     output_buffer: *mut f32,
     buffer_size: usize,
     actual_size: *mut usize,
 ) -> EmbedderErrorCode {
-    // Validate pointers
-    if handle.is_null() {
-        return EmbedderErrorCode::InvalidHandle;
-    }
-    if text.is_null() || output_buffer.is_null() || actual_size.is_null() {
+    if output_buffer.is_null() || actual_size.is_null() {
         return EmbedderErrorCode::NullPointer;
     }
 
-    let handle = unsafe { &mut *handle };
-
-    // Convert C string to Rust string
-    let text_str = match unsafe { CStr::from_ptr(text) }.to_str() {
+    // Convert the input to a Rust string
+    let text_str = match unsafe { text.decode() } {
         Ok(s) => s,
         Err(e) => {
-            handle.last_error = Some(format!("Invalid UTF-8: {}", e));
-            return EmbedderErrorCode::InvalidUtf8;
+            handle.last_error = Some(e.message(None));
+            return e.code;
         }
     };
 
     // Generate embedding
-    match handle.embedder.embed(text_str) {
+    match handle.embedder.embed(&text_str) {
         Ok(embedding) => {
             let embed_len = embedding.len();
             unsafe { *actual_size = embed_len };
@@ -131,61 +607,32 @@ pub unsafe extern "C" fn embedder_embed(
     }
 }
 
-/// Generate embeddings for multiple text strings (batch processing).
-///
-/// # Parameters
-/// - `handle`: Pointer to EmbedderHandle
-/// - `texts`: Array of C string pointers
-/// - `num_texts`: Number of texts in the array
-/// - `output_buffer`: Pre-allocated buffer to receive all embeddings (flattened)
-/// - `buffer_size`: Total size of output_buffer (number of f32 elements)
-/// - `embedding_dim`: Output parameter - embedding dimension per text
-/// - `total_written`: Output parameter - total number of floats written
-///
-/// # Returns
-/// - EmbedderErrorCode indicating success or failure
+/// Shared implementation of the batch embedding functions with a caller-provided buffer.
 ///
 /// # Safety
-/// - handle must be valid
-/// - texts must point to an array of num_texts valid C string pointers
-/// - output_buffer must have space for num_texts * embedding_dim floats
-#[unsafe(no_mangle)]
-pub unsafe extern "C" fn embedder_embed_batch(
-    handle: *mut EmbedderHandle,
-    texts: *const *const c_char,
-    num_texts: usize,
+/// - Every text pointer must be valid for its encoding
+/// - output_buffer must point to at least buffer_size f32 elements
+/// - embedding_dim and total_written must be valid pointers
+unsafe fn embed_texts(
+    handle: &mut EmbedderHandle,
+    texts: &[FfiText],
     output_buffer: *mut f32,
     buffer_size: usize,
     embedding_dim: *mut usize,
     total_written: *mut usize,
 ) -> EmbedderErrorCode {
-    if handle.is_null() || texts.is_null() || output_buffer.is_null() {
-        return EmbedderErrorCode::NullPointer;
-    }
-    if embedding_dim.is_null() || total_written.is_null() {
-        return EmbedderErrorCode::NullPointer;
-    }
-
-    let handle = unsafe { &mut *handle };
-    let text_ptrs = unsafe { slice::from_raw_parts(texts, num_texts) };
-
     let mut offset = 0;
 
-    for (i, &text_ptr) in text_ptrs.iter().enumerate() {
-        if text_ptr.is_null() {
-            handle.last_error = Some(format!("Null text pointer at index {}", i));
-            return EmbedderErrorCode::NullPointer;
-        }
-
-        let text_str = match unsafe { CStr::from_ptr(text_ptr) }.to_str() {
+    for (i, &text) in texts.iter().enumerate() {
+        let text_str = match unsafe { text.decode() } {
             Ok(s) => s,
             Err(e) => {
-                handle.last_error = Some(format!("Invalid UTF-8 at index {}: {}", i, e));
-                return EmbedderErrorCode::InvalidUtf8;
+                handle.last_error = Some(e.message(Some(i)));
+                return e.code;
             }
         };
 
-        match handle.embedder.embed(text_str) {
+        match handle.embedder.embed(&text_str) {
             Ok(embedding) => {
                 if i == 0 {
                     let dim = embedding.len();
@@ -220,70 +667,1331 @@ pub unsafe extern "C" fn embedder_embed_batch(
     EmbedderErrorCode::Success
 }
 
-/// Get the last error message from the embedder.
-///
-/// # Parameters
-/// - `handle`: Pointer to EmbedderHandle
-///
-/// # Returns
-/// - C string containing the error message, or null if no error
-/// - The returned string is valid until the next operation on this handle
+/// Shared implementation of the batch embedding functions with a library-allocated result.
 ///
 /// # Safety
-/// - handle must be valid
-/// - The returned string pointer is only valid until the next call to any embedder function
-/// - The caller must NOT free the returned string
-#[unsafe(no_mangle)]
-pub unsafe extern "C" fn embedder_get_last_error(handle: *mut EmbedderHandle) -> *const c_char {
-    if handle.is_null() {
-        return ptr::null();
-    }
+/// - Every text pointer must be valid for its encoding
+unsafe fn embed_texts_alloc(handle: &mut EmbedderHandle, texts: &[FfiText]) -> *mut EmbeddingBatch {
+    let dimension = handle.embedder.dimension();
+    let mut batch = EmbeddingBatch {
+        data: vec![0.0; texts.len() * dimension],
+        dimension,
+        status: vec![EmbedderErrorCode::Success; texts.len()],
+    };
+    handle.last_error = None;
 
-    let handle = unsafe { &*handle };
+    for (i, &text) in texts.iter().enumerate() {
+        let (status, error) = match unsafe { text.decode() } {
+            Err(e) => (e.code, e.message(Some(i))),
+            Ok(text_str) => match handle.embedder.embed(&text_str) {
+                Ok(embedding) if embedding.len() == dimension => {
+                    batch.data[i * dimension..(i + 1) * dimension].copy_from_slice(&embedding);
+                    continue;
+                }
+                Ok(embedding) => (
+                    EmbedderErrorCode::EmbeddingFailed,
+                    format!(
+                        "Embedding failed at text {}: expected dimension {} but got {}",
+                        i,
+                        dimension,
+                        embedding.len()
+                    ),
+                ),
+                Err(e) => (
+                    EmbedderErrorCode::EmbeddingFailed,
+                    format!("Embedding failed at text {}: {}", i, e),
+                ),
+            },
+        };
 
-    match &handle.last_error {
-        Some(err) => {
-            // This creates a potential memory leak, but it's safer for FFI
-            // Alternative: use a fixed buffer in EmbedderHandle
-            match CString::new(err.as_str()) {
-                Ok(c_string) => c_string.into_raw(),
-                Err(_) => ptr::null(),
-            }
+        batch.status[i] = status;
+        if handle.last_error.is_none() {
+            handle.last_error = Some(error);
         }
-        None => ptr::null(),
     }
+
+    Box::into_raw(Box::new(batch))
 }
 
-/// Free the error string returned by embedder_get_last_error.
+/// Generate an embedding for a single text string.
 ///
 /// # Parameters
-/// - `error_str`: String pointer returned by embedder_get_last_error()
+/// - `handle`: Pointer to EmbedderHandle returned by embedder_init()
+/// - `text`: C string containing the input text
+/// - `output_buffer`: Pre-allocated buffer to receive the embedding floats
+/// - `buffer_size`: Size of the output_buffer (number of f32 elements it can hold)
+/// - `actual_size`: Output parameter - will be set to the actual embedding dimension
+///
+/// # Returns
+/// - EmbedderErrorCode indicating success or failure
 ///
 /// # Safety
-/// - error_str must be a pointer returned by embedder_get_last_error()
-/// - Must only be called once per error string
+/// - handle must be a valid pointer returned by embedder_init()
+/// - text must be a valid null-terminated C string
+/// - output_buffer must point to allocated memory of at least buffer_size f32 elements
+/// - actual_size must be a valid pointer to write the output size
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn embedder_free_error(error_str: *mut c_char) {
-    if !error_str.is_null() {
-        unsafe { drop(CString::from_raw(error_str)) };
-    }
+pub unsafe extern "C" fn embedder_embed(
+    handle: *mut EmbedderHandle,
+    text: *const c_char,
+    output_buffer: *mut f32,
+    buffer_size: usize,
+    actual_size: *mut usize,
+) -> EmbedderErrorCode {
+    guard_handle(handle, || {
+        // Validate pointers
+        if handle.is_null() {
+            return EmbedderErrorCode::InvalidHandle;
+        }
+        if text.is_null() {
+            return EmbedderErrorCode::NullPointer;
+        }
+
+        let handle = unsafe { &mut *handle };
+        unsafe {
+            embed_text(
+                handle,
+                FfiText::CStr(text),
+                output_buffer,
+                buffer_size,
+                actual_size,
+            )
+        }
+    })
 }
 
-/// Free the embedder handle and release all associated resources.
+/// Generate an embedding for a UTF-8 text given as a pointer and a byte length.
+///
+/// The text does not need to be null-terminated and may contain NUL characters.
 ///
 /// # Parameters
-/// - `handle`: Pointer to EmbedderHandle to free
+/// - `handle`: Pointer to EmbedderHandle returned by embedder_init()
+/// - `text`: Pointer to the UTF-8 bytes (may be null if `text_len` is 0)
+/// - `text_len`: Number of bytes in `text`
+/// - `output_buffer`, `buffer_size`, `actual_size`: as in `embedder_embed()`
+///
+/// # Returns
+/// - EmbedderErrorCode indicating success or failure
 ///
 /// # Safety
 /// - handle must be a valid pointer returned by embedder_init()
-/// - handle must not be used after this call
-/// - Must only be called once per handle
+/// - text must point to at least text_len bytes
+/// - output_buffer must point to allocated memory of at least buffer_size f32 elements
+/// - actual_size must be a valid pointer to write the output size
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn embedder_free(handle: *mut EmbedderHandle) {
-    if !handle.is_null() {
-        unsafe { drop(Box::from_raw(handle)) };
-    }
-}
+pub unsafe extern "C" fn embedder_embed_utf8(
+    handle: *mut EmbedderHandle,
+    text: *const u8,
+    text_len: usize,
+    output_buffer: *mut f32,
+    buffer_size: usize,
+    actual_size: *mut usize,
+) -> EmbedderErrorCode {
+    guard_handle(handle, || {
+        if handle.is_null() {
+            return EmbedderErrorCode::InvalidHandle;
+        }
+
+        let handle = unsafe { &mut *handle };
+        unsafe {
+            embed_text(
+                handle,
+                FfiText::Utf8(text, text_len),
+                output_buffer,
+                buffer_size,
+                actual_size,
+            )
+        }
+    })
+}
+
+/// Generate an embedding for a UTF-16 text given as a pointer and a length in code units.
+///
+/// This matches the native string layout of .NET and PowerShell. Unpaired
+/// surrogates are reported as `InvalidUtf8`.
+///
+/// # Parameters
+/// - `handle`: Pointer to EmbedderHandle returned by embedder_init()
+/// - `text`: Pointer to the UTF-16 code units (may be null if `text_len` is 0)
+/// - `text_len`: Number of u16 code units in `text`
+/// - `output_buffer`, `buffer_size`, `actual_size`: as in `embedder_embed()`
+///
+/// # Returns
+/// - EmbedderErrorCode indicating success or failure
+///
+/// # Safety
+/// - handle must be a valid pointer returned by embedder_init()
+/// - text must point to at least text_len u16 elements
+/// - output_buffer must point to allocated memory of at least buffer_size f32 elements
+/// - actual_size must be a valid pointer to write the output size
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn embedder_embed_utf16(
+    handle: *mut EmbedderHandle,
+    text: *const u16,
+    text_len: usize,
+    output_buffer: *mut f32,
+    buffer_size: usize,
+    actual_size: *mut usize,
+) -> EmbedderErrorCode {
+    guard_handle(handle, || {
+        if handle.is_null() {
+            return EmbedderErrorCode::InvalidHandle;
+        }
+
+        let handle = unsafe { &mut *handle };
+        unsafe {
+            embed_text(
+                handle,
+                FfiText::Utf16(text, text_len),
+                output_buffer,
+                buffer_size,
+                actual_size,
+            )
+        }
+    })
+}
+
+/// Generate an embedding for an image, in the vector space of the texts.
+///
+/// Only text–image models ("clip", "siglip") embed images; the image is
+/// decoded, resized and normalized by the library.
+///
+/// # Parameters
+/// - `handle`: Pointer to EmbedderHandle returned by embedder_init()
+/// - `image`: Pointer to the bytes of a PNG or JPEG file
+/// - `image_len`: Number of bytes in `image`
+/// - `output_buffer`, `buffer_size`, `actual_size`: as in `embedder_embed()`
+///
+/// # Returns
+/// - EmbedderErrorCode indicating success or failure; `InvalidArgument` if the
+///   model does not embed images
+///
+/// # Safety
+/// - handle must be a valid pointer returned by embedder_init()
+/// - image must point to at least image_len bytes
+/// - output_buffer must point to allocated memory of at least buffer_size f32 elements
+/// - actual_size must be a valid pointer to write the output size
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn embedder_embed_image(
+    handle: *mut EmbedderHandle,
+    image: *const u8,
+    image_len: usize,
+    output_buffer: *mut f32,
+    buffer_size: usize,
+    actual_size: *mut usize,
+) -> EmbedderErrorCode {
+    guard_handle(handle, || {
+        if handle.is_null() {
+            return EmbedderErrorCode::InvalidHandle;
+        }
+        if image.is_null() || output_buffer.is_null() || actual_size.is_null() {
+            return EmbedderErrorCode::NullPointer;
+        }
+
+        let handle = unsafe { &mut *handle };
+        if handle.embedder.info().image_size.is_none() {
+            handle.last_error = Some(format!(
+                "Model {} does not embed images",
+                handle.embedder.info().model_id
+            ));
+            return EmbedderErrorCode::InvalidArgument;
+        }
+
+        let image = unsafe { slice::from_raw_parts(image, image_len) };
+        match handle.embedder.embed_image(image) {
+            Ok(embedding) => {
+                let embed_len = embedding.len();
+                unsafe { *actual_size = embed_len };
+
+                if embed_len > buffer_size {
+                    handle.last_error = Some(format!(
+                        "Buffer too small: need {} but got {}",
+                        embed_len, buffer_size
+                    ));
+                    return EmbedderErrorCode::BufferTooSmall;
+                }
+
+                let output_slice = unsafe { slice::from_raw_parts_mut(output_buffer, embed_len) };
+                output_slice.copy_from_slice(&embedding);
+
+                handle.last_error = None;
+                EmbedderErrorCode::Success
+            }
+            Err(e) => {
+                handle.last_error = Some(format!("Embedding failed: {}", e));
+                EmbedderErrorCode::EmbeddingFailed
+            }
+        }
+    })
+}
+
+/// Generate embeddings for multiple text strings (batch processing).
+///
+/// # Parameters
+/// - `handle`: Pointer to EmbedderHandle
+/// - `texts`: Array of C string pointers
+/// - `num_texts`: Number of texts in the array
+/// - `output_buffer`: Pre-allocated buffer to receive all embeddings (flattened)
+/// - `buffer_size`: Total size of output_buffer (number of f32 elements)
+/// - `embedding_dim`: Output parameter - embedding dimension per text
+/// - `total_written`: Output parameter - total number of floats written
+///
+/// # Returns
+/// - EmbedderErrorCode indicating success or failure
+///
+/// # Safety
+/// - handle must be valid
+/// - texts must point to an array of num_texts valid C string pointers
+/// - output_buffer must have space for num_texts * embedding_dim floats
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn embedder_embed_batch(
+    handle: *mut EmbedderHandle,
+    texts: *const *const c_char,
+    num_texts: usize,
+    output_buffer: *mut f32,
+    buffer_size: usize,
+    embedding_dim: *mut usize,
+    total_written: *mut usize,
+) -> EmbedderErrorCode {
+    guard_handle(handle, || {
+        if handle.is_null() || texts.is_null() || output_buffer.is_null() {
+            return EmbedderErrorCode::NullPointer;
+        }
+        if embedding_dim.is_null() || total_written.is_null() {
+            return EmbedderErrorCode::NullPointer;
+        }
+
+        let handle = unsafe { &mut *handle };
+        let texts: Vec<FfiText> = unsafe { slice::from_raw_parts(texts, num_texts) }
+            .iter()
+            .map(|&text| FfiText::CStr(text))
+            .collect();
+
+        unsafe {
+            embed_texts(
+                handle,
+                &texts,
+                output_buffer,
+                buffer_size,
+                embedding_dim,
+                total_written,
+            )
+        }
+    })
+}
+
+/// Generate embeddings for multiple UTF-8 texts given as pointers and byte lengths.
+///
+/// # Parameters
+/// - `handle`: Pointer to EmbedderHandle
+/// - `texts`: Array of pointers to UTF-8 bytes
+/// - `text_lens`: Array with the byte length of each text
+/// - `num_texts`: Number of texts in the arrays
+/// - `output_buffer`, `buffer_size`, `embedding_dim`, `total_written`: as in `embedder_embed_batch()`
+///
+/// # Returns
+/// - EmbedderErrorCode indicating success or failure
+///
+/// # Safety
+/// - handle must be valid
+/// - texts and text_lens must point to arrays of num_texts elements
+/// - output_buffer must have space for num_texts * embedding_dim floats
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn embedder_embed_batch_utf8(
+    handle: *mut EmbedderHandle,
+    texts: *const *const u8,
+    text_lens: *const usize,
+    num_texts: usize,
+    output_buffer: *mut f32,
+    buffer_size: usize,
+    embedding_dim: *mut usize,
+    total_written: *mut usize,
+) -> EmbedderErrorCode {
+    guard_handle(handle, || {
+        if handle.is_null() || output_buffer.is_null() {
+            return EmbedderErrorCode::NullPointer;
+        }
+        if embedding_dim.is_null() || total_written.is_null() {
+            return EmbedderErrorCode::NullPointer;
+        }
+        let Some(texts) = (unsafe { sized_texts(texts, text_lens, num_texts, FfiText::Utf8) })
+        else {
+            return EmbedderErrorCode::NullPointer;
+        };
+
+        let handle = unsafe { &mut *handle };
+        unsafe {
+            embed_texts(
+                handle,
+                &texts,
+                output_buffer,
+                buffer_size,
+                embedding_dim,
+                total_written,
+            )
+        }
+    })
+}
+
+/// Generate embeddings for multiple UTF-16 texts given as pointers and code unit lengths.
+///
+/// # Parameters
+/// - `handle`: Pointer to EmbedderHandle
+/// - `texts`: Array of pointers to UTF-16 code units
+/// - `text_lens`: Array with the length of each text in u16 code units
+/// - `num_texts`: Number of texts in the arrays
+/// - `output_buffer`, `buffer_size`, `embedding_dim`, `total_written`: as in `embedder_embed_batch()`
+///
+/// # Returns
+/// - EmbedderErrorCode indicating success or failure
+///
+/// # Safety
+/// - handle must be valid
+/// - texts and text_lens must point to arrays of num_texts elements
+/// - output_buffer must have space for num_texts * embedding_dim floats
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn embedder_embed_batch_utf16(
+    handle: *mut EmbedderHandle,
+    texts: *const *const u16,
+    text_lens: *const usize,
+    num_texts: usize,
+    output_buffer: *mut f32,
+    buffer_size: usize,
+    embedding_dim: *mut usize,
+    total_written: *mut usize,
+) -> EmbedderErrorCode {
+    guard_handle(handle, || {
+        if handle.is_null() || output_buffer.is_null() {
+            return EmbedderErrorCode::NullPointer;
+        }
+        if embedding_dim.is_null() || total_written.is_null() {
+            return EmbedderErrorCode::NullPointer;
+        }
+        let Some(texts) = (unsafe { sized_texts(texts, text_lens, num_texts, FfiText::Utf16) })
+        else {
+            return EmbedderErrorCode::NullPointer;
+        };
+
+        let handle = unsafe { &mut *handle };
+        unsafe {
+            embed_texts(
+                handle,
+                &texts,
+                output_buffer,
+                buffer_size,
+                embedding_dim,
+                total_written,
+            )
+        }
+    })
+}
+
+/// Progress and cancellation callbacks for `embedder_embed_batch_progress()`.
+///
+/// Both callbacks are optional and are invoked on the calling thread, between
+/// batches. `user_data` is passed through unchanged. Like `EmbedderOptions`,
+/// the struct is size-prefixed.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct EmbedderProgress {
+    pub struct_size: usize,
+    /// Called after each batch with the number of texts embedded so far and the total
+    pub on_progress:
+        Option<unsafe extern "C" fn(done: usize, total: usize, user_data: *mut c_void)>,
+    /// Polled before each batch; a non-zero return value cancels the operation
+    pub is_cancelled: Option<unsafe extern "C" fn(user_data: *mut c_void) -> i32>,
+    pub user_data: *mut c_void,
+}
+
+impl Default for EmbedderProgress {
+    fn default() -> Self {
+        Self {
+            struct_size: mem::size_of::<Self>(),
+            on_progress: None,
+            is_cancelled: None,
+            user_data: ptr::null_mut(),
+        }
+    }
+}
+
+impl abi::SizePrefixed for EmbedderProgress {
+    const FIELD_ENDS: &'static [usize] = &[
+        mem::size_of::<usize>(),
+        mem::offset_of!(Self, on_progress) + mem::size_of::<Option<unsafe extern "C" fn()>>(),
+        mem::offset_of!(Self, is_cancelled) + mem::size_of::<Option<unsafe extern "C" fn()>>(),
+        mem::offset_of!(Self, user_data) + mem::size_of::<*mut c_void>(),
+    ];
+}
+
+impl EmbedProgress for EmbedderProgress {
+    fn on_progress(&mut self, done: usize, total: usize) {
+        if let Some(on_progress) = self.on_progress {
+            unsafe { on_progress(done, total, self.user_data) };
+        }
+    }
+
+    fn is_cancelled(&mut self) -> bool {
+        match self.is_cancelled {
+            Some(is_cancelled) => unsafe { is_cancelled(self.user_data) != 0 },
+            None => false,
+        }
+    }
+}
+
+/// Batch size used by `embedder_embed_batch_progress()` when 0 is passed
+const DEFAULT_BATCH_SIZE: usize = 32;
+
+/// Generate embeddings for multiple texts in batches, reporting progress and
+/// allowing cancellation between batches.
+///
+/// # Parameters
+/// - `handle`: Pointer to EmbedderHandle
+/// - `texts`: Array of C string pointers
+/// - `num_texts`: Number of texts in the array
+/// - `batch_size`: Number of texts run through the model at once (0 for the default of 32)
+/// - `output_buffer`: Pre-allocated buffer for all embeddings (flattened)
+/// - `buffer_size`: Total size of output_buffer, at least num_texts * dimension floats
+/// - `embedding_dim`: Output parameter - embedding dimension per text
+/// - `num_completed`: Output parameter - number of texts whose embedding was written
+/// - `progress`: Optional callbacks (may be null)
+///
+/// # Returns
+/// - `Success` when all texts were embedded
+/// - `Cancelled` when `is_cancelled` returned non-zero; the first `num_completed`
+///   embeddings are written to output_buffer
+/// - `InvalidArgument` if `num_texts * dimension` overflows
+/// - Another EmbedderErrorCode on failure
+///
+/// # Safety
+/// - handle must be valid
+/// - texts must point to an array of num_texts valid C string pointers
+/// - output_buffer must point to at least buffer_size floats
+/// - progress must be null or point to a valid EmbedderProgress for the whole call
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn embedder_embed_batch_progress(
+    handle: *mut EmbedderHandle,
+    texts: *const *const c_char,
+    num_texts: usize,
+    batch_size: usize,
+    output_buffer: *mut f32,
+    buffer_size: usize,
+    embedding_dim: *mut usize,
+    num_completed: *mut usize,
+    progress: *const EmbedderProgress,
+) -> EmbedderErrorCode {
+    guard_handle(handle, || {
+        if handle.is_null() || texts.is_null() || output_buffer.is_null() {
+            return EmbedderErrorCode::NullPointer;
+        }
+        if embedding_dim.is_null() || num_completed.is_null() {
+            return EmbedderErrorCode::NullPointer;
+        }
+
+        let handle = unsafe { &mut *handle };
+        let dim = handle.embedder.dimension();
+        unsafe { *embedding_dim = dim };
+        unsafe { *num_completed = 0 };
+        let Some(needed) = num_texts.checked_mul(dim) else {
+            handle.last_error = Some(format!(
+                "{} texts of dimension {} overflow the buffer size",
+                num_texts, dim
+            ));
+            return EmbedderErrorCode::InvalidArgument;
+        };
+        if needed > buffer_size {
+            handle.last_error = Some(format!(
+                "Buffer too small: need {} but got {}",
+                needed, buffer_size
+            ));
+            return EmbedderErrorCode::BufferTooSmall;
+        }
+
+        let text_ptrs = unsafe { slice::from_raw_parts(texts, num_texts) };
+        let mut column = Vec::with_capacity(num_texts);
+        for (i, &text) in text_ptrs.iter().enumerate() {
+            match unsafe { FfiText::CStr(text).decode() } {
+                Ok(s) => column.push(s.into_owned()),
+                Err(e) => {
+                    handle.last_error = Some(e.message(Some(i)));
+                    return e.code;
+                }
+            }
+        }
+
+        let mut progress = if progress.is_null() {
+            EmbedderProgress::default()
+        } else {
+            match unsafe { abi::read_size_prefixed(progress) } {
+                Some(progress) => progress,
+                None => return EmbedderErrorCode::InvalidArgument,
+            }
+        };
+        let batch_size = if batch_size == 0 {
+            DEFAULT_BATCH_SIZE
+        } else {
+            batch_size
+        };
+
+        let (embeddings, code) =
+            match handle
+                .embedder
+                .embed_with_progress(column, batch_size, &mut progress)
+            {
+                Ok(embeddings) => (embeddings, EmbedderErrorCode::Success),
+                Err(EmbeddingError::Cancelled { embeddings }) => {
+                    handle.last_error = Some(format!(
+                        "Embedding cancelled after {} of {} texts",
+                        embeddings.len(),
+                        num_texts
+                    ));
+                    (embeddings, EmbedderErrorCode::Cancelled)
+                }
+                Err(e) => {
+                    handle.last_error = Some(format!("Embedding failed: {}", e));
+                    return EmbedderErrorCode::EmbeddingFailed;
+                }
+            };
+
+        let output_slice = unsafe { slice::from_raw_parts_mut(output_buffer, buffer_size) };
+        for (row, embedding) in output_slice.chunks_exact_mut(dim).zip(&embeddings) {
+            row.copy_from_slice(embedding);
+        }
+        unsafe { *num_completed = embeddings.len() };
+
+        if code == EmbedderErrorCode::Success {
+            handle.last_error = None;
+        }
+        code
+    })
+}
+
+/// Embeddings allocated by the library, returned by `embedder_embed_batch_alloc()`.
+///
+/// Rows are stored contiguously; the row of a text that failed is zero-filled
+/// and its status holds the error code.
+pub struct EmbeddingBatch {
+    data: Vec<f32>,
+    dimension: usize,
+    status: Vec<EmbedderErrorCode>,
+}
+
+/// Generate embeddings for multiple texts into a batch allocated by the library.
+///
+/// Unlike `embedder_embed_batch()`, a failing text does not abort the batch:
+/// its status is recorded per item and the remaining texts are still embedded.
+///
+/// # Parameters
+/// - `handle`: Pointer to EmbedderHandle
+/// - `texts`: Array of C string pointers
+/// - `num_texts`: Number of texts in the array
+///
+/// # Returns
+/// - Pointer to EmbeddingBatch, or null if handle or texts is null
+/// - The last error of the handle describes the first failing text, if any
+///
+/// # Safety
+/// - handle must be valid
+/// - texts must point to an array of num_texts C string pointers (null entries
+///   are reported as `NullPointer` for that item)
+/// - The returned batch must be freed with `embedder_batch_free()`
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn embedder_embed_batch_alloc(
+    handle: *mut EmbedderHandle,
+    texts: *const *const c_char,
+    num_texts: usize,
+) -> *mut EmbeddingBatch {
+    guard_handle_or(handle, ptr::null_mut(), || {
+        if handle.is_null() || texts.is_null() {
+            return ptr::null_mut();
+        }
+
+        let handle = unsafe { &mut *handle };
+        let texts: Vec<FfiText> = unsafe { slice::from_raw_parts(texts, num_texts) }
+            .iter()
+            .map(|&text| FfiText::CStr(text))
+            .collect();
+
+        unsafe { embed_texts_alloc(handle, &texts) }
+    })
+}
+
+/// Same as `embedder_embed_batch_alloc()` for UTF-8 texts given as pointers and byte lengths.
+///
+/// # Safety
+/// - handle must be valid
+/// - texts and text_lens must point to arrays of num_texts elements
+/// - The returned batch must be freed with `embedder_batch_free()`
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn embedder_embed_batch_alloc_utf8(
+    handle: *mut EmbedderHandle,
+    texts: *const *const u8,
+    text_lens: *const usize,
+    num_texts: usize,
+) -> *mut EmbeddingBatch {
+    guard_handle_or(handle, ptr::null_mut(), || {
+        if handle.is_null() {
+            return ptr::null_mut();
+        }
+        let Some(texts) = (unsafe { sized_texts(texts, text_lens, num_texts, FfiText::Utf8) })
+        else {
+            return ptr::null_mut();
+        };
+
+        let handle = unsafe { &mut *handle };
+        unsafe { embed_texts_alloc(handle, &texts) }
+    })
+}
+
+/// Same as `embedder_embed_batch_alloc()` for UTF-16 texts given as pointers and
+/// code unit lengths. Unpaired surrogates are reported per item as `InvalidUtf8`.
+///
+/// # Safety
+/// - handle must be valid
+/// - texts and text_lens must point to arrays of num_texts elements
+/// - The returned batch must be freed with `embedder_batch_free()`
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn embedder_embed_batch_alloc_utf16(
+    handle: *mut EmbedderHandle,
+    texts: *const *const u16,
+    text_lens: *const usize,
+    num_texts: usize,
+) -> *mut EmbeddingBatch {
+    guard_handle_or(handle, ptr::null_mut(), || {
+        if handle.is_null() {
+            return ptr::null_mut();
+        }
+        let Some(texts) = (unsafe { sized_texts(texts, text_lens, num_texts, FfiText::Utf16) })
+        else {
+            return ptr::null_mut();
+        };
+
+        let handle = unsafe { &mut *handle };
+        unsafe { embed_texts_alloc(handle, &texts) }
+    })
+}
+
+/// Get the number of items in a batch.
+///
+/// # Safety
+/// - batch must be null or a pointer returned by embedder_embed_batch_alloc()
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn embedder_batch_count(batch: *const EmbeddingBatch) -> usize {
+    guard_or(0, || {
+        if batch.is_null() {
+            return 0;
+        }
+        unsafe { &*batch }.status.len()
+    })
+}
+
+/// Get the embedding dimension of every row in a batch.
+///
+/// # Safety
+/// - batch must be null or a pointer returned by embedder_embed_batch_alloc()
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn embedder_batch_dimension(batch: *const EmbeddingBatch) -> usize {
+    guard_or(0, || {
+        if batch.is_null() {
+            return 0;
+        }
+        unsafe { &*batch }.dimension
+    })
+}
+
+/// Get the status of one item in a batch.
+///
+/// # Returns
+/// - `Success` if the row holds a valid embedding, the item's error code otherwise
+/// - `NullPointer` if batch is null, `InvalidArgument` if index is out of range
+///
+/// # Safety
+/// - batch must be null or a pointer returned by embedder_embed_batch_alloc()
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn embedder_batch_status(
+    batch: *const EmbeddingBatch,
+    index: usize,
+) -> EmbedderErrorCode {
+    guard_or(EmbedderErrorCode::Panic, || {
+        if batch.is_null() {
+            return EmbedderErrorCode::NullPointer;
+        }
+        match unsafe { &*batch }.status.get(index) {
+            Some(&status) => status,
+            None => EmbedderErrorCode::InvalidArgument,
+        }
+    })
+}
+
+/// Get a pointer to the embedding of one item in a batch.
+///
+/// # Returns
+/// - Pointer to `embedder_batch_dimension()` floats, or null if the batch is
+///   null, the index is out of range or the item failed
+///
+/// # Safety
+/// - batch must be null or a pointer returned by embedder_embed_batch_alloc()
+/// - The returned pointer is valid until `embedder_batch_free()`
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn embedder_batch_row(
+    batch: *const EmbeddingBatch,
+    index: usize,
+) -> *const f32 {
+    guard_or(ptr::null(), || {
+        if batch.is_null() {
+            return ptr::null();
+        }
+        let batch = unsafe { &*batch };
+        match batch.status.get(index) {
+            Some(EmbedderErrorCode::Success) => batch.data[index * batch.dimension..].as_ptr(),
+            _ => ptr::null(),
+        }
+    })
+}
+
+/// Get a pointer to all rows of a batch, stored contiguously
+/// (`count * dimension` floats, failed rows are zero-filled).
+///
+/// # Safety
+/// - batch must be null or a pointer returned by embedder_embed_batch_alloc()
+/// - The returned pointer is valid until `embedder_batch_free()`
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn embedder_batch_data(batch: *const EmbeddingBatch) -> *const f32 {
+    guard_or(ptr::null(), || {
+        if batch.is_null() {
+            return ptr::null();
+        }
+        unsafe { &*batch }.data.as_ptr()
+    })
+}
+
+/// Free a batch returned by embedder_embed_batch_alloc().
+///
+/// # Safety
+/// - batch must be null or a pointer returned by embedder_embed_batch_alloc()
+/// - batch and its row pointers must not be used after this call
+/// - Must only be called once per batch
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn embedder_batch_free(batch: *mut EmbeddingBatch) {
+    guard_or((), || {
+        if !batch.is_null() {
+            unsafe { drop(Box::from_raw(batch)) };
+        }
+    })
+}
+
+/// Compute the similarity between two texts.
+///
+/// Both texts are embedded with the handle's model. Cosine is computed from the
+/// norms of the embeddings; dot only gives the same score when the handle
+/// normalizes its embeddings (the default, see `EmbedderOptions::normalize`).
+///
+/// # Parameters
+/// - `handle`: Pointer to EmbedderHandle
+/// - `text_a`, `text_b`: C strings containing the texts to compare
+/// - `metric`: `EMBEDDER_SIMILARITY_COSINE` (0) or `EMBEDDER_SIMILARITY_DOT` (1)
+/// - `score`: Output parameter - similarity score
+///
+/// # Returns
+/// - EmbedderErrorCode indicating success or failure
+///
+/// # Safety
+/// - handle must be valid
+/// - text_a and text_b must be valid null-terminated C strings
+/// - score must be a valid pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn embedder_similarity(
+    handle: *mut EmbedderHandle,
+    text_a: *const c_char,
+    text_b: *const c_char,
+    metric: u32,
+    score: *mut f32,
+) -> EmbedderErrorCode {
+    guard_handle(handle, || {
+        if handle.is_null() {
+            return EmbedderErrorCode::InvalidHandle;
+        }
+        if score.is_null() {
+            return EmbedderErrorCode::NullPointer;
+        }
+
+        let handle = unsafe { &mut *handle };
+        let Some(metric) = similarity_metric(metric) else {
+            handle.last_error = Some(format!("Unknown similarity metric: {}", metric));
+            return EmbedderErrorCode::InvalidArgument;
+        };
+
+        let mut embeddings = Vec::with_capacity(2);
+        for (i, text) in [text_a, text_b].into_iter().enumerate() {
+            let text_str = match unsafe { FfiText::CStr(text).decode() } {
+                Ok(s) => s,
+                Err(e) => {
+                    handle.last_error = Some(e.message(Some(i)));
+                    return e.code;
+                }
+            };
+            match handle.embedder.embed(&text_str) {
+                Ok(embedding) => embeddings.push(embedding),
+                Err(e) => {
+                    handle.last_error = Some(format!("Embedding failed at text {}: {}", i, e));
+                    return EmbedderErrorCode::EmbeddingFailed;
+                }
+            }
+        }
+
+        unsafe { *score = metric.score(&embeddings[0], &embeddings[1]) };
+        handle.last_error = None;
+        EmbedderErrorCode::Success
+    })
+}
+
+/// Compute the similarity between two embedding vectors.
+///
+/// # Parameters
+/// - `a`, `b`: Vectors of `dim` floats each
+/// - `dim`: Vector dimension
+/// - `metric`: `EMBEDDER_SIMILARITY_COSINE` (0) or `EMBEDDER_SIMILARITY_DOT` (1)
+/// - `score`: Output parameter - similarity score
+///
+/// # Returns
+/// - EmbedderErrorCode indicating success or failure
+///
+/// # Safety
+/// - a and b must point to at least dim floats
+/// - score must be a valid pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn embedder_similarity_vectors(
+    a: *const f32,
+    b: *const f32,
+    dim: usize,
+    metric: u32,
+    score: *mut f32,
+) -> EmbedderErrorCode {
+    guard_or(EmbedderErrorCode::Panic, || {
+        if a.is_null() || b.is_null() || score.is_null() {
+            return EmbedderErrorCode::NullPointer;
+        }
+        let Some(metric) = similarity_metric(metric) else {
+            return EmbedderErrorCode::InvalidArgument;
+        };
+
+        let a = unsafe { slice::from_raw_parts(a, dim) };
+        let b = unsafe { slice::from_raw_parts(b, dim) };
+        unsafe { *score = metric.score(a, b) };
+        EmbedderErrorCode::Success
+    })
+}
+
+/// Compute the similarity of every row of `a` against every row of `b`.
+///
+/// # Parameters
+/// - `a`: Flattened matrix of `rows_a * dim` floats
+/// - `rows_a`: Number of vectors in `a`
+/// - `b`: Flattened matrix of `rows_b * dim` floats
+/// - `rows_b`: Number of vectors in `b`
+/// - `dim`: Vector dimension (must be greater than 0)
+/// - `metric`: `EMBEDDER_SIMILARITY_COSINE` (0) or `EMBEDDER_SIMILARITY_DOT` (1)
+/// - `output_buffer`: Receives the row-major `rows_a * rows_b` score matrix
+/// - `buffer_size`: Size of output_buffer (number of f32 elements)
+///
+/// # Returns
+/// - EmbedderErrorCode indicating success or failure; `InvalidArgument` if a
+///   matrix size overflows
+///
+/// # Safety
+/// - a and b must point to rows_a * dim and rows_b * dim floats
+/// - output_buffer must point to at least buffer_size floats
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn embedder_similarity_matrix(
+    a: *const f32,
+    rows_a: usize,
+    b: *const f32,
+    rows_b: usize,
+    dim: usize,
+    metric: u32,
+    output_buffer: *mut f32,
+    buffer_size: usize,
+) -> EmbedderErrorCode {
+    guard_or(EmbedderErrorCode::Panic, || {
+        if a.is_null() || b.is_null() || output_buffer.is_null() {
+            return EmbedderErrorCode::NullPointer;
+        }
+        let Some(metric) = similarity_metric(metric) else {
+            return EmbedderErrorCode::InvalidArgument;
+        };
+        if dim == 0 {
+            return EmbedderErrorCode::InvalidArgument;
+        }
+        let sizes = (
+            rows_a.checked_mul(rows_b),
+            rows_a.checked_mul(dim),
+            rows_b.checked_mul(dim),
+        );
+        let (Some(scores_len), Some(a_len), Some(b_len)) = sizes else {
+            return EmbedderErrorCode::InvalidArgument;
+        };
+        if scores_len > buffer_size {
+            return EmbedderErrorCode::BufferTooSmall;
+        }
+
+        let a = unsafe { slice::from_raw_parts(a, a_len) };
+        let b = unsafe { slice::from_raw_parts(b, b_len) };
+        let scores = similarity::similarity_matrix(a, b, dim, metric);
+
+        let output_slice = unsafe { slice::from_raw_parts_mut(output_buffer, scores.len()) };
+        output_slice.copy_from_slice(&scores);
+        EmbedderErrorCode::Success
+    })
+}
+
+/// Find the `k` rows of a caller-supplied embedding matrix most similar to a query.
+///
+/// # Parameters
+/// - `query`: Query vector of `dim` floats
+/// - `matrix`: Flattened matrix of `num_rows * dim` floats
+/// - `num_rows`: Number of vectors in `matrix`
+/// - `dim`: Vector dimension (must be greater than 0)
+/// - `k`: Maximum number of results
+/// - `metric`: `EMBEDDER_SIMILARITY_COSINE` (0) or `EMBEDDER_SIMILARITY_DOT` (1)
+/// - `out_indices`: Receives up to `k` row indices, best first
+/// - `out_scores`: Receives the matching scores
+/// - `out_count`: Output parameter - number of results written (`min(k, num_rows)`)
+///
+/// # Returns
+/// - EmbedderErrorCode indicating success or failure; `InvalidArgument` if
+///   `num_rows * dim` overflows
+///
+/// # Safety
+/// - query must point to dim floats and matrix to num_rows * dim floats
+/// - out_indices and out_scores must point to at least k elements
+/// - out_count must be a valid pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn embedder_topk(
+    query: *const f32,
+    matrix: *const f32,
+    num_rows: usize,
+    dim: usize,
+    k: usize,
+    metric: u32,
+    out_indices: *mut usize,
+    out_scores: *mut f32,
+    out_count: *mut usize,
+) -> EmbedderErrorCode {
+    guard_or(EmbedderErrorCode::Panic, || {
+        if query.is_null() || matrix.is_null() || out_count.is_null() {
+            return EmbedderErrorCode::NullPointer;
+        }
+        if out_indices.is_null() || out_scores.is_null() {
+            return EmbedderErrorCode::NullPointer;
+        }
+        let Some(metric) = similarity_metric(metric) else {
+            return EmbedderErrorCode::InvalidArgument;
+        };
+        if dim == 0 {
+            return EmbedderErrorCode::InvalidArgument;
+        }
+        let Some(matrix_len) = num_rows.checked_mul(dim) else {
+            return EmbedderErrorCode::InvalidArgument;
+        };
+
+        let query = unsafe { slice::from_raw_parts(query, dim) };
+        let matrix = unsafe { slice::from_raw_parts(matrix, matrix_len) };
+        let results = similarity::top_k(query, matrix, dim, k, metric);
+
+        let indices = unsafe { slice::from_raw_parts_mut(out_indices, results.len()) };
+        let scores = unsafe { slice::from_raw_parts_mut(out_scores, results.len()) };
+        for (i, (index, score)) in results.iter().enumerate() {
+            indices[i] = *index;
+            scores[i] = *score;
+        }
+        unsafe { *out_count = results.len() };
+        EmbedderErrorCode::Success
+    })
+}
+
+/// Score how relevant each of several documents is to a query with a
+/// cross-encoder ("ms_marco_minilm", "cross_encoder"), e.g. to rerank the best
+/// results of `embedder_topk()`.
+///
+/// # Parameters
+/// - `handle`: Pointer to EmbedderHandle of a cross-encoder
+/// - `query`: Null-terminated UTF-8 query
+/// - `documents`: Array of null-terminated UTF-8 documents
+/// - `num_documents`: Number of documents in the array
+/// - `out_scores`: Receives one score per document, in the order of the
+///   documents; higher is more relevant
+///
+/// # Returns
+/// - EmbedderErrorCode indicating success or failure; `InvalidArgument` if the
+///   model is not a cross-encoder
+///
+/// # Safety
+/// - handle must be valid
+/// - query must be a valid C string and documents must point to num_documents valid C strings
+/// - out_scores must point to at least num_documents f32 elements
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn embedder_rerank(
+    handle: *mut EmbedderHandle,
+    query: *const c_char,
+    documents: *const *const c_char,
+    num_documents: usize,
+    out_scores: *mut f32,
+) -> EmbedderErrorCode {
+    guard_handle(handle, || {
+        if handle.is_null() || query.is_null() || documents.is_null() || out_scores.is_null() {
+            return EmbedderErrorCode::NullPointer;
+        }
+
+        let handle = unsafe { &mut *handle };
+        if !handle.embedder.info().cross_encoder {
+            handle.last_error = Some(format!(
+                "Model {} is not a cross-encoder",
+                handle.embedder.info().model_id
+            ));
+            return EmbedderErrorCode::InvalidArgument;
+        }
+
+        let query = match unsafe { FfiText::CStr(query).decode() } {
+            Ok(query) => query,
+            Err(e) => {
+                handle.last_error = Some(e.message(None));
+                return e.code;
+            }
+        };
+        let mut texts = Vec::with_capacity(num_documents);
+        for (i, &document) in unsafe { slice::from_raw_parts(documents, num_documents) }
+            .iter()
+            .enumerate()
+        {
+            match unsafe { FfiText::CStr(document).decode() } {
+                Ok(text) => texts.push(text),
+                Err(e) => {
+                    handle.last_error = Some(e.message(Some(i)));
+                    return e.code;
+                }
+            }
+        }
+        let texts: Vec<&str> = texts.iter().map(|text| text.as_ref()).collect();
+
+        match handle.embedder.rerank(&query, &texts, DEFAULT_BATCH_SIZE) {
+            Ok(scores) => {
+                let output_slice = unsafe { slice::from_raw_parts_mut(out_scores, scores.len()) };
+                output_slice.copy_from_slice(&scores);
+                handle.last_error = None;
+                EmbedderErrorCode::Success
+            }
+            Err(e) => {
+                handle.last_error = Some(format!("Reranking failed: {}", e));
+                EmbedderErrorCode::EmbeddingFailed
+            }
+        }
+    })
+}
+
+/// Get the embedding dimension of the loaded model.
+///
+/// # Parameters
+/// - `handle`: Pointer to EmbedderHandle
+///
+/// # Returns
+/// - Number of f32 elements in each embedding, or 0 if the handle is null
+///
+/// # Safety
+/// - handle must be valid
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn embedder_get_dimension(handle: *mut EmbedderHandle) -> usize {
+    guard_or(0, || {
+        if handle.is_null() {
+            return 0;
+        }
+
+        let handle = unsafe { &*handle };
+        handle.embedder.dimension()
+    })
+}
+
+/// Get the maximum number of tokens the loaded model accepts per text.
+///
+/// # Parameters
+/// - `handle`: Pointer to EmbedderHandle
+///
+/// # Returns
+/// - Maximum sequence length in tokens, or 0 if the handle is null
+///
+/// # Safety
+/// - handle must be valid
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn embedder_get_max_tokens(handle: *mut EmbedderHandle) -> usize {
+    guard_or(0, || {
+        if handle.is_null() {
+            return 0;
+        }
+
+        let handle = unsafe { &*handle };
+        handle.embedder.max_tokens()
+    })
+}
+
+/// Get the metadata of the loaded model as a JSON string.
+///
+/// The JSON object contains `model_id`, `revision`, `dimension`, `max_tokens`,
+/// `pooling`, `normalize` and `dtype`.
+///
+/// # Parameters
+/// - `handle`: Pointer to EmbedderHandle
+///
+/// # Returns
+/// - C string with the JSON metadata, or null if the handle is null
+///
+/// # Safety
+/// - handle must be valid
+/// - The returned string is owned by the handle and valid until `embedder_free()`
+/// - The caller must NOT free the returned string
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn embedder_get_model_info(handle: *mut EmbedderHandle) -> *const c_char {
+    guard_or(ptr::null(), || {
+        if handle.is_null() {
+            return ptr::null();
+        }
+
+        let handle = unsafe { &*handle };
+        handle.model_info.as_ptr()
+    })
+}
+
+/// Get the last error message from the embedder.
+///
+/// # Parameters
+/// - `handle`: Pointer to EmbedderHandle
+///
+/// # Returns
+/// - C string containing the error message, or null if no error
+/// - The returned string is valid until the next operation on this handle
+///
+/// # Safety
+/// - handle must be valid
+/// - The returned string pointer is only valid until the next call to any embedder function
+/// - The caller must NOT free the returned string
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn embedder_get_last_error(handle: *mut EmbedderHandle) -> *const c_char {
+    guard_or(ptr::null(), || {
+        if handle.is_null() {
+            return ptr::null();
+        }
+
+        let handle = unsafe { &*handle };
+
+        match &handle.last_error {
+            Some(err) => {
+                // This creates a potential memory leak, but it's safer for FFI
+                // Alternative: use a fixed buffer in EmbedderHandle
+                match CString::new(err.as_str()) {
+                    Ok(c_string) => c_string.into_raw(),
+                    Err(_) => ptr::null(),
+                }
+            }
+            None => ptr::null(),
+        }
+    })
+}
+
+/// Free the error string returned by embedder_get_last_error.
+///
+/// # Parameters
+/// - `error_str`: String pointer returned by embedder_get_last_error()
+///
+/// # Safety
+/// - error_str must be a pointer returned by embedder_get_last_error()
+/// - Must only be called once per error string
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn embedder_free_error(error_str: *mut c_char) {
+    guard_or((), || {
+        if !error_str.is_null() {
+            unsafe { drop(CString::from_raw(error_str)) };
+        }
+    })
+}
+
+/// Free the embedder handle and release all associated resources.
+///
+/// # Parameters
+/// - `handle`: Pointer to EmbedderHandle to free
+///
+/// # Safety
+/// - handle must be a valid pointer returned by embedder_init()
+/// - handle must not be used after this call
+/// - Must only be called once per handle
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn embedder_free(handle: *mut EmbedderHandle) {
+    guard_or((), || {
+        if !handle.is_null() {
+            unsafe { drop(Box::from_raw(handle)) };
+        }
+    })
+}
+
+/// Register a callback receiving the library's log events (model loading,
+/// hub downloads, inference).
+///
+/// # Parameters
+/// - `callback`: Function called for each event, or null to stop logging
+/// - `max_level`: Most verbose level forwarded, from `EMBEDDER_LOG_ERROR` (1)
+///   to `EMBEDDER_LOG_TRACE` (5); `EMBEDDER_LOG_OFF` (0) forwards nothing
+/// - `user_data`: Passed unchanged to every callback invocation
+///
+/// # Returns
+/// - `Success`, or `InitializationFailed` if another `tracing` subscriber is
+///   already installed globally in this process
+///
+/// # Safety
+/// - The callback may be invoked from any thread that calls into the library.
+///   It may call `embedder_set_log_callback()` itself; the change applies from
+///   the next event
+/// - user_data must stay valid until the callback is replaced or removed and
+///   its running invocations have returned
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn embedder_set_log_callback(
+    callback: Option<EmbedderLogCallback>,
+    max_level: i32,
+    user_data: *mut c_void,
+) -> EmbedderErrorCode {
+    guard_or(EmbedderErrorCode::Panic, || {
+        if logging::set_callback(callback, max_level, user_data) {
+            EmbedderErrorCode::Success
+        } else {
+            EmbedderErrorCode::InitializationFailed
+        }
+    })
+}
+
+/// Get the ABI version of the library.
+///
+/// # Returns
+/// - `major << 16 | minor`. The major version changes on incompatible changes,
+///   the minor version when functions or trailing struct fields are added.
+///   Bindings built for `major.minor` work with any library of the same major
+///   version and a minor version at least as high.
+#[unsafe(no_mangle)]
+pub extern "C" fn embedder_abi_version() -> u32 {
+    abi::ABI_VERSION
+}
+
+/// Get a JSON description of what this library supports.
+///
+/// The JSON object contains `version`, `abi_version`, `models`, `pooling`,
+/// `output_dtypes`, `similarity_metrics`, `input_encodings`, `thread_safety`
+/// and the list of exported `functions`. `models` lists the models registered
+/// when it is called, including those added with `register_model` from Rust.
+///
+/// # Returns
+/// - C string valid for the life of the process, or null if it could not be built
+///
+/// # Safety
+/// - Always safe to call
+/// - Returned string is owned by the library and must NOT be freed
+#[unsafe(no_mangle)]
+pub extern "C" fn embedder_get_capabilities() -> *const c_char {
+    guard_or(ptr::null(), || abi::capabilities().as_ptr())
+}
 
 /// Get the library version string.
 ///
@@ -298,5 +2006,17 @@ pub extern "C" fn embedder_version() -> *const c_char {
     concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char
 }
 
-// Re-export for use in the CLI binary
-pub use embed_utils::build_text_embedder;
+// Stable Rust API, see the crate documentation
+pub use api::{
+    DType, Device, DistillOptions, DistilledModel, EmbedProgress, Embedder, EmbedderBuilder, Error,
+    ModelInfo, MultiVectorEmbedding, PaddingSide, Pooling, SUPPORTED_MODELS, SparseEmbedding, Task,
+};
+pub use embed_utils::similarity::{self, Similarity};
+
+// Extension point for other model architectures. These signatures use candle
+// types, so they follow the re-exported candle version rather than semver.
+pub use candle_core;
+pub use candle_nn;
+pub use embed_utils::{
+    EmbedModel, ImagePreprocessing, LoadedModel, ModelLoader, register_model, registered_models,
+};
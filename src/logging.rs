@@ -0,0 +1,136 @@
+// logging.rs - forwards `tracing` events to a logging callback registered by the host
+use once_cell::sync::OnceCell;
+use std::ffi::CString;
+use std::fmt::Write;
+use std::os::raw::{c_char, c_void};
+use std::sync::RwLock;
+use tracing::field::{Field, Visit};
+use tracing::subscriber::Interest;
+use tracing::{Event, Level, Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::prelude::*;
+
+/// Logging callback signature: `level` is one of the `EMBEDDER_LOG_*` values,
+/// `target` the emitting module and `message` the formatted event. Both strings
+/// are only valid for the duration of the call.
+pub type EmbedderLogCallback = unsafe extern "C" fn(
+    level: i32,
+    target: *const c_char,
+    message: *const c_char,
+    user_data: *mut c_void,
+);
+
+pub const EMBEDDER_LOG_OFF: i32 = 0;
+pub const EMBEDDER_LOG_ERROR: i32 = 1;
+pub const EMBEDDER_LOG_WARN: i32 = 2;
+pub const EMBEDDER_LOG_INFO: i32 = 3;
+pub const EMBEDDER_LOG_DEBUG: i32 = 4;
+pub const EMBEDDER_LOG_TRACE: i32 = 5;
+
+#[derive(Clone, Copy)]
+struct LogSink {
+    callback: EmbedderLogCallback,
+    max_level: i32,
+    user_data: *mut c_void,
+}
+
+// The host guarantees that `user_data` can be used from any thread calling into the library.
+unsafe impl Send for LogSink {}
+unsafe impl Sync for LogSink {}
+
+static SINK: RwLock<Option<LogSink>> = RwLock::new(None);
+static INSTALLED: OnceCell<bool> = OnceCell::new();
+
+fn level_value(level: &Level) -> i32 {
+    match *level {
+        Level::ERROR => EMBEDDER_LOG_ERROR,
+        Level::WARN => EMBEDDER_LOG_WARN,
+        Level::INFO => EMBEDDER_LOG_INFO,
+        Level::DEBUG => EMBEDDER_LOG_DEBUG,
+        Level::TRACE => EMBEDDER_LOG_TRACE,
+    }
+}
+
+/// Register (or with `None`, remove) the host logging callback.
+///
+/// Returns false if the callback layer could not be installed because another
+/// global `tracing` subscriber is already set in this process.
+pub fn set_callback(
+    callback: Option<EmbedderLogCallback>,
+    max_level: i32,
+    user_data: *mut c_void,
+) -> bool {
+    let installed = *INSTALLED.get_or_init(|| {
+        tracing_subscriber::registry()
+            .with(CallbackLayer)
+            .try_init()
+            .is_ok()
+    });
+
+    let mut sink = SINK.write().unwrap_or_else(|e| e.into_inner());
+    *sink = callback.map(|callback| LogSink {
+        callback,
+        max_level,
+        user_data,
+    });
+    installed
+}
+
+/// `tracing` layer forwarding events to the registered `LogSink`
+struct CallbackLayer;
+
+impl<S: Subscriber> Layer<S> for CallbackLayer {
+    fn register_callsite(&self, _metadata: &'static Metadata<'static>) -> Interest {
+        // The filter changes at runtime, so it is evaluated for every event
+        Interest::sometimes()
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, _ctx: Context<'_, S>) -> bool {
+        let sink = SINK.read().unwrap_or_else(|e| e.into_inner());
+        match sink.as_ref() {
+            Some(sink) => level_value(metadata.level()) <= sink.max_level,
+            None => false,
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        // Copied out so the lock is released before calling back into the
+        // host, which may replace the callback from inside it
+        let Some(sink) = *SINK.read().unwrap_or_else(|e| e.into_inner()) else {
+            return;
+        };
+
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        visitor.message.push_str(&visitor.fields);
+
+        let metadata = event.metadata();
+        let target = CString::new(metadata.target()).unwrap_or_default();
+        let message = CString::new(visitor.message.replace('\0', " ")).unwrap_or_default();
+        unsafe {
+            (sink.callback)(
+                level_value(metadata.level()),
+                target.as_ptr(),
+                message.as_ptr(),
+                sink.user_data,
+            )
+        };
+    }
+}
+
+/// Formats an event as `message key=value ...`
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
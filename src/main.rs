@@ -1,6 +1,11 @@
-use clap::Parser;
-use rust_transformer::build_text_embedder;
+use clap::{Parser, Subcommand, ValueEnum};
+use rust_transformer::{
+    DType, DistillOptions, Embedder, MultiVectorEmbedding, SparseEmbedding, Task,
+};
 use serde::Serialize;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tracing::info;
 
 /// A standalone CLI for text-to-embedding transformations.
 #[derive(Parser, Debug)]
@@ -26,28 +31,141 @@ use serde::Serialize;
     # Process multiple texts (compact output):\n  \
     rust_transformer_cli --text '[\"Hello world\", \"Goodbye\"]' --json-input\n\n  \
     # Combine model and pretty output:\n  \
-    rust_transformer_cli --text \"Hello world\" --model jina --pretty"
+    rust_transformer_cli --text \"Hello world\" --model jina --pretty\n\n  \
+    # Embed a search query with a model trained with task prefixes:\n  \
+    rust_transformer_cli --text \"What is rust?\" --model nomic --task query\n\n  \
+    # Run a decoder embedder in half precision with a custom instruction:\n  \
+    rust_transformer_cli --text \"What is rust?\" --model gte_qwen2 --dtype f16 --task query \\\n    \
+    --query-prefix \"Instruct: Given a question, retrieve answers\\nQuery: \"\n\n  \
+    # Match a text query against screenshots with a text–image model:\n  \
+    rust_transformer_cli --text \"a login form\" --model clip\n  \
+    rust_transformer_cli --image login.png --image settings.jpg --model clip --pretty\n\n  \
+    # Rerank search candidates (one JSON string or {\"text\": ...} object per line) with a cross-encoder:\n  \
+    rust_transformer_cli rerank --model ms_marco_minilm --query \"What is rust?\" --input candidates.jsonl --top-k 3\n\n  \
+    # Write sparse SPLADE term weights as JSON Lines, one {\"text\": ..., \"sparse\": {token_id: weight}} per text:\n  \
+    rust_transformer_cli --text '[\"Hello world\", \"Goodbye\"]' --json-input --model splade --sparse > sparse.jsonl\n\n  \
+    # Write ColBERT token vectors as JSON Lines, one {\"text\": ..., \"vectors\": [[...], ...]} per text:\n  \
+    rust_transformer_cli --text '[\"Hello world\", \"Goodbye\"]' --json-input --model colbert --multi-vector > colbert.jsonl\n\n  \
+    # Show model metadata (dimension, max tokens, pooling, ...):\n  \
+    rust_transformer_cli info --model jina --pretty\n\n  \
+    # Distill a small static model from MiniLM and embed with it:\n  \
+    rust_transformer_cli distill --model mini_lm_v2 --output minilm_static --pca-dims 256 --sif-coefficient 1e-4\n  \
+    MODEL2VEC_MODEL_FOLDER=minilm_static rust_transformer_cli --text \"Hello world\" --model model2vec",
+    subcommand_negates_reqs = true
 )]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// The input text to be transformed into an embedding.
-    #[arg(short, long)]
-    text: String,
+    #[arg(short, long, required_unless_present = "image")]
+    text: Option<String>,
+
+    /// Embed a PNG or JPEG image instead, with a text–image model ('clip' or 'siglip'); repeat for several images.
+    #[arg(long, conflicts_with = "text")]
+    image: Vec<PathBuf>,
+
+    /// Output sparse term weights ({token_id: weight}) as JSON Lines, one object per text, with a sparse model ('splade').
+    #[arg(long, default_value_t = false, conflicts_with = "image")]
+    sparse: bool,
 
-    /// The transformer model to use (e.g., 'mini_lm_v2' or 'jina').
-    #[arg(short, long, default_value = "mini_lm_v2")]
+    /// Output one normalized vector per token as JSON Lines, one object per text, with a late-interaction model ('colbert'); texts are documents unless '--task query' is given.
+    #[arg(long, default_value_t = false, conflicts_with_all = ["image", "sparse"])]
+    multi_vector: bool,
+
+    /// The transformer model to use (e.g., 'mini_lm_v2', 'jina' or any other registered model).
+    #[arg(short, long, global = true, default_value = "mini_lm_v2")]
     model: String,
 
     /// Output pretty-printed JSON instead of compact JSON.
-    #[arg(short, long, default_value_t = false)]
+    #[arg(short, long, global = true, default_value_t = false)]
     pretty: bool,
 
-    /// Enable verbose output with additional information.
+    /// Enable verbose output with additional information (log events go to stderr).
     #[arg(short, long, default_value_t = false)]
     verbose: bool,
 
     /// Treat input text as a JSON array of strings.
     #[arg(short, long, default_value_t = false)]
     json_input: bool,
+
+    /// Embed the texts as search queries or documents, prepending the model's task prefix (e.g. for 'nomic').
+    #[arg(long, value_enum)]
+    task: Option<TaskArg>,
+
+    /// Prefix of texts embedded with '--task query', replacing the model's own (e.g. an instruction template); '\n' is a newline.
+    #[arg(long)]
+    query_prefix: Option<String>,
+
+    /// Type the model weights are loaded as; f16 halves the memory of large models.
+    #[arg(long, global = true, value_enum, default_value = "f32")]
+    dtype: DTypeArg,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum TaskArg {
+    Query,
+    Document,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum DTypeArg {
+    F32,
+    F16,
+    Bf16,
+}
+
+impl From<DTypeArg> for DType {
+    fn from(dtype: DTypeArg) -> Self {
+        match dtype {
+            DTypeArg::F32 => DType::F32,
+            DTypeArg::F16 => DType::F16,
+            DTypeArg::Bf16 => DType::BF16,
+        }
+    }
+}
+
+impl From<TaskArg> for Task {
+    fn from(task: TaskArg) -> Self {
+        match task {
+            TaskArg::Query => Task::Query,
+            TaskArg::Document => Task::Document,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print the model metadata (dimension, max tokens, pooling, dtype) as JSON.
+    Info,
+    /// Distill a static model: embed every vocabulary token and write a model2vec model, loadable with '--model model2vec' from MODEL2VEC_MODEL_FOLDER.
+    Distill {
+        /// Folder the static model is written to.
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Reduce the token vectors to this many dimensions with PCA.
+        #[arg(long)]
+        pca_dims: Option<usize>,
+
+        /// Weight tokens by their estimated frequency (SIF) so common ones count less; model2vec uses 1e-4.
+        #[arg(long)]
+        sif_coefficient: Option<f32>,
+    },
+    /// Score candidate passages against a query with a cross-encoder (e.g. '--model ms_marco_minilm') and print them best first.
+    Rerank {
+        /// The query the candidates are scored against.
+        #[arg(short, long)]
+        query: String,
+
+        /// JSON Lines file with one candidate per line, a JSON string or an object with a "text" field; '-' reads standard input.
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Print only the best N candidates.
+        #[arg(long)]
+        top_k: Option<usize>,
+    },
 }
 
 #[derive(Serialize)]
@@ -56,16 +174,92 @@ struct EmbedResult {
     embed: Vec<f32>,
 }
 
+#[derive(Serialize)]
+struct SparseEmbedResult {
+    text: String,
+    sparse: SparseEmbedding,
+}
+
+#[derive(Serialize)]
+struct MultiVectorEmbedResult {
+    text: String,
+    vectors: MultiVectorEmbedding,
+}
+
+#[derive(Serialize)]
+struct ImageEmbedResult {
+    image: PathBuf,
+    embed: Vec<f32>,
+}
+
+#[derive(Serialize)]
+struct RerankResult {
+    /// Position of the candidate in the input, from 0, not counting blank lines
+    index: usize,
+    score: f32,
+    text: String,
+}
+
+/// Candidate texts of a JSON Lines file: each line is a JSON string or an
+/// object with a "text" field
+fn read_candidates(path: &Path) -> Result<Vec<String>, String> {
+    let mut input = String::new();
+    let read = if path == Path::new("-") {
+        std::io::stdin().read_to_string(&mut input).map(|_| ())
+    } else {
+        std::fs::read_to_string(path).map(|text| input = text)
+    };
+    read.map_err(|e| format!("Error reading '{}': {}", path.display(), e))?;
+
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let candidate: serde_json::Value = serde_json::from_str(line)
+                .map_err(|e| format!("Invalid JSON on line {}: {}", i + 1, e))?;
+            match candidate.get("text").unwrap_or(&candidate) {
+                serde_json::Value::String(text) => Ok(text.clone()),
+                _ => Err(format!(
+                    "Line {} is neither a string nor an object with a \"text\" string",
+                    i + 1
+                )),
+            }
+        })
+        .collect()
+}
+
+/// The texts of `--text`: a JSON array of strings with `--json-input`, else
+/// the text itself
+fn input_texts(text: &str, json_input: bool) -> Result<Vec<String>, String> {
+    if json_input {
+        serde_json::from_str(text).map_err(|e| format!("Failed to parse JSON array: {}", e))
+    } else {
+        Ok(vec![text.to_string()])
+    }
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
 
     if args.verbose {
-        println!("Initializing model: {}...", args.model);
+        tracing_subscriber::fmt()
+            .with_writer(std::io::stderr)
+            .with_max_level(tracing::Level::DEBUG)
+            .init();
     }
+    info!(model = %args.model, "Initializing model");
 
     // --- STEP 1: INITIALIZE THE EMBEDDER ---
     // This loads the tokenizer and model weights, which is the slow part.
-    let mut embedder = match build_text_embedder(&args.model) {
+    let mut builder = Embedder::builder(&args.model).dtype(args.dtype.into());
+    if let Some(task) = args.task {
+        builder = builder.task(task.into());
+    }
+    if let Some(prefix) = &args.query_prefix {
+        builder = builder.query_prefix(prefix.replace("\\n", "\n"));
+    }
+    let mut embedder = match builder.build() {
         Ok(e) => e,
         Err(e) => {
             eprintln!("\nError initializing embedder for {}: {}", args.model, e);
@@ -73,13 +267,180 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
+    if let Some(Command::Info) = args.command {
+        let output = if args.pretty {
+            serde_json::to_string_pretty(embedder.info())?
+        } else {
+            serde_json::to_string(embedder.info())?
+        };
+        println!("{}", output);
+        return Ok(());
+    }
+
+    if let Some(Command::Distill {
+        output,
+        pca_dims,
+        sif_coefficient,
+    }) = &args.command
+    {
+        let mut options = DistillOptions::new();
+        if let Some(dims) = pca_dims {
+            options = options.pca_dims(*dims);
+        }
+        if let Some(coefficient) = sif_coefficient {
+            options = options.sif_coefficient(*coefficient);
+        }
+        let distilled = match embedder.distill(output, &options) {
+            Ok(distilled) => distilled,
+            Err(e) => {
+                eprintln!("\nError distilling {}: {}", args.model, e);
+                std::process::exit(1);
+            }
+        };
+        let output = if args.pretty {
+            serde_json::to_string_pretty(&distilled)?
+        } else {
+            serde_json::to_string(&distilled)?
+        };
+        println!("{}", output);
+        return Ok(());
+    }
+
+    if let Some(Command::Rerank {
+        query,
+        input,
+        top_k,
+    }) = &args.command
+    {
+        let candidates = match read_candidates(input) {
+            Ok(candidates) => candidates,
+            Err(e) => {
+                eprintln!("\n{}", e);
+                std::process::exit(1);
+            }
+        };
+        let scores = match embedder.rerank(query, &candidates) {
+            Ok(scores) => scores,
+            Err(e) => {
+                eprintln!("\nError reranking with {}: {}", args.model, e);
+                std::process::exit(1);
+            }
+        };
+        info!(candidates = scores.len(), "Reranking output");
+
+        let mut results: Vec<RerankResult> = candidates
+            .into_iter()
+            .zip(scores)
+            .enumerate()
+            .map(|(index, (text, score))| RerankResult { index, score, text })
+            .collect();
+        results.sort_by(|a, b| b.score.total_cmp(&a.score));
+        results.truncate(top_k.unwrap_or(results.len()));
+        let output = if args.pretty {
+            serde_json::to_string_pretty(&results)?
+        } else {
+            serde_json::to_string(&results)?
+        };
+        println!("{}", output);
+        return Ok(());
+    }
+
+    if !args.image.is_empty() {
+        let mut images = Vec::with_capacity(args.image.len());
+        for path in &args.image {
+            match std::fs::read(path) {
+                Ok(image) => images.push(image),
+                Err(e) => {
+                    eprintln!("\nError reading image '{}': {}", path.display(), e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        let embeddings = match embedder.embed_images(&images) {
+            Ok(embeddings) => embeddings,
+            Err(e) => {
+                eprintln!("\nError during transformation: {}", e);
+                std::process::exit(1);
+            }
+        };
+        info!(images = embeddings.len(), "Transformation output");
+
+        // A single image prints its embedding, like a single text
+        let output = if let [embedding] = embeddings.as_slice() {
+            if args.pretty {
+                serde_json::to_string_pretty(embedding)?
+            } else {
+                serde_json::to_string(embedding)?
+            }
+        } else {
+            let results: Vec<ImageEmbedResult> = args
+                .image
+                .into_iter()
+                .zip(embeddings)
+                .map(|(image, embed)| ImageEmbedResult { image, embed })
+                .collect();
+            if args.pretty {
+                serde_json::to_string_pretty(&results)?
+            } else {
+                serde_json::to_string(&results)?
+            }
+        };
+        println!("{}", output);
+        return Ok(());
+    }
+
+    let text = args.text.unwrap_or_default();
+
+    // Sparse and multi-vector embeddings are written as JSON Lines, so large
+    // outputs can be streamed into an index
+    if args.sparse {
+        let texts = input_texts(&text, args.json_input)?;
+        let embeddings = match embedder.embed_sparse(&texts) {
+            Ok(embeddings) => embeddings,
+            Err(e) => {
+                eprintln!("\nError during transformation: {}", e);
+                std::process::exit(1);
+            }
+        };
+        info!(texts = embeddings.len(), "Transformation output");
+
+        for (text, sparse) in texts.into_iter().zip(embeddings) {
+            println!(
+                "{}",
+                serde_json::to_string(&SparseEmbedResult { text, sparse })?
+            );
+        }
+        return Ok(());
+    }
+
+    if args.multi_vector {
+        let texts = input_texts(&text, args.json_input)?;
+        let task = args.task.map_or(Task::Document, Task::from);
+        let embeddings = match embedder.embed_tokens(&texts, task) {
+            Ok(embeddings) => embeddings,
+            Err(e) => {
+                eprintln!("\nError during transformation: {}", e);
+                std::process::exit(1);
+            }
+        };
+        info!(texts = embeddings.len(), "Transformation output");
+
+        for (text, vectors) in texts.into_iter().zip(embeddings) {
+            println!(
+                "{}",
+                serde_json::to_string(&MultiVectorEmbedResult { text, vectors })?
+            );
+        }
+        return Ok(());
+    }
+
     // --- STEP 2: GENERATE THE EMBEDDING ---
     // This is the fast part—the actual inference.
 
     // Check if input should be treated as JSON array
     let output = if args.json_input {
         // Handle JSON array input
-        let text_array: Vec<String> = serde_json::from_str(&args.text)
+        let text_array: Vec<String> = serde_json::from_str(&text)
             .map_err(|e| format!("Failed to parse JSON array: {}", e))?;
 
         let mut results = Vec::new();
@@ -98,12 +459,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
         }
 
-        if args.verbose {
-            println!(
-                "\nTransformation Output ({} texts processed):",
-                results.len()
-            );
-        }
+        info!(texts = results.len(), "Transformation output");
 
         if args.pretty {
             serde_json::to_string_pretty(&results)?
@@ -112,14 +468,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     } else {
         // Handle single text input
-        match embedder.embed(&args.text) {
+        match embedder.embed(&text) {
             Ok(embedding_array) => {
-                if args.verbose {
-                    println!(
-                        "\nTransformation Output ({} elements):",
-                        embedding_array.len()
-                    );
-                }
+                info!(elements = embedding_array.len(), "Transformation output");
 
                 if args.pretty {
                     serde_json::to_string_pretty(&embedding_array)?
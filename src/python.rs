@@ -0,0 +1,181 @@
+// python.rs - native Python extension module (PyO3), enabled by the `python` feature
+use crate::embed_utils::{self, Embed, EmbeddingError, PaddingSide, Pooling, Task, TextEmbedder};
+use numpy::PyArray2;
+use numpy::ndarray::Array2;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::sync::Mutex;
+
+fn to_py_err(err: EmbeddingError) -> PyErr {
+    match err {
+        EmbeddingError::ModelTypeError(_) => PyValueError::new_err(err.to_string()),
+        _ => PyRuntimeError::new_err(err.to_string()),
+    }
+}
+
+fn parse_pooling(pooling: &str) -> PyResult<Pooling> {
+    match pooling {
+        "mean" => Ok(Pooling::Mean),
+        "cls" => Ok(Pooling::Cls),
+        "last_token" => Ok(Pooling::LastToken),
+        _ => Err(PyValueError::new_err(format!(
+            "Unknown pooling: '{}'. Supported pooling: 'mean', 'cls', 'last_token'",
+            pooling
+        ))),
+    }
+}
+
+fn parse_task(task: &str) -> PyResult<Task> {
+    match task {
+        "query" => Ok(Task::Query),
+        "document" => Ok(Task::Document),
+        _ => Err(PyValueError::new_err(format!(
+            "Unknown task: '{}'. Supported tasks: 'query', 'document'",
+            task
+        ))),
+    }
+}
+
+/// Text embedder backed by a native transformer model.
+///
+/// Embedders for the same model share their weights, so creating several with
+/// different pooling or normalization settings is cheap.
+#[pyclass(name = "Embedder", module = "rust_transformer")]
+struct PyEmbedder {
+    embedder: Mutex<TextEmbedder>,
+}
+
+#[pymethods]
+impl PyEmbedder {
+    #[new]
+    #[pyo3(signature = (model = "mini_lm_v2", *, normalize = true, max_tokens = None, pooling = None, task = None, query_prefix = None))]
+    fn new(
+        py: Python<'_>,
+        model: &str,
+        normalize: bool,
+        max_tokens: Option<usize>,
+        pooling: Option<&str>,
+        task: Option<&str>,
+        query_prefix: Option<String>,
+    ) -> PyResult<Self> {
+        let pooling = pooling.map(parse_pooling).transpose()?;
+        let task = task.map(parse_task).transpose()?;
+        let mut embedder = py
+            .detach(|| embed_utils::build_text_embedder(model))
+            .map_err(to_py_err)?;
+        embedder.set_normalize(normalize);
+        if let Some(pooling) = pooling {
+            embedder.set_pooling(pooling);
+        }
+        embedder.set_task(task);
+        if query_prefix.is_some() {
+            embedder.set_query_prefix(query_prefix);
+        }
+        if let Some(max_tokens) = max_tokens {
+            embedder.set_max_tokens(max_tokens).map_err(to_py_err)?;
+        }
+
+        Ok(Self {
+            embedder: Mutex::new(embedder),
+        })
+    }
+
+    /// Embed a list of texts into a `(len(texts), dimension)` float32 array.
+    ///
+    /// `normalize` overrides the embedder setting for this call. The GIL is
+    /// released while the model runs.
+    #[pyo3(signature = (texts, batch_size = 32, normalize = None))]
+    fn encode<'py>(
+        &self,
+        py: Python<'py>,
+        texts: Vec<String>,
+        batch_size: usize,
+        normalize: Option<bool>,
+    ) -> PyResult<Bound<'py, PyArray2<f32>>> {
+        if batch_size == 0 {
+            return Err(PyValueError::new_err("batch_size must be greater than 0"));
+        }
+
+        let rows = texts.len();
+        let (embeddings, dimension) = py
+            .detach(|| {
+                let mut embedder = self.embedder.lock().unwrap_or_else(|e| e.into_inner());
+                let dimension = embedder.dimension();
+                let default_normalize = embedder.info().normalize;
+                embedder.set_normalize(normalize.unwrap_or(default_normalize));
+                let embeddings = Embed::embed(&mut *embedder, texts, batch_size);
+                embedder.set_normalize(default_normalize);
+                embeddings.map(|embeddings| (embeddings, dimension))
+            })
+            .map_err(to_py_err)?;
+
+        // The flattened buffer is handed to NumPy without another copy
+        let data: Vec<f32> = embeddings.into_iter().flatten().collect();
+        let array = Array2::from_shape_vec((rows, dimension), data)
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(PyArray2::from_owned_array(py, array))
+    }
+
+    /// Dimension of the embedding vectors
+    #[getter]
+    fn dimension(&self) -> usize {
+        self.embedder.lock().unwrap_or_else(|e| e.into_inner()).dimension()
+    }
+
+    /// Maximum number of tokens per text; longer texts are truncated
+    #[getter]
+    fn max_tokens(&self) -> usize {
+        self.embedder.lock().unwrap_or_else(|e| e.into_inner()).max_tokens()
+    }
+
+    /// Model metadata (model_id, revision, dimension, max_tokens, pooling,
+    /// padding_side, normalize, dtype, query_prefix, document_prefix) as a dict
+    #[getter]
+    fn info<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let embedder = self.embedder.lock().unwrap_or_else(|e| e.into_inner());
+        let info = embedder.info();
+        let dict = PyDict::new(py);
+        dict.set_item("model_id", &info.model_id)?;
+        dict.set_item("revision", &info.revision)?;
+        dict.set_item("dimension", info.dimension)?;
+        dict.set_item("max_tokens", info.max_tokens)?;
+        dict.set_item(
+            "pooling",
+            match info.pooling {
+                Pooling::Mean => "mean",
+                Pooling::Cls => "cls",
+                Pooling::LastToken => "last_token",
+            },
+        )?;
+        dict.set_item(
+            "padding_side",
+            match info.padding_side {
+                PaddingSide::Right => "right",
+                PaddingSide::Left => "left",
+            },
+        )?;
+        dict.set_item("normalize", info.normalize)?;
+        dict.set_item("dtype", &info.dtype)?;
+        dict.set_item("query_prefix", &info.query_prefix)?;
+        dict.set_item("document_prefix", &info.document_prefix)?;
+        Ok(dict)
+    }
+
+    fn __repr__(&self) -> String {
+        let embedder = self.embedder.lock().unwrap_or_else(|e| e.into_inner());
+        format!(
+            "Embedder(model_id='{}', dimension={})",
+            embedder.info().model_id,
+            embedder.dimension()
+        )
+    }
+}
+
+#[pymodule]
+fn rust_transformer(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyEmbedder>()?;
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    m.add("SUPPORTED_MODELS", embed_utils::SUPPORTED_MODELS.to_vec())?;
+    Ok(())
+}
@@ -0,0 +1,103 @@
+// sqlite.rs - SQLite loadable extension, enabled by the `sqlite` feature
+//
+// Registers the SQL functions
+//   embed(text [, model])  -> BLOB of little-endian float32 values
+//   cosine_sim(blob, blob) -> REAL (also available as vec_cosine)
+//
+// sqlite> .load ./target/release/librust_transformer
+// sqlite> SELECT title FROM docs ORDER BY cosine_sim(emb, embed('query')) DESC LIMIT 5;
+use crate::embed_utils::similarity;
+use crate::embed_utils::{self, TextEmbedder};
+use once_cell::sync::Lazy;
+use rusqlite::functions::{Context, FunctionFlags};
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, Error, Result, ffi};
+use std::collections::HashMap;
+use std::os::raw::{c_char, c_int};
+use std::sync::Mutex;
+use tracing::debug;
+
+const DEFAULT_MODEL: &str = "mini_lm_v2";
+
+/// Embedders by model name, built on first use and kept for the process lifetime
+static EMBEDDERS: Lazy<Mutex<HashMap<String, TextEmbedder>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn user_error(message: impl Into<String>) -> Error {
+    Error::UserFunctionError(message.into().into())
+}
+
+fn embed(ctx: &Context<'_>) -> Result<Vec<u8>> {
+    let text = match ctx.get_raw(0) {
+        ValueRef::Text(text) => std::str::from_utf8(text).map_err(|e| user_error(e.to_string()))?,
+        _ => return Err(user_error("embed() expects a TEXT argument")),
+    };
+    let model = if ctx.len() > 1 {
+        ctx.get::<String>(1)?
+    } else {
+        DEFAULT_MODEL.to_string()
+    };
+
+    let mut embedders = EMBEDDERS.lock().unwrap_or_else(|e| e.into_inner());
+    if !embedders.contains_key(&model) {
+        debug!(model = %model, "Loading model for SQLite extension");
+        let embedder = embed_utils::build_text_embedder(&model)
+            .map_err(|e| Error::UserFunctionError(e.into()))?;
+        embedders.insert(model.clone(), embedder);
+    }
+    let embedder = embedders.get_mut(&model).expect("embedder inserted above");
+
+    let embedding = embedder
+        .embed(text)
+        .map_err(|e| Error::UserFunctionError(e.into()))?;
+    Ok(embedding.iter().flat_map(|x| x.to_le_bytes()).collect())
+}
+
+/// Decode a BLOB written by `embed()`
+fn vector_arg(ctx: &Context<'_>, idx: usize) -> Result<Vec<f32>> {
+    match ctx.get_raw(idx) {
+        ValueRef::Blob(bytes) if bytes.len() % 4 == 0 => Ok(bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect()),
+        ValueRef::Blob(_) => Err(user_error(
+            "vector BLOB length must be a multiple of 4 (float32)",
+        )),
+        _ => Err(user_error("expected a vector BLOB from embed()")),
+    }
+}
+
+fn cosine_sim(ctx: &Context<'_>) -> Result<f64> {
+    let (a, b) = (vector_arg(ctx, 0)?, vector_arg(ctx, 1)?);
+    if a.len() != b.len() {
+        return Err(user_error(format!(
+            "vector dimensions differ: {} and {}",
+            a.len(),
+            b.len()
+        )));
+    }
+    Ok(similarity::cosine(&a, &b) as f64)
+}
+
+fn register_functions(db: Connection) -> Result<bool> {
+    let flags = FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC;
+    db.create_scalar_function(c"embed", 1, flags, embed)?;
+    db.create_scalar_function(c"embed", 2, flags, embed)?;
+    db.create_scalar_function(c"cosine_sim", 2, flags, cosine_sim)?;
+    db.create_scalar_function(c"vec_cosine", 2, flags, cosine_sim)?;
+    Ok(false)
+}
+
+/// Entry point called by SQLite's `load_extension()`; the name is derived by
+/// SQLite from the library file name `rust_transformer`.
+///
+/// # Safety
+/// - Must only be called by SQLite with a valid connection and API routines
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_rusttransformer_init(
+    db: *mut ffi::sqlite3,
+    pz_err_msg: *mut *mut c_char,
+    p_api: *mut ffi::sqlite3_api_routines,
+) -> c_int {
+    unsafe { Connection::extension_init2(db, pz_err_msg, p_api, register_functions) }
+}
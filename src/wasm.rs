@@ -0,0 +1,90 @@
+// wasm.rs - wasm-bindgen API for browsers and node, enabled by the `wasm` feature
+//
+// Models are loaded from byte buffers, since there is no filesystem or hub access:
+//
+//   const embedder = new Embedder("mini_lm_v2", tokenizerJson, weights);
+//   const vector = embedder.embed("Hello world");            // Float32Array
+//   const vectors = embedder.embedBatch(["a", "b"], 16);     // Array<Float32Array>
+use crate::embed_utils::{self, Embed, EmbeddingError, TextEmbedder, TextEmbedderBuilder};
+use js_sys::{Array, Float32Array};
+use wasm_bindgen::prelude::*;
+
+fn to_js_error(err: EmbeddingError) -> JsError {
+    JsError::new(&err.to_string())
+}
+
+/// Text embedder running on the CPU inside WebAssembly
+#[wasm_bindgen]
+pub struct Embedder {
+    embedder: TextEmbedder,
+}
+
+#[wasm_bindgen]
+impl Embedder {
+    /// Load a model from the contents of its `tokenizer.json`, `model.safetensors`
+    /// and optionally `config.json`. `model` selects the architecture
+    /// ("mini_lm_v2" or "jina").
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        model: &str,
+        tokenizer: &[u8],
+        weights: Vec<u8>,
+        config: Option<Vec<u8>>,
+    ) -> Result<Embedder, JsError> {
+        let model_type = embed_utils::model_type(model).map_err(to_js_error)?;
+        let mut builder = TextEmbedderBuilder::new(model_type)
+            .tokenizer_bytes(tokenizer.to_vec())
+            .weights_bytes(weights);
+        if let Some(config) = config {
+            builder = builder.config_bytes(config);
+        }
+        let embedder = builder.build().map_err(to_js_error)?;
+        Ok(Self { embedder })
+    }
+
+    /// Embed a single text
+    pub fn embed(&mut self, text: &str) -> Result<Float32Array, JsError> {
+        let embedding = self.embedder.embed(text).map_err(to_js_error)?;
+        Ok(Float32Array::from(embedding.as_slice()))
+    }
+
+    /// Embed several texts, `batchSize` at a time (default 32)
+    #[wasm_bindgen(js_name = embedBatch)]
+    pub fn embed_batch(
+        &mut self,
+        texts: Vec<String>,
+        batch_size: Option<usize>,
+    ) -> Result<Array, JsError> {
+        let batch_size = batch_size.unwrap_or(32).max(1);
+        let embeddings =
+            Embed::embed(&mut self.embedder, texts, batch_size).map_err(to_js_error)?;
+        Ok(embeddings
+            .iter()
+            .map(|embedding| Float32Array::from(embedding.as_slice()))
+            .collect())
+    }
+
+    /// Dimension of the embedding vectors
+    #[wasm_bindgen(getter)]
+    pub fn dimension(&self) -> usize {
+        self.embedder.dimension()
+    }
+
+    /// Maximum number of tokens per text; longer texts are truncated
+    #[wasm_bindgen(getter, js_name = maxTokens)]
+    pub fn max_tokens(&self) -> usize {
+        self.embedder.max_tokens()
+    }
+
+    /// Model metadata as a JSON string
+    #[wasm_bindgen(getter)]
+    pub fn info(&self) -> Result<String, JsError> {
+        Ok(serde_json::to_string(self.embedder.info())?)
+    }
+
+    /// Enable or disable L2 normalization of the embeddings
+    #[wasm_bindgen(js_name = setNormalize)]
+    pub fn set_normalize(&mut self, normalize: bool) {
+        self.embedder.set_normalize(normalize);
+    }
+}
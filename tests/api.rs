@@ -0,0 +1,587 @@
+// Tests for the public Rust API; they only use items exported from the crate
+// root, like a dependent crate would. The model is the tiny BERT from `common`.
+mod common;
+
+use common::{CONFIG, TOKENIZER, assert_close, fixture_builder, tiny_weights};
+use rust_transformer::candle_core::{Device as CandleDevice, Tensor};
+use rust_transformer::candle_nn::{Embedding, Module, VarBuilder, embedding};
+use rust_transformer::{
+    DType, Device, DistillOptions, EmbedModel, EmbedProgress, Embedder, EmbedderBuilder, Error,
+    LoadedModel, ModelLoader, Pooling, Task, register_model, registered_models, similarity,
+};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, mpsc};
+use std::time::Duration;
+
+fn builder() -> EmbedderBuilder {
+    EmbedderBuilder::new("mini_lm_v2")
+        .tokenizer_bytes(TOKENIZER.to_vec())
+        .config_bytes(CONFIG.to_vec())
+        .weights_bytes(tiny_weights())
+}
+
+fn norm(v: &[f32]) -> f32 {
+    v.iter().map(|x| x * x).sum::<f32>().sqrt()
+}
+
+/// Directory with the model files, unique per test
+fn model_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "rust_transformer_api_{}_{}",
+        name,
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("tokenizer.json"), TOKENIZER).unwrap();
+    std::fs::write(dir.join("config.json"), CONFIG).unwrap();
+    std::fs::write(dir.join("model.safetensors"), tiny_weights()).unwrap();
+    dir
+}
+
+#[test]
+fn builds_with_defaults() {
+    let embedder = builder().build().unwrap();
+    assert_eq!(embedder.dimension(), 32);
+    assert_eq!(embedder.max_tokens(), 64);
+    assert_eq!(embedder.batch_size(), 32);
+
+    let info = embedder.info();
+    assert_eq!(info.dimension, 32);
+    assert_eq!(info.pooling, Pooling::Mean);
+    assert!(info.normalize);
+    assert_eq!(info.dtype, "f32");
+}
+
+#[test]
+fn builds_from_paths() {
+    let dir = model_dir("paths");
+    let mut from_paths = EmbedderBuilder::new("mini_lm_v2")
+        .tokenizer_path(dir.join("tokenizer.json"))
+        .config_path(dir.join("config.json"))
+        .weights_path(dir.join("model.safetensors"))
+        .build()
+        .unwrap();
+    let mut from_bytes = builder().build().unwrap();
+
+    assert_close(
+        &from_paths.embed("hello world").unwrap(),
+        &from_bytes.embed("hello world").unwrap(),
+        1e-6,
+        "hello world",
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn embedders_from_the_same_files_share_their_weights() {
+    let dir = model_dir("shared_paths");
+    let from_paths = |weights: PathBuf| {
+        EmbedderBuilder::new("mini_lm_v2")
+            .tokenizer_path(dir.join("tokenizer.json"))
+            .config_path(dir.join("config.json"))
+            .weights_path(weights)
+            .build()
+            .unwrap()
+    };
+    let first = from_paths(dir.join("model.safetensors"));
+    let second = from_paths(dir.join(".").join("model.safetensors"));
+    assert!(first.shares_weights(&second));
+
+    // Bytes have no path to share them by
+    assert!(!first.shares_weights(&builder().build().unwrap()));
+    // Nor do the same weights with another config
+    std::fs::copy(dir.join("config.json"), dir.join("other.json")).unwrap();
+    let other_config = EmbedderBuilder::new("mini_lm_v2")
+        .tokenizer_path(dir.join("tokenizer.json"))
+        .config_path(dir.join("other.json"))
+        .weights_path(dir.join("model.safetensors"))
+        .build()
+        .unwrap();
+    assert!(!first.shares_weights(&other_config));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn embed_returns_normalized_vector() {
+    let mut embedder = builder().build().unwrap();
+    let embedding = embedder.embed("hello world").unwrap();
+    assert_eq!(embedding.len(), 32);
+    assert!((norm(&embedding) - 1.0).abs() < 1e-4);
+
+    let mut raw = builder().normalize(false).build().unwrap();
+    assert!(!raw.info().normalize);
+    let raw = raw.embed("hello world").unwrap();
+    assert!((norm(&raw) - 1.0).abs() > 1e-4);
+    assert!((similarity::cosine(&embedding, &raw) - 1.0).abs() < 1e-4);
+}
+
+#[test]
+fn embed_batch_matches_single() {
+    let texts = ["the cat sat on the mat", "dogs are fast", "hello"];
+    let mut embedder = builder().batch_size(2).build().unwrap();
+    let batch = embedder.embed_batch(&texts).unwrap();
+    assert_eq!(batch.len(), texts.len());
+
+    for (text, row) in texts.iter().zip(&batch) {
+        assert_close(row, &embedder.embed(text).unwrap(), 1e-5, text);
+    }
+
+    let owned: Vec<String> = texts.iter().map(|text| text.to_string()).collect();
+    assert_eq!(embedder.embed_batch(&owned).unwrap().len(), texts.len());
+    assert!(embedder.embed_batch::<&str>(&[]).unwrap().is_empty());
+}
+
+/// Records progress and cancels once `cancel_at` texts are embedded
+struct CancelAt {
+    cancel_at: usize,
+    reported: Vec<(usize, usize)>,
+}
+
+impl EmbedProgress for CancelAt {
+    fn on_progress(&mut self, done: usize, total: usize) {
+        self.reported.push((done, total));
+    }
+
+    fn is_cancelled(&mut self) -> bool {
+        self.reported
+            .last()
+            .is_some_and(|&(done, _)| done >= self.cancel_at)
+    }
+}
+
+#[test]
+fn cancelling_a_batch_returns_the_completed_embeddings() {
+    let texts = [
+        "the cat sat on the mat",
+        "dogs are fast",
+        "hello",
+        "goodbye",
+        "world",
+    ];
+    let mut embedder = builder().batch_size(2).build().unwrap();
+
+    let mut progress = CancelAt {
+        cancel_at: usize::MAX,
+        reported: Vec::new(),
+    };
+    let all = embedder
+        .embed_batch_with_progress(&texts, &mut progress)
+        .unwrap();
+    assert_eq!(progress.reported, [(2, 5), (4, 5), (5, 5)]);
+    assert_eq!(all, embedder.embed_batch(&texts).unwrap());
+
+    let mut progress = CancelAt {
+        cancel_at: 2,
+        reported: Vec::new(),
+    };
+    let err = embedder
+        .embed_batch_with_progress(&texts, &mut progress)
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Embedding cancelled after 2 texts");
+    let Error::Cancelled { embeddings } = err else {
+        panic!("expected Error::Cancelled, got {err:?}");
+    };
+    assert_eq!(progress.reported, [(2, 5)]);
+    assert_eq!(embeddings, all[..2]);
+}
+
+#[test]
+fn pooling_and_max_tokens_are_applied() {
+    let mut mean = builder().build().unwrap();
+    let mut cls = builder().pooling(Pooling::Cls).build().unwrap();
+    assert_eq!(cls.info().pooling, Pooling::Cls);
+    let text = "the cat sat on the mat";
+    assert_ne!(mean.embed(text).unwrap(), cls.embed(text).unwrap());
+
+    let truncated = builder().max_tokens(8).build().unwrap();
+    assert_eq!(truncated.max_tokens(), 8);
+    let capped = builder().max_tokens(10_000).build().unwrap();
+    assert_eq!(capped.max_tokens(), 64);
+}
+
+#[test]
+fn clones_are_independent_and_thread_safe() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Embedder>();
+    assert_send_sync::<Error>();
+
+    let mut embedder = builder().build().unwrap();
+    let expected = embedder.embed("hello world").unwrap();
+    let handles: Vec<_> = (0..2)
+        .map(|_| {
+            let mut embedder = embedder.clone();
+            std::thread::spawn(move || embedder.embed("hello world").unwrap())
+        })
+        .collect();
+    for handle in handles {
+        assert_close(&handle.join().unwrap(), &expected, 1e-6, "hello world");
+    }
+}
+
+#[test]
+fn reports_typed_errors() {
+    let err = EmbedderBuilder::new("does_not_exist").build().unwrap_err();
+    assert!(matches!(err, Error::UnknownModel(ref name) if name == "does_not_exist"));
+
+    let err = builder().batch_size(0).build().unwrap_err();
+    assert!(matches!(err, Error::InvalidArgument(_)));
+
+    let err = builder().dtype(DType::F16).build().unwrap_err();
+    assert!(matches!(err, Error::InvalidArgument(_)));
+
+    let err = EmbedderBuilder::new("mini_lm_v2")
+        .weights_bytes(tiny_weights())
+        .build()
+        .unwrap_err();
+    assert!(matches!(err, Error::InvalidArgument(_)));
+
+    let err = builder()
+        .tokenizer_path("/does/not/exist/tokenizer.json")
+        .build()
+        .unwrap_err();
+    assert!(matches!(err, Error::Io(_)));
+
+    let err = builder()
+        .config_bytes(b"{ not json".to_vec())
+        .build()
+        .unwrap_err();
+    assert!(matches!(err, Error::Config(_)));
+
+    // The default architecture does not match the tiny weights
+    let err = EmbedderBuilder::new("mini_lm_v2")
+        .tokenizer_bytes(TOKENIZER.to_vec())
+        .weights_bytes(tiny_weights())
+        .build()
+        .unwrap_err();
+    assert!(matches!(err, Error::Model(_)));
+
+    // Only text–image models embed images
+    let embedder = builder().build().unwrap();
+    assert_eq!(embedder.info().image_size, None);
+    let err = embedder.embed_images(&[b"\x89PNG"]).unwrap_err();
+    assert!(matches!(err, Error::InvalidArgument(_)));
+
+    // Only cross-encoders rerank
+    let mut embedder = builder().build().unwrap();
+    assert!(!embedder.info().cross_encoder);
+    let err = embedder.rerank("hello", &["world"]).unwrap_err();
+    assert!(matches!(err, Error::InvalidArgument(_)));
+
+    // Only sparse models output term weights
+    assert!(!embedder.info().sparse);
+    let err = embedder.embed_sparse(&["hello"]).unwrap_err();
+    assert!(matches!(err, Error::InvalidArgument(_)));
+
+    // Only late-interaction models output token vectors
+    assert!(!embedder.info().multi_vector);
+    let err = embedder.embed_tokens(&["hello"], Task::Query).unwrap_err();
+    assert!(matches!(err, Error::InvalidArgument(_)));
+}
+
+#[test]
+fn unavailable_device_is_an_error() {
+    // candle is built without CUDA support
+    let err = builder().device(Device::Cuda(0)).build().unwrap_err();
+    assert!(matches!(err, Error::Device(_)));
+}
+
+/// Averages the word embeddings of the tiny BERT, without any attention
+struct WordEmbeddings {
+    embeddings: Embedding,
+    device: CandleDevice,
+}
+
+impl EmbedModel for WordEmbeddings {
+    fn device(&self) -> &CandleDevice {
+        &self.device
+    }
+
+    fn forward(
+        &self,
+        input_ids: &Tensor,
+        _token_type_ids: &Tensor,
+        _attention_mask: Option<&Tensor>,
+    ) -> rust_transformer::candle_core::Result<Tensor> {
+        self.embeddings.forward(input_ids)
+    }
+}
+
+struct WordEmbeddingsLoader {
+    folder: PathBuf,
+}
+
+impl ModelLoader for WordEmbeddingsLoader {
+    fn model_id(&self) -> &str {
+        "example/word-embeddings"
+    }
+
+    fn local_path(&self) -> Option<PathBuf> {
+        Some(self.folder.clone())
+    }
+
+    fn load(&self, vb: VarBuilder, config: Option<&[u8]>) -> Result<LoadedModel, Error> {
+        let config: serde_json::Value = serde_json::from_slice(config.expect("config.json"))?;
+        let dim = |key: &str| config[key].as_u64().unwrap() as usize;
+        let device = vb.device().clone();
+        let embeddings = embedding(
+            dim("vocab_size"),
+            dim("hidden_size"),
+            vb.pp("embeddings.word_embeddings"),
+        )?;
+        Ok(LoadedModel::new(
+            WordEmbeddings { embeddings, device },
+            dim("hidden_size"),
+            dim("max_position_embeddings"),
+        ))
+    }
+}
+
+#[test]
+fn registered_models_resolve_by_name() {
+    let dir = model_dir("registry");
+    register_model(
+        "Word_Embeddings",
+        WordEmbeddingsLoader {
+            folder: dir.clone(),
+        },
+    );
+    assert!(registered_models().contains(&"word_embeddings".to_string()));
+
+    let mut embedder = Embedder::new("word_embeddings").unwrap();
+    assert_eq!(embedder.info().model_id, "example/word-embeddings");
+    assert_eq!(embedder.dimension(), 32);
+    let embedding = embedder.embed("hello world").unwrap();
+    assert!((norm(&embedding) - 1.0).abs() < 1e-4);
+
+    // Different architecture over the same files, so not the BERT embedding
+    let mut bert = builder().build().unwrap();
+    assert_ne!(embedding, bert.embed("hello world").unwrap());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+/// Waits in `load` until the test releases it, to hold a load in progress
+struct Gate {
+    started: Mutex<mpsc::Sender<()>>,
+    release: Mutex<mpsc::Receiver<()>>,
+}
+
+/// Loads like `WordEmbeddingsLoader`, counting its loads
+struct CountingLoader {
+    inner: WordEmbeddingsLoader,
+    loads: Arc<AtomicUsize>,
+    gate: Option<Gate>,
+}
+
+impl CountingLoader {
+    fn new(folder: PathBuf) -> (Self, Arc<AtomicUsize>) {
+        let loads = Arc::new(AtomicUsize::new(0));
+        let loader = Self {
+            inner: WordEmbeddingsLoader { folder },
+            loads: loads.clone(),
+            gate: None,
+        };
+        (loader, loads)
+    }
+}
+
+impl ModelLoader for CountingLoader {
+    fn model_id(&self) -> &str {
+        self.inner.model_id()
+    }
+
+    fn local_path(&self) -> Option<PathBuf> {
+        self.inner.local_path()
+    }
+
+    fn load(&self, vb: VarBuilder, config: Option<&[u8]>) -> Result<LoadedModel, Error> {
+        self.loads.fetch_add(1, Ordering::SeqCst);
+        if let Some(gate) = &self.gate {
+            gate.started.lock().unwrap().send(()).unwrap();
+            let released = gate
+                .release
+                .lock()
+                .unwrap()
+                .recv_timeout(Duration::from_secs(10));
+            if released.is_err() {
+                return Err(Error::InvalidArgument("load was never released".into()));
+            }
+        }
+        self.inner.load(vb, config)
+    }
+}
+
+#[test]
+fn embedders_of_a_model_share_its_weights() {
+    let dir = model_dir("shared");
+    let (loader, loads) = CountingLoader::new(dir.clone());
+    register_model("shared_words", loader);
+
+    let mut mean = Embedder::new("shared_words").unwrap();
+    let mut cls = Embedder::builder("shared_words")
+        .pooling(Pooling::Cls)
+        .normalize(false)
+        .build()
+        .unwrap();
+    assert!(mean.shares_weights(&cls));
+    assert!(mean.shares_weights(&mean.clone()));
+    assert_eq!(loads.load(Ordering::SeqCst), 1);
+
+    // Each embedder keeps its own settings over the shared weights
+    assert_eq!(mean.info().pooling, Pooling::Mean);
+    assert!(mean.info().normalize);
+    assert_eq!(cls.info().pooling, Pooling::Cls);
+    assert!(!cls.info().normalize);
+    assert_ne!(
+        mean.embed("hello world").unwrap(),
+        cls.embed("hello world").unwrap()
+    );
+
+    // The weights are released with the last embedder using them
+    drop((mean, cls));
+    let _reloaded = Embedder::new("shared_words").unwrap();
+    assert_eq!(loads.load(Ordering::SeqCst), 2);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn models_from_different_folders_are_not_shared() {
+    let (first_dir, second_dir) = (model_dir("folder_a"), model_dir("folder_b"));
+    let (loader, _) = CountingLoader::new(first_dir.clone());
+    register_model("moved_words", loader);
+    let first = Embedder::new("moved_words").unwrap();
+
+    // Same name and model ID, read from another folder
+    let (loader, loads) = CountingLoader::new(second_dir.clone());
+    register_model("moved_words", loader);
+    let second = Embedder::new("moved_words").unwrap();
+    assert!(!first.shares_weights(&second));
+    assert_eq!(loads.load(Ordering::SeqCst), 1);
+
+    std::fs::remove_dir_all(first_dir).unwrap();
+    std::fs::remove_dir_all(second_dir).unwrap();
+}
+
+#[test]
+fn loading_a_model_does_not_block_other_models() {
+    let (slow_dir, fast_dir) = (model_dir("slow"), model_dir("fast"));
+    let (started_tx, started) = mpsc::channel();
+    let (release, release_rx) = mpsc::channel();
+    let (mut slow, _) = CountingLoader::new(slow_dir.clone());
+    slow.gate = Some(Gate {
+        started: Mutex::new(started_tx),
+        release: Mutex::new(release_rx),
+    });
+    register_model("slow_words", slow);
+    let (fast, _) = CountingLoader::new(fast_dir.clone());
+    register_model("fast_words", fast);
+
+    let loading = std::thread::spawn(|| Embedder::new("slow_words"));
+    started.recv().unwrap();
+    // Loads while "slow_words" is still loading
+    Embedder::new("fast_words").unwrap();
+    release.send(()).unwrap();
+    loading.join().unwrap().unwrap();
+
+    std::fs::remove_dir_all(slow_dir).unwrap();
+    std::fs::remove_dir_all(fast_dir).unwrap();
+}
+
+/// Lookup table of a distilled static model
+fn static_table(dir: &std::path::Path) -> Vec<Vec<f32>> {
+    let tensors = rust_transformer::candle_core::safetensors::load(
+        dir.join("model.safetensors"),
+        &CandleDevice::Cpu,
+    )
+    .unwrap();
+    tensors["embeddings"].to_vec2().unwrap()
+}
+
+fn static_embedder(dir: &std::path::Path) -> Embedder {
+    EmbedderBuilder::new("model2vec")
+        .tokenizer_path(dir.join("tokenizer.json"))
+        .weights_path(dir.join("model.safetensors"))
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn distilled_model_keeps_the_vector_space() {
+    let dir = std::env::temp_dir().join(format!("rust_transformer_distill_{}", std::process::id()));
+    let mut bert = builder().build().unwrap();
+    let distilled = bert.distill(&dir, &DistillOptions::new()).unwrap();
+    assert_eq!(distilled.vocab_size, 78);
+    assert_eq!(distilled.dimension, 32);
+    let config: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(dir.join("config.json")).unwrap()).unwrap();
+    assert_eq!(config["model_type"], "model2vec");
+
+    // A single word is embedded by the transformer exactly like when it was
+    // distilled, so both models agree on it
+    let mut static_model = static_embedder(&dir);
+    assert_eq!(static_model.dimension(), 32);
+    for word in ["hello", "world", "rust"] {
+        assert_close(
+            &static_model.embed(word).unwrap(),
+            &bert.embed(word).unwrap(),
+            1e-4,
+            word,
+        );
+    }
+    // Distilling leaves the embedder's own settings alone
+    assert!(bert.info().normalize);
+    assert!((norm(&bert.embed("hello world").unwrap()) - 1.0).abs() < 1e-4);
+
+    // Frequency weighting scales down the first (most frequent) tokens most
+    let plain = static_table(&dir);
+    bert.distill(&dir, &DistillOptions::new().sif_coefficient(1e-2))
+        .unwrap();
+    let weighted = static_table(&dir);
+    let weight = |row: usize| norm(&weighted[row]) / norm(&plain[row]);
+    assert!(weight(0) < weight(10) && weight(10) < weight(70) && weight(70) < 1.0);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn distill_rejects_models_without_one_vector_per_text() {
+    let dir = std::env::temp_dir().join(format!("rust_transformer_reject_{}", std::process::id()));
+    for (model, folder, kind) in [
+        ("splade", "tiny_splade", "sparse model"),
+        ("colbert", "tiny_colbert", "multi-vector model"),
+        ("cross_encoder", "tiny_cross_encoder", "cross-encoder"),
+    ] {
+        let mut embedder = fixture_builder(model, folder).build().unwrap();
+        let err = embedder.distill(&dir, &DistillOptions::new()).unwrap_err();
+        assert!(matches!(err, Error::InvalidArgument(_)), "{err}");
+        assert!(err.to_string().contains(kind), "{err}");
+        assert!(!dir.exists());
+    }
+}
+
+#[test]
+fn distill_reduces_dimensions_with_pca() {
+    let dir = std::env::temp_dir().join(format!("rust_transformer_pca_{}", std::process::id()));
+    let mut bert = builder().build().unwrap();
+    let distilled = bert
+        .distill(&dir, &DistillOptions::new().pca_dims(8))
+        .unwrap();
+    assert_eq!(distilled.dimension, 8);
+    assert_eq!(static_embedder(&dir).embed("hello world").unwrap().len(), 8);
+
+    // Principal components: centered, uncorrelated, largest variance first
+    let table = static_table(&dir);
+    let covariance = |a: usize, b: usize| {
+        table.iter().map(|row| row[a] * row[b]).sum::<f32>() / table.len() as f32
+    };
+    for a in 0..8 {
+        let mean = table.iter().map(|row| row[a]).sum::<f32>() / table.len() as f32;
+        assert!(mean.abs() < 1e-4, "mean {mean}");
+        for b in a + 1..8 {
+            assert!(covariance(a, b).abs() < 1e-4, "covariance {a} {b}");
+        }
+        if a > 0 {
+            assert!(covariance(a - 1, a - 1) >= covariance(a, a));
+        }
+    }
+    std::fs::remove_dir_all(dir).unwrap();
+}
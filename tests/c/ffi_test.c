@@ -0,0 +1,129 @@
+/*
+ * C test of the library, compiled against docs/rust_transformer.h and linked
+ * with the cdylib by tests/c_abi.rs. Arguments: tokenizer.json, config.json
+ * and model.safetensors of the tiny BERT. Exits with 0 when every check passes.
+ */
+
+#include <math.h>
+#include <stdio.h>
+#include "rust_transformer.h"
+
+static int failures = 0;
+
+#define CHECK(condition)                                                    \
+    do {                                                                    \
+        if (!(condition)) {                                                 \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, \
+                    #condition);                                            \
+            failures++;                                                     \
+        }                                                                   \
+    } while (0)
+
+static int close_to(float a, float b) {
+    return fabsf(a - b) < 1e-5f;
+}
+
+static void test_similarity_vectors(void) {
+    const float a[] = {1.0f, 2.0f, 2.0f};
+    const float b[] = {2.0f, 4.0f, 4.0f};
+    const float zero[] = {0.0f, 0.0f, 0.0f};
+    float score = -1.0f;
+
+    CHECK(embedder_similarity_vectors(a, b, 3, EMBEDDER_SIMILARITY_COSINE, &score) == EMBEDDER_SUCCESS);
+    CHECK(close_to(score, 1.0f));
+    CHECK(embedder_similarity_vectors(a, b, 3, EMBEDDER_SIMILARITY_DOT, &score) == EMBEDDER_SUCCESS);
+    CHECK(close_to(score, 18.0f));
+    CHECK(embedder_similarity_vectors(a, zero, 3, EMBEDDER_SIMILARITY_COSINE, &score) == EMBEDDER_SUCCESS);
+    CHECK(score == 0.0f);
+    CHECK(embedder_similarity_vectors(a, b, 3, 7, &score) == EMBEDDER_INVALID_ARGUMENT);
+    CHECK(embedder_similarity_vectors(NULL, b, 3, EMBEDDER_SIMILARITY_DOT, &score) == EMBEDDER_NULL_POINTER);
+}
+
+static void test_similarity_matrix(void) {
+    const float a[] = {1.0f, 0.0f, 0.0f, 2.0f};
+    const float b[] = {1.0f, 0.0f, 0.0f, 1.0f, 0.0f, 0.0f};
+    const float expected[] = {1.0f, 0.0f, 0.0f, 0.0f, 1.0f, 0.0f};
+    float scores[6];
+
+    CHECK(embedder_similarity_matrix(a, 2, b, 3, 2, EMBEDDER_SIMILARITY_COSINE, scores, 6) == EMBEDDER_SUCCESS);
+    for (size_t i = 0; i < 6; i++) {
+        CHECK(close_to(scores[i], expected[i]));
+    }
+    CHECK(embedder_similarity_matrix(a, 2, b, 3, 2, EMBEDDER_SIMILARITY_DOT, scores, 6) == EMBEDDER_SUCCESS);
+    CHECK(close_to(scores[4], 2.0f));
+
+    CHECK(embedder_similarity_matrix(a, 2, b, 3, 2, EMBEDDER_SIMILARITY_DOT, scores, 5) == EMBEDDER_BUFFER_TOO_SMALL);
+    CHECK(embedder_similarity_matrix(a, 2, b, 3, 0, EMBEDDER_SIMILARITY_DOT, scores, 6) == EMBEDDER_INVALID_ARGUMENT);
+    CHECK(embedder_similarity_matrix(a, 2, b, 3, 2, EMBEDDER_SIMILARITY_DOT, NULL, 6) == EMBEDDER_NULL_POINTER);
+}
+
+static void test_topk(void) {
+    /* Rows 0 and 1 tie, as do rows 2 and 3 */
+    const float matrix[] = {1.0f, 0.0f, 0.0f, 1.0f, 2.0f, 0.0f, 0.0f, 2.0f};
+    const float query[] = {1.0f, 1.0f};
+    size_t indices[8];
+    float scores[8];
+    size_t count = 0;
+
+    CHECK(embedder_topk(query, matrix, 4, 2, 8, EMBEDDER_SIMILARITY_DOT, indices, scores, &count) == EMBEDDER_SUCCESS);
+    CHECK(count == 4);
+    CHECK(indices[0] == 2 && indices[1] == 3 && indices[2] == 0 && indices[3] == 1);
+    CHECK(close_to(scores[0], 2.0f) && close_to(scores[3], 1.0f));
+
+    CHECK(embedder_topk(query, matrix, 4, 2, 0, EMBEDDER_SIMILARITY_DOT, indices, scores, &count) == EMBEDDER_SUCCESS);
+    CHECK(count == 0);
+    CHECK(embedder_topk(query, matrix, 4, 0, 2, EMBEDDER_SIMILARITY_DOT, indices, scores, &count) == EMBEDDER_INVALID_ARGUMENT);
+}
+
+static void test_handle(const char* tokenizer, const char* config, const char* weights) {
+    const char* texts[] = {"The cat sits on the mat.", "A kitten rests on a rug.", "Rust is fast."};
+    float embeddings[3 * 32];
+    float matrix[9];
+    size_t dim = 0;
+    float score = 0.0f;
+
+    CHECK(embedder_init_from_files("mini_lm_v2", "missing/tokenizer.json", config, weights) == NULL);
+
+    EmbedderHandle handle = embedder_init_from_files("mini_lm_v2", tokenizer, config, weights);
+    CHECK(handle != NULL);
+    if (handle == NULL) {
+        return;
+    }
+    CHECK(embedder_get_dimension(handle) == 32);
+    for (size_t i = 0; i < 3; i++) {
+        CHECK(embedder_embed(handle, texts[i], embeddings + i * 32, 32, &dim) == EMBEDDER_SUCCESS);
+        CHECK(dim == 32);
+    }
+
+    /* Normalized embeddings: the diagonal is 1 and the matrix is symmetric */
+    CHECK(embedder_similarity_matrix(embeddings, 3, embeddings, 3, 32, EMBEDDER_SIMILARITY_DOT, matrix, 9) == EMBEDDER_SUCCESS);
+    for (size_t i = 0; i < 3; i++) {
+        CHECK(fabsf(matrix[i * 3 + i] - 1.0f) < 1e-4f);
+        for (size_t j = 0; j < 3; j++) {
+            CHECK(close_to(matrix[i * 3 + j], matrix[j * 3 + i]));
+        }
+    }
+
+    CHECK(embedder_similarity(handle, texts[0], texts[1], EMBEDDER_SIMILARITY_COSINE, &score) == EMBEDDER_SUCCESS);
+    CHECK(fabsf(score - matrix[1]) < 1e-4f);
+    embedder_free(handle);
+}
+
+int main(int argc, char** argv) {
+    if (argc != 4) {
+        fprintf(stderr, "usage: %s TOKENIZER CONFIG WEIGHTS\n", argv[0]);
+        return 2;
+    }
+    CHECK((embedder_abi_version() >> 16) == EMBEDDER_ABI_VERSION_MAJOR);
+
+    test_similarity_vectors();
+    test_similarity_matrix();
+    test_topk();
+    test_handle(argv[1], argv[2], argv[3]);
+
+    if (failures > 0) {
+        fprintf(stderr, "%d check(s) failed\n", failures);
+        return 1;
+    }
+    return 0;
+}
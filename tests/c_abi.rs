@@ -0,0 +1,64 @@
+// Builds tests/c/ffi_test.c against docs/rust_transformer.h, links it with the
+// cdylib cargo built for this test run and runs it on the tiny BERT from
+// `common`. Skipped when no C compiler is available (set CC to choose one).
+#![cfg(unix)]
+
+mod common;
+
+use common::{CONFIG, TOKENIZER, tiny_weights};
+use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Directory of the library, next to the test executables
+fn library_dir() -> PathBuf {
+    let exe = std::env::current_exe().unwrap();
+    let dir = exe.parent().unwrap().to_path_buf();
+    let library = dir.join(format!("{DLL_PREFIX}rust_transformer{DLL_SUFFIX}"));
+    assert!(library.exists(), "{} not built", library.display());
+    dir
+}
+
+#[test]
+fn c_program_uses_the_library() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let out = Path::new(env!("CARGO_TARGET_TMPDIR")).join("c_abi");
+    std::fs::create_dir_all(&out).unwrap();
+    std::fs::write(out.join("tokenizer.json"), TOKENIZER).unwrap();
+    std::fs::write(out.join("config.json"), CONFIG).unwrap();
+    std::fs::write(out.join("model.safetensors"), tiny_weights()).unwrap();
+
+    let lib_dir = library_dir();
+    let program = out.join("ffi_test");
+    let compiler = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let compiled = Command::new(&compiler)
+        .arg(root.join("tests/c/ffi_test.c"))
+        .arg("-I")
+        .arg(root.join("docs"))
+        .arg("-L")
+        .arg(&lib_dir)
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .args(["-lrust_transformer", "-lm", "-o"])
+        .arg(&program)
+        .status();
+    let compiled = match compiled {
+        Ok(status) => status,
+        Err(e) => {
+            eprintln!("Skipping the C test, cannot run {compiler}: {e}");
+            return;
+        }
+    };
+    assert!(compiled.success(), "{compiler} failed");
+
+    let output = Command::new(&program)
+        .arg(out.join("tokenizer.json"))
+        .arg(out.join("config.json"))
+        .arg(out.join("model.safetensors"))
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,93 @@
+// Golden-vector tests for the text–image architectures (CLIP and SigLIP)
+// against reference embeddings checked into tests/fixtures/tiny_clip and
+// tests/fixtures/tiny_siglip. The fixtures, their images and the reference
+// vectors are written by generate.py in those folders, whose plain-Python
+// forward passes follow HuggingFace's CLIPModel and SiglipModel.
+mod common;
+
+use common::{Fixture, assert_close, fixture, references_under};
+use rust_transformer::{Error, Pooling, similarity};
+
+const CLIP: Fixture = Fixture {
+    model_id: "openai/clip-vit-base-patch32",
+    dimension: 16,
+    max_tokens: 64,
+    texts_key: Some("texts"),
+    ..Fixture::new("clip", "tiny_clip")
+};
+
+// Texts are padded to the full context whatever the batch
+const SIGLIP: Fixture = Fixture {
+    model_id: "google/siglip2-base-patch16-224",
+    dimension: 24,
+    max_tokens: 24,
+    texts_key: Some("texts"),
+    ..Fixture::new("siglip", "tiny_siglip")
+};
+
+common::fixture_tests!(clip: CLIP);
+common::fixture_tests!(siglip: SIGLIP);
+
+#[test]
+fn reports_pooling_and_image_size() {
+    let info = CLIP.embedder().info().clone();
+    assert_eq!(info.pooling, Pooling::LastToken);
+    assert_eq!(info.image_size, Some(16));
+
+    let info = SIGLIP.embedder().info().clone();
+    assert_eq!(info.pooling, Pooling::Cls);
+    assert_eq!(info.image_size, Some(16));
+}
+
+#[test]
+fn clip_images_match_reference() {
+    // square.png has the input size; wide.png is center-cropped
+    let images = references_under("tiny_clip", "images");
+    let names: Vec<String> = images.iter().map(|r| r.get("image")).collect();
+    let embedder = CLIP.embedder();
+    let files: Vec<Vec<u8>> = names
+        .iter()
+        .map(|name| std::fs::read(fixture(&format!("tiny_clip/{name}"))).unwrap())
+        .collect();
+    let embeddings = embedder.embed_images(&files).unwrap();
+    assert_eq!(embeddings.len(), images.len());
+    for ((embedding, reference), name) in embeddings.iter().zip(&images).zip(&names) {
+        assert_close(embedding, &reference.embedding, 1e-4, name);
+    }
+
+    let single = embedder
+        .embed_image_file(fixture("tiny_clip/square.png"))
+        .unwrap();
+    assert_close(&single, &images[0].embedding, 1e-4, "square.png");
+}
+
+#[test]
+fn siglip_images_match_reference() {
+    let images = references_under("tiny_siglip", "images");
+    let image = SIGLIP
+        .embedder()
+        .embed_image_file(fixture("tiny_siglip/square.png"))
+        .unwrap();
+    assert_close(&image, &images[0].embedding, 1e-4, "square.png");
+}
+
+#[test]
+fn texts_and_images_share_the_vector_space() {
+    let mut embedder = CLIP.embedder();
+    let text = embedder.embed("hello world").unwrap();
+    let image = embedder
+        .embed_image_file(fixture("tiny_clip/square.png"))
+        .unwrap();
+    assert_eq!(text.len(), image.len());
+    let score = similarity::cosine(&text, &image);
+    assert!(score.is_finite() && score.abs() <= 1.0 + 1e-6);
+}
+
+#[test]
+fn rejects_undecodable_images() {
+    let err = CLIP
+        .embedder()
+        .embed_images(&[b"not an image"])
+        .unwrap_err();
+    assert!(matches!(err, Error::Image(_)), "{err}");
+}
@@ -0,0 +1,348 @@
+// Tiny randomly initialized BERT shared by the integration tests (see
+// python/tests/tiny_model.py); its weights are generated here so no binary
+// fixture is needed. Also loads the golden-vector fixtures of
+// tests/fixtures/tiny_*, written by the generate.py in each folder, and holds
+// the checks they all share (`Fixture`, `fixture_tests!`).
+//
+// Each test binary compiles its own copy and uses only part of it.
+#![allow(dead_code, unused_imports, unused_macros)]
+
+use rust_transformer::{Embedder, EmbedderBuilder, Task};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::path::PathBuf;
+use tokenizers::Tokenizer;
+
+pub const CONFIG: &[u8] = include_bytes!("../fixtures/tiny_bert/config.json");
+pub const TOKENIZER: &[u8] = include_bytes!("../fixtures/tiny_bert/tokenizer.json");
+
+/// Safetensors file with the tensors candle's BertModel expects for `CONFIG`
+pub fn tiny_weights() -> Vec<u8> {
+    let config: Value = serde_json::from_slice(CONFIG).unwrap();
+    let dim = |key: &str| config[key].as_u64().unwrap() as usize;
+    let (hidden, intermediate) = (dim("hidden_size"), dim("intermediate_size"));
+
+    let mut shapes = vec![
+        (
+            "embeddings.word_embeddings.weight".to_string(),
+            vec![dim("vocab_size"), hidden],
+        ),
+        (
+            "embeddings.position_embeddings.weight".to_string(),
+            vec![dim("max_position_embeddings"), hidden],
+        ),
+        (
+            "embeddings.token_type_embeddings.weight".to_string(),
+            vec![dim("type_vocab_size"), hidden],
+        ),
+        ("embeddings.LayerNorm.weight".to_string(), vec![hidden]),
+        ("embeddings.LayerNorm.bias".to_string(), vec![hidden]),
+    ];
+    for i in 0..dim("num_hidden_layers") {
+        let layer = format!("encoder.layer.{i}");
+        for name in [
+            "attention.self.query",
+            "attention.self.key",
+            "attention.self.value",
+            "attention.output.dense",
+        ] {
+            shapes.push((format!("{layer}.{name}.weight"), vec![hidden, hidden]));
+            shapes.push((format!("{layer}.{name}.bias"), vec![hidden]));
+        }
+        shapes.push((
+            format!("{layer}.intermediate.dense.weight"),
+            vec![intermediate, hidden],
+        ));
+        shapes.push((
+            format!("{layer}.intermediate.dense.bias"),
+            vec![intermediate],
+        ));
+        shapes.push((
+            format!("{layer}.output.dense.weight"),
+            vec![hidden, intermediate],
+        ));
+        shapes.push((format!("{layer}.output.dense.bias"), vec![hidden]));
+        for name in ["attention.output.LayerNorm", "output.LayerNorm"] {
+            shapes.push((format!("{layer}.{name}.weight"), vec![hidden]));
+            shapes.push((format!("{layer}.{name}.bias"), vec![hidden]));
+        }
+    }
+
+    // Small deterministic pseudo-random weights
+    let mut state = 0x2545_f491_u32;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        (state as f32 / u32::MAX as f32 - 0.5) * 0.05
+    };
+
+    let mut header = serde_json::Map::new();
+    let mut data = Vec::new();
+    for (name, shape) in shapes {
+        let count: usize = shape.iter().product();
+        let start = data.len();
+        for _ in 0..count {
+            let value = if name.ends_with("LayerNorm.weight") {
+                1.0
+            } else if name.ends_with("bias") {
+                0.0
+            } else {
+                next()
+            };
+            data.extend_from_slice(&f32::to_le_bytes(value));
+        }
+        header.insert(
+            name,
+            serde_json::json!({ "dtype": "F32", "shape": shape, "data_offsets": [start, data.len()] }),
+        );
+    }
+
+    let header = serde_json::to_vec(&header).unwrap();
+    let mut file = (header.len() as u64).to_le_bytes().to_vec();
+    file.extend_from_slice(&header);
+    file.extend_from_slice(&data);
+    file
+}
+
+/// Path of `file` under tests/fixtures
+pub fn fixture(file: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(file)
+}
+
+/// Contents of the reference.json of a fixture folder
+pub fn reference_json(folder: &str) -> Value {
+    let path = fixture(&format!("{folder}/reference.json"));
+    serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap()
+}
+
+/// A reference entry: a text with its token IDs and embedding, each empty
+/// when the entry has none, and the architecture-specific fields (see `get`)
+pub struct Reference {
+    pub text: String,
+    pub task: Option<Task>,
+    pub ids: Vec<u32>,
+    pub embedding: Vec<f32>,
+    entry: Value,
+}
+
+impl Reference {
+    fn new(entry: &Value) -> Self {
+        Self {
+            text: entry["text"].as_str().unwrap_or_default().to_string(),
+            task: match entry["task"].as_str() {
+                Some("query") => Some(Task::Query),
+                Some("document") => Some(Task::Document),
+                _ => None,
+            },
+            ids: serde_json::from_value(entry["ids"].clone()).unwrap_or_default(),
+            embedding: serde_json::from_value(entry["embedding"].clone()).unwrap_or_default(),
+            entry: entry.clone(),
+        }
+    }
+
+    /// Field `key` of the entry, such as a cross-encoder's `score`
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> T {
+        serde_json::from_value(self.entry[key].clone())
+            .unwrap_or_else(|e| panic!("'{key}' of '{}': {e}", self.text))
+    }
+}
+
+/// Reference entries of a fixture folder whose reference.json is a list
+pub fn references(folder: &str) -> Vec<Reference> {
+    parse_references(&reference_json(folder))
+}
+
+/// Reference entries listed under `key` of a fixture folder's reference.json,
+/// such as the `texts` and `images` of text–image models
+pub fn references_under(folder: &str, key: &str) -> Vec<Reference> {
+    parse_references(&reference_json(folder)[key])
+}
+
+fn parse_references(list: &Value) -> Vec<Reference> {
+    list.as_array()
+        .unwrap()
+        .iter()
+        .map(Reference::new)
+        .collect()
+}
+
+/// Path of the tokenizer of a fixture folder, tiny BERT's unless it has its own
+pub fn fixture_tokenizer(folder: &str) -> PathBuf {
+    let own = fixture(&format!("{folder}/tokenizer.json"));
+    if own.exists() {
+        own
+    } else {
+        fixture("tiny_bert/tokenizer.json")
+    }
+}
+
+/// Builder of `model` on the files of a fixture folder
+pub fn fixture_builder(model: &str, folder: &str) -> EmbedderBuilder {
+    EmbedderBuilder::new(model)
+        .tokenizer_path(fixture_tokenizer(folder))
+        .config_path(fixture(&format!("{folder}/config.json")))
+        .weights_path(fixture(&format!("{folder}/model.safetensors")))
+}
+
+pub fn assert_close(actual: &[f32], expected: &[f32], tolerance: f32, name: &str) {
+    assert_eq!(actual.len(), expected.len(), "'{name}'");
+    for (a, e) in actual.iter().zip(expected) {
+        assert!((a - e).abs() < tolerance, "'{name}': {a} != {e}");
+    }
+}
+
+/// `assert_close` on each row, such as the token vectors of a multi-vector
+/// embedding
+pub fn assert_rows_close(actual: &[Vec<f32>], expected: &[Vec<f32>], tolerance: f32, name: &str) {
+    assert_eq!(actual.len(), expected.len(), "'{name}'");
+    for (row, (a, e)) in actual.iter().zip(expected).enumerate() {
+        assert_close(a, e, tolerance, &format!("{name}, row {row}"));
+    }
+}
+
+/// A golden-vector fixture: the tiny model in a tests/fixtures folder and the
+/// reference texts its generate.py embedded. The checks every fixture shares
+/// are methods, turned into tests by `fixture_tests!`.
+pub struct Fixture {
+    /// Model name the fixture is loaded as
+    pub model: &'static str,
+    pub folder: &'static str,
+    pub model_id: &'static str,
+    pub dimension: usize,
+    pub max_tokens: usize,
+    /// Instruction the `Task::Query` references were embedded with, when it
+    /// is not the model's own
+    pub query_prefix: Option<&'static str>,
+    /// Key of the reference texts when reference.json is not a list
+    pub texts_key: Option<&'static str>,
+    /// Whether the reference IDs include the special tokens
+    pub special_tokens: bool,
+}
+
+impl Fixture {
+    /// Fixture `folder` loaded as `model`; the expected shape is left for the
+    /// caller to fill in
+    pub const fn new(model: &'static str, folder: &'static str) -> Self {
+        Self {
+            model,
+            folder,
+            model_id: "",
+            dimension: 0,
+            max_tokens: 0,
+            query_prefix: None,
+            texts_key: None,
+            special_tokens: true,
+        }
+    }
+
+    pub fn builder(&self) -> EmbedderBuilder {
+        fixture_builder(self.model, self.folder)
+    }
+
+    pub fn embedder(&self) -> Embedder {
+        self.builder().build().unwrap()
+    }
+
+    /// Embedder of texts embedded as `task`, as the references were
+    fn task_embedder(&self, task: Option<Task>) -> Embedder {
+        let Some(task) = task else {
+            return self.embedder();
+        };
+        let builder = self.builder().task(task);
+        match self.query_prefix {
+            Some(prefix) => builder.query_prefix(prefix),
+            None => builder,
+        }
+        .build()
+        .unwrap()
+    }
+
+    pub fn references(&self) -> Vec<Reference> {
+        match self.texts_key {
+            Some(key) => references_under(self.folder, key),
+            None => references(self.folder),
+        }
+    }
+
+    pub fn tokenizer_matches_reference_ids(&self) {
+        let tokenizer = Tokenizer::from_file(fixture_tokenizer(self.folder)).unwrap();
+        for reference in self.references() {
+            let info = self.task_embedder(reference.task).info().clone();
+            let prefix = match reference.task {
+                Some(Task::Query) => info.query_prefix.unwrap(),
+                Some(Task::Document) => info.document_prefix.unwrap(),
+                _ => String::new(),
+            };
+            let text = format!("{prefix}{}", reference.text);
+            let encoding = tokenizer
+                .encode(text.as_str(), self.special_tokens)
+                .unwrap();
+            assert_eq!(encoding.get_ids(), reference.ids, "'{text}'");
+        }
+    }
+
+    pub fn reports_model_shape(&self) {
+        let embedder = self.embedder();
+        assert_eq!(embedder.dimension(), self.dimension);
+        assert_eq!(embedder.max_tokens(), self.max_tokens);
+        assert_eq!(embedder.info().model_id, self.model_id);
+    }
+
+    pub fn single_embeddings_match_reference(&self) {
+        for reference in self.references() {
+            let embedding = self
+                .task_embedder(reference.task)
+                .embed(&reference.text)
+                .unwrap();
+            assert_close(&embedding, &reference.embedding, 1e-4, &reference.text);
+        }
+    }
+
+    /// The references without a task, embedded in one batch; they have
+    /// different lengths, so all but the longest are padded
+    pub fn padded_batch_matches_reference(&self) {
+        let references: Vec<_> = self
+            .references()
+            .into_iter()
+            .filter(|r| r.task.is_none())
+            .collect();
+        let texts: Vec<&str> = references.iter().map(|r| r.text.as_str()).collect();
+        let embeddings = self.embedder().embed_batch(&texts).unwrap();
+        assert_eq!(embeddings.len(), texts.len());
+        for (embedding, reference) in embeddings.iter().zip(&references) {
+            assert_close(embedding, &reference.embedding, 1e-4, &reference.text);
+        }
+    }
+}
+
+/// Module `$module` with a test per `Fixture` check of `$fixture`, a const
+/// of the test file; all of them unless the checks are listed after `=>`.
+///
+/// ```ignore
+/// common::fixture_tests!(clip: CLIP => reports_model_shape);
+/// ```
+macro_rules! fixture_tests {
+    ($module:ident: $fixture:ident) => {
+        $crate::common::fixture_tests!(
+            $module: $fixture =>
+                tokenizer_matches_reference_ids,
+                reports_model_shape,
+                single_embeddings_match_reference,
+                padded_batch_matches_reference,
+        );
+    };
+    ($module:ident: $fixture:ident => $($check:ident),+ $(,)?) => {
+        mod $module {
+            $(
+                #[test]
+                fn $check() {
+                    super::$fixture.$check();
+                }
+            )+
+        }
+    };
+}
+pub(crate) use fixture_tests;
@@ -0,0 +1,63 @@
+// Golden-vector tests for the decoder (Qwen2/Mistral) architecture against
+// reference embeddings checked into tests/fixtures/tiny_qwen2. The fixture and
+// the reference vectors are written by generate.py in that folder, whose
+// plain-Python forward pass follows HuggingFace's Qwen2Model with last-token
+// pooling. In the padded batch, texts are padded on the left, so their last
+// token stays at the end of the sequence.
+mod common;
+
+use common::{Fixture, assert_close};
+use rust_transformer::{DType, PaddingSide, Pooling, Task};
+
+/// Instruction the fixture's query texts were embedded with
+const QUERY_PREFIX: &str = "search query ";
+
+const FIXTURE: Fixture = Fixture {
+    model_id: "Alibaba-NLP/gte-Qwen2-1.5B-instruct",
+    dimension: 32,
+    max_tokens: 64,
+    query_prefix: Some(QUERY_PREFIX),
+    ..Fixture::new("gte_qwen2", "tiny_qwen2")
+};
+
+common::fixture_tests!(decoder: FIXTURE);
+
+#[test]
+fn reports_decoder_defaults() {
+    let embedder = FIXTURE.embedder();
+    let info = embedder.info();
+    assert_eq!(info.pooling, Pooling::LastToken);
+    assert_eq!(info.padding_side, PaddingSide::Left);
+    assert!(
+        info.query_prefix
+            .as_deref()
+            .unwrap()
+            .starts_with("Instruct: ")
+    );
+}
+
+#[test]
+fn half_precision_stays_close_to_reference() {
+    let mut embedder = FIXTURE.builder().dtype(DType::F16).build().unwrap();
+    for reference in FIXTURE.references().iter().filter(|r| r.task.is_none()) {
+        let embedding = embedder.embed(&reference.text).unwrap();
+        assert_close(&embedding, &reference.embedding, 2e-2, &reference.text);
+    }
+}
+
+#[test]
+fn query_instruction_changes_embedding() {
+    let text = "is rust fast?";
+    let plain = FIXTURE.embedder().embed(text).unwrap();
+    let mut instructed = FIXTURE.builder().task(Task::Query).build().unwrap();
+    let default_instruction = instructed.embed(text).unwrap();
+    let mut custom = FIXTURE
+        .builder()
+        .task(Task::Query)
+        .query_prefix(QUERY_PREFIX)
+        .build()
+        .unwrap();
+    let custom_instruction = custom.embed(text).unwrap();
+    assert_ne!(plain, default_instruction);
+    assert_ne!(default_instruction, custom_instruction);
+}
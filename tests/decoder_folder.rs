@@ -0,0 +1,106 @@
+// Loads the tiny Qwen2 decoder of tests/fixtures/tiny_qwen2 from a model
+// folder through `GTE_QWEN2_MODEL_FOLDER`, with and without the
+// sentence-transformers settings files. The variable is process-wide, so this
+// runs as the only test of its own test binary.
+mod common;
+
+use common::{assert_close, fixture, fixture_builder, fixture_tokenizer, references};
+use rust_transformer::{Embedder, Error, PaddingSide, Pooling};
+use std::path::{Path, PathBuf};
+
+/// Folder with the tiny Qwen2 files and `files`, given as (path, contents)
+fn model_folder(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "rust_transformer_decoder_{name}_{}",
+        std::process::id()
+    ));
+    std::fs::create_dir_all(dir.join("1_Pooling")).unwrap();
+    std::fs::copy(fixture_tokenizer("tiny_qwen2"), dir.join("tokenizer.json")).unwrap();
+    for file in ["config.json", "model.safetensors"] {
+        std::fs::copy(fixture(&format!("tiny_qwen2/{file}")), dir.join(file)).unwrap();
+    }
+    for (file, contents) in files {
+        std::fs::write(dir.join(file), contents).unwrap();
+    }
+    dir
+}
+
+fn load(folder: &Path) -> Result<Embedder, Error> {
+    // Safety: this is the only test, so no other thread reads the environment
+    unsafe { std::env::set_var("GTE_QWEN2_MODEL_FOLDER", folder) };
+    Embedder::new("gte_qwen2")
+}
+
+#[test]
+fn folder_settings_override_the_decoder_defaults() {
+    let text = "the cat sat on the mat";
+
+    // Without the files, the decoder's own last-token pooling and left padding
+    let plain = model_folder("plain", &[]);
+    let mut embedder = load(&plain).unwrap();
+    assert_eq!(embedder.info().pooling, Pooling::LastToken);
+    assert_eq!(embedder.info().padding_side, PaddingSide::Left);
+    let mut reference = fixture_builder("gte_qwen2", "tiny_qwen2").build().unwrap();
+    assert_close(
+        &embedder.embed(text).unwrap(),
+        &reference.embed(text).unwrap(),
+        1e-5,
+        text,
+    );
+
+    let mean = model_folder(
+        "mean",
+        &[
+            (
+                "1_Pooling/config.json",
+                r#"{"word_embedding_dimension": 32, "pooling_mode_cls_token": false,
+                    "pooling_mode_mean_tokens": true, "pooling_mode_lasttoken": false}"#,
+            ),
+            ("tokenizer_config.json", r#"{"padding_side": "right"}"#),
+        ],
+    );
+    let mut embedder = load(&mean).unwrap();
+    assert_eq!(embedder.info().pooling, Pooling::Mean);
+    assert_eq!(embedder.info().padding_side, PaddingSide::Right);
+    let mut reference = fixture_builder("gte_qwen2", "tiny_qwen2")
+        .pooling(Pooling::Mean)
+        .build()
+        .unwrap();
+    assert_close(
+        &embedder.embed(text).unwrap(),
+        &reference.embed(text).unwrap(),
+        1e-5,
+        text,
+    );
+
+    // Last-token pooling still finds the last token of right-padded texts
+    let right = model_folder(
+        "right",
+        &[("tokenizer_config.json", r#"{"padding_side": "right"}"#)],
+    );
+    let mut embedder = load(&right).unwrap();
+    assert_eq!(embedder.info().pooling, Pooling::LastToken);
+    assert_eq!(embedder.info().padding_side, PaddingSide::Right);
+    let references: Vec<_> = references("tiny_qwen2")
+        .into_iter()
+        .filter(|r| r.task.is_none())
+        .collect();
+    let texts: Vec<&str> = references.iter().map(|r| r.text.as_str()).collect();
+    let embeddings = embedder.embed_batch(&texts).unwrap();
+    for (embedding, reference) in embeddings.iter().zip(&references) {
+        assert_close(embedding, &reference.embedding, 1e-4, &reference.text);
+    }
+
+    let max = model_folder(
+        "max",
+        &[(
+            "1_Pooling/config.json",
+            r#"{"pooling_mode_max_tokens": true}"#,
+        )],
+    );
+    assert!(matches!(load(&max), Err(Error::Config(_))));
+
+    for dir in [plain, mean, right, max] {
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
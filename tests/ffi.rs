@@ -0,0 +1,975 @@
+// Tests for the C ABI, calling the `extern "C"` functions the way a C caller
+// would. The model is the tiny BERT from `common`, loaded from buffers, and
+// the tiny CLIP of tests/fixtures/tiny_clip for images.
+mod common;
+
+use common::{
+    CONFIG, TOKENIZER, assert_close, fixture, fixture_tokenizer, reference_json, tiny_weights,
+};
+use rust_transformer::candle_core::{Device as CandleDevice, Tensor};
+use rust_transformer::candle_nn::{Embedding, Module, VarBuilder, embedding};
+use rust_transformer::{
+    EMBEDDER_POOLING_CLS, EMBEDDER_SIMILARITY_DOT, EmbedModel, EmbedderErrorCode, EmbedderHandle,
+    EmbedderOptions, EmbedderProgress, EmbeddingBatch, Error, LoadedModel, ModelLoader,
+    embedder_batch_count, embedder_batch_data, embedder_batch_dimension, embedder_batch_free,
+    embedder_batch_row, embedder_batch_status, embedder_embed, embedder_embed_batch_alloc,
+    embedder_embed_batch_alloc_utf8, embedder_embed_batch_alloc_utf16,
+    embedder_embed_batch_progress, embedder_embed_batch_utf8, embedder_embed_batch_utf16,
+    embedder_embed_image, embedder_embed_utf8, embedder_embed_utf16, embedder_free,
+    embedder_free_error, embedder_get_capabilities, embedder_get_last_error,
+    embedder_get_max_tokens, embedder_get_model_info, embedder_init_from_buffers,
+    embedder_init_from_files, embedder_init_with_options, embedder_options_init,
+    embedder_similarity_matrix, embedder_topk, register_model, registered_models,
+};
+use serde_json::Value;
+use std::ffi::{CStr, CString};
+use std::mem::{offset_of, size_of};
+use std::os::raw::c_void;
+use std::path::PathBuf;
+use std::ptr;
+use std::sync::OnceLock;
+
+/// Handle on the tiny BERT loaded as `model_name`
+fn handle_for(model_name: &str) -> *mut EmbedderHandle {
+    let name = CString::new(model_name).unwrap();
+    let weights = tiny_weights();
+    let handle = unsafe {
+        embedder_init_from_buffers(
+            name.as_ptr(),
+            TOKENIZER.as_ptr(),
+            TOKENIZER.len(),
+            CONFIG.as_ptr(),
+            CONFIG.len(),
+            weights.as_ptr(),
+            weights.len(),
+        )
+    };
+    assert!(!handle.is_null(), "no handle for {model_name}");
+    handle
+}
+
+fn tiny_handle() -> *mut EmbedderHandle {
+    handle_for("mini_lm_v2")
+}
+
+/// Embedding of `text` through `embedder_embed()`
+fn embed(handle: *mut EmbedderHandle, text: &str) -> Vec<f32> {
+    let text = CString::new(text).unwrap();
+    let mut output = vec![0.0f32; 32];
+    let mut size = 0;
+    let code = unsafe {
+        embedder_embed(
+            handle,
+            text.as_ptr(),
+            output.as_mut_ptr(),
+            output.len(),
+            &mut size,
+        )
+    };
+    assert_eq!(code, EmbedderErrorCode::Success);
+    output.truncate(size);
+    output
+}
+
+/// Last error of `handle`, freed after copying it
+fn last_error(handle: *mut EmbedderHandle) -> Option<String> {
+    let error = unsafe { embedder_get_last_error(handle) };
+    if error.is_null() {
+        return None;
+    }
+    let message = unsafe { CStr::from_ptr(error) }
+        .to_string_lossy()
+        .into_owned();
+    unsafe { embedder_free_error(error as *mut _) };
+    Some(message)
+}
+
+/// Model whose forward pass always panics
+struct Panicking {
+    device: CandleDevice,
+}
+
+impl EmbedModel for Panicking {
+    fn device(&self) -> &CandleDevice {
+        &self.device
+    }
+
+    fn forward(
+        &self,
+        _input_ids: &Tensor,
+        _token_type_ids: &Tensor,
+        _attention_mask: Option<&Tensor>,
+    ) -> rust_transformer::candle_core::Result<Tensor> {
+        panic!("forward pass exploded");
+    }
+}
+
+struct PanickingLoader;
+
+impl ModelLoader for PanickingLoader {
+    fn model_id(&self) -> &str {
+        "example/panicking"
+    }
+
+    fn local_path(&self) -> Option<PathBuf> {
+        None
+    }
+
+    fn load(&self, vb: VarBuilder, _config: Option<&[u8]>) -> Result<LoadedModel, Error> {
+        let device = vb.device().clone();
+        Ok(LoadedModel::new(Panicking { device }, 32, 128))
+    }
+}
+
+#[test]
+fn panics_become_the_panic_code() {
+    register_model("panicking", PanickingLoader);
+    let handle = handle_for("panicking");
+    let text = CString::new("hello world").unwrap();
+    let mut output = [0.0f32; 32];
+    let mut size = 0;
+
+    let code = unsafe {
+        embedder_embed(
+            handle,
+            text.as_ptr(),
+            output.as_mut_ptr(),
+            output.len(),
+            &mut size,
+        )
+    };
+    assert_eq!(code, EmbedderErrorCode::Panic);
+    let error = last_error(handle).unwrap();
+    assert!(error.starts_with("Panic: "), "{error}");
+    assert!(error.contains("forward pass exploded"), "{error}");
+
+    // The handle stays usable after a panic
+    let code = unsafe { embedder_embed(handle, ptr::null(), output.as_mut_ptr(), 32, &mut size) };
+    assert_eq!(code, EmbedderErrorCode::NullPointer);
+    unsafe { embedder_free(handle) };
+}
+
+#[test]
+fn embeds_through_the_c_abi() {
+    let handle = tiny_handle();
+    let embedding = embed(handle, "hello world");
+    assert_eq!(embedding.len(), 32);
+    let norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+    assert!((norm - 1.0).abs() < 1e-4);
+    assert!(last_error(handle).is_none());
+    unsafe { embedder_free(handle) };
+}
+
+/// Path of `file` in a temporary folder holding the tiny BERT files
+fn model_file(dir: &str, file: &str) -> CString {
+    let dir =
+        std::env::temp_dir().join(format!("rust_transformer_ffi_{dir}_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("tokenizer.json"), TOKENIZER).unwrap();
+    std::fs::write(dir.join("config.json"), CONFIG).unwrap();
+    std::fs::write(dir.join("model.safetensors"), tiny_weights()).unwrap();
+    let weights = tiny_weights();
+    std::fs::write(
+        dir.join("truncated.safetensors"),
+        &weights[..weights.len() / 2],
+    )
+    .unwrap();
+    CString::new(dir.join(file).to_str().unwrap()).unwrap()
+}
+
+/// Handle from `embedder_init_from_files()`, null on failure
+fn handle_from_files(
+    model_name: &str,
+    tokenizer: &CString,
+    config: Option<&CString>,
+    weights: &CString,
+) -> *mut EmbedderHandle {
+    let name = CString::new(model_name).unwrap();
+    unsafe {
+        embedder_init_from_files(
+            name.as_ptr(),
+            tokenizer.as_ptr(),
+            config.map_or(ptr::null(), |config| config.as_ptr()),
+            weights.as_ptr(),
+        )
+    }
+}
+
+#[test]
+fn init_from_files_matches_buffers() {
+    let tokenizer = model_file("files", "tokenizer.json");
+    let config = model_file("files", "config.json");
+    let weights = model_file("files", "model.safetensors");
+    let from_files = handle_from_files("mini_lm_v2", &tokenizer, Some(&config), &weights);
+    assert!(!from_files.is_null());
+    let from_buffers = tiny_handle();
+
+    let (a, b) = (
+        embed(from_files, "hello world"),
+        embed(from_buffers, "hello world"),
+    );
+    assert_eq!(a.len(), b.len());
+    assert!(a.iter().zip(&b).all(|(a, b)| (a - b).abs() < 1e-6));
+    assert_eq!(model_info(from_files)["max_tokens"], 64);
+    unsafe {
+        embedder_free(from_files);
+        embedder_free(from_buffers);
+    }
+}
+
+#[test]
+fn init_from_files_fails_with_null() {
+    let tokenizer = model_file("bad_files", "tokenizer.json");
+    let config = model_file("bad_files", "config.json");
+    let weights = model_file("bad_files", "model.safetensors");
+    let missing = model_file("bad_files", "missing.safetensors");
+    let truncated = model_file("bad_files", "truncated.safetensors");
+
+    let fails = |model_name, tokenizer, config, weights| {
+        handle_from_files(model_name, tokenizer, config, weights).is_null()
+    };
+    assert!(fails("mini_lm_v2", &tokenizer, Some(&config), &missing));
+    assert!(fails("mini_lm_v2", &missing, Some(&config), &weights));
+    assert!(fails("mini_lm_v2", &tokenizer, Some(&missing), &weights));
+    assert!(fails("mini_lm_v2", &tokenizer, Some(&config), &truncated));
+    assert!(fails("unknown_model", &tokenizer, Some(&config), &weights));
+    // Weights from the wrong architecture
+    assert!(fails("mini_lm_v2", &config, Some(&config), &weights));
+
+    let name = CString::new("mini_lm_v2").unwrap();
+    let handle = unsafe {
+        embedder_init_from_files(
+            name.as_ptr(),
+            ptr::null(),
+            config.as_ptr(),
+            weights.as_ptr(),
+        )
+    };
+    assert!(handle.is_null());
+    let handle = unsafe {
+        embedder_init_from_files(
+            name.as_ptr(),
+            tokenizer.as_ptr(),
+            config.as_ptr(),
+            ptr::null(),
+        )
+    };
+    assert!(handle.is_null());
+}
+
+#[test]
+fn init_from_buffers_fails_with_null() {
+    let name = CString::new("mini_lm_v2").unwrap();
+    let weights = tiny_weights();
+    let init = |tokenizer: &[u8], weights: &[u8]| unsafe {
+        embedder_init_from_buffers(
+            name.as_ptr(),
+            tokenizer.as_ptr(),
+            tokenizer.len(),
+            CONFIG.as_ptr(),
+            CONFIG.len(),
+            weights.as_ptr(),
+            weights.len(),
+        )
+    };
+    assert!(init(TOKENIZER, &weights[..weights.len() / 2]).is_null());
+    assert!(init(TOKENIZER, &[]).is_null());
+    assert!(init(&TOKENIZER[..TOKENIZER.len() / 2], &weights).is_null());
+
+    let handle = unsafe {
+        embedder_init_from_buffers(
+            name.as_ptr(),
+            TOKENIZER.as_ptr(),
+            TOKENIZER.len(),
+            CONFIG.as_ptr(),
+            CONFIG.len() / 2,
+            weights.as_ptr(),
+            weights.len(),
+        )
+    };
+    assert!(handle.is_null());
+    let handle = unsafe {
+        embedder_init_from_buffers(
+            name.as_ptr(),
+            TOKENIZER.as_ptr(),
+            TOKENIZER.len(),
+            CONFIG.as_ptr(),
+            CONFIG.len(),
+            ptr::null(),
+            weights.len(),
+        )
+    };
+    assert!(handle.is_null());
+}
+
+#[test]
+fn batch_alloc_keeps_going_after_a_failing_item() {
+    let handle = tiny_handle();
+    let hello = CString::new("hello world").unwrap();
+    let other = CString::new("another text").unwrap();
+    let texts = [hello.as_ptr(), ptr::null(), other.as_ptr()];
+
+    let batch = unsafe { embedder_embed_batch_alloc(handle, texts.as_ptr(), texts.len()) };
+    assert!(!batch.is_null());
+    assert_eq!(unsafe { embedder_batch_count(batch) }, 3);
+    assert_eq!(unsafe { embedder_batch_dimension(batch) }, 32);
+
+    let statuses: Vec<_> = (0..3)
+        .map(|i| unsafe { embedder_batch_status(batch, i) })
+        .collect();
+    assert_eq!(
+        statuses,
+        [
+            EmbedderErrorCode::Success,
+            EmbedderErrorCode::NullPointer,
+            EmbedderErrorCode::Success
+        ]
+    );
+    assert!(last_error(handle).is_some());
+
+    let row = |i| {
+        let row = unsafe { embedder_batch_row(batch, i) };
+        assert!(!row.is_null());
+        unsafe { std::slice::from_raw_parts(row, 32) }.to_vec()
+    };
+    assert_eq!(row(0), embed(handle, "hello world"));
+    assert_eq!(row(2), embed(handle, "another text"));
+    assert!(unsafe { embedder_batch_row(batch, 1) }.is_null());
+
+    // Rows are contiguous and the failed one is zero-filled
+    let data = unsafe { std::slice::from_raw_parts(embedder_batch_data(batch), 3 * 32) };
+    assert_eq!(&data[..32], row(0).as_slice());
+    assert!(data[32..64].iter().all(|&x| x == 0.0));
+    assert_eq!(&data[64..], row(2).as_slice());
+
+    unsafe { embedder_batch_free(batch) };
+    unsafe { embedder_free(handle) };
+}
+
+#[test]
+fn batch_accessors_reject_bad_arguments() {
+    let handle = tiny_handle();
+    let text = CString::new("hello world").unwrap();
+    let texts = [text.as_ptr()];
+    let batch = unsafe { embedder_embed_batch_alloc(handle, texts.as_ptr(), 1) };
+
+    assert_eq!(
+        unsafe { embedder_batch_status(batch, 1) },
+        EmbedderErrorCode::InvalidArgument
+    );
+    assert!(unsafe { embedder_batch_row(batch, 1) }.is_null());
+
+    assert_eq!(
+        unsafe { embedder_batch_status(ptr::null(), 0) },
+        EmbedderErrorCode::NullPointer
+    );
+    assert_eq!(unsafe { embedder_batch_count(ptr::null()) }, 0);
+    assert_eq!(unsafe { embedder_batch_dimension(ptr::null()) }, 0);
+    assert!(unsafe { embedder_batch_row(ptr::null(), 0) }.is_null());
+    assert!(unsafe { embedder_batch_data(ptr::null()) }.is_null());
+    assert!(unsafe { embedder_embed_batch_alloc(handle, ptr::null(), 1) }.is_null());
+    unsafe { embedder_batch_free(ptr::null_mut()) };
+
+    unsafe { embedder_batch_free(batch) };
+    unsafe { embedder_free(handle) };
+}
+
+/// Statuses of every item of a batch, freeing it
+fn batch_statuses(batch: *mut EmbeddingBatch) -> Vec<EmbedderErrorCode> {
+    assert!(!batch.is_null());
+    let count = unsafe { embedder_batch_count(batch) };
+    let statuses = (0..count)
+        .map(|i| unsafe { embedder_batch_status(batch, i) })
+        .collect();
+    unsafe { embedder_batch_free(batch) };
+    statuses
+}
+
+#[test]
+fn sized_texts_embed_like_c_strings() {
+    let handle = tiny_handle();
+    let text = "héllo wörld";
+    let expected = embed(handle, text);
+    let mut output = [0.0f32; 32];
+    let mut size = 0;
+
+    let code = unsafe {
+        embedder_embed_utf8(
+            handle,
+            text.as_ptr(),
+            text.len(),
+            output.as_mut_ptr(),
+            output.len(),
+            &mut size,
+        )
+    };
+    assert_eq!(code, EmbedderErrorCode::Success);
+    assert_eq!(&output[..size], expected.as_slice());
+
+    let units: Vec<u16> = text.encode_utf16().collect();
+    let code = unsafe {
+        embedder_embed_utf16(
+            handle,
+            units.as_ptr(),
+            units.len(),
+            output.as_mut_ptr(),
+            output.len(),
+            &mut size,
+        )
+    };
+    assert_eq!(code, EmbedderErrorCode::Success);
+    assert_eq!(&output[..size], expected.as_slice());
+
+    // The length is explicit, so NUL is an ordinary character
+    let code = unsafe {
+        embedder_embed_utf8(
+            handle,
+            "a\0b".as_ptr(),
+            3,
+            output.as_mut_ptr(),
+            output.len(),
+            &mut size,
+        )
+    };
+    assert_eq!(code, EmbedderErrorCode::Success);
+    unsafe { embedder_free(handle) };
+}
+
+#[test]
+fn invalid_encodings_are_rejected() {
+    let handle = tiny_handle();
+    let mut output = [0.0f32; 32];
+    let mut size = 0;
+
+    let bytes = b"caf\xe9";
+    let code = unsafe {
+        embedder_embed_utf8(
+            handle,
+            bytes.as_ptr(),
+            bytes.len(),
+            output.as_mut_ptr(),
+            output.len(),
+            &mut size,
+        )
+    };
+    assert_eq!(code, EmbedderErrorCode::InvalidUtf8);
+    assert!(last_error(handle).unwrap().starts_with("Invalid UTF-8"));
+
+    let lone_surrogate = [0x0068, 0xd800, 0x0069];
+    let code = unsafe {
+        embedder_embed_utf16(
+            handle,
+            lone_surrogate.as_ptr(),
+            lone_surrogate.len(),
+            output.as_mut_ptr(),
+            output.len(),
+            &mut size,
+        )
+    };
+    assert_eq!(code, EmbedderErrorCode::InvalidUtf8);
+    assert!(last_error(handle).unwrap().starts_with("Invalid UTF-16"));
+
+    // Batches stop at the first invalid text
+    let texts = [b"valid".as_ptr(), bytes.as_ptr()];
+    let lens = [5, bytes.len()];
+    let mut buffer = [0.0f32; 64];
+    let (mut dim, mut written) = (0, 0);
+    let code = unsafe {
+        embedder_embed_batch_utf8(
+            handle,
+            texts.as_ptr(),
+            lens.as_ptr(),
+            2,
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            &mut dim,
+            &mut written,
+        )
+    };
+    assert_eq!(code, EmbedderErrorCode::InvalidUtf8);
+    assert!(last_error(handle).unwrap().contains("at index 1"));
+
+    let valid: Vec<u16> = "valid".encode_utf16().collect();
+    let texts = [lone_surrogate.as_ptr(), valid.as_ptr()];
+    let lens = [lone_surrogate.len(), valid.len()];
+    let code = unsafe {
+        embedder_embed_batch_utf16(
+            handle,
+            texts.as_ptr(),
+            lens.as_ptr(),
+            2,
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            &mut dim,
+            &mut written,
+        )
+    };
+    assert_eq!(code, EmbedderErrorCode::InvalidUtf8);
+    assert!(last_error(handle).unwrap().contains("at index 0"));
+    unsafe { embedder_free(handle) };
+}
+
+#[test]
+fn batch_alloc_reports_invalid_encodings_per_item() {
+    let handle = tiny_handle();
+    let ok = EmbedderErrorCode::Success;
+    let invalid = EmbedderErrorCode::InvalidUtf8;
+
+    let bytes: [&[u8]; 3] = [b"valid", b"caf\xe9", b""];
+    let texts: Vec<_> = bytes.iter().map(|text| text.as_ptr()).collect();
+    let lens: Vec<_> = bytes.iter().map(|text| text.len()).collect();
+    let batch =
+        unsafe { embedder_embed_batch_alloc_utf8(handle, texts.as_ptr(), lens.as_ptr(), 3) };
+    assert_eq!(batch_statuses(batch), [ok, invalid, ok]);
+
+    let valid: Vec<u16> = "valid".encode_utf16().collect();
+    let lone_surrogate = [0xdc00u16];
+    let texts = [valid.as_ptr(), lone_surrogate.as_ptr(), ptr::null()];
+    let lens = [valid.len(), 1, 4];
+    let batch =
+        unsafe { embedder_embed_batch_alloc_utf16(handle, texts.as_ptr(), lens.as_ptr(), 3) };
+    assert_eq!(
+        batch_statuses(batch),
+        [ok, invalid, EmbedderErrorCode::NullPointer]
+    );
+    assert!(
+        last_error(handle)
+            .unwrap()
+            .starts_with("Invalid UTF-16 at index 1")
+    );
+    unsafe { embedder_free(handle) };
+}
+
+/// Texts embedded so far, as reported by `on_progress`
+unsafe extern "C" fn record_progress(done: usize, _total: usize, user_data: *mut c_void) {
+    unsafe { *(user_data as *mut usize) = done };
+}
+
+/// Cancels once two texts are embedded
+unsafe extern "C" fn cancel_after_two(user_data: *mut c_void) -> i32 {
+    (unsafe { *(user_data as *const usize) } >= 2) as i32
+}
+
+#[test]
+fn cancelling_keeps_the_completed_embeddings() {
+    let handle = tiny_handle();
+    let texts: Vec<CString> = (0..5)
+        .map(|i| CString::new(format!("text number {i}")).unwrap())
+        .collect();
+    let pointers: Vec<_> = texts.iter().map(|text| text.as_ptr()).collect();
+    let mut done = 0usize;
+    let progress = EmbedderProgress {
+        on_progress: Some(record_progress),
+        is_cancelled: Some(cancel_after_two),
+        user_data: &mut done as *mut usize as *mut c_void,
+        ..Default::default()
+    };
+    let mut output = vec![0.0f32; 5 * 32];
+    let (mut dim, mut completed) = (0, 0);
+
+    let code = unsafe {
+        embedder_embed_batch_progress(
+            handle,
+            pointers.as_ptr(),
+            pointers.len(),
+            1,
+            output.as_mut_ptr(),
+            output.len(),
+            &mut dim,
+            &mut completed,
+            &progress,
+        )
+    };
+    assert_eq!(code, EmbedderErrorCode::Cancelled);
+    assert_eq!((dim, completed, done), (32, 2, 2));
+    assert!(
+        last_error(handle)
+            .unwrap()
+            .contains("cancelled after 2 of 5")
+    );
+    assert_eq!(output[..32], embed(handle, "text number 0"));
+    assert_eq!(output[32..64], embed(handle, "text number 1"));
+    assert!(output[64..].iter().all(|&x| x == 0.0));
+    unsafe { embedder_free(handle) };
+}
+
+/// Cancels before the first batch
+unsafe extern "C" fn cancel_always(_user_data: *mut c_void) -> i32 {
+    1
+}
+
+#[test]
+fn a_size_ending_mid_field_leaves_that_field_out() {
+    let handle = tiny_handle();
+    let text = CString::new("text number 0").unwrap();
+    let texts = [text.as_ptr()];
+    let mut progress = EmbedderProgress {
+        is_cancelled: Some(cancel_always),
+        ..Default::default()
+    };
+    // Half of the `is_cancelled` pointer, which must not be called through
+    progress.struct_size = offset_of!(EmbedderProgress, is_cancelled) + 4;
+    let mut output = [0.0f32; 32];
+    let (mut dim, mut completed) = (0, 0);
+
+    let code = unsafe {
+        embedder_embed_batch_progress(
+            handle,
+            texts.as_ptr(),
+            texts.len(),
+            0,
+            output.as_mut_ptr(),
+            output.len(),
+            &mut dim,
+            &mut completed,
+            &progress,
+        )
+    };
+    assert_eq!(code, EmbedderErrorCode::Success);
+    assert_eq!(completed, 1);
+    unsafe { embedder_free(handle) };
+
+    // embedder_options_init() does not write half of `model_name` either
+    let marker = c"marker";
+    let mut options = EmbedderOptions {
+        model_name: marker.as_ptr(),
+        ..Default::default()
+    };
+    options.struct_size = offset_of!(EmbedderOptions, model_name) + 4;
+    let code = unsafe { embedder_options_init(&mut options) };
+    assert_eq!(code, EmbedderErrorCode::Success);
+    assert_eq!(options.model_name, marker.as_ptr());
+}
+
+#[test]
+fn overflowing_sizes_are_invalid_arguments() {
+    let handle = tiny_handle();
+    let text = CString::new("hello").unwrap();
+    let texts = [text.as_ptr()];
+    let mut output = [0.0f32; 32];
+    let (mut dim, mut completed) = (0, 0);
+
+    // The sizes are checked before the texts are read
+    let code = unsafe {
+        embedder_embed_batch_progress(
+            handle,
+            texts.as_ptr(),
+            usize::MAX / 16,
+            0,
+            output.as_mut_ptr(),
+            output.len(),
+            &mut dim,
+            &mut completed,
+            ptr::null(),
+        )
+    };
+    assert_eq!(code, EmbedderErrorCode::InvalidArgument);
+    assert_eq!(completed, 0);
+    unsafe { embedder_free(handle) };
+
+    // The sizes are checked before the vectors are read as slices
+    let vector = [1.0f32, 0.0];
+    let mut scores = [0.0f32; 4];
+    let code = unsafe {
+        embedder_similarity_matrix(
+            vector.as_ptr(),
+            usize::MAX,
+            vector.as_ptr(),
+            2,
+            2,
+            EMBEDDER_SIMILARITY_DOT,
+            scores.as_mut_ptr(),
+            scores.len(),
+        )
+    };
+    assert_eq!(code, EmbedderErrorCode::InvalidArgument);
+    let code = unsafe {
+        embedder_similarity_matrix(
+            vector.as_ptr(),
+            2,
+            vector.as_ptr(),
+            1,
+            usize::MAX / 2 + 1,
+            EMBEDDER_SIMILARITY_DOT,
+            scores.as_mut_ptr(),
+            scores.len(),
+        )
+    };
+    assert_eq!(code, EmbedderErrorCode::InvalidArgument);
+
+    let (mut indices, mut count) = ([0usize; 2], 0);
+    let code = unsafe {
+        embedder_topk(
+            vector.as_ptr(),
+            vector.as_ptr(),
+            usize::MAX / 2 + 1,
+            2,
+            2,
+            EMBEDDER_SIMILARITY_DOT,
+            indices.as_mut_ptr(),
+            scores.as_mut_ptr(),
+            &mut count,
+        )
+    };
+    assert_eq!(code, EmbedderErrorCode::InvalidArgument);
+}
+
+/// JSON returned by `embedder_get_capabilities()`
+fn capabilities() -> Value {
+    let json = unsafe { CStr::from_ptr(embedder_get_capabilities()) };
+    serde_json::from_slice(json.to_bytes()).unwrap()
+}
+
+#[test]
+fn capabilities_list_the_registered_models() {
+    let before = embedder_get_capabilities();
+    register_model("capability_probe", PanickingLoader);
+
+    let models: Vec<String> = serde_json::from_value(capabilities()["models"].clone()).unwrap();
+    assert!(models.contains(&"mini_lm_v2".to_string()));
+    assert!(models.contains(&"capability_probe".to_string()));
+    assert!(models.len() <= registered_models().len());
+
+    // Strings returned earlier stay valid
+    let before = unsafe { CStr::from_ptr(before) };
+    assert!(serde_json::from_slice::<Value>(before.to_bytes()).is_ok());
+    assert_eq!(capabilities()["abi_version_major"], 1);
+}
+
+/// Model whose embedding of a text is the sum of its word embeddings
+struct WordEmbeddings {
+    embeddings: Embedding,
+    device: CandleDevice,
+}
+
+impl EmbedModel for WordEmbeddings {
+    fn device(&self) -> &CandleDevice {
+        &self.device
+    }
+
+    fn forward(
+        &self,
+        input_ids: &Tensor,
+        _token_type_ids: &Tensor,
+        _attention_mask: Option<&Tensor>,
+    ) -> rust_transformer::candle_core::Result<Tensor> {
+        self.embeddings.forward(input_ids)
+    }
+}
+
+/// Loads `WordEmbeddings` from a folder with the tiny BERT files, so handles
+/// can be opened by name with `embedder_init_with_options()`
+struct WordEmbeddingsLoader {
+    folder: PathBuf,
+}
+
+impl ModelLoader for WordEmbeddingsLoader {
+    fn model_id(&self) -> &str {
+        "example/ffi-word-embeddings"
+    }
+
+    fn local_path(&self) -> Option<PathBuf> {
+        Some(self.folder.clone())
+    }
+
+    fn load(&self, vb: VarBuilder, config: Option<&[u8]>) -> Result<LoadedModel, Error> {
+        let config: Value = serde_json::from_slice(config.expect("config.json"))?;
+        let dim = |key: &str| config[key].as_u64().unwrap() as usize;
+        let device = vb.device().clone();
+        let embeddings = embedding(
+            dim("vocab_size"),
+            dim("hidden_size"),
+            vb.pp("embeddings.word_embeddings"),
+        )?;
+        Ok(LoadedModel::new(
+            WordEmbeddings { embeddings, device },
+            dim("hidden_size"),
+            dim("max_position_embeddings"),
+        ))
+    }
+}
+
+/// Name of a registered model loading from a temporary folder
+fn folder_model() -> CString {
+    static REGISTERED: OnceLock<()> = OnceLock::new();
+    REGISTERED.get_or_init(|| {
+        let dir = std::env::temp_dir().join(format!(
+            "rust_transformer_ffi_options_{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("tokenizer.json"), TOKENIZER).unwrap();
+        std::fs::write(dir.join("config.json"), CONFIG).unwrap();
+        std::fs::write(dir.join("model.safetensors"), tiny_weights()).unwrap();
+        register_model("ffi_word_embeddings", WordEmbeddingsLoader { folder: dir });
+    });
+    CString::new("ffi_word_embeddings").unwrap()
+}
+
+fn model_info(handle: *mut EmbedderHandle) -> Value {
+    let info = unsafe { CStr::from_ptr(embedder_get_model_info(handle)) };
+    serde_json::from_slice(info.to_bytes()).unwrap()
+}
+
+#[test]
+fn options_from_an_older_header_get_defaults() {
+    let name = folder_model();
+    let mut options = EmbedderOptions {
+        model_name: name.as_ptr(),
+        normalize: 0,
+        // Beyond the older struct, so never read
+        max_tokens: 16,
+        pooling: 99,
+        ..Default::default()
+    };
+    options.struct_size = offset_of!(EmbedderOptions, max_tokens);
+
+    let handle = unsafe { embedder_init_with_options(&options) };
+    assert!(!handle.is_null());
+    assert_eq!(unsafe { embedder_get_max_tokens(handle) }, 64);
+    assert_eq!(model_info(handle)["normalize"], false);
+    assert_eq!(model_info(handle)["pooling"], "mean");
+    unsafe { embedder_free(handle) };
+}
+
+#[test]
+fn options_from_a_newer_header_are_accepted() {
+    /// `EmbedderOptions` with a field added by a later version
+    #[repr(C)]
+    struct NewerOptions {
+        options: EmbedderOptions,
+        added: u64,
+    }
+
+    let name = folder_model();
+    let mut newer = NewerOptions {
+        options: EmbedderOptions {
+            model_name: name.as_ptr(),
+            max_tokens: 16,
+            pooling: EMBEDDER_POOLING_CLS,
+            ..Default::default()
+        },
+        added: u64::MAX,
+    };
+    newer.options.struct_size = size_of::<NewerOptions>();
+
+    let handle = unsafe { embedder_init_with_options(&newer.options) };
+    assert!(!handle.is_null());
+    assert_eq!(unsafe { embedder_get_max_tokens(handle) }, 16);
+    assert_eq!(model_info(handle)["pooling"], "cls");
+    unsafe { embedder_free(handle) };
+
+    // embedder_options_init() keeps the caller's size and leaves the rest alone
+    newer.options.max_tokens = 7;
+    let code = unsafe { embedder_options_init(&mut newer.options) };
+    assert_eq!(code, EmbedderErrorCode::Success);
+    assert_eq!(newer.options.struct_size, size_of::<NewerOptions>());
+    assert_eq!(newer.options.max_tokens, 0);
+    assert_eq!(newer.added, u64::MAX);
+}
+
+#[test]
+fn options_too_small_for_their_size_are_rejected() {
+    let name = folder_model();
+    let mut options = EmbedderOptions {
+        model_name: name.as_ptr(),
+        ..Default::default()
+    };
+    options.struct_size = size_of::<usize>() - 1;
+    assert!(unsafe { embedder_init_with_options(&options) }.is_null());
+    assert_eq!(
+        unsafe { embedder_options_init(&mut options) },
+        EmbedderErrorCode::InvalidArgument
+    );
+
+    // An older caller's struct is only filled up to its size
+    options.struct_size = offset_of!(EmbedderOptions, normalize);
+    options.normalize = 5;
+    let code = unsafe { embedder_options_init(&mut options) };
+    assert_eq!(code, EmbedderErrorCode::Success);
+    assert!(options.model_name.is_null());
+    assert_eq!(options.normalize, 5);
+}
+
+/// Embedding of `image` through `embedder_embed_image()`, or its error code
+fn embed_image(handle: *mut EmbedderHandle, image: &[u8]) -> Result<Vec<f32>, EmbedderErrorCode> {
+    let mut output = vec![0.0f32; 32];
+    let mut size = 0;
+    let code = unsafe {
+        embedder_embed_image(
+            handle,
+            image.as_ptr(),
+            image.len(),
+            output.as_mut_ptr(),
+            output.len(),
+            &mut size,
+        )
+    };
+    if code != EmbedderErrorCode::Success {
+        return Err(code);
+    }
+    output.truncate(size);
+    Ok(output)
+}
+
+#[test]
+fn embeds_images_through_the_c_abi() {
+    let path = |file: &str| CString::new(fixture(file).to_str().unwrap()).unwrap();
+    let tokenizer = CString::new(fixture_tokenizer("tiny_clip").to_str().unwrap()).unwrap();
+    let clip = handle_from_files(
+        "clip",
+        &tokenizer,
+        Some(&path("tiny_clip/config.json")),
+        &path("tiny_clip/model.safetensors"),
+    );
+    assert!(!clip.is_null());
+
+    let references = &reference_json("tiny_clip")["images"];
+    for name in ["square.png", "wide.png"] {
+        let reference = references
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|reference| reference["image"] == name)
+            .unwrap();
+        let expected: Vec<f32> = serde_json::from_value(reference["embedding"].clone()).unwrap();
+        let image = std::fs::read(fixture(&format!("tiny_clip/{name}"))).unwrap();
+        let embedding = embed_image(clip, &image).unwrap();
+        assert_close(&embedding, &expected, 1e-4, name);
+        assert!(last_error(clip).is_none());
+    }
+
+    let image = std::fs::read(fixture("tiny_clip/square.png")).unwrap();
+    let (mut output, mut size) = ([0.0f32; 8], 0);
+    let code = unsafe {
+        embedder_embed_image(
+            clip,
+            image.as_ptr(),
+            image.len(),
+            output.as_mut_ptr(),
+            8,
+            &mut size,
+        )
+    };
+    assert_eq!(code, EmbedderErrorCode::BufferTooSmall);
+    assert_eq!(size, 16);
+    assert_eq!(
+        embed_image(clip, b"not an image"),
+        Err(EmbedderErrorCode::EmbeddingFailed)
+    );
+    assert!(last_error(clip).is_some());
+    let code =
+        unsafe { embedder_embed_image(clip, ptr::null(), 0, output.as_mut_ptr(), 8, &mut size) };
+    assert_eq!(code, EmbedderErrorCode::NullPointer);
+    unsafe { embedder_free(clip) };
+
+    // Text-only models do not embed images
+    let text_only = tiny_handle();
+    assert_eq!(
+        embed_image(text_only, &image),
+        Err(EmbedderErrorCode::InvalidArgument)
+    );
+    let error = last_error(text_only).unwrap();
+    assert!(error.contains("does not embed images"), "{error}");
+    unsafe { embedder_free(text_only) };
+}
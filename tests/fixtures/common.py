@@ -0,0 +1,112 @@
+"""
+Helpers shared by the generate.py scripts of the tiny_* fixtures: the
+tiny_bert vocabulary and tokenization of the test texts, the plain-Python
+tensor operations of their forward passes and the safetensors and PNG
+writers. Only the standard library is used. The scripts import it with
+
+    sys.path.insert(0, str(Path(__file__).resolve().parent.parent))
+    from common import ...
+"""
+
+import json
+import math
+import struct
+import sys
+import unicodedata
+import zlib
+from array import array
+
+
+def load_vocab(folder):
+    """Vocabulary of tests/fixtures/tiny_bert, next to the fixture `folder`"""
+    tokenizer = json.loads((folder.parent / "tiny_bert" / "tokenizer.json").read_text())
+    return tokenizer["model"]["vocab"]
+
+
+def words(text, vocab):
+    """Token IDs the tiny_bert tokenizer produces for a text without special
+    tokens; words missing from the vocabulary become [UNK]"""
+    ids = []
+    for word in text.lower().split():
+        # BertPreTokenizer splits punctuation off into separate tokens
+        token = ""
+        for c in word:
+            if unicodedata.category(c).startswith("P"):
+                if token:
+                    ids.append(vocab.get(token, vocab["[UNK]"]))
+                ids.append(vocab.get(c, vocab["[UNK]"]))
+                token = ""
+            else:
+                token += c
+        if token:
+            ids.append(vocab.get(token, vocab["[UNK]"]))
+    return ids
+
+
+def tokenize(text, vocab):
+    """Token IDs of `[CLS] text [SEP]`"""
+    return [vocab["[CLS]"]] + words(text, vocab) + [vocab["[SEP]"]]
+
+
+def write_safetensors(path, tensors, dtypes=None):
+    """Write `tensors`, a dict of name -> (shape, values), stored as F32
+    unless `dtypes` gives another dtype ("I64") for the name"""
+    header = {}
+    data = bytearray()
+    for name, (shape, values) in tensors.items():
+        dtype = (dtypes or {}).get(name, "F32")
+        values = array({"F32": "f", "I64": "q"}[dtype], values)
+        if sys.byteorder != "little":
+            values.byteswap()
+        start = len(data)
+        data += values.tobytes()
+        header[name] = {"dtype": dtype, "shape": list(shape), "data_offsets": [start, len(data)]}
+
+    header_bytes = json.dumps(header).encode("utf-8")
+    header_bytes += b" " * (-len(header_bytes) % 8)
+    with open(path, "wb") as f:
+        f.write(struct.pack("<Q", len(header_bytes)))
+        f.write(header_bytes)
+        f.write(data)
+
+
+def write_png(path, pixels):
+    """Write rows of (r, g, b) pixels as an 8-bit RGB PNG"""
+
+    def chunk(kind, data):
+        return struct.pack(">I", len(data)) + kind + data + struct.pack(">I", zlib.crc32(kind + data))
+
+    height, width = len(pixels), len(pixels[0])
+    # Filter type 0 (none) before every row
+    raw = b"".join(b"\x00" + bytes(v for pixel in row for v in pixel) for row in pixels)
+    with open(path, "wb") as f:
+        f.write(b"\x89PNG\r\n\x1a\n")
+        f.write(chunk(b"IHDR", struct.pack(">IIBBBBB", width, height, 8, 2, 0, 0, 0)))
+        f.write(chunk(b"IDAT", zlib.compress(raw)))
+        f.write(chunk(b"IEND", b""))
+
+
+def matrix(weights, name):
+    (rows, cols), values = weights[name]
+    return [list(values[r * cols:(r + 1) * cols]) for r in range(rows)]
+
+
+def vector(weights, name):
+    return list(weights[name][1])
+
+
+def add(x, y):
+    return [[a + b for a, b in zip(rx, ry)] for rx, ry in zip(x, y)]
+
+
+def gelu(v):
+    return 0.5 * v * (1.0 + math.erf(v / math.sqrt(2.0)))
+
+
+def silu(v):
+    return v / (1.0 + math.exp(-v))
+
+
+def normalize(v):
+    norm = math.sqrt(sum(x * x for x in v))
+    return [x / norm for x in v]
@@ -0,0 +1,280 @@
+"""
+Writes the tiny CLIP fixture used by tests/clip.rs: config.json, randomly
+initialized model.safetensors, two PNG images, and reference.json with the
+token IDs and embeddings of a few texts and of the images. The tokenizer is
+the WordPiece one of tests/fixtures/tiny_bert, whose trailing [SEP] stands in
+for the end-of-text token.
+
+The reference embeddings come from the plain-Python forward pass below, which
+follows HuggingFace's CLIPModel: pre-norm transformer layers with quick GELU,
+causal attention and the end-of-text token for texts, the class token for
+images, then the projections and L2 normalization. The images are preprocessed
+like CLIPImageProcessor; square.png already has the input size and wide.png
+the input height, so neither is resampled and wide.png is only center-cropped.
+Only the standard library is used:
+
+    python tests/fixtures/tiny_clip/generate.py
+"""
+
+import json
+import math
+import random
+import sys
+from array import array
+from pathlib import Path
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent))
+from common import add, load_vocab, matrix, normalize, tokenize, vector, write_png, write_safetensors  # noqa: E402
+
+TEXT_HIDDEN_SIZE = 32
+VISION_HIDDEN_SIZE = 24
+NUM_LAYERS = 2
+NUM_HEADS = 4
+TEXT_INTERMEDIATE_SIZE = 48
+VISION_INTERMEDIATE_SIZE = 40
+MAX_POSITIONS = 64
+PROJECTION_DIM = 16
+IMAGE_SIZE = 16
+PATCH_SIZE = 8
+LAYER_NORM_EPS = 1e-5
+
+MEAN = [0.48145466, 0.4578275, 0.40821073]
+STD = [0.26862954, 0.26130258, 0.27577711]
+
+TEXTS = [
+    "hello world",
+    "the cat sat on the mat",
+    "rust is a fast text embedding model.",
+    "the dogs sat on the mat and the cats are in the rain!",
+]
+# Name -> (width, height)
+IMAGES = {"square.png": (16, 16), "wide.png": (24, 16)}
+
+
+def config(vocab):
+    return {
+        "architectures": ["CLIPModel"],
+        "model_type": "clip",
+        "projection_dim": PROJECTION_DIM,
+        "text_config": {
+            "vocab_size": max(vocab.values()) + 1,
+            "hidden_size": TEXT_HIDDEN_SIZE,
+            "intermediate_size": TEXT_INTERMEDIATE_SIZE,
+            "num_hidden_layers": NUM_LAYERS,
+            "num_attention_heads": NUM_HEADS,
+            "max_position_embeddings": MAX_POSITIONS,
+            "hidden_act": "quick_gelu",
+            "layer_norm_eps": LAYER_NORM_EPS,
+        },
+        "vision_config": {
+            "hidden_size": VISION_HIDDEN_SIZE,
+            "intermediate_size": VISION_INTERMEDIATE_SIZE,
+            "num_hidden_layers": NUM_LAYERS,
+            "num_attention_heads": NUM_HEADS,
+            "num_channels": 3,
+            "image_size": IMAGE_SIZE,
+            "patch_size": PATCH_SIZE,
+            "hidden_act": "quick_gelu",
+            "layer_norm_eps": LAYER_NORM_EPS,
+        },
+        "torch_dtype": "float32",
+    }
+
+
+def encoder_tensors(prefix, hidden, intermediate):
+    shapes = {}
+    for i in range(NUM_LAYERS):
+        layer = f"{prefix}.encoder.layers.{i}"
+        for name in ("q_proj", "k_proj", "v_proj", "out_proj"):
+            shapes[f"{layer}.self_attn.{name}.weight"] = (hidden, hidden)
+            shapes[f"{layer}.self_attn.{name}.bias"] = (hidden,)
+        for norm in ("layer_norm1", "layer_norm2"):
+            shapes[f"{layer}.{norm}.weight"] = (hidden,)
+            shapes[f"{layer}.{norm}.bias"] = (hidden,)
+        shapes[f"{layer}.mlp.fc1.weight"] = (intermediate, hidden)
+        shapes[f"{layer}.mlp.fc1.bias"] = (intermediate,)
+        shapes[f"{layer}.mlp.fc2.weight"] = (hidden, intermediate)
+        shapes[f"{layer}.mlp.fc2.bias"] = (hidden,)
+    return shapes
+
+
+def tensors(vocab_size):
+    """Tensor name -> shape, as expected by CLIPModel"""
+    num_positions = (IMAGE_SIZE // PATCH_SIZE) ** 2 + 1
+    shapes = {
+        "text_model.embeddings.token_embedding.weight": (vocab_size, TEXT_HIDDEN_SIZE),
+        "text_model.embeddings.position_embedding.weight": (MAX_POSITIONS, TEXT_HIDDEN_SIZE),
+        "text_model.final_layer_norm.weight": (TEXT_HIDDEN_SIZE,),
+        "text_model.final_layer_norm.bias": (TEXT_HIDDEN_SIZE,),
+        "text_projection.weight": (PROJECTION_DIM, TEXT_HIDDEN_SIZE),
+        "vision_model.embeddings.class_embedding": (VISION_HIDDEN_SIZE,),
+        "vision_model.embeddings.patch_embedding.weight": (VISION_HIDDEN_SIZE, 3, PATCH_SIZE, PATCH_SIZE),
+        "vision_model.embeddings.position_embedding.weight": (num_positions, VISION_HIDDEN_SIZE),
+        "vision_model.pre_layrnorm.weight": (VISION_HIDDEN_SIZE,),
+        "vision_model.pre_layrnorm.bias": (VISION_HIDDEN_SIZE,),
+        "vision_model.post_layernorm.weight": (VISION_HIDDEN_SIZE,),
+        "vision_model.post_layernorm.bias": (VISION_HIDDEN_SIZE,),
+        "visual_projection.weight": (PROJECTION_DIM, VISION_HIDDEN_SIZE),
+    }
+    shapes.update(encoder_tensors("text_model", TEXT_HIDDEN_SIZE, TEXT_INTERMEDIATE_SIZE))
+    shapes.update(encoder_tensors("vision_model", VISION_HIDDEN_SIZE, VISION_INTERMEDIATE_SIZE))
+    return shapes
+
+
+def random_weights(shapes, seed=0):
+    """Name -> (shape, float32 values); every tensor is random so that each
+    one affects the reference embeddings"""
+    rng = random.Random(seed)
+    weights = {}
+    for name, shape in shapes.items():
+        count = math.prod(shape)
+        if "norm" in name and name.endswith("weight"):
+            values = [1.0 + rng.gauss(0.0, 0.1) for _ in range(count)]
+        elif name.endswith("bias"):
+            values = [rng.gauss(0.0, 0.05) for _ in range(count)]
+        elif "patch_embedding" in name:
+            values = [rng.gauss(0.0, 0.05) for _ in range(count)]
+        else:
+            values = [rng.gauss(0.0, 0.2) for _ in range(count)]
+        weights[name] = (shape, array("f", values))
+    return weights
+
+
+def random_image(width, height, seed):
+    """Rows of (r, g, b) pixels: a gradient with noise"""
+    rng = random.Random(seed)
+    return [
+        [
+            tuple(min(255, max(0, (x * 9 + y * 5 + c * 70) % 256 + rng.randint(-30, 30))) for c in range(3))
+            for x in range(width)
+        ]
+        for y in range(height)
+    ]
+
+
+def pixel_values(pixels):
+    """CLIPImageProcessor without resampling: center crop, rescale, normalize;
+    (channel, y, x) nested lists"""
+    height, width = len(pixels), len(pixels[0])
+    top, left = (height - IMAGE_SIZE) // 2, (width - IMAGE_SIZE) // 2
+    return [
+        [
+            [(pixels[top + y][left + x][c] / 255.0 - MEAN[c]) / STD[c] for x in range(IMAGE_SIZE)]
+            for y in range(IMAGE_SIZE)
+        ]
+        for c in range(3)
+    ]
+
+
+# --- Reference forward pass, one sequence or image at a time ---
+
+
+def linear(x, weights, prefix, bias=True):
+    w = matrix(weights, prefix + ".weight")
+    b = vector(weights, prefix + ".bias") if bias else [0.0] * len(w)
+    return [[sum(xi * wi for xi, wi in zip(row, w_row)) + bj for w_row, bj in zip(w, b)] for row in x]
+
+
+def layer_norm(x, weights, prefix):
+    gamma, beta = vector(weights, prefix + ".weight"), vector(weights, prefix + ".bias")
+    out = []
+    for row in x:
+        mean = sum(row) / len(row)
+        var = sum((v - mean) ** 2 for v in row) / len(row)
+        out.append([(v - mean) / math.sqrt(var + LAYER_NORM_EPS) * g + b for v, g, b in zip(row, gamma, beta)])
+    return out
+
+
+def quick_gelu(v):
+    return v / (1.0 + math.exp(-1.702 * v))
+
+
+def attention(x, weights, prefix, causal):
+    q = linear(x, weights, prefix + ".q_proj")
+    k = linear(x, weights, prefix + ".k_proj")
+    v = linear(x, weights, prefix + ".v_proj")
+    hidden = len(x[0])
+    head_dim = hidden // NUM_HEADS
+    context = [[0.0] * hidden for _ in x]
+    for h in range(NUM_HEADS):
+        cols = slice(h * head_dim, (h + 1) * head_dim)
+        qh, kh, vh = [row[cols] for row in q], [row[cols] for row in k], [row[cols] for row in v]
+        for i in range(len(x)):
+            # Causal for texts: tokens only see themselves and earlier tokens
+            seen = range(i + 1) if causal else range(len(x))
+            scores = [sum(a * b for a, b in zip(qh[i], kh[j])) / math.sqrt(head_dim) for j in seen]
+            top = max(scores)
+            exp = [math.exp(s - top) for s in scores]
+            total = sum(exp)
+            for c in range(head_dim):
+                context[i][h * head_dim + c] = sum(e / total * vh[j][c] for j, e in zip(seen, exp))
+    return linear(context, weights, prefix + ".out_proj")
+
+
+def mlp(x, weights, prefix):
+    hidden = [[quick_gelu(v) for v in row] for row in linear(x, weights, prefix + ".fc1")]
+    return linear(hidden, weights, prefix + ".fc2")
+
+
+def encoder(x, weights, prefix, causal):
+    for i in range(NUM_LAYERS):
+        layer = f"{prefix}.encoder.layers.{i}"
+        x = add(attention(layer_norm(x, weights, f"{layer}.layer_norm1"), weights, f"{layer}.self_attn", causal), x)
+        x = add(mlp(layer_norm(x, weights, f"{layer}.layer_norm2"), weights, f"{layer}.mlp"), x)
+    return x
+
+
+def embed_text(ids, weights):
+    tokens = matrix(weights, "text_model.embeddings.token_embedding.weight")
+    positions = matrix(weights, "text_model.embeddings.position_embedding.weight")
+    x = [add([tokens[token]], [positions[i]])[0] for i, token in enumerate(ids)]
+    x = layer_norm(encoder(x, weights, "text_model", causal=True), weights, "text_model.final_layer_norm")
+    # The end-of-text token, last in the sequence
+    return normalize(linear(x[-1:], weights, "text_projection", bias=False)[0])
+
+
+def embed_image(values, weights):
+    (out, channels, patch_size, _), kernel = weights["vision_model.embeddings.patch_embedding.weight"]
+    per_side = IMAGE_SIZE // PATCH_SIZE
+    x = [vector(weights, "vision_model.embeddings.class_embedding")]
+    # Patches in row-major order, each the convolution with stride PATCH_SIZE
+    for py in range(per_side):
+        for px in range(per_side):
+            patch = [
+                values[c][py * patch_size + ky][px * patch_size + kx]
+                for c in range(channels)
+                for ky in range(patch_size)
+                for kx in range(patch_size)
+            ]
+            x.append([sum(p * kernel[o * len(patch) + i] for i, p in enumerate(patch)) for o in range(out)])
+    x = add(x, matrix(weights, "vision_model.embeddings.position_embedding.weight"))
+    x = layer_norm(x, weights, "vision_model.pre_layrnorm")
+    x = encoder(x, weights, "vision_model", causal=False)
+    pooled = layer_norm(x[:1], weights, "vision_model.post_layernorm")
+    return normalize(linear(pooled, weights, "visual_projection", bias=False)[0])
+
+
+def main():
+    folder = Path(__file__).parent
+    vocab = load_vocab(folder)
+    model_config = config(vocab)
+    weights = random_weights(tensors(model_config["text_config"]["vocab_size"]))
+    (folder / "config.json").write_text(json.dumps(model_config, indent=2) + "\n")
+    write_safetensors(folder / "model.safetensors", weights)
+
+    texts = []
+    for text in TEXTS:
+        ids = tokenize(text, vocab)
+        texts.append({"text": text, "ids": ids, "embedding": [round(v, 7) for v in embed_text(ids, weights)]})
+    images = []
+    for seed, (name, (width, height)) in enumerate(IMAGES.items()):
+        pixels = random_image(width, height, seed)
+        write_png(folder / name, pixels)
+        embedding = embed_image(pixel_values(pixels), weights)
+        images.append({"image": name, "embedding": [round(v, 7) for v in embedding]})
+    reference = {"texts": texts, "images": images}
+    (folder / "reference.json").write_text(json.dumps(reference, indent=2) + "\n")
+
+
+if __name__ == "__main__":
+    main()
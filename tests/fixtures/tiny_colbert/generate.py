@@ -0,0 +1,226 @@
+"""
+Writes the tiny ColBERT fixture used by tests/multi_vector.rs: config.json,
+randomly initialized model.safetensors, tokenizer.json, and reference.json with
+the token IDs of a few texts and their token vectors as a query and as a
+document. The tokenizer is the WordPiece one of tests/fixtures/tiny_bert with
+ColBERT's [unused0] and [unused1] marker tokens added.
+
+The reference vectors come from the plain-Python forward pass below, which
+follows ColBERT's HF_ColBERT: the BERT encoder (absolute positions, token type
+embeddings, post-LayerNorm layers, exact GELU), then the linear projection
+without bias to PROJECTION_SIZE dimensions, and every vector is L2-normalized.
+Like ColBERT's QueryTokenizer, a query is `[CLS] [unused0] text [SEP]` padded
+with [MASK] to QUERY_MAX_TOKENS; the [MASK] tokens are not attended to, but
+every token keeps its vector. Like its DocTokenizer, a document is
+`[CLS] [unused1] text [SEP]`, and tokens that are a single punctuation
+character are left out. Only the standard library is used:
+
+    python tests/fixtures/tiny_colbert/generate.py
+"""
+
+import json
+import math
+import random
+import string
+import sys
+from array import array
+from pathlib import Path
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent))
+from common import add, gelu, matrix, tokenize, vector, write_safetensors  # noqa: E402
+
+HIDDEN_SIZE = 32
+NUM_LAYERS = 2
+NUM_HEADS = 4
+INTERMEDIATE_SIZE = 64
+MAX_POSITIONS = 64
+LAYER_NORM_EPS = 1e-12
+PROJECTION_SIZE = 16
+# colbertv2.0's query_maxlen
+QUERY_MAX_TOKENS = 32
+QUERY_MARKER = "[unused0]"
+DOCUMENT_MARKER = "[unused1]"
+
+TEXTS = [
+    "hello world",
+    "is rust fast?",
+    "rust is a fast text embedding model.",
+    "the cat sat on the mat",
+]
+
+
+def config(vocab):
+    return {
+        "architectures": ["HF_ColBERT"],
+        "model_type": "bert",
+        "vocab_size": max(vocab.values()) + 1,
+        "hidden_size": HIDDEN_SIZE,
+        "num_hidden_layers": NUM_LAYERS,
+        "num_attention_heads": NUM_HEADS,
+        "intermediate_size": INTERMEDIATE_SIZE,
+        "hidden_act": "gelu",
+        "hidden_dropout_prob": 0.1,
+        "max_position_embeddings": MAX_POSITIONS,
+        "type_vocab_size": 2,
+        "initializer_range": 0.02,
+        "layer_norm_eps": LAYER_NORM_EPS,
+        "pad_token_id": 0,
+        "position_embedding_type": "absolute",
+    }
+
+
+def tensors(vocab_size):
+    """Tensor name -> shape, as saved from HF_ColBERT"""
+    shapes = {
+        "bert.embeddings.word_embeddings.weight": (vocab_size, HIDDEN_SIZE),
+        "bert.embeddings.position_embeddings.weight": (MAX_POSITIONS, HIDDEN_SIZE),
+        "bert.embeddings.token_type_embeddings.weight": (2, HIDDEN_SIZE),
+        "bert.embeddings.LayerNorm.weight": (HIDDEN_SIZE,),
+        "bert.embeddings.LayerNorm.bias": (HIDDEN_SIZE,),
+    }
+    for i in range(NUM_LAYERS):
+        layer = f"bert.encoder.layer.{i}"
+        for name in ("query", "key", "value"):
+            shapes[f"{layer}.attention.self.{name}.weight"] = (HIDDEN_SIZE, HIDDEN_SIZE)
+            shapes[f"{layer}.attention.self.{name}.bias"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.attention.output.dense.weight"] = (HIDDEN_SIZE, HIDDEN_SIZE)
+        shapes[f"{layer}.attention.output.dense.bias"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.attention.output.LayerNorm.weight"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.attention.output.LayerNorm.bias"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.intermediate.dense.weight"] = (INTERMEDIATE_SIZE, HIDDEN_SIZE)
+        shapes[f"{layer}.intermediate.dense.bias"] = (INTERMEDIATE_SIZE,)
+        shapes[f"{layer}.output.dense.weight"] = (HIDDEN_SIZE, INTERMEDIATE_SIZE)
+        shapes[f"{layer}.output.dense.bias"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.output.LayerNorm.weight"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.output.LayerNorm.bias"] = (HIDDEN_SIZE,)
+    shapes["linear.weight"] = (PROJECTION_SIZE, HIDDEN_SIZE)
+    return shapes
+
+
+def random_weights(shapes, seed=0):
+    """Name -> (shape, float32 values); every tensor is random so that each
+    one affects the reference vectors"""
+    rng = random.Random(seed)
+    weights = {}
+    for name, shape in shapes.items():
+        count = math.prod(shape)
+        if "LayerNorm" in name:
+            if name.endswith("weight"):
+                values = [1.0 + rng.gauss(0.0, 0.1) for _ in range(count)]
+            else:
+                values = [rng.gauss(0.0, 0.05) for _ in range(count)]
+        else:
+            values = [rng.gauss(0.0, 0.2) for _ in range(count)]
+        weights[name] = (shape, array("f", values))
+    return weights
+
+
+# --- Reference forward pass, one text without padding ---
+
+
+def linear(x, weights, prefix):
+    w, b = matrix(weights, prefix + ".weight"), vector(weights, prefix + ".bias")
+    return [[sum(xi * wi for xi, wi in zip(row, w_row)) + bj for w_row, bj in zip(w, b)] for row in x]
+
+
+def layer_norm(x, weights, prefix):
+    gamma, beta = vector(weights, prefix + ".weight"), vector(weights, prefix + ".bias")
+    out = []
+    for row in x:
+        mean = sum(row) / len(row)
+        var = sum((v - mean) ** 2 for v in row) / len(row)
+        out.append([(v - mean) / math.sqrt(var + LAYER_NORM_EPS) * g + b for v, g, b in zip(row, gamma, beta)])
+    return out
+
+
+def attention(x, mask, weights, prefix):
+    q = linear(x, weights, prefix + ".self.query")
+    k = linear(x, weights, prefix + ".self.key")
+    v = linear(x, weights, prefix + ".self.value")
+    head = HIDDEN_SIZE // NUM_HEADS
+    context = [[0.0] * HIDDEN_SIZE for _ in x]
+    for h in range(NUM_HEADS):
+        cols = range(h * head, (h + 1) * head)
+        for i in range(len(x)):
+            scores = [sum(q[i][c] * k[j][c] for c in cols) / math.sqrt(head) for j in range(len(x))]
+            top = max(s for s, m in zip(scores, mask) if m)
+            exp = [math.exp(s - top) if m else 0.0 for s, m in zip(scores, mask)]
+            total = sum(exp)
+            for c in cols:
+                context[i][c] = sum(e / total * v[j][c] for j, e in enumerate(exp))
+    out = linear(context, weights, prefix + ".output.dense")
+    return layer_norm(add(out, x), weights, prefix + ".output.LayerNorm")
+
+
+def forward(ids, mask, weights):
+    word = matrix(weights, "bert.embeddings.word_embeddings.weight")
+    position = matrix(weights, "bert.embeddings.position_embeddings.weight")
+    token_type = matrix(weights, "bert.embeddings.token_type_embeddings.weight")
+    x = [
+        [w + p + t for w, p, t in zip(word[token], position[i], token_type[type_id])]
+        for i, (token, type_id) in enumerate(zip(ids, [0] * len(ids)))
+    ]
+    x = layer_norm(x, weights, "bert.embeddings.LayerNorm")
+    for i in range(NUM_LAYERS):
+        layer = f"bert.encoder.layer.{i}"
+        x = attention(x, mask, weights, f"{layer}.attention")
+        hidden = [[gelu(v) for v in row] for row in linear(x, weights, f"{layer}.intermediate.dense")]
+        x = layer_norm(add(linear(hidden, weights, f"{layer}.output.dense"), x), weights, f"{layer}.output.LayerNorm")
+    return x
+
+
+def token_vectors(ids, mask, weights, skiplist):
+    """Normalized projected vectors of the tokens not in the skiplist; tokens
+    with a 0 in `mask` are not attended to"""
+    hidden = [row for row, token in zip(forward(ids, mask, weights), ids) if token not in skiplist]
+    projection = matrix(weights, "linear.weight")
+    vectors = []
+    for row in hidden:
+        vector = [sum(h * w for h, w in zip(row, w_row)) for w_row in projection]
+        norm = math.sqrt(sum(v * v for v in vector))
+        vectors.append([round(v / norm, 7) for v in vector])
+    return vectors
+
+
+def write_tokenizer(folder):
+    """tiny_bert's tokenizer.json with the marker tokens appended to the
+    vocabulary; returns the vocabulary"""
+    tokenizer = json.loads((folder.parent / "tiny_bert" / "tokenizer.json").read_text())
+    vocab = tokenizer["model"]["vocab"]
+    for marker in (QUERY_MARKER, DOCUMENT_MARKER):
+        vocab[marker] = max(vocab.values()) + 1
+    (folder / "tokenizer.json").write_text(json.dumps(tokenizer))
+    return vocab
+
+
+def main():
+    folder = Path(__file__).parent
+    vocab = write_tokenizer(folder)
+    skiplist = {vocab[c] for c in string.punctuation if c in vocab}
+    model_config = config(vocab)
+    weights = random_weights(tensors(model_config["vocab_size"]))
+    (folder / "config.json").write_text(json.dumps(model_config, indent=2) + "\n")
+    write_safetensors(folder / "model.safetensors", weights)
+
+    reference = []
+    for text in TEXTS:
+        ids = tokenize(text, vocab)
+        query = ids[:1] + [vocab[QUERY_MARKER]] + ids[1:]
+        mask = [1] * len(query) + [0] * (QUERY_MAX_TOKENS - len(query))
+        query += [vocab["[MASK]"]] * (QUERY_MAX_TOKENS - len(query))
+        document = ids[:1] + [vocab[DOCUMENT_MARKER]] + ids[1:]
+        reference.append(
+            {
+                "text": text,
+                "ids": ids,
+                "query_ids": query,
+                "query_vectors": token_vectors(query, mask, weights, set()),
+                "document_ids": document,
+                "document_vectors": token_vectors(document, [1] * len(document), weights, skiplist),
+            }
+        )
+    (folder / "reference.json").write_text(json.dumps(reference, indent=2) + "\n")
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,204 @@
+"""
+Writes the tiny cross-encoder fixture used by tests/rerank.rs: config.json,
+randomly initialized model.safetensors, and reference.json with the token IDs,
+token type IDs and scores of a few query–passage pairs. The tokenizer is the
+WordPiece one of tests/fixtures/tiny_bert.
+
+The reference scores come from the plain-Python forward pass below, which
+follows HuggingFace's BertForSequenceClassification with one label: the BERT
+encoder (absolute positions, token type embeddings, post-LayerNorm layers,
+exact GELU) over `[CLS] query [SEP] passage [SEP]`, the pooler (dense + tanh
+on the [CLS] token) and the classifier, whose output logit is the score.
+Only the standard library is used:
+
+    python tests/fixtures/tiny_cross_encoder/generate.py
+"""
+
+import json
+import math
+import random
+import sys
+from array import array
+from pathlib import Path
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent))
+from common import add, gelu, load_vocab, matrix, vector, words, write_safetensors  # noqa: E402
+
+HIDDEN_SIZE = 32
+NUM_LAYERS = 2
+NUM_HEADS = 4
+INTERMEDIATE_SIZE = 64
+MAX_POSITIONS = 64
+LAYER_NORM_EPS = 1e-12
+
+PAIRS = [
+    ("is rust fast?", "rust is a fast text embedding model."),
+    ("is rust fast?", "the cat sat on the mat"),
+    ("is rust fast?", "the weather is sunny today"),
+    ("search query", "hello world"),
+]
+
+
+def config(vocab):
+    return {
+        "architectures": ["BertForSequenceClassification"],
+        "model_type": "bert",
+        "vocab_size": max(vocab.values()) + 1,
+        "hidden_size": HIDDEN_SIZE,
+        "num_hidden_layers": NUM_LAYERS,
+        "num_attention_heads": NUM_HEADS,
+        "intermediate_size": INTERMEDIATE_SIZE,
+        "hidden_act": "gelu",
+        "hidden_dropout_prob": 0.1,
+        "max_position_embeddings": MAX_POSITIONS,
+        "type_vocab_size": 2,
+        "initializer_range": 0.02,
+        "layer_norm_eps": LAYER_NORM_EPS,
+        "pad_token_id": 0,
+        "position_embedding_type": "absolute",
+        "id2label": {"0": "LABEL_0"},
+        "label2id": {"LABEL_0": 0},
+    }
+
+
+def tokenize_pair(query, passage, vocab):
+    """Token IDs and token type IDs of `[CLS] query [SEP] passage [SEP]`"""
+    first = [vocab["[CLS]"]] + words(query, vocab) + [vocab["[SEP]"]]
+    second = words(passage, vocab) + [vocab["[SEP]"]]
+    return first + second, [0] * len(first) + [1] * len(second)
+
+
+def tensors(vocab_size):
+    """Tensor name -> shape, as saved from BertForSequenceClassification"""
+    shapes = {
+        "bert.embeddings.word_embeddings.weight": (vocab_size, HIDDEN_SIZE),
+        "bert.embeddings.position_embeddings.weight": (MAX_POSITIONS, HIDDEN_SIZE),
+        "bert.embeddings.token_type_embeddings.weight": (2, HIDDEN_SIZE),
+        "bert.embeddings.LayerNorm.weight": (HIDDEN_SIZE,),
+        "bert.embeddings.LayerNorm.bias": (HIDDEN_SIZE,),
+    }
+    for i in range(NUM_LAYERS):
+        layer = f"bert.encoder.layer.{i}"
+        for name in ("query", "key", "value"):
+            shapes[f"{layer}.attention.self.{name}.weight"] = (HIDDEN_SIZE, HIDDEN_SIZE)
+            shapes[f"{layer}.attention.self.{name}.bias"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.attention.output.dense.weight"] = (HIDDEN_SIZE, HIDDEN_SIZE)
+        shapes[f"{layer}.attention.output.dense.bias"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.attention.output.LayerNorm.weight"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.attention.output.LayerNorm.bias"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.intermediate.dense.weight"] = (INTERMEDIATE_SIZE, HIDDEN_SIZE)
+        shapes[f"{layer}.intermediate.dense.bias"] = (INTERMEDIATE_SIZE,)
+        shapes[f"{layer}.output.dense.weight"] = (HIDDEN_SIZE, INTERMEDIATE_SIZE)
+        shapes[f"{layer}.output.dense.bias"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.output.LayerNorm.weight"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.output.LayerNorm.bias"] = (HIDDEN_SIZE,)
+    shapes["bert.pooler.dense.weight"] = (HIDDEN_SIZE, HIDDEN_SIZE)
+    shapes["bert.pooler.dense.bias"] = (HIDDEN_SIZE,)
+    shapes["classifier.weight"] = (1, HIDDEN_SIZE)
+    shapes["classifier.bias"] = (1,)
+    return shapes
+
+
+def random_weights(shapes, seed=0):
+    """Name -> (shape, float32 values); every tensor is random so that each
+    one affects the reference scores"""
+    rng = random.Random(seed)
+    weights = {}
+    for name, shape in shapes.items():
+        count = math.prod(shape)
+        if "LayerNorm" in name:
+            if name.endswith("weight"):
+                values = [1.0 + rng.gauss(0.0, 0.1) for _ in range(count)]
+            else:
+                values = [rng.gauss(0.0, 0.05) for _ in range(count)]
+        else:
+            values = [rng.gauss(0.0, 0.2) for _ in range(count)]
+        weights[name] = (shape, array("f", values))
+    return weights
+
+
+# --- Reference forward pass, one pair without padding ---
+
+
+def linear(x, weights, prefix):
+    w, b = matrix(weights, prefix + ".weight"), vector(weights, prefix + ".bias")
+    return [[sum(xi * wi for xi, wi in zip(row, w_row)) + bj for w_row, bj in zip(w, b)] for row in x]
+
+
+def layer_norm(x, weights, prefix):
+    gamma, beta = vector(weights, prefix + ".weight"), vector(weights, prefix + ".bias")
+    out = []
+    for row in x:
+        mean = sum(row) / len(row)
+        var = sum((v - mean) ** 2 for v in row) / len(row)
+        out.append([(v - mean) / math.sqrt(var + LAYER_NORM_EPS) * g + b for v, g, b in zip(row, gamma, beta)])
+    return out
+
+
+def attention(x, weights, prefix):
+    q = linear(x, weights, prefix + ".self.query")
+    k = linear(x, weights, prefix + ".self.key")
+    v = linear(x, weights, prefix + ".self.value")
+    head = HIDDEN_SIZE // NUM_HEADS
+    context = [[0.0] * HIDDEN_SIZE for _ in x]
+    for h in range(NUM_HEADS):
+        cols = range(h * head, (h + 1) * head)
+        for i in range(len(x)):
+            scores = [sum(q[i][c] * k[j][c] for c in cols) / math.sqrt(head) for j in range(len(x))]
+            top = max(scores)
+            exp = [math.exp(s - top) for s in scores]
+            total = sum(exp)
+            for c in cols:
+                context[i][c] = sum(e / total * v[j][c] for j, e in enumerate(exp))
+    out = linear(context, weights, prefix + ".output.dense")
+    return layer_norm(add(out, x), weights, prefix + ".output.LayerNorm")
+
+
+def forward(ids, type_ids, weights):
+    word = matrix(weights, "bert.embeddings.word_embeddings.weight")
+    position = matrix(weights, "bert.embeddings.position_embeddings.weight")
+    token_type = matrix(weights, "bert.embeddings.token_type_embeddings.weight")
+    x = [
+        [w + p + t for w, p, t in zip(word[token], position[i], token_type[type_id])]
+        for i, (token, type_id) in enumerate(zip(ids, type_ids))
+    ]
+    x = layer_norm(x, weights, "bert.embeddings.LayerNorm")
+    for i in range(NUM_LAYERS):
+        layer = f"bert.encoder.layer.{i}"
+        x = attention(x, weights, f"{layer}.attention")
+        hidden = [[gelu(v) for v in row] for row in linear(x, weights, f"{layer}.intermediate.dense")]
+        x = layer_norm(add(linear(hidden, weights, f"{layer}.output.dense"), x), weights, f"{layer}.output.LayerNorm")
+    return x
+
+
+def score(ids, type_ids, weights):
+    cls = forward(ids, type_ids, weights)[:1]
+    pooled = [[math.tanh(v) for v in row] for row in linear(cls, weights, "bert.pooler.dense")]
+    return linear(pooled, weights, "classifier")[0][0]
+
+
+def main():
+    folder = Path(__file__).parent
+    vocab = load_vocab(folder)
+    model_config = config(vocab)
+    weights = random_weights(tensors(model_config["vocab_size"]))
+    (folder / "config.json").write_text(json.dumps(model_config, indent=2) + "\n")
+    write_safetensors(folder / "model.safetensors", weights)
+
+    reference = []
+    for query, passage in PAIRS:
+        ids, type_ids = tokenize_pair(query, passage, vocab)
+        reference.append(
+            {
+                "query": query,
+                "passage": passage,
+                "ids": ids,
+                "type_ids": type_ids,
+                "score": round(score(ids, type_ids, weights), 7),
+            }
+        )
+    (folder / "reference.json").write_text(json.dumps(reference, indent=2) + "\n")
+
+
+if __name__ == "__main__":
+    main()
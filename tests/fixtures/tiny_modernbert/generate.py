@@ -0,0 +1,197 @@
+"""
+Writes the tiny ModernBERT fixture used by tests/modernbert.rs: config.json,
+randomly initialized model.safetensors, and reference.json with the token IDs
+and embeddings of a few texts. The tokenizer is the WordPiece one of
+tests/fixtures/tiny_bert.
+
+The reference embeddings come from the plain-Python forward pass below, which
+follows HuggingFace's ModernBertModel (pre-LayerNorm layers without biases,
+global attention every GLOBAL_EVERY layers and a sliding window in the
+others, each with its own rotary theta, GeGLU feed-forward, final norm)
+followed by mean pooling and L2 normalization. Only the standard library is
+used:
+
+    python tests/fixtures/tiny_modernbert/generate.py
+"""
+
+import json
+import math
+import random
+import sys
+from array import array
+from pathlib import Path
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent))
+from common import add, gelu, load_vocab, matrix, tokenize, vector, write_safetensors  # noqa: E402
+
+HIDDEN_SIZE = 32
+NUM_LAYERS = 3
+NUM_HEADS = 4
+INTERMEDIATE_SIZE = 48
+MAX_POSITIONS = 64
+NORM_EPS = 1e-5
+GLOBAL_ROPE_THETA = 160000.0
+LOCAL_ROPE_THETA = 10000.0
+GLOBAL_EVERY = 2
+# Tokens in local layers see neighbours at most LOCAL_ATTENTION // 2 away
+LOCAL_ATTENTION = 4
+
+TEXTS = [
+    "hello world",
+    "the cat sat on the mat",
+    "rust is a fast text embedding model.",
+    "the dogs sat on the mat and the cats are in the rain!",
+]
+
+
+def config(vocab):
+    return {
+        "architectures": ["ModernBertModel"],
+        "model_type": "modernbert",
+        "vocab_size": max(vocab.values()) + 1,
+        "hidden_size": HIDDEN_SIZE,
+        "intermediate_size": INTERMEDIATE_SIZE,
+        "num_hidden_layers": NUM_LAYERS,
+        "num_attention_heads": NUM_HEADS,
+        "max_position_embeddings": MAX_POSITIONS,
+        "hidden_activation": "gelu",
+        "norm_eps": NORM_EPS,
+        "norm_bias": False,
+        "attention_bias": False,
+        "mlp_bias": False,
+        "global_rope_theta": GLOBAL_ROPE_THETA,
+        "local_rope_theta": LOCAL_ROPE_THETA,
+        "global_attn_every_n_layers": GLOBAL_EVERY,
+        "local_attention": LOCAL_ATTENTION,
+        "pad_token_id": vocab["[PAD]"],
+        "classifier_pooling": "mean",
+    }
+
+
+def tensors(vocab_size):
+    """Tensor name -> shape, as expected by ModernBertModel"""
+    shapes = {
+        "embeddings.tok_embeddings.weight": (vocab_size, HIDDEN_SIZE),
+        "embeddings.norm.weight": (HIDDEN_SIZE,),
+        "final_norm.weight": (HIDDEN_SIZE,),
+    }
+    for i in range(NUM_LAYERS):
+        layer = f"layers.{i}"
+        # The first layer reuses the embedding norm
+        if i > 0:
+            shapes[f"{layer}.attn_norm.weight"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.attn.Wqkv.weight"] = (3 * HIDDEN_SIZE, HIDDEN_SIZE)
+        shapes[f"{layer}.attn.Wo.weight"] = (HIDDEN_SIZE, HIDDEN_SIZE)
+        shapes[f"{layer}.mlp_norm.weight"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.mlp.Wi.weight"] = (2 * INTERMEDIATE_SIZE, HIDDEN_SIZE)
+        shapes[f"{layer}.mlp.Wo.weight"] = (HIDDEN_SIZE, INTERMEDIATE_SIZE)
+    return shapes
+
+
+def random_weights(shapes, seed=0):
+    """Name -> (shape, float32 values); every tensor is random so that each
+    one affects the reference embeddings"""
+    rng = random.Random(seed)
+    weights = {}
+    for name, shape in shapes.items():
+        count = math.prod(shape)
+        if "norm" in name:
+            values = [1.0 + rng.gauss(0.0, 0.1) for _ in range(count)]
+        else:
+            values = [rng.gauss(0.0, 0.2) for _ in range(count)]
+        weights[name] = (shape, array("f", values))
+    return weights
+
+
+# --- Reference forward pass, one sequence without padding ---
+
+
+def linear(x, weights, name):
+    w = matrix(weights, name)
+    return [[sum(xi * wi for xi, wi in zip(row, w_row)) for w_row in w] for row in x]
+
+
+def layer_norm(x, weights, name):
+    gamma = vector(weights, name)
+    out = []
+    for row in x:
+        mean = sum(row) / len(row)
+        var = sum((v - mean) ** 2 for v in row) / len(row)
+        out.append([(v - mean) / math.sqrt(var + NORM_EPS) * g for v, g in zip(row, gamma)])
+    return out
+
+
+def rotate(head, position, theta):
+    """Non-interleaved rotary embedding: dimension i pairs with i + dim / 2"""
+    half = len(head) // 2
+    out = list(head)
+    for i in range(half):
+        angle = position * theta ** (-2 * i / len(head))
+        cos, sin = math.cos(angle), math.sin(angle)
+        out[i] = head[i] * cos - head[i + half] * sin
+        out[i + half] = head[i + half] * cos + head[i] * sin
+    return out
+
+
+def attention(x, weights, prefix, is_global):
+    theta = GLOBAL_ROPE_THETA if is_global else LOCAL_ROPE_THETA
+    qkv = linear(x, weights, prefix + ".Wqkv.weight")
+    head = HIDDEN_SIZE // NUM_HEADS
+    context = [[0.0] * HIDDEN_SIZE for _ in x]
+    for h in range(NUM_HEADS):
+        cols = slice(h * head, (h + 1) * head)
+        q = [rotate(row[:HIDDEN_SIZE][cols], i, theta) for i, row in enumerate(qkv)]
+        k = [rotate(row[HIDDEN_SIZE:2 * HIDDEN_SIZE][cols], i, theta) for i, row in enumerate(qkv)]
+        v = [row[2 * HIDDEN_SIZE:][cols] for row in qkv]
+        for i in range(len(x)):
+            visible = [j for j in range(len(x)) if is_global or abs(i - j) <= LOCAL_ATTENTION // 2]
+            scores = [sum(a * b for a, b in zip(q[i], k[j])) / math.sqrt(head) for j in visible]
+            top = max(scores)
+            exp = [math.exp(s - top) for s in scores]
+            total = sum(exp)
+            for c in range(head):
+                context[i][h * head + c] = sum(e / total * v[j][c] for j, e in zip(visible, exp))
+    return linear(context, weights, prefix + ".Wo.weight")
+
+
+def mlp(x, weights, prefix):
+    hidden = linear(x, weights, prefix + ".Wi.weight")
+    hidden = [[gelu(a) * g for a, g in zip(row[:INTERMEDIATE_SIZE], row[INTERMEDIATE_SIZE:])] for row in hidden]
+    return linear(hidden, weights, prefix + ".Wo.weight")
+
+
+def forward(ids, weights):
+    embeddings = matrix(weights, "embeddings.tok_embeddings.weight")
+    x = layer_norm([embeddings[token] for token in ids], weights, "embeddings.norm.weight")
+    for i in range(NUM_LAYERS):
+        layer = f"layers.{i}"
+        normed = x if i == 0 else layer_norm(x, weights, f"{layer}.attn_norm.weight")
+        x = add(attention(normed, weights, f"{layer}.attn", i % GLOBAL_EVERY == 0), x)
+        x = add(mlp(layer_norm(x, weights, f"{layer}.mlp_norm.weight"), weights, f"{layer}.mlp"), x)
+    return layer_norm(x, weights, "final_norm.weight")
+
+
+def embed(ids, weights):
+    tokens = forward(ids, weights)
+    mean = [sum(col) / len(tokens) for col in zip(*tokens)]
+    norm = math.sqrt(sum(v * v for v in mean))
+    return [v / norm for v in mean]
+
+
+def main():
+    folder = Path(__file__).parent
+    vocab = load_vocab(folder)
+    model_config = config(vocab)
+    weights = random_weights(tensors(model_config["vocab_size"]))
+    (folder / "config.json").write_text(json.dumps(model_config, indent=2) + "\n")
+    write_safetensors(folder / "model.safetensors", weights)
+
+    reference = []
+    for text in TEXTS:
+        ids = tokenize(text, vocab)
+        reference.append({"text": text, "ids": ids, "embedding": [round(v, 7) for v in embed(ids, weights)]})
+    (folder / "reference.json").write_text(json.dumps(reference, indent=2) + "\n")
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,201 @@
+"""
+Writes the tiny NomicBert fixture used by tests/nomic.rs: config.json,
+randomly initialized model.safetensors, and reference.json with the token IDs
+and embeddings of a few texts. The tokenizer is the WordPiece one of
+tests/fixtures/tiny_bert.
+
+The reference embeddings come from the plain-Python forward pass below, which
+follows nomic-ai's NomicBertModel (no position embeddings, non-interleaved
+rotary embeddings on queries and keys, SwiGLU feed-forward, post-LayerNorm
+blocks, no projection biases) followed by mean pooling and L2 normalization.
+Only the standard library is used:
+
+    python tests/fixtures/tiny_nomic_bert/generate.py
+"""
+
+import json
+import math
+import random
+import sys
+from array import array
+from pathlib import Path
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent))
+from common import add, load_vocab, matrix, silu, tokenize, vector, write_safetensors  # noqa: E402
+
+HIDDEN_SIZE = 32
+NUM_LAYERS = 2
+NUM_HEADS = 4
+INTERMEDIATE_SIZE = 48
+MAX_POSITIONS = 64
+MAX_TRAINED_POSITIONS = 48
+LAYER_NORM_EPS = 1e-12
+ROTARY_BASE = 1000.0
+
+PREFIXES = {"query": "search_query: ", "document": "search_document: "}
+TEXTS = [
+    ("hello world", None),
+    ("the cat sat on the mat", None),
+    ("rust is fast", None),
+    ("is rust fast?", "query"),
+    ("rust is a fast text embedding model.", "document"),
+]
+
+
+def config(vocab):
+    return {
+        "architectures": ["NomicBertModel"],
+        "model_type": "nomic_bert",
+        "vocab_size": max(vocab.values()) + 1,
+        "n_embd": HIDDEN_SIZE,
+        "n_head": NUM_HEADS,
+        "n_layer": NUM_LAYERS,
+        "n_inner": INTERMEDIATE_SIZE,
+        "n_positions": MAX_POSITIONS,
+        "max_trained_positions": MAX_TRAINED_POSITIONS,
+        "type_vocab_size": 2,
+        "layer_norm_epsilon": LAYER_NORM_EPS,
+        "activation_function": "swiglu",
+        "rotary_emb_base": ROTARY_BASE,
+        "rotary_emb_fraction": 1.0,
+        "rotary_emb_interleaved": False,
+        "qkv_proj_bias": False,
+        "mlp_fc1_bias": False,
+        "mlp_fc2_bias": False,
+        "prenorm": False,
+    }
+
+
+def tensors(vocab_size):
+    """Tensor name -> shape, as expected by NomicBertModel"""
+    shapes = {
+        "embeddings.word_embeddings.weight": (vocab_size, HIDDEN_SIZE),
+        "embeddings.token_type_embeddings.weight": (2, HIDDEN_SIZE),
+        "emb_ln.weight": (HIDDEN_SIZE,),
+        "emb_ln.bias": (HIDDEN_SIZE,),
+    }
+    for i in range(NUM_LAYERS):
+        layer = f"encoder.layers.{i}"
+        shapes[f"{layer}.attn.Wqkv.weight"] = (3 * HIDDEN_SIZE, HIDDEN_SIZE)
+        shapes[f"{layer}.attn.out_proj.weight"] = (HIDDEN_SIZE, HIDDEN_SIZE)
+        shapes[f"{layer}.mlp.fc11.weight"] = (INTERMEDIATE_SIZE, HIDDEN_SIZE)
+        shapes[f"{layer}.mlp.fc12.weight"] = (INTERMEDIATE_SIZE, HIDDEN_SIZE)
+        shapes[f"{layer}.mlp.fc2.weight"] = (HIDDEN_SIZE, INTERMEDIATE_SIZE)
+        for norm in ("norm1", "norm2"):
+            shapes[f"{layer}.{norm}.weight"] = (HIDDEN_SIZE,)
+            shapes[f"{layer}.{norm}.bias"] = (HIDDEN_SIZE,)
+    return shapes
+
+
+def random_weights(shapes, seed=0):
+    """Name -> (shape, float32 values); every tensor is random so that each
+    one affects the reference embeddings"""
+    rng = random.Random(seed)
+    weights = {}
+    for name, shape in shapes.items():
+        count = math.prod(shape)
+        if "norm" in name or "emb_ln" in name:
+            if name.endswith("weight"):
+                values = [1.0 + rng.gauss(0.0, 0.1) for _ in range(count)]
+            else:
+                values = [rng.gauss(0.0, 0.05) for _ in range(count)]
+        else:
+            values = [rng.gauss(0.0, 0.2) for _ in range(count)]
+        weights[name] = (shape, array("f", values))
+    return weights
+
+
+# --- Reference forward pass, one sequence without padding ---
+
+
+def linear(x, weights, name):
+    w = matrix(weights, name)
+    return [[sum(xi * wi for xi, wi in zip(row, w_row)) for w_row in w] for row in x]
+
+
+def layer_norm(x, weights, prefix):
+    gamma, beta = vector(weights, prefix + ".weight"), vector(weights, prefix + ".bias")
+    out = []
+    for row in x:
+        mean = sum(row) / len(row)
+        var = sum((v - mean) ** 2 for v in row) / len(row)
+        out.append([(v - mean) / math.sqrt(var + LAYER_NORM_EPS) * g + b for v, g, b in zip(row, gamma, beta)])
+    return out
+
+
+def rotate(head, position):
+    """Non-interleaved rotary embedding: dimension i pairs with i + dim / 2"""
+    half = len(head) // 2
+    out = list(head)
+    for i in range(half):
+        angle = position * ROTARY_BASE ** (-2 * i / len(head))
+        cos, sin = math.cos(angle), math.sin(angle)
+        out[i] = head[i] * cos - head[i + half] * sin
+        out[i + half] = head[i + half] * cos + head[i] * sin
+    return out
+
+
+def attention(x, weights, prefix):
+    qkv = linear(x, weights, prefix + ".Wqkv.weight")
+    head = HIDDEN_SIZE // NUM_HEADS
+    context = [[0.0] * HIDDEN_SIZE for _ in x]
+    for h in range(NUM_HEADS):
+        cols = slice(h * head, (h + 1) * head)
+        q = [rotate(row[:HIDDEN_SIZE][cols], i) for i, row in enumerate(qkv)]
+        k = [rotate(row[HIDDEN_SIZE:2 * HIDDEN_SIZE][cols], i) for i, row in enumerate(qkv)]
+        v = [row[2 * HIDDEN_SIZE:][cols] for row in qkv]
+        for i in range(len(x)):
+            scores = [sum(a * b for a, b in zip(q[i], k[j])) / math.sqrt(head) for j in range(len(x))]
+            top = max(scores)
+            exp = [math.exp(s - top) for s in scores]
+            total = sum(exp)
+            for c in range(head):
+                context[i][h * head + c] = sum(e / total * v[j][c] for j, e in enumerate(exp))
+    return linear(context, weights, prefix + ".out_proj.weight")
+
+
+def mlp(x, weights, prefix):
+    value = linear(x, weights, prefix + ".fc11.weight")
+    gate = linear(x, weights, prefix + ".fc12.weight")
+    hidden = [[a * silu(g) for a, g in zip(rv, rg)] for rv, rg in zip(value, gate)]
+    return linear(hidden, weights, prefix + ".fc2.weight")
+
+
+def forward(ids, weights):
+    word = matrix(weights, "embeddings.word_embeddings.weight")
+    token_type = matrix(weights, "embeddings.token_type_embeddings.weight")
+    x = [[w + t for w, t in zip(word[token], token_type[0])] for token in ids]
+    x = layer_norm(x, weights, "emb_ln")
+    for i in range(NUM_LAYERS):
+        layer = f"encoder.layers.{i}"
+        x = layer_norm(add(attention(x, weights, f"{layer}.attn"), x), weights, f"{layer}.norm1")
+        x = layer_norm(add(mlp(x, weights, f"{layer}.mlp"), x), weights, f"{layer}.norm2")
+    return x
+
+
+def embed(ids, weights):
+    tokens = forward(ids, weights)
+    mean = [sum(col) / len(tokens) for col in zip(*tokens)]
+    norm = math.sqrt(sum(v * v for v in mean))
+    return [v / norm for v in mean]
+
+
+def main():
+    folder = Path(__file__).parent
+    vocab = load_vocab(folder)
+    model_config = config(vocab)
+    weights = random_weights(tensors(model_config["vocab_size"]))
+    (folder / "config.json").write_text(json.dumps(model_config, indent=2) + "\n")
+    write_safetensors(folder / "model.safetensors", weights)
+
+    reference = []
+    for text, task in TEXTS:
+        ids = tokenize(PREFIXES.get(task, "") + text, vocab)
+        reference.append(
+            {"text": text, "task": task, "ids": ids, "embedding": [round(v, 7) for v in embed(ids, weights)]}
+        )
+    (folder / "reference.json").write_text(json.dumps(reference, indent=2) + "\n")
+
+
+if __name__ == "__main__":
+    main()
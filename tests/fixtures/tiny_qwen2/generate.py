@@ -0,0 +1,202 @@
+"""
+Writes the tiny Qwen2 fixture used by tests/decoder.rs: config.json,
+randomly initialized model.safetensors, and reference.json with the token IDs
+and embeddings of a few texts. The tokenizer is the WordPiece one of
+tests/fixtures/tiny_bert, whose trailing [SEP] stands in for the
+end-of-sequence token.
+
+The reference embeddings come from the plain-Python forward pass below, which
+follows HuggingFace's Qwen2Model (RMSNorm, grouped-query causal attention
+with biased query/key/value projections, rotary embeddings, SwiGLU
+feed-forward, final norm) followed by last-token pooling and L2
+normalization. Only the standard library is used:
+
+    python tests/fixtures/tiny_qwen2/generate.py
+"""
+
+import json
+import math
+import random
+import sys
+from array import array
+from pathlib import Path
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent))
+from common import add, load_vocab, matrix, silu, tokenize, vector, write_safetensors  # noqa: E402
+
+HIDDEN_SIZE = 32
+NUM_LAYERS = 2
+NUM_HEADS = 4
+NUM_KV_HEADS = 2
+HEAD_DIM = HIDDEN_SIZE // NUM_HEADS
+INTERMEDIATE_SIZE = 48
+MAX_POSITIONS = 64
+RMS_NORM_EPS = 1e-6
+ROPE_THETA = 10000.0
+
+QUERY_PREFIX = "search query "
+TEXTS = [
+    ("hello world", None),
+    ("the cat sat on the mat", None),
+    ("rust is a fast text embedding model.", None),
+    ("the dogs sat on the mat and the cats are in the rain!", None),
+    ("is rust fast?", "query"),
+]
+
+
+def config(vocab):
+    return {
+        "architectures": ["Qwen2Model"],
+        "model_type": "qwen2",
+        "vocab_size": max(vocab.values()) + 1,
+        "hidden_size": HIDDEN_SIZE,
+        "intermediate_size": INTERMEDIATE_SIZE,
+        "num_hidden_layers": NUM_LAYERS,
+        "num_attention_heads": NUM_HEADS,
+        "num_key_value_heads": NUM_KV_HEADS,
+        "max_position_embeddings": MAX_POSITIONS,
+        "rms_norm_eps": RMS_NORM_EPS,
+        "rope_theta": ROPE_THETA,
+        "hidden_act": "silu",
+        "sliding_window": 4,
+        "use_sliding_window": False,
+        "tie_word_embeddings": False,
+        "torch_dtype": "float32",
+    }
+
+
+def tensors(vocab_size):
+    """Tensor name -> shape, as expected by Qwen2Model"""
+    shapes = {
+        "embed_tokens.weight": (vocab_size, HIDDEN_SIZE),
+        "norm.weight": (HIDDEN_SIZE,),
+    }
+    for i in range(NUM_LAYERS):
+        layer = f"layers.{i}"
+        shapes[f"{layer}.input_layernorm.weight"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.self_attn.q_proj.weight"] = (NUM_HEADS * HEAD_DIM, HIDDEN_SIZE)
+        shapes[f"{layer}.self_attn.q_proj.bias"] = (NUM_HEADS * HEAD_DIM,)
+        for name in ("k_proj", "v_proj"):
+            shapes[f"{layer}.self_attn.{name}.weight"] = (NUM_KV_HEADS * HEAD_DIM, HIDDEN_SIZE)
+            shapes[f"{layer}.self_attn.{name}.bias"] = (NUM_KV_HEADS * HEAD_DIM,)
+        shapes[f"{layer}.self_attn.o_proj.weight"] = (HIDDEN_SIZE, NUM_HEADS * HEAD_DIM)
+        shapes[f"{layer}.post_attention_layernorm.weight"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.mlp.gate_proj.weight"] = (INTERMEDIATE_SIZE, HIDDEN_SIZE)
+        shapes[f"{layer}.mlp.up_proj.weight"] = (INTERMEDIATE_SIZE, HIDDEN_SIZE)
+        shapes[f"{layer}.mlp.down_proj.weight"] = (HIDDEN_SIZE, INTERMEDIATE_SIZE)
+    return shapes
+
+
+def random_weights(shapes, seed=0):
+    """Name -> (shape, float32 values); every tensor is random so that each
+    one affects the reference embeddings"""
+    rng = random.Random(seed)
+    weights = {}
+    for name, shape in shapes.items():
+        count = math.prod(shape)
+        if "norm" in name:
+            values = [1.0 + rng.gauss(0.0, 0.1) for _ in range(count)]
+        elif name.endswith("bias"):
+            values = [rng.gauss(0.0, 0.05) for _ in range(count)]
+        else:
+            values = [rng.gauss(0.0, 0.2) for _ in range(count)]
+        weights[name] = (shape, array("f", values))
+    return weights
+
+
+# --- Reference forward pass, one sequence without padding ---
+
+
+def linear(x, weights, prefix, bias=False):
+    w = matrix(weights, prefix + ".weight")
+    b = vector(weights, prefix + ".bias") if bias else [0.0] * len(w)
+    return [[sum(xi * wi for xi, wi in zip(row, w_row)) + bj for w_row, bj in zip(w, b)] for row in x]
+
+
+def rms_norm(x, weights, name):
+    gamma = vector(weights, name)
+    out = []
+    for row in x:
+        rms = math.sqrt(sum(v * v for v in row) / len(row) + RMS_NORM_EPS)
+        out.append([v / rms * g for v, g in zip(row, gamma)])
+    return out
+
+
+def rotate(head, position):
+    """Non-interleaved rotary embedding: dimension i pairs with i + dim / 2"""
+    half = len(head) // 2
+    out = list(head)
+    for i in range(half):
+        angle = position * ROPE_THETA ** (-2 * i / len(head))
+        cos, sin = math.cos(angle), math.sin(angle)
+        out[i] = head[i] * cos - head[i + half] * sin
+        out[i + half] = head[i + half] * cos + head[i] * sin
+    return out
+
+
+def attention(x, weights, prefix):
+    q = linear(x, weights, prefix + ".q_proj", bias=True)
+    k = linear(x, weights, prefix + ".k_proj", bias=True)
+    v = linear(x, weights, prefix + ".v_proj", bias=True)
+    context = [[0.0] * (NUM_HEADS * HEAD_DIM) for _ in x]
+    for h in range(NUM_HEADS):
+        # Grouped-query attention: consecutive query heads share a key/value head
+        kv = h // (NUM_HEADS // NUM_KV_HEADS)
+        q_cols = slice(h * HEAD_DIM, (h + 1) * HEAD_DIM)
+        kv_cols = slice(kv * HEAD_DIM, (kv + 1) * HEAD_DIM)
+        qh = [rotate(row[q_cols], i) for i, row in enumerate(q)]
+        kh = [rotate(row[kv_cols], i) for i, row in enumerate(k)]
+        vh = [row[kv_cols] for row in v]
+        for i in range(len(x)):
+            # Causal: tokens only see themselves and earlier tokens
+            scores = [sum(a * b for a, b in zip(qh[i], kh[j])) / math.sqrt(HEAD_DIM) for j in range(i + 1)]
+            top = max(scores)
+            exp = [math.exp(s - top) for s in scores]
+            total = sum(exp)
+            for c in range(HEAD_DIM):
+                context[i][h * HEAD_DIM + c] = sum(e / total * vh[j][c] for j, e in enumerate(exp))
+    return linear(context, weights, prefix + ".o_proj")
+
+
+def mlp(x, weights, prefix):
+    gate = linear(x, weights, prefix + ".gate_proj")
+    up = linear(x, weights, prefix + ".up_proj")
+    hidden = [[silu(g) * u for g, u in zip(rg, ru)] for rg, ru in zip(gate, up)]
+    return linear(hidden, weights, prefix + ".down_proj")
+
+
+def forward(ids, weights):
+    embeddings = matrix(weights, "embed_tokens.weight")
+    x = [embeddings[token] for token in ids]
+    for i in range(NUM_LAYERS):
+        layer = f"layers.{i}"
+        x = add(attention(rms_norm(x, weights, f"{layer}.input_layernorm.weight"), weights, f"{layer}.self_attn"), x)
+        x = add(mlp(rms_norm(x, weights, f"{layer}.post_attention_layernorm.weight"), weights, f"{layer}.mlp"), x)
+    return rms_norm(x, weights, "norm.weight")
+
+
+def embed(ids, weights):
+    last = forward(ids, weights)[-1]
+    norm = math.sqrt(sum(v * v for v in last))
+    return [v / norm for v in last]
+
+
+def main():
+    folder = Path(__file__).parent
+    vocab = load_vocab(folder)
+    model_config = config(vocab)
+    weights = random_weights(tensors(model_config["vocab_size"]))
+    (folder / "config.json").write_text(json.dumps(model_config, indent=2) + "\n")
+    write_safetensors(folder / "model.safetensors", weights)
+
+    reference = []
+    for text, task in TEXTS:
+        ids = tokenize((QUERY_PREFIX if task == "query" else "") + text, vocab)
+        reference.append(
+            {"text": text, "task": task, "ids": ids, "embedding": [round(v, 7) for v in embed(ids, weights)]}
+        )
+    (folder / "reference.json").write_text(json.dumps(reference, indent=2) + "\n")
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,291 @@
+"""
+Writes the tiny SigLIP fixture used by tests/clip.rs: config.json, randomly
+initialized model.safetensors, a PNG image, and reference.json with the token
+IDs and embeddings of a few texts and of the image. The tokenizer is the
+WordPiece one of tests/fixtures/tiny_bert.
+
+The reference embeddings come from the plain-Python forward pass below, which
+follows HuggingFace's SiglipModel: pre-norm transformer layers with tanh GELU
+and no attention mask; texts are padded with [PAD] to the full context and
+embedded as their last position through the text head, images through the
+attention pooling head, then L2 normalization. The image already has the input
+size, so the SiglipImageProcessor resize leaves it unchanged. Only the
+standard library is used:
+
+    python tests/fixtures/tiny_siglip/generate.py
+"""
+
+import json
+import math
+import random
+import sys
+from array import array
+from pathlib import Path
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent))
+from common import add, load_vocab, matrix, normalize, tokenize, vector, write_png, write_safetensors  # noqa: E402
+
+HIDDEN_SIZE = 24
+NUM_LAYERS = 2
+NUM_HEADS = 4
+INTERMEDIATE_SIZE = 40
+MAX_POSITIONS = 24
+IMAGE_SIZE = 16
+PATCH_SIZE = 8
+LAYER_NORM_EPS = 1e-6
+
+TEXTS = [
+    "hello world",
+    "the cat sat on the mat",
+    "rust is a fast text embedding model.",
+    "the dogs sat on the mat and the cats are in the rain!",
+]
+IMAGE = "square.png"
+
+
+def config(vocab):
+    return {
+        "architectures": ["SiglipModel"],
+        "model_type": "siglip",
+        "text_config": {
+            "vocab_size": max(vocab.values()) + 1,
+            "hidden_size": HIDDEN_SIZE,
+            "intermediate_size": INTERMEDIATE_SIZE,
+            "num_hidden_layers": NUM_LAYERS,
+            "num_attention_heads": NUM_HEADS,
+            "max_position_embeddings": MAX_POSITIONS,
+            "hidden_act": "gelu_pytorch_tanh",
+            "layer_norm_eps": LAYER_NORM_EPS,
+            "pad_token_id": vocab["[PAD]"],
+        },
+        "vision_config": {
+            "hidden_size": HIDDEN_SIZE,
+            "intermediate_size": INTERMEDIATE_SIZE,
+            "num_hidden_layers": NUM_LAYERS,
+            "num_attention_heads": NUM_HEADS,
+            "num_channels": 3,
+            "image_size": IMAGE_SIZE,
+            "patch_size": PATCH_SIZE,
+            "hidden_act": "gelu_pytorch_tanh",
+            "layer_norm_eps": LAYER_NORM_EPS,
+        },
+        "torch_dtype": "float32",
+    }
+
+
+def encoder_tensors(prefix):
+    shapes = {}
+    for i in range(NUM_LAYERS):
+        layer = f"{prefix}.encoder.layers.{i}"
+        for name in ("q_proj", "k_proj", "v_proj", "out_proj"):
+            shapes[f"{layer}.self_attn.{name}.weight"] = (HIDDEN_SIZE, HIDDEN_SIZE)
+            shapes[f"{layer}.self_attn.{name}.bias"] = (HIDDEN_SIZE,)
+        for norm in ("layer_norm1", "layer_norm2"):
+            shapes[f"{layer}.{norm}.weight"] = (HIDDEN_SIZE,)
+            shapes[f"{layer}.{norm}.bias"] = (HIDDEN_SIZE,)
+        shapes.update(mlp_tensors(f"{layer}.mlp"))
+    return shapes
+
+
+def mlp_tensors(prefix):
+    return {
+        f"{prefix}.fc1.weight": (INTERMEDIATE_SIZE, HIDDEN_SIZE),
+        f"{prefix}.fc1.bias": (INTERMEDIATE_SIZE,),
+        f"{prefix}.fc2.weight": (HIDDEN_SIZE, INTERMEDIATE_SIZE),
+        f"{prefix}.fc2.bias": (HIDDEN_SIZE,),
+    }
+
+
+def tensors(vocab_size):
+    """Tensor name -> shape, as expected by SiglipModel"""
+    num_patches = (IMAGE_SIZE // PATCH_SIZE) ** 2
+    shapes = {
+        "text_model.embeddings.token_embedding.weight": (vocab_size, HIDDEN_SIZE),
+        "text_model.embeddings.position_embedding.weight": (MAX_POSITIONS, HIDDEN_SIZE),
+        "text_model.final_layer_norm.weight": (HIDDEN_SIZE,),
+        "text_model.final_layer_norm.bias": (HIDDEN_SIZE,),
+        "text_model.head.weight": (HIDDEN_SIZE, HIDDEN_SIZE),
+        "text_model.head.bias": (HIDDEN_SIZE,),
+        "vision_model.embeddings.patch_embedding.weight": (HIDDEN_SIZE, 3, PATCH_SIZE, PATCH_SIZE),
+        "vision_model.embeddings.patch_embedding.bias": (HIDDEN_SIZE,),
+        "vision_model.embeddings.position_embedding.weight": (num_patches, HIDDEN_SIZE),
+        "vision_model.post_layernorm.weight": (HIDDEN_SIZE,),
+        "vision_model.post_layernorm.bias": (HIDDEN_SIZE,),
+        "vision_model.head.probe": (1, 1, HIDDEN_SIZE),
+        "vision_model.head.attention.in_proj_weight": (3 * HIDDEN_SIZE, HIDDEN_SIZE),
+        "vision_model.head.attention.in_proj_bias": (3 * HIDDEN_SIZE,),
+        "vision_model.head.attention.out_proj.weight": (HIDDEN_SIZE, HIDDEN_SIZE),
+        "vision_model.head.attention.out_proj.bias": (HIDDEN_SIZE,),
+        "vision_model.head.layernorm.weight": (HIDDEN_SIZE,),
+        "vision_model.head.layernorm.bias": (HIDDEN_SIZE,),
+    }
+    shapes.update(mlp_tensors("vision_model.head.mlp"))
+    shapes.update(encoder_tensors("text_model"))
+    shapes.update(encoder_tensors("vision_model"))
+    return shapes
+
+
+def random_weights(shapes, seed=0):
+    """Name -> (shape, float32 values); every tensor is random so that each
+    one affects the reference embeddings"""
+    rng = random.Random(seed)
+    weights = {}
+    for name, shape in shapes.items():
+        count = math.prod(shape)
+        if "norm" in name and name.endswith("weight"):
+            values = [1.0 + rng.gauss(0.0, 0.1) for _ in range(count)]
+        elif "bias" in name:
+            values = [rng.gauss(0.0, 0.05) for _ in range(count)]
+        elif "patch_embedding" in name:
+            values = [rng.gauss(0.0, 0.05) for _ in range(count)]
+        else:
+            values = [rng.gauss(0.0, 0.2) for _ in range(count)]
+        weights[name] = (shape, array("f", values))
+    return weights
+
+
+def random_image(width, height, seed):
+    """Rows of (r, g, b) pixels: a gradient with noise"""
+    rng = random.Random(seed)
+    return [
+        [
+            tuple(min(255, max(0, (x * 7 + y * 11 + c * 60) % 256 + rng.randint(-30, 30))) for c in range(3))
+            for x in range(width)
+        ]
+        for y in range(height)
+    ]
+
+
+def pixel_values(pixels):
+    """SiglipImageProcessor of an image at the input size: rescale and
+    normalize with mean and std 0.5; (channel, y, x) nested lists"""
+    return [[[(row[x][c] / 255.0 - 0.5) / 0.5 for x in range(IMAGE_SIZE)] for row in pixels] for c in range(3)]
+
+
+# --- Reference forward pass, one sequence or image at a time ---
+
+
+def dense(x, w, b):
+    return [[sum(xi * wi for xi, wi in zip(row, w_row)) + bj for w_row, bj in zip(w, b)] for row in x]
+
+
+def linear(x, weights, prefix):
+    return dense(x, matrix(weights, prefix + ".weight"), vector(weights, prefix + ".bias"))
+
+
+def layer_norm(x, weights, prefix):
+    gamma, beta = vector(weights, prefix + ".weight"), vector(weights, prefix + ".bias")
+    out = []
+    for row in x:
+        mean = sum(row) / len(row)
+        var = sum((v - mean) ** 2 for v in row) / len(row)
+        out.append([(v - mean) / math.sqrt(var + LAYER_NORM_EPS) * g + b for v, g, b in zip(row, gamma, beta)])
+    return out
+
+
+def gelu_tanh(v):
+    return 0.5 * v * (1.0 + math.tanh(math.sqrt(2.0 / math.pi) * (v + 0.044715 * v**3)))
+
+
+def attend(q, k, v):
+    """Multi-head attention of the queries `q` over `k` and `v`, without mask"""
+    head_dim = HIDDEN_SIZE // NUM_HEADS
+    context = [[0.0] * HIDDEN_SIZE for _ in q]
+    for h in range(NUM_HEADS):
+        cols = slice(h * head_dim, (h + 1) * head_dim)
+        qh, kh, vh = [row[cols] for row in q], [row[cols] for row in k], [row[cols] for row in v]
+        for i in range(len(q)):
+            scores = [sum(a * b for a, b in zip(qh[i], key)) / math.sqrt(head_dim) for key in kh]
+            top = max(scores)
+            exp = [math.exp(s - top) for s in scores]
+            total = sum(exp)
+            for c in range(head_dim):
+                context[i][h * head_dim + c] = sum(e / total * vh[j][c] for j, e in enumerate(exp))
+    return context
+
+
+def attention(x, weights, prefix):
+    q = linear(x, weights, prefix + ".q_proj")
+    k = linear(x, weights, prefix + ".k_proj")
+    v = linear(x, weights, prefix + ".v_proj")
+    return linear(attend(q, k, v), weights, prefix + ".out_proj")
+
+
+def mlp(x, weights, prefix):
+    hidden = [[gelu_tanh(v) for v in row] for row in linear(x, weights, prefix + ".fc1")]
+    return linear(hidden, weights, prefix + ".fc2")
+
+
+def encoder(x, weights, prefix):
+    for i in range(NUM_LAYERS):
+        layer = f"{prefix}.encoder.layers.{i}"
+        x = add(attention(layer_norm(x, weights, f"{layer}.layer_norm1"), weights, f"{layer}.self_attn"), x)
+        x = add(mlp(layer_norm(x, weights, f"{layer}.layer_norm2"), weights, f"{layer}.mlp"), x)
+    return x
+
+
+def embed_text(ids, pad_id, weights):
+    ids = ids + [pad_id] * (MAX_POSITIONS - len(ids))
+    tokens = matrix(weights, "text_model.embeddings.token_embedding.weight")
+    positions = matrix(weights, "text_model.embeddings.position_embedding.weight")
+    x = add([tokens[token] for token in ids], positions)
+    x = layer_norm(encoder(x, weights, "text_model"), weights, "text_model.final_layer_norm")
+    # The last position, padding included
+    return normalize(linear(x[-1:], weights, "text_model.head")[0])
+
+
+def embed_image(values, weights):
+    (out, channels, patch_size, _), kernel = weights["vision_model.embeddings.patch_embedding.weight"]
+    bias = vector(weights, "vision_model.embeddings.patch_embedding.bias")
+    per_side = IMAGE_SIZE // PATCH_SIZE
+    x = []
+    # Patches in row-major order, each the convolution with stride PATCH_SIZE
+    for py in range(per_side):
+        for px in range(per_side):
+            patch = [
+                values[c][py * patch_size + ky][px * patch_size + kx]
+                for c in range(channels)
+                for ky in range(patch_size)
+                for kx in range(patch_size)
+            ]
+            x.append([sum(p * kernel[o * len(patch) + i] for i, p in enumerate(patch)) + bias[o] for o in range(out)])
+    x = add(x, matrix(weights, "vision_model.embeddings.position_embedding.weight"))
+    x = layer_norm(encoder(x, weights, "vision_model"), weights, "vision_model.post_layernorm")
+
+    # Attention pooling: a learned probe attends over the patches
+    head = "vision_model.head"
+    in_proj = matrix(weights, f"{head}.attention.in_proj_weight")
+    in_bias = vector(weights, f"{head}.attention.in_proj_bias")
+    h = HIDDEN_SIZE
+    probe = [vector(weights, f"{head}.probe")]
+    q = dense(probe, in_proj[:h], in_bias[:h])
+    k = dense(x, in_proj[h : 2 * h], in_bias[h : 2 * h])
+    v = dense(x, in_proj[2 * h :], in_bias[2 * h :])
+    pooled = linear(attend(q, k, v), weights, f"{head}.attention.out_proj")
+    pooled = add(mlp(layer_norm(pooled, weights, f"{head}.layernorm"), weights, f"{head}.mlp"), pooled)
+    return normalize(pooled[0])
+
+
+def main():
+    folder = Path(__file__).parent
+    vocab = load_vocab(folder)
+    model_config = config(vocab)
+    weights = random_weights(tensors(model_config["text_config"]["vocab_size"]))
+    (folder / "config.json").write_text(json.dumps(model_config, indent=2) + "\n")
+    write_safetensors(folder / "model.safetensors", weights)
+
+    texts = []
+    for text in TEXTS:
+        ids = tokenize(text, vocab)
+        embedding = embed_text(ids, vocab["[PAD]"], weights)
+        texts.append({"text": text, "ids": ids, "embedding": [round(v, 7) for v in embedding]})
+    pixels = random_image(IMAGE_SIZE, IMAGE_SIZE, seed=2)
+    write_png(folder / IMAGE, pixels)
+    embedding = embed_image(pixel_values(pixels), weights)
+    images = [{"image": IMAGE, "embedding": [round(v, 7) for v in embedding]}]
+    reference = {"texts": texts, "images": images}
+    (folder / "reference.json").write_text(json.dumps(reference, indent=2) + "\n")
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,205 @@
+"""
+Writes the tiny SPLADE fixture used by tests/sparse.rs: config.json, randomly
+initialized model.safetensors, and reference.json with the token IDs and the
+sparse embeddings of a few texts. The tokenizer is the WordPiece one of
+tests/fixtures/tiny_bert.
+
+The reference weights come from the plain-Python forward pass below, which
+follows HuggingFace's BertForMaskedLM as SPLADE uses it: the BERT encoder
+(absolute positions, token type embeddings, post-LayerNorm layers, exact GELU)
+over `[CLS] text [SEP]`, the masked LM head (dense, GELU, LayerNorm, then the
+decoder tied to the word embeddings) and, for every vocabulary token, the
+maximum over the positions of log(1 + ReLU(logit)). The checkpoint leaves the
+decoder weight out, like the tied ones on the Hub, and the decoder bias is
+negative on average so that most weights are 0. Only the standard library is
+used:
+
+    python tests/fixtures/tiny_splade/generate.py
+"""
+
+import json
+import math
+import random
+import sys
+from array import array
+from pathlib import Path
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent))
+from common import add, gelu, load_vocab, matrix, tokenize, vector, write_safetensors  # noqa: E402
+
+HIDDEN_SIZE = 32
+NUM_LAYERS = 2
+NUM_HEADS = 4
+INTERMEDIATE_SIZE = 64
+MAX_POSITIONS = 64
+LAYER_NORM_EPS = 1e-12
+DECODER_BIAS = -1.5
+
+TEXTS = [
+    "hello world",
+    "rust is a fast text embedding model.",
+    "the cat sat on the mat",
+    "search query",
+]
+
+
+def config(vocab):
+    return {
+        "architectures": ["BertForMaskedLM"],
+        "model_type": "bert",
+        "vocab_size": max(vocab.values()) + 1,
+        "hidden_size": HIDDEN_SIZE,
+        "num_hidden_layers": NUM_LAYERS,
+        "num_attention_heads": NUM_HEADS,
+        "intermediate_size": INTERMEDIATE_SIZE,
+        "hidden_act": "gelu",
+        "hidden_dropout_prob": 0.1,
+        "max_position_embeddings": MAX_POSITIONS,
+        "type_vocab_size": 2,
+        "initializer_range": 0.02,
+        "layer_norm_eps": LAYER_NORM_EPS,
+        "pad_token_id": 0,
+        "position_embedding_type": "absolute",
+        "tie_word_embeddings": True,
+    }
+
+
+def tensors(vocab_size):
+    """Tensor name -> shape, as saved from BertForMaskedLM with tied decoder
+    weights"""
+    shapes = {
+        "bert.embeddings.word_embeddings.weight": (vocab_size, HIDDEN_SIZE),
+        "bert.embeddings.position_embeddings.weight": (MAX_POSITIONS, HIDDEN_SIZE),
+        "bert.embeddings.token_type_embeddings.weight": (2, HIDDEN_SIZE),
+        "bert.embeddings.LayerNorm.weight": (HIDDEN_SIZE,),
+        "bert.embeddings.LayerNorm.bias": (HIDDEN_SIZE,),
+    }
+    for i in range(NUM_LAYERS):
+        layer = f"bert.encoder.layer.{i}"
+        for name in ("query", "key", "value"):
+            shapes[f"{layer}.attention.self.{name}.weight"] = (HIDDEN_SIZE, HIDDEN_SIZE)
+            shapes[f"{layer}.attention.self.{name}.bias"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.attention.output.dense.weight"] = (HIDDEN_SIZE, HIDDEN_SIZE)
+        shapes[f"{layer}.attention.output.dense.bias"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.attention.output.LayerNorm.weight"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.attention.output.LayerNorm.bias"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.intermediate.dense.weight"] = (INTERMEDIATE_SIZE, HIDDEN_SIZE)
+        shapes[f"{layer}.intermediate.dense.bias"] = (INTERMEDIATE_SIZE,)
+        shapes[f"{layer}.output.dense.weight"] = (HIDDEN_SIZE, INTERMEDIATE_SIZE)
+        shapes[f"{layer}.output.dense.bias"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.output.LayerNorm.weight"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.output.LayerNorm.bias"] = (HIDDEN_SIZE,)
+    shapes["cls.predictions.transform.dense.weight"] = (HIDDEN_SIZE, HIDDEN_SIZE)
+    shapes["cls.predictions.transform.dense.bias"] = (HIDDEN_SIZE,)
+    shapes["cls.predictions.transform.LayerNorm.weight"] = (HIDDEN_SIZE,)
+    shapes["cls.predictions.transform.LayerNorm.bias"] = (HIDDEN_SIZE,)
+    shapes["cls.predictions.bias"] = (vocab_size,)
+    return shapes
+
+
+def random_weights(shapes, seed=0):
+    """Name -> (shape, float32 values); every tensor is random so that each
+    one affects the reference weights"""
+    rng = random.Random(seed)
+    weights = {}
+    for name, shape in shapes.items():
+        count = math.prod(shape)
+        if "LayerNorm" in name:
+            if name.endswith("weight"):
+                values = [1.0 + rng.gauss(0.0, 0.1) for _ in range(count)]
+            else:
+                values = [rng.gauss(0.0, 0.05) for _ in range(count)]
+        else:
+            values = [rng.gauss(0.0, 0.2) for _ in range(count)]
+        weights[name] = (shape, array("f", values))
+    return weights
+
+
+# --- Reference forward pass, one text without padding ---
+
+
+def linear(x, weights, prefix):
+    w, b = matrix(weights, prefix + ".weight"), vector(weights, prefix + ".bias")
+    return [[sum(xi * wi for xi, wi in zip(row, w_row)) + bj for w_row, bj in zip(w, b)] for row in x]
+
+
+def layer_norm(x, weights, prefix):
+    gamma, beta = vector(weights, prefix + ".weight"), vector(weights, prefix + ".bias")
+    out = []
+    for row in x:
+        mean = sum(row) / len(row)
+        var = sum((v - mean) ** 2 for v in row) / len(row)
+        out.append([(v - mean) / math.sqrt(var + LAYER_NORM_EPS) * g + b for v, g, b in zip(row, gamma, beta)])
+    return out
+
+
+def attention(x, weights, prefix):
+    q = linear(x, weights, prefix + ".self.query")
+    k = linear(x, weights, prefix + ".self.key")
+    v = linear(x, weights, prefix + ".self.value")
+    head = HIDDEN_SIZE // NUM_HEADS
+    context = [[0.0] * HIDDEN_SIZE for _ in x]
+    for h in range(NUM_HEADS):
+        cols = range(h * head, (h + 1) * head)
+        for i in range(len(x)):
+            scores = [sum(q[i][c] * k[j][c] for c in cols) / math.sqrt(head) for j in range(len(x))]
+            top = max(scores)
+            exp = [math.exp(s - top) for s in scores]
+            total = sum(exp)
+            for c in cols:
+                context[i][c] = sum(e / total * v[j][c] for j, e in enumerate(exp))
+    out = linear(context, weights, prefix + ".output.dense")
+    return layer_norm(add(out, x), weights, prefix + ".output.LayerNorm")
+
+
+def forward(ids, type_ids, weights):
+    word = matrix(weights, "bert.embeddings.word_embeddings.weight")
+    position = matrix(weights, "bert.embeddings.position_embeddings.weight")
+    token_type = matrix(weights, "bert.embeddings.token_type_embeddings.weight")
+    x = [
+        [w + p + t for w, p, t in zip(word[token], position[i], token_type[type_id])]
+        for i, (token, type_id) in enumerate(zip(ids, type_ids))
+    ]
+    x = layer_norm(x, weights, "bert.embeddings.LayerNorm")
+    for i in range(NUM_LAYERS):
+        layer = f"bert.encoder.layer.{i}"
+        x = attention(x, weights, f"{layer}.attention")
+        hidden = [[gelu(v) for v in row] for row in linear(x, weights, f"{layer}.intermediate.dense")]
+        x = layer_norm(add(linear(hidden, weights, f"{layer}.output.dense"), x), weights, f"{layer}.output.LayerNorm")
+    return x
+
+
+def sparse_embedding(ids, weights):
+    """Token ID -> weight of the tokens with a non-zero weight"""
+    hidden = forward(ids, [0] * len(ids), weights)
+    hidden = [[gelu(v) for v in row] for row in linear(hidden, weights, "cls.predictions.transform.dense")]
+    hidden = layer_norm(hidden, weights, "cls.predictions.transform.LayerNorm")
+    decoder = matrix(weights, "bert.embeddings.word_embeddings.weight")
+    bias = vector(weights, "cls.predictions.bias")
+    sparse = {}
+    for token, (row, b) in enumerate(zip(decoder, bias)):
+        logit = max(sum(h * w for h, w in zip(position, row)) + b for position in hidden)
+        if logit > 0.0:
+            sparse[str(token)] = round(math.log1p(logit), 7)
+    return sparse
+
+
+def main():
+    folder = Path(__file__).parent
+    vocab = load_vocab(folder)
+    model_config = config(vocab)
+    weights = random_weights(tensors(model_config["vocab_size"]))
+    shape, bias = weights["cls.predictions.bias"]
+    weights["cls.predictions.bias"] = (shape, array("f", [DECODER_BIAS + v for v in bias]))
+    (folder / "config.json").write_text(json.dumps(model_config, indent=2) + "\n")
+    write_safetensors(folder / "model.safetensors", weights)
+
+    reference = []
+    for text in TEXTS:
+        ids = tokenize(text, vocab)
+        reference.append({"text": text, "ids": ids, "sparse": sparse_embedding(ids, weights)})
+    (folder / "reference.json").write_text(json.dumps(reference, indent=2) + "\n")
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,80 @@
+"""
+Writes the tiny model2vec fixture used by tests/static_model.rs:
+model.safetensors with a random quantized table ("embeddings" with fewer rows
+than the vocabulary, "mapping" from token IDs to rows and per-token
+"weights"), and reference.json with the token IDs and embeddings of a few
+texts. The tokenizer is the WordPiece one of tests/fixtures/tiny_bert.
+
+The reference embeddings follow model2vec's StaticModel.encode: tokenize
+without special tokens, drop unknown tokens, average the weighted rows and
+L2-normalize. Only the standard library is used:
+
+    python tests/fixtures/tiny_static/generate.py
+"""
+
+import json
+import math
+import random
+import sys
+from array import array
+from pathlib import Path
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent))
+from common import load_vocab, words, write_safetensors  # noqa: E402
+
+DIMENSION = 16
+ROWS = 40
+
+TEXTS = [
+    "hello world",
+    "the cat sat on the mat",
+    "rust is a fast text embedding model.",
+    "the dogs sat on the mat and the cats are in the rain!",
+    "hello zebra world",
+    "zebra",
+]
+
+
+def main():
+    folder = Path(__file__).parent
+    vocab = load_vocab(folder)
+    vocab_size = max(vocab.values()) + 1
+
+    rng = random.Random(0)
+    embeddings = [[rng.gauss(0.0, 1.0) for _ in range(DIMENSION)] for _ in range(ROWS)]
+    mapping = [rng.randrange(ROWS) for _ in range(vocab_size)]
+    weights = [rng.uniform(0.5, 2.0) for _ in range(vocab_size)]
+    write_safetensors(
+        folder / "model.safetensors",
+        {
+            "embeddings": ((ROWS, DIMENSION), [v for row in embeddings for v in row]),
+            "mapping": ((vocab_size,), mapping),
+            "weights": ((vocab_size,), weights),
+        },
+        dtypes={"mapping": "I64"},
+    )
+    (folder / "config.json").write_text(
+        json.dumps({"model_type": "model2vec", "architectures": ["StaticModel"], "hidden_dim": DIMENSION, "normalize": True}, indent=2)
+        + "\n"
+    )
+
+    # Round the stored table to float32, like the Rust side reads it
+    embeddings = [list(array("f", row)) for row in embeddings]
+    weights = list(array("f", weights))
+    reference = []
+    for text in TEXTS:
+        ids = words(text, vocab)
+        known = [i for i in ids if i != vocab["[UNK]"]]
+        embedding = [0.0] * DIMENSION
+        for i in known:
+            for d in range(DIMENSION):
+                embedding[d] += embeddings[mapping[i]][d] * weights[i] / len(known)
+        norm = math.sqrt(sum(v * v for v in embedding))
+        if norm > 0:
+            embedding = [v / norm for v in embedding]
+        reference.append({"text": text, "ids": ids, "embedding": [round(v, 7) for v in embedding]})
+    (folder / "reference.json").write_text(json.dumps(reference, indent=2) + "\n")
+
+
+if __name__ == "__main__":
+    main()
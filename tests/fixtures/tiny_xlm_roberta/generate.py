@@ -0,0 +1,265 @@
+"""
+Writes the tiny XLM-RoBERTa fixture used by tests/xlm_roberta.rs: config.json,
+a sentencepiece-style (Unigram + Metaspace) tokenizer.json, randomly
+initialized model.safetensors, and reference.json with the token IDs and
+embeddings of a few texts.
+
+The reference embeddings come from the plain-Python forward pass below, which
+follows HuggingFace's XLMRobertaModel (position IDs offset by the padding
+index, post-LayerNorm encoder, exact GELU) followed by mean pooling and L2
+normalization. Only the standard library is used:
+
+    python tests/fixtures/tiny_xlm_roberta/generate.py
+"""
+
+import json
+import math
+import random
+import sys
+from array import array
+from pathlib import Path
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent))
+from common import add, gelu, matrix, vector, write_safetensors  # noqa: E402
+
+HIDDEN_SIZE = 32
+NUM_LAYERS = 2
+NUM_HEADS = 4
+INTERMEDIATE_SIZE = 64
+PAD_ID = 1
+# Position IDs start at PAD_ID + 1, so 32 tokens need 34 positions
+MAX_POSITIONS = 34
+LAYER_NORM_EPS = 1e-5
+
+SPECIAL_TOKENS = ["<s>", "<pad>", "</s>", "<unk>"]
+WORDS = [
+    "hallo", "welt", "ciao", "mondo", "hola", "mundo", "hello", "world",
+    "wetter", "heute", "tempo", "oggi", "tiempo", "hoy", "das", "il", "el",
+    "ist", "è", "es", "schön", "bello", "bueno",
+]
+PIECES = ["▁" + word for word in WORDS] + ["▁"] + list("abcdefghijklmnopqrstuvwxyzäöüßèéñ")
+VOCAB = SPECIAL_TOKENS + PIECES
+
+TEXTS = [
+    "Hallo Welt",
+    "ciao mondo",
+    "Das Wetter ist heute schön",
+    "el tiempo es bueno hoy",
+    "hello xyz",
+]
+
+
+def config():
+    return {
+        "architectures": ["XLMRobertaModel"],
+        "model_type": "xlm-roberta",
+        "vocab_size": len(VOCAB),
+        "hidden_size": HIDDEN_SIZE,
+        "num_hidden_layers": NUM_LAYERS,
+        "num_attention_heads": NUM_HEADS,
+        "intermediate_size": INTERMEDIATE_SIZE,
+        "hidden_act": "gelu",
+        "hidden_dropout_prob": 0.1,
+        "attention_probs_dropout_prob": 0.1,
+        "max_position_embeddings": MAX_POSITIONS,
+        "type_vocab_size": 1,
+        "initializer_range": 0.02,
+        "layer_norm_eps": LAYER_NORM_EPS,
+        "pad_token_id": PAD_ID,
+        "bos_token_id": 0,
+        "eos_token_id": 2,
+        "position_embedding_type": "absolute",
+    }
+
+
+def tokenizer():
+    def special(token):
+        return {"id": token, "type_id": 0}
+
+    metaspace = {"type": "Metaspace", "replacement": "▁", "prepend_scheme": "always", "split": True}
+    return {
+        "version": "1.0",
+        "truncation": None,
+        "padding": None,
+        "added_tokens": [
+            {
+                "id": i,
+                "content": token,
+                "single_word": False,
+                "lstrip": False,
+                "rstrip": False,
+                "normalized": False,
+                "special": True,
+            }
+            for i, token in enumerate(SPECIAL_TOKENS)
+        ],
+        "normalizer": {"type": "Lowercase"},
+        "pre_tokenizer": metaspace,
+        "post_processor": {
+            "type": "TemplateProcessing",
+            "single": [
+                {"SpecialToken": special("<s>")},
+                {"Sequence": {"id": "A", "type_id": 0}},
+                {"SpecialToken": special("</s>")},
+            ],
+            "pair": [
+                {"SpecialToken": special("<s>")},
+                {"Sequence": {"id": "A", "type_id": 0}},
+                {"SpecialToken": special("</s>")},
+                {"SpecialToken": special("</s>")},
+                {"Sequence": {"id": "B", "type_id": 0}},
+                {"SpecialToken": special("</s>")},
+            ],
+            "special_tokens": {
+                token: {"id": token, "ids": [VOCAB.index(token)], "tokens": [token]}
+                for token in ("<s>", "</s>")
+            },
+        },
+        "decoder": metaspace,
+        "model": {
+            "type": "Unigram",
+            "unk_id": VOCAB.index("<unk>"),
+            # Whole words score better than their characters
+            "vocab": [[token, 0.0] for token in SPECIAL_TOKENS]
+            + [[piece, -2.0 if len(piece) > 1 else -10.0] for piece in PIECES],
+            "byte_fallback": False,
+        },
+    }
+
+
+def tokenize(text):
+    """Token IDs the tokenizer above produces for the test texts"""
+    ids = [VOCAB.index("<s>")]
+    for word in text.lower().split():
+        piece = "▁" + word
+        if piece in VOCAB:
+            ids.append(VOCAB.index(piece))
+        else:
+            ids.append(VOCAB.index("▁"))
+            ids.extend(VOCAB.index(c) for c in word)
+    ids.append(VOCAB.index("</s>"))
+    return ids
+
+
+def tensors():
+    """Tensor name -> shape, as expected by candle's XLMRobertaModel"""
+    shapes = {
+        "embeddings.word_embeddings.weight": (len(VOCAB), HIDDEN_SIZE),
+        "embeddings.position_embeddings.weight": (MAX_POSITIONS, HIDDEN_SIZE),
+        "embeddings.token_type_embeddings.weight": (1, HIDDEN_SIZE),
+        "embeddings.LayerNorm.weight": (HIDDEN_SIZE,),
+        "embeddings.LayerNorm.bias": (HIDDEN_SIZE,),
+    }
+    for i in range(NUM_LAYERS):
+        layer = f"encoder.layer.{i}"
+        for name in ("query", "key", "value"):
+            shapes[f"{layer}.attention.self.{name}.weight"] = (HIDDEN_SIZE, HIDDEN_SIZE)
+            shapes[f"{layer}.attention.self.{name}.bias"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.attention.output.dense.weight"] = (HIDDEN_SIZE, HIDDEN_SIZE)
+        shapes[f"{layer}.attention.output.dense.bias"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.attention.output.LayerNorm.weight"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.attention.output.LayerNorm.bias"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.intermediate.dense.weight"] = (INTERMEDIATE_SIZE, HIDDEN_SIZE)
+        shapes[f"{layer}.intermediate.dense.bias"] = (INTERMEDIATE_SIZE,)
+        shapes[f"{layer}.output.dense.weight"] = (HIDDEN_SIZE, INTERMEDIATE_SIZE)
+        shapes[f"{layer}.output.dense.bias"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.output.LayerNorm.weight"] = (HIDDEN_SIZE,)
+        shapes[f"{layer}.output.LayerNorm.bias"] = (HIDDEN_SIZE,)
+    return shapes
+
+
+def random_weights(shapes, seed=0):
+    """Name -> (shape, float32 values); every tensor is random so that each
+    one affects the reference embeddings"""
+    rng = random.Random(seed)
+    weights = {}
+    for name, shape in shapes.items():
+        count = math.prod(shape)
+        if name.endswith("LayerNorm.weight"):
+            values = [1.0 + rng.gauss(0.0, 0.1) for _ in range(count)]
+        elif name.endswith("bias"):
+            values = [rng.gauss(0.0, 0.05) for _ in range(count)]
+        else:
+            values = [rng.gauss(0.0, 0.2) for _ in range(count)]
+        weights[name] = (shape, array("f", values))
+    return weights
+
+
+# --- Reference forward pass, one sequence without padding ---
+
+
+def linear(x, weights, prefix):
+    w, b = matrix(weights, prefix + ".weight"), vector(weights, prefix + ".bias")
+    return [[sum(xi * wi for xi, wi in zip(row, w_row)) + bj for w_row, bj in zip(w, b)] for row in x]
+
+
+def layer_norm(x, weights, prefix):
+    gamma, beta = vector(weights, prefix + ".weight"), vector(weights, prefix + ".bias")
+    out = []
+    for row in x:
+        mean = sum(row) / len(row)
+        var = sum((v - mean) ** 2 for v in row) / len(row)
+        out.append([(v - mean) / math.sqrt(var + LAYER_NORM_EPS) * g + b for v, g, b in zip(row, gamma, beta)])
+    return out
+
+
+def attention(x, weights, prefix):
+    q = linear(x, weights, prefix + ".self.query")
+    k = linear(x, weights, prefix + ".self.key")
+    v = linear(x, weights, prefix + ".self.value")
+    head = HIDDEN_SIZE // NUM_HEADS
+    context = [[0.0] * HIDDEN_SIZE for _ in x]
+    for h in range(NUM_HEADS):
+        cols = range(h * head, (h + 1) * head)
+        for i in range(len(x)):
+            scores = [sum(q[i][c] * k[j][c] for c in cols) / math.sqrt(head) for j in range(len(x))]
+            top = max(scores)
+            exp = [math.exp(s - top) for s in scores]
+            total = sum(exp)
+            for c in cols:
+                context[i][c] = sum(e / total * v[j][c] for j, e in enumerate(exp))
+    out = linear(context, weights, prefix + ".output.dense")
+    return layer_norm(add(out, x), weights, prefix + ".output.LayerNorm")
+
+
+def forward(ids, weights):
+    word = matrix(weights, "embeddings.word_embeddings.weight")
+    position = matrix(weights, "embeddings.position_embeddings.weight")
+    token_type = matrix(weights, "embeddings.token_type_embeddings.weight")
+    # XLM-RoBERTa numbers positions from PAD_ID + 1
+    x = [
+        [w + p + t for w, p, t in zip(word[token], position[PAD_ID + 1 + i], token_type[0])]
+        for i, token in enumerate(ids)
+    ]
+    x = layer_norm(x, weights, "embeddings.LayerNorm")
+    for i in range(NUM_LAYERS):
+        layer = f"encoder.layer.{i}"
+        x = attention(x, weights, f"{layer}.attention")
+        hidden = [[gelu(v) for v in row] for row in linear(x, weights, f"{layer}.intermediate.dense")]
+        x = layer_norm(add(linear(hidden, weights, f"{layer}.output.dense"), x), weights, f"{layer}.output.LayerNorm")
+    return x
+
+
+def embed(ids, weights):
+    tokens = forward(ids, weights)
+    mean = [sum(col) / len(tokens) for col in zip(*tokens)]
+    norm = math.sqrt(sum(v * v for v in mean))
+    return [v / norm for v in mean]
+
+
+def main():
+    folder = Path(__file__).parent
+    weights = random_weights(tensors())
+    (folder / "config.json").write_text(json.dumps(config(), indent=2) + "\n")
+    (folder / "tokenizer.json").write_text(json.dumps(tokenizer(), ensure_ascii=False) + "\n")
+    write_safetensors(folder / "model.safetensors", weights)
+
+    reference = []
+    for text in TEXTS:
+        ids = tokenize(text)
+        reference.append({"text": text, "ids": ids, "embedding": [round(v, 7) for v in embed(ids, weights)]})
+    (folder / "reference.json").write_text(json.dumps(reference, indent=2, ensure_ascii=False) + "\n")
+
+
+if __name__ == "__main__":
+    main()
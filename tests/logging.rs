@@ -0,0 +1,111 @@
+// Tests for `embedder_set_log_callback`. The callback is process-wide state, so
+// these tests run one at a time in their own test binary.
+mod common;
+
+use common::{CONFIG, TOKENIZER, tiny_weights};
+use rust_transformer::{
+    EMBEDDER_LOG_DEBUG, EMBEDDER_LOG_INFO, EmbedderErrorCode, embedder_free,
+    embedder_init_from_buffers, embedder_set_log_callback,
+};
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_void};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static SERIAL: Mutex<()> = Mutex::new(());
+static EVENTS: Mutex<Vec<(i32, String, String)>> = Mutex::new(Vec::new());
+
+unsafe extern "C" fn record(
+    level: i32,
+    target: *const c_char,
+    message: *const c_char,
+    user_data: *mut c_void,
+) {
+    let target = unsafe { CStr::from_ptr(target) }
+        .to_string_lossy()
+        .into_owned();
+    let message = unsafe { CStr::from_ptr(message) }
+        .to_string_lossy()
+        .into_owned();
+    EVENTS.lock().unwrap().push((level, target, message));
+    unsafe { &*(user_data as *const AtomicUsize) }.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Removes itself on the first event
+unsafe extern "C" fn record_once(
+    level: i32,
+    target: *const c_char,
+    message: *const c_char,
+    user_data: *mut c_void,
+) {
+    unsafe { record(level, target, message, user_data) };
+    let code = unsafe { embedder_set_log_callback(None, 0, std::ptr::null_mut()) };
+    assert_eq!(code, EmbedderErrorCode::Success);
+}
+
+/// Load the tiny BERT from buffers, which logs at info level
+fn load_model() {
+    let name = CString::new("mini_lm_v2").unwrap();
+    let weights = tiny_weights();
+    let handle = unsafe {
+        embedder_init_from_buffers(
+            name.as_ptr(),
+            TOKENIZER.as_ptr(),
+            TOKENIZER.len(),
+            CONFIG.as_ptr(),
+            CONFIG.len(),
+            weights.as_ptr(),
+            weights.len(),
+        )
+    };
+    assert!(!handle.is_null());
+    unsafe { embedder_free(handle) };
+}
+
+#[test]
+fn callback_receives_events() {
+    let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    EVENTS.lock().unwrap().clear();
+    let count = AtomicUsize::new(0);
+    let user_data = &count as *const AtomicUsize as *mut c_void;
+
+    let code = unsafe { embedder_set_log_callback(Some(record), EMBEDDER_LOG_INFO, user_data) };
+    assert_eq!(code, EmbedderErrorCode::Success);
+    load_model();
+    let code = unsafe { embedder_set_log_callback(None, 0, std::ptr::null_mut()) };
+    assert_eq!(code, EmbedderErrorCode::Success);
+
+    let events = EVENTS.lock().unwrap().clone();
+    assert_eq!(count.load(Ordering::SeqCst), events.len());
+    let (level, target, message) = events
+        .iter()
+        .find(|(_, _, message)| message.starts_with("Model loaded from explicit files"))
+        .expect("no model loading event");
+    assert_eq!(*level, EMBEDDER_LOG_INFO);
+    assert!(target.starts_with("rust_transformer"), "{target}");
+    assert!(message.contains("model_id="), "{message}");
+    // Debug events are above the requested level
+    assert!(
+        events
+            .iter()
+            .all(|(level, _, _)| *level <= EMBEDDER_LOG_INFO)
+    );
+
+    // Nothing is forwarded once the callback is removed
+    load_model();
+    assert_eq!(count.load(Ordering::SeqCst), events.len());
+}
+
+#[test]
+fn callback_can_replace_itself() {
+    let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    EVENTS.lock().unwrap().clear();
+    let count = AtomicUsize::new(0);
+    let user_data = &count as *const AtomicUsize as *mut c_void;
+
+    let code =
+        unsafe { embedder_set_log_callback(Some(record_once), EMBEDDER_LOG_DEBUG, user_data) };
+    assert_eq!(code, EmbedderErrorCode::Success);
+    load_model();
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+}
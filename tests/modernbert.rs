@@ -0,0 +1,17 @@
+// Golden-vector tests for the ModernBERT architecture against reference
+// embeddings checked into tests/fixtures/tiny_modernbert. The fixture and the
+// reference vectors are written by generate.py in that folder, whose
+// plain-Python forward pass follows HuggingFace's ModernBertModel. In the
+// padded batch, texts are also unpadded again inside the model.
+mod common;
+
+use common::Fixture;
+
+const FIXTURE: Fixture = Fixture {
+    model_id: "nomic-ai/modernbert-embed-base",
+    dimension: 32,
+    max_tokens: 64,
+    ..Fixture::new("modernbert", "tiny_modernbert")
+};
+
+common::fixture_tests!(modernbert: FIXTURE);
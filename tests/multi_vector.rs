@@ -0,0 +1,147 @@
+// Golden-vector tests for ColBERT token embeddings against reference vectors
+// checked into tests/fixtures/tiny_colbert. The fixture and the reference
+// vectors are written by generate.py in that folder, whose plain-Python
+// forward pass follows ColBERT's HF_ColBERT with its query and document
+// tokenizers: [Q] and [D] markers, [MASK] padding of queries and the
+// punctuation skiplist of documents.
+mod common;
+
+use common::{Fixture, Reference, assert_rows_close};
+use rust_transformer::similarity::{dot, max_sim};
+use rust_transformer::{MultiVectorEmbedding, Task};
+
+const FIXTURE: Fixture = Fixture {
+    model_id: "colbert-ir/colbertv2.0",
+    // The projection size, read from the weights
+    dimension: 16,
+    max_tokens: 64,
+    ..Fixture::new("colbert", "tiny_colbert")
+};
+
+// colbertv2.0's query_maxlen
+const QUERY_MAX_TOKENS: usize = 32;
+
+common::fixture_tests!(colbert: FIXTURE => tokenizer_matches_reference_ids, reports_model_shape);
+
+/// Reference token IDs and vectors of a text embedded as `task`
+fn expected(reference: &Reference, task: Task) -> (Vec<u32>, MultiVectorEmbedding) {
+    let side = match task {
+        Task::Query => "query",
+        _ => "document",
+    };
+    (
+        reference.get(&format!("{side}_ids")),
+        reference.get(&format!("{side}_vectors")),
+    )
+}
+
+/// Every reference, embedded as `task` one at a time and in batches of
+/// different sizes, matches its reference vectors
+fn check_vectors(task: Task) {
+    let references = FIXTURE.references();
+    let texts: Vec<&str> = references.iter().map(|r| r.text.as_str()).collect();
+    for batch_size in [1, 3, 32] {
+        let mut colbert = FIXTURE.builder().batch_size(batch_size).build().unwrap();
+        let embeddings = colbert.embed_tokens(&texts, task).unwrap();
+        assert_eq!(embeddings.len(), texts.len());
+        for (vectors, reference) in embeddings.iter().zip(&references) {
+            let (_, expected) = expected(reference, task);
+            assert_rows_close(vectors, &expected, 1e-4, &reference.text);
+            for vector in vectors {
+                assert!((dot(vector, vector) - 1.0).abs() < 1e-5);
+            }
+        }
+    }
+}
+
+#[test]
+fn reports_multi_vector() {
+    let info = FIXTURE.embedder().info().clone();
+    assert!(info.multi_vector);
+    assert_eq!(info.query_max_tokens, Some(QUERY_MAX_TOKENS));
+    let json = serde_json::to_value(info).unwrap();
+    assert_eq!(json["multi_vector"], true);
+    assert_eq!(json["query_max_tokens"], QUERY_MAX_TOKENS);
+}
+
+#[test]
+fn query_vectors_match_reference() {
+    check_vectors(Task::Query);
+}
+
+#[test]
+fn document_vectors_match_reference() {
+    check_vectors(Task::Document);
+}
+
+#[test]
+fn queries_are_marked_and_padded_with_mask() {
+    // "is rust fast?" is [CLS] [Q] is rust fast ? [SEP], then 25 [MASK]s;
+    // every token keeps its vector, "?" and the [MASK]s included
+    let references = FIXTURE.references();
+    let question = references
+        .iter()
+        .find(|r| r.text == "is rust fast?")
+        .unwrap();
+    let (ids, vectors) = expected(question, Task::Query);
+    assert_eq!(ids.len(), QUERY_MAX_TOKENS);
+    assert_eq!(&ids[2..7], &question.ids[1..]);
+    assert_ne!(ids[1], ids[2]);
+    assert_eq!(vectors.len(), QUERY_MAX_TOKENS);
+
+    let mut colbert = FIXTURE.embedder();
+    let query = colbert
+        .embed_tokens(&[&question.text], Task::Query)
+        .unwrap();
+    assert_eq!(query[0].len(), QUERY_MAX_TOKENS);
+    // A lower token limit shortens queries too
+    let mut colbert = FIXTURE.builder().max_tokens(8).build().unwrap();
+    let long = &references[2].text;
+    let query = colbert.embed_tokens(&[long], Task::Query).unwrap();
+    assert_eq!(query[0].len(), 8);
+}
+
+#[test]
+fn documents_are_marked_and_skip_punctuation() {
+    // "is rust fast?" is [CLS] [D] is rust fast ? [SEP]; "?" is left out
+    let references = FIXTURE.references();
+    let question = references
+        .iter()
+        .find(|r| r.text == "is rust fast?")
+        .unwrap();
+    let (ids, vectors) = expected(question, Task::Document);
+    assert_eq!(ids.len(), question.ids.len() + 1);
+    assert_eq!(vectors.len(), ids.len() - 1);
+
+    // The markers tell queries and documents apart
+    let mut colbert = FIXTURE.embedder();
+    let (query_ids, _) = expected(question, Task::Query);
+    assert_ne!(ids[1], query_ids[1]);
+    let query = colbert
+        .embed_tokens(&[&question.text], Task::Query)
+        .unwrap();
+    let document = colbert
+        .embed_tokens(&[&question.text], Task::Document)
+        .unwrap();
+    assert!(dot(&query[0][0], &document[0][0]) < 1.0 - 1e-4);
+}
+
+#[test]
+fn max_sim_sums_best_matches() {
+    let query = vec![vec![1.0, 0.0], vec![0.0, 1.0]];
+    let document = vec![vec![0.6, 0.8], vec![1.0, 0.0], vec![0.0, -1.0]];
+    assert!((max_sim(&query, &document) - 1.8).abs() < 1e-6);
+    assert_eq!(max_sim(&query, &Vec::new()), 0.0);
+
+    // A document matches itself best: every token finds itself, scoring 1.0 each
+    let references = FIXTURE.references();
+    let mut colbert = FIXTURE.embedder();
+    let texts: Vec<&str> = references.iter().map(|r| r.text.as_str()).collect();
+    let embeddings = colbert.embed_tokens(&texts, Task::Document).unwrap();
+    let document = &embeddings[1];
+    let own = max_sim(document, document);
+    assert!((own - document.len() as f32).abs() < 1e-4);
+    for other in &embeddings {
+        assert!(max_sim(document, other) <= own + 1e-4);
+    }
+}
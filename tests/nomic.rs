@@ -0,0 +1,39 @@
+// Parity tests for the NomicBert architecture against reference embeddings
+// checked into tests/fixtures/tiny_nomic_bert. The fixture and the reference
+// vectors are written by generate.py in that folder, whose plain-Python forward
+// pass follows nomic-ai's NomicBertModel.
+mod common;
+
+use common::Fixture;
+use rust_transformer::Task;
+
+const FIXTURE: Fixture = Fixture {
+    model_id: "nomic-ai/nomic-embed-text-v1.5",
+    dimension: 32,
+    // Capped at max_trained_positions rather than n_positions
+    max_tokens: 48,
+    ..Fixture::new("nomic", "tiny_nomic_bert")
+};
+
+common::fixture_tests!(nomic: FIXTURE);
+
+#[test]
+fn reports_task_prefixes() {
+    let embedder = FIXTURE.embedder();
+    let info = embedder.info();
+    assert_eq!(info.query_prefix.as_deref(), Some("search_query: "));
+    assert_eq!(info.document_prefix.as_deref(), Some("search_document: "));
+}
+
+#[test]
+fn task_prefix_changes_embedding() {
+    let plain = FIXTURE.embedder().embed("rust is fast").unwrap();
+    let query = FIXTURE
+        .builder()
+        .task(Task::Query)
+        .build()
+        .unwrap()
+        .embed("rust is fast")
+        .unwrap();
+    assert_ne!(plain, query);
+}
@@ -0,0 +1,109 @@
+// Golden-score tests for BERT cross-encoders against reference scores checked
+// into tests/fixtures/tiny_cross_encoder. The fixture and the reference scores
+// are written by generate.py in that folder, whose plain-Python forward pass
+// follows HuggingFace's BertForSequenceClassification.
+mod common;
+
+use common::{Fixture, Reference, fixture, fixture_tokenizer};
+use rust_transformer::{EmbedderBuilder, Error};
+use serde_json::Value;
+use tokenizers::Tokenizer;
+
+const FIXTURE: Fixture = Fixture {
+    model_id: "cross-encoder/ms-marco-MiniLM-L6-v2",
+    dimension: 32,
+    max_tokens: 64,
+    ..Fixture::new("cross_encoder", "tiny_cross_encoder")
+};
+
+common::fixture_tests!(cross_encoder: FIXTURE => reports_model_shape);
+
+/// Query and passage of a reference pair
+fn pair(reference: &Reference) -> (String, String) {
+    (reference.get("query"), reference.get("passage"))
+}
+
+#[test]
+fn tokenizer_matches_reference_pairs() {
+    let tokenizer = Tokenizer::from_file(fixture_tokenizer(FIXTURE.folder)).unwrap();
+    for reference in FIXTURE.references() {
+        let (query, passage) = pair(&reference);
+        let encoding = tokenizer
+            .encode((query.as_str(), passage.as_str()), true)
+            .unwrap();
+        assert_eq!(encoding.get_ids(), reference.ids, "{query} / {passage}");
+        assert_eq!(
+            encoding.get_type_ids(),
+            reference.get::<Vec<u32>>("type_ids"),
+            "{query} / {passage}"
+        );
+    }
+}
+
+#[test]
+fn reports_cross_encoder() {
+    let info = FIXTURE.embedder().info().clone();
+    assert!(info.cross_encoder);
+    let json = serde_json::to_value(info).unwrap();
+    assert_eq!(json["cross_encoder"], true);
+}
+
+#[test]
+fn scores_match_reference() {
+    let mut reranker = FIXTURE.embedder();
+    for reference in FIXTURE.references() {
+        let (query, passage) = pair(&reference);
+        let scores = reranker.rerank(&query, &[&passage]).unwrap();
+        let expected: f32 = reference.get("score");
+        assert!(
+            (scores[0] - expected).abs() < 1e-4,
+            "'{query}' / '{passage}': {} != {expected}",
+            scores[0]
+        );
+    }
+}
+
+#[test]
+fn batches_match_single_pairs() {
+    // Passages of different lengths are padded together, in one batch or two
+    let (passages, expected): (Vec<String>, Vec<f32>) = FIXTURE
+        .references()
+        .iter()
+        .filter(|r| pair(r).0 == "is rust fast?")
+        .map(|r| (pair(r).1, r.get::<f32>("score")))
+        .unzip();
+    for batch_size in [1, 2, 32] {
+        let mut reranker = FIXTURE.builder().batch_size(batch_size).build().unwrap();
+        let scores = reranker.rerank("is rust fast?", &passages).unwrap();
+        assert_eq!(scores.len(), passages.len());
+        for ((score, expected), passage) in scores.iter().zip(&expected).zip(&passages) {
+            assert!(
+                (score - expected).abs() < 1e-4,
+                "batch of {batch_size}, '{passage}': {score} != {expected}"
+            );
+        }
+    }
+    let none: [&str; 0] = [];
+    assert!(
+        FIXTURE
+            .embedder()
+            .rerank("is rust fast?", &none)
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn rejects_classifiers_with_several_labels() {
+    let mut config: Value =
+        serde_json::from_slice(&std::fs::read(fixture("tiny_cross_encoder/config.json")).unwrap())
+            .unwrap();
+    config["id2label"] = serde_json::json!({ "0": "contradiction", "1": "entailment" });
+    let err = EmbedderBuilder::new("cross_encoder")
+        .tokenizer_path(fixture("tiny_bert/tokenizer.json"))
+        .config_bytes(serde_json::to_vec(&config).unwrap())
+        .weights_path(fixture("tiny_cross_encoder/model.safetensors"))
+        .build()
+        .unwrap_err();
+    assert!(matches!(err, Error::Config(_)), "{err}");
+}
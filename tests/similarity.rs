@@ -0,0 +1,84 @@
+// Tests for the similarity functions on hand-written vectors; they need no model.
+use rust_transformer::Similarity;
+use rust_transformer::similarity::{cosine, dot, similarity_matrix, top_k};
+
+#[test]
+fn cosine_ignores_the_norms() {
+    let a = [1.0, 2.0, 2.0];
+    let b = [2.0, 4.0, 4.0];
+    assert_eq!(dot(&a, &b), 18.0);
+    assert!((cosine(&a, &b) - 1.0).abs() < 1e-6);
+    assert!((cosine(&a, &[-1.0, -2.0, -2.0]) + 1.0).abs() < 1e-6);
+    assert_eq!(cosine(&[1.0, 0.0], &[0.0, 1.0]), 0.0);
+
+    // Dot only matches cosine for unit vectors
+    assert_ne!(
+        Similarity::Dot.score(&a, &b),
+        Similarity::Cosine.score(&a, &b)
+    );
+    let unit = [0.6, 0.8];
+    assert!((Similarity::Dot.score(&unit, &unit) - cosine(&unit, &unit)).abs() < 1e-6);
+}
+
+#[test]
+fn zero_vectors_score_zero() {
+    let zero = [0.0; 3];
+    let a = [1.0, 2.0, 3.0];
+    assert_eq!(dot(&zero, &a), 0.0);
+    assert_eq!(cosine(&zero, &a), 0.0);
+    assert_eq!(cosine(&a, &zero), 0.0);
+    assert_eq!(cosine(&zero, &zero), 0.0);
+    assert!(!cosine(&zero, &zero).is_nan());
+}
+
+#[test]
+fn similarity_matrix_is_row_major() {
+    let a = [1.0, 0.0, 0.0, 2.0];
+    let b = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+    assert_eq!(
+        similarity_matrix(&a, &b, 2, Similarity::Dot),
+        [1.0, 0.0, 0.0, 0.0, 2.0, 0.0]
+    );
+    // The zero row of `b` scores 0 against everything
+    assert_eq!(
+        similarity_matrix(&a, &b, 2, Similarity::Cosine),
+        [1.0, 0.0, 0.0, 0.0, 1.0, 0.0]
+    );
+    assert!(similarity_matrix(&[], &b, 2, Similarity::Dot).is_empty());
+}
+
+#[test]
+fn top_k_sorts_best_first() {
+    let matrix = [0.0, 1.0, 1.0, 0.0, 3.0, 0.0, -1.0, 0.0];
+    let results = top_k(&[1.0, 0.0], &matrix, 2, 2, Similarity::Dot);
+    assert_eq!(results, [(2, 3.0), (1, 1.0)]);
+}
+
+#[test]
+fn top_k_keeps_the_order_of_ties() {
+    let matrix = [1.0, 0.0, 0.0, 1.0, 2.0, 0.0, 0.0, 2.0];
+    let results = top_k(&[1.0, 1.0], &matrix, 2, 4, Similarity::Dot);
+    assert_eq!(results, [(2, 2.0), (3, 2.0), (0, 1.0), (1, 1.0)]);
+
+    // Under cosine all four rows tie
+    let indices: Vec<usize> = top_k(&[1.0, 1.0], &matrix, 2, 3, Similarity::Cosine)
+        .into_iter()
+        .map(|(index, _)| index)
+        .collect();
+    assert_eq!(indices, [0, 1, 2]);
+}
+
+#[test]
+fn top_k_returns_at_most_the_number_of_rows() {
+    let matrix = [1.0, 0.0, 0.0, 1.0];
+    assert_eq!(top_k(&[1.0, 0.0], &matrix, 2, 10, Similarity::Dot).len(), 2);
+    assert!(top_k(&[1.0, 0.0], &matrix, 2, 0, Similarity::Dot).is_empty());
+    assert!(top_k(&[1.0, 0.0], &[], 2, 3, Similarity::Dot).is_empty());
+}
+
+#[test]
+fn top_k_with_a_zero_query() {
+    let matrix = [1.0, 0.0, 0.0, 1.0, 0.5, 0.5];
+    let results = top_k(&[0.0, 0.0], &matrix, 2, 5, Similarity::Cosine);
+    assert_eq!(results, [(0, 0.0), (1, 0.0), (2, 0.0)]);
+}
@@ -0,0 +1,88 @@
+// Golden-weight tests for SPLADE sparse embeddings against reference weights
+// checked into tests/fixtures/tiny_splade. The fixture and the reference
+// weights are written by generate.py in that folder, whose plain-Python
+// forward pass follows HuggingFace's BertForMaskedLM and SPLADE's max pooling.
+mod common;
+
+use common::{Fixture, Reference, assert_close};
+use rust_transformer::{SparseEmbedding, similarity::sparse_dot};
+
+const FIXTURE: Fixture = Fixture {
+    model_id: "naver/splade-cocondenser-ensembledistil",
+    // One weight per vocabulary token
+    dimension: 78,
+    max_tokens: 64,
+    ..Fixture::new("splade", "tiny_splade")
+};
+
+common::fixture_tests!(splade: FIXTURE => tokenizer_matches_reference_ids, reports_model_shape);
+
+/// Weight of every vocabulary token, so that a token the tolerance puts on
+/// either side of 0 may be missing from one of two embeddings compared
+fn dense(sparse: &SparseEmbedding) -> Vec<f32> {
+    (0..FIXTURE.dimension as u32)
+        .map(|id| sparse.get(&id).copied().unwrap_or(0.0))
+        .collect()
+}
+
+fn expected(reference: &Reference) -> Vec<f32> {
+    dense(&reference.get("sparse"))
+}
+
+#[test]
+fn reports_sparse() {
+    let info = FIXTURE.embedder().info().clone();
+    assert!(info.sparse);
+    let json = serde_json::to_value(info).unwrap();
+    assert_eq!(json["sparse"], true);
+}
+
+#[test]
+fn weights_match_reference() {
+    let mut splade = FIXTURE.embedder();
+    for reference in FIXTURE.references() {
+        let sparse = splade.embed_sparse(&[&reference.text]).unwrap();
+        assert_close(
+            &dense(&sparse[0]),
+            &expected(&reference),
+            1e-4,
+            &reference.text,
+        );
+        assert!(sparse[0].values().all(|&weight| weight > 0.0));
+    }
+}
+
+#[test]
+fn batches_match_single_texts() {
+    // Texts of different lengths are padded together, in one batch or two
+    let references = FIXTURE.references();
+    let texts: Vec<&str> = references.iter().map(|r| r.text.as_str()).collect();
+    for batch_size in [1, 3, 32] {
+        let mut splade = FIXTURE.builder().batch_size(batch_size).build().unwrap();
+        let embeddings = splade.embed_sparse(&texts).unwrap();
+        assert_eq!(embeddings.len(), texts.len());
+        for (sparse, reference) in embeddings.iter().zip(&references) {
+            assert_close(&dense(sparse), &expected(reference), 1e-4, &reference.text);
+        }
+    }
+}
+
+#[test]
+fn sparse_dot_sums_shared_tokens() {
+    let a = SparseEmbedding::from([(1, 2.0), (5, 1.0), (9, 0.5)]);
+    let b = SparseEmbedding::from([(5, 3.0), (9, 4.0)]);
+    assert_eq!(sparse_dot(&a, &b), 5.0);
+    assert_eq!(sparse_dot(&b, &a), 5.0);
+    assert_eq!(sparse_dot(&a, &SparseEmbedding::new()), 0.0);
+
+    let mut splade = FIXTURE.embedder();
+    let references = FIXTURE.references();
+    let embeddings = splade
+        .embed_sparse(&[&references[0].text, &references[1].text])
+        .unwrap();
+    let expected: f32 = embeddings[0]
+        .iter()
+        .map(|(id, x)| x * embeddings[1].get(id).unwrap_or(&0.0))
+        .sum();
+    assert!((sparse_dot(&embeddings[0], &embeddings[1]) - expected).abs() < 1e-6);
+}
@@ -0,0 +1,119 @@
+// Tests for the SQLite extension (`sqlite` feature). The system libsqlite3
+// runs `sqlite3_rusttransformer_init()` as an auto extension on every
+// connection it opens, the way `.load` would; that also hands rusqlite, built
+// as a loadable extension, the SQLite API. The default model is the tiny BERT
+// from `common`, loaded from a folder through `BERT_MODEL_FOLDER`. The
+// variable is process-wide, so this runs as the only test of its own binary.
+#![cfg(feature = "sqlite")]
+mod common;
+
+use common::{CONFIG, TOKENIZER, assert_close, tiny_weights};
+use rusqlite::{Connection, Error};
+use rust_transformer::Embedder;
+use rust_transformer::similarity::cosine;
+use std::os::raw::{c_char, c_int, c_void};
+use std::ptr;
+
+#[link(name = "sqlite3")]
+unsafe extern "C" {
+    fn sqlite3_auto_extension(entry: Option<unsafe extern "C" fn()>) -> c_int;
+    fn sqlite3_open(filename: *const c_char, db: *mut *mut c_void) -> c_int;
+    fn sqlite3_close(db: *mut c_void) -> c_int;
+}
+
+type ExtensionInit = unsafe extern "C" fn(*mut c_void, *mut *mut c_char, *const c_void) -> c_int;
+
+unsafe extern "C" {
+    fn sqlite3_rusttransformer_init(
+        db: *mut c_void,
+        pz_err_msg: *mut *mut c_char,
+        p_api: *const c_void,
+    ) -> c_int;
+}
+
+/// In-memory connection with the extension's functions registered
+fn connection() -> Connection {
+    unsafe {
+        // SQLite casts the entry point back to its real signature
+        let entry = std::mem::transmute::<ExtensionInit, unsafe extern "C" fn()>(
+            sqlite3_rusttransformer_init,
+        );
+        assert_eq!(sqlite3_auto_extension(Some(entry)), 0);
+        // rusqlite can only open connections once an extension init passed it
+        // the API, so open the first one through libsqlite3 directly
+        let mut db = ptr::null_mut();
+        assert_eq!(sqlite3_open(c":memory:".as_ptr(), &mut db), 0);
+        sqlite3_close(db);
+    }
+    Connection::open_in_memory().unwrap()
+}
+
+fn blob_to_vector(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+/// SQL error message of a query returning one REAL
+fn query_error(db: &Connection, sql: &str) -> String {
+    match db.query_row(sql, [], |row| row.get::<_, f64>(0)) {
+        Err(Error::SqliteFailure(_, Some(message))) => message,
+        other => panic!("{sql}: expected an SQL error, got {other:?}"),
+    }
+}
+
+#[test]
+fn sql_functions_embed_and_compare_texts() {
+    let dir = std::env::temp_dir().join(format!("rust_transformer_sqlite_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("tokenizer.json"), TOKENIZER).unwrap();
+    std::fs::write(dir.join("config.json"), CONFIG).unwrap();
+    std::fs::write(dir.join("model.safetensors"), tiny_weights()).unwrap();
+    // Safety: this is the only test, so no other thread reads the environment
+    unsafe { std::env::set_var("BERT_MODEL_FOLDER", &dir) };
+
+    let db = connection();
+    let mut embedder = Embedder::new("mini_lm_v2").unwrap();
+    let (a, b) = ("the cat sat on the mat", "a dog ran in the park");
+
+    // embed() returns dim little-endian float32 values, with or without the model name
+    let blob: Vec<u8> = db
+        .query_row("SELECT embed(?1)", [a], |row| row.get(0))
+        .unwrap();
+    assert_eq!(blob.len(), 4 * embedder.info().dimension);
+    assert_close(&blob_to_vector(&blob), &embedder.embed(a).unwrap(), 1e-6, a);
+    let named: Vec<u8> = db
+        .query_row("SELECT embed(?1, 'mini_lm_v2')", [a], |row| row.get(0))
+        .unwrap();
+    assert_eq!(named, blob);
+
+    let expected = cosine(&embedder.embed(a).unwrap(), &embedder.embed(b).unwrap());
+    for function in ["cosine_sim", "vec_cosine"] {
+        let score: f64 = db
+            .query_row(
+                &format!("SELECT {function}(embed(?1), embed(?2))"),
+                [a, b],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(
+            (score as f32 - expected).abs() < 1e-6,
+            "{function}: {score}"
+        );
+    }
+
+    // Bad arguments are SQL errors rather than panics
+    assert!(query_error(&db, "SELECT embed(NULL)").contains("TEXT"));
+    assert!(!query_error(&db, "SELECT embed('text', NULL)").is_empty());
+    assert!(query_error(&db, "SELECT embed('text', 'no_such_model')").contains("no_such_model"));
+    for function in ["cosine_sim", "vec_cosine"] {
+        let null = format!("SELECT {function}(NULL, embed('text'))");
+        assert!(query_error(&db, &null).contains("vector BLOB"));
+        let odd = format!("SELECT {function}(x'000000', x'000000')");
+        assert!(query_error(&db, &odd).contains("multiple of 4"));
+        let mismatched = format!("SELECT {function}(x'0000803f', x'0000803f0000803f')");
+        assert!(query_error(&db, &mismatched).contains("dimensions differ"));
+    }
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,46 @@
+// Golden-vector tests for static (model2vec) models against reference
+// embeddings checked into tests/fixtures/tiny_static. The fixture and the
+// reference vectors are written by generate.py in that folder, which follows
+// model2vec's StaticModel.encode.
+mod common;
+
+use common::{Fixture, assert_close};
+use rust_transformer::Pooling;
+
+// Unknown words are left out of the average and a text of only unknown words
+// embeds to zeros; the reference IDs have no special tokens
+const FIXTURE: Fixture = Fixture {
+    model_id: "minishlab/potion-base-8M",
+    dimension: 16,
+    max_tokens: 512,
+    special_tokens: false,
+    ..Fixture::new("model2vec", "tiny_static")
+};
+
+common::fixture_tests!(static_model: FIXTURE);
+
+#[test]
+fn reports_mean_pooling() {
+    assert_eq!(FIXTURE.embedder().info().pooling, Pooling::Mean);
+}
+
+#[test]
+fn unnormalized_embeddings_keep_their_length() {
+    let mut embedder = FIXTURE.builder().normalize(false).build().unwrap();
+    let reference = &FIXTURE.references()[0];
+    let embedding = embedder.embed(&reference.text).unwrap();
+    let norm = embedding.iter().map(|v| v * v).sum::<f32>().sqrt();
+    assert!((norm - 1.0).abs() > 1e-3);
+    let normalized: Vec<f32> = embedding.iter().map(|v| v / norm).collect();
+    assert_close(&normalized, &reference.embedding, 1e-5, &reference.text);
+}
+
+#[test]
+fn other_pooling_runs_token_vectors_through_the_usual_path() {
+    // CLS pooling takes the vector of the [CLS] token the tokenizer adds
+    let mut cls = FIXTURE.builder().pooling(Pooling::Cls).build().unwrap();
+    let first = cls.embed("hello world").unwrap();
+    let second = cls.embed("the cat sat on the mat").unwrap();
+    assert_eq!(first.len(), 16);
+    assert_close(&first, &second, 1e-5, "[CLS]");
+}
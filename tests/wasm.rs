@@ -0,0 +1,72 @@
+// Tests for the wasm-bindgen API, run with a headless runner:
+//
+//   wasm-pack test --node -- --features wasm
+//
+// The model is the tiny BERT from `common`.
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+mod common;
+
+use common::{CONFIG, TOKENIZER, tiny_weights};
+use rust_transformer::wasm::Embedder;
+use serde_json::Value;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+fn embedder() -> Embedder {
+    Embedder::new(
+        "mini_lm_v2",
+        TOKENIZER,
+        tiny_weights(),
+        Some(CONFIG.to_vec()),
+    )
+    .unwrap_or_else(|_| panic!("failed to load the tiny model"))
+}
+
+fn norm(v: &[f32]) -> f32 {
+    v.iter().map(|x| x * x).sum::<f32>().sqrt()
+}
+
+#[wasm_bindgen_test]
+fn loads_from_bytes() {
+    let embedder = embedder();
+    assert_eq!(embedder.dimension(), 32);
+    assert_eq!(embedder.max_tokens(), 64);
+    let info: Value = serde_json::from_str(&embedder.info().unwrap()).unwrap();
+    assert_eq!(info["dimension"], 32);
+}
+
+#[wasm_bindgen_test]
+fn embed_returns_normalized_vector() {
+    let mut embedder = embedder();
+    let embedding = embedder.embed("hello world").unwrap().to_vec();
+    assert_eq!(embedding.len(), 32);
+    assert!((norm(&embedding) - 1.0).abs() < 1e-4);
+
+    embedder.set_normalize(false);
+    let raw = embedder.embed("hello world").unwrap().to_vec();
+    assert!((norm(&raw) - 1.0).abs() > 1e-4);
+}
+
+#[wasm_bindgen_test]
+fn embed_batch_matches_single() {
+    let mut embedder = embedder();
+    let texts = vec![
+        "the cat sat on the mat".to_string(),
+        "dogs are fast".to_string(),
+    ];
+    let batch = embedder.embed_batch(texts.clone(), Some(1)).unwrap();
+    assert_eq!(batch.length(), 2);
+
+    for (i, text) in texts.iter().enumerate() {
+        let row = js_sys::Float32Array::from(batch.get(i as u32)).to_vec();
+        let single = embedder.embed(text).unwrap().to_vec();
+        for (a, b) in row.iter().zip(&single) {
+            assert!((a - b).abs() < 1e-5);
+        }
+    }
+}
+
+#[wasm_bindgen_test]
+fn rejects_unknown_model() {
+    assert!(Embedder::new("does_not_exist", TOKENIZER, tiny_weights(), None).is_err());
+}
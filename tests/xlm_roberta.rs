@@ -0,0 +1,17 @@
+// Parity tests for the XLM-RoBERTa architecture against reference embeddings
+// checked into tests/fixtures/tiny_xlm_roberta. The fixture and the reference
+// vectors are written by generate.py in that folder, whose plain-Python forward
+// pass follows HuggingFace's XLMRobertaModel.
+mod common;
+
+use common::Fixture;
+
+const FIXTURE: Fixture = Fixture {
+    model_id: "intfloat/multilingual-e5-base",
+    dimension: 32,
+    // 34 positions, numbered from pad_token_id + 1
+    max_tokens: 32,
+    ..Fixture::new("xlm_roberta", "tiny_xlm_roberta")
+};
+
+common::fixture_tests!(xlm_roberta: FIXTURE);